@@ -0,0 +1,164 @@
+use crate::domain::cluster::cluster::SaslMechanism;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AclResourceType {
+    Topic,
+    Group,
+    Cluster,
+    TransactionalId,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AclPatternType {
+    Literal,
+    Prefixed,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AclOperation {
+    All,
+    Read,
+    Write,
+    Create,
+    Delete,
+    Alter,
+    Describe,
+    ClusterAction,
+    DescribeConfigs,
+    AlterConfigs,
+    IdempotentWrite,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum AclPermission {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AclBinding {
+    pub resource_type: AclResourceType,
+    pub resource_name: String,
+    pub pattern_type: AclPatternType,
+    pub principal: String,
+    pub host: String,
+    pub operation: AclOperation,
+    pub permission: AclPermission,
+}
+
+/// Shape of an exported ACL file.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AclDocument {
+    pub acls: Vec<AclBinding>,
+}
+
+/// Difference between an ACL file and a cluster. Bindings only present on the
+/// cluster are reported but never deleted by an import.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AclImportPlan {
+    pub to_create: Vec<AclBinding>,
+    pub already_present: usize,
+    pub only_on_cluster: Vec<AclBinding>,
+    /// False for a dry run.
+    pub applied: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ApplicationRole {
+    Consumer,
+    Producer,
+    ConsumerProducer,
+}
+
+/// Outcome of provisioning an application's topics, SCRAM user and ACLs.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProvisionedApplication {
+    pub principal: String,
+    pub username: String,
+    /// Generated SCRAM password. It is not stored, so this is the only time
+    /// it can be read.
+    pub password: String,
+    pub mechanism: SaslMechanism,
+    pub created_topics: Vec<String>,
+    /// Bindings created for the application; ones that already existed are
+    /// left out.
+    pub created_acls: Vec<AclBinding>,
+}
+
+/// An operation a permission probe tries against the cluster.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionCheck {
+    DescribeTopics,
+    CreateTopics,
+    ReadGroups,
+    AlterConfigs,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionStatus {
+    Allowed,
+    Denied,
+    /// The probe could not tell, e.g. there was no topic to try it on.
+    Unknown,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PermissionProbe {
+    pub check: PermissionCheck,
+    pub status: PermissionStatus,
+    pub detail: Option<String>,
+}
+
+/// What the cluster's configured principal was found to be allowed to do.
+/// Checks that change state run as validate-only requests.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PermissionReport {
+    pub cluster_id: Uuid,
+    /// SASL username or OAuth client id the checks ran as, if any.
+    pub principal: Option<String>,
+    pub probed_at: i64,
+    pub checks: Vec<PermissionProbe>,
+}
+
+/// Bindings an application needs for its role. Consumers read with a
+/// consumer group named after the application.
+pub fn application_acls(
+    principal: &str,
+    group: &str,
+    topics: &[String],
+    role: ApplicationRole,
+) -> Vec<AclBinding> {
+    let binding = |resource_type, resource_name: &str, operation| AclBinding {
+        resource_type,
+        resource_name: resource_name.to_string(),
+        pattern_type: AclPatternType::Literal,
+        principal: principal.to_string(),
+        host: "*".to_string(),
+        operation,
+        permission: AclPermission::Allow,
+    };
+
+    let consumes = role != ApplicationRole::Producer;
+    let produces = role != ApplicationRole::Consumer;
+
+    let mut acls = Vec::new();
+    for topic in topics {
+        acls.push(binding(
+            AclResourceType::Topic,
+            topic,
+            AclOperation::Describe,
+        ));
+        if consumes {
+            acls.push(binding(AclResourceType::Topic, topic, AclOperation::Read));
+        }
+        if produces {
+            acls.push(binding(AclResourceType::Topic, topic, AclOperation::Write));
+        }
+    }
+    if consumes {
+        acls.push(binding(AclResourceType::Group, group, AclOperation::Read));
+    }
+    acls
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Fires when a group's lag rises above `threshold`. `topic` narrows the
+/// check to one topic; without it the group's total lag is compared.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LagAlertRule {
+    pub id: Uuid,
+    pub cluster_id: Uuid,
+    pub group_id: String,
+    pub topic: Option<String>,
+    pub threshold: i64,
+    pub interval_secs: u64,
+}
+
+/// Emitted as `alert-triggered` when a rule's threshold is crossed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LagAlert {
+    pub rule_id: Uuid,
+    pub cluster_id: Uuid,
+    pub group_id: String,
+    pub topic: Option<String>,
+    pub lag: i64,
+    pub threshold: i64,
+    pub timestamp: i64,
+}
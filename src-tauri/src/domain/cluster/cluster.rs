@@ -7,6 +7,257 @@ pub struct Cluster {
     pub name: String,
     pub brokers: String,
     pub security: SecurityConfig,
+    /// Incremented on every save. Updates carrying a version are rejected
+    /// when the stored one has moved on; `None` saves unconditionally.
+    #[serde(default)]
+    pub version: Option<i64>,
+    #[serde(default)]
+    pub updated_at: Option<i64>,
+    /// Set while the cluster sits in the trash awaiting restore or purge.
+    #[serde(default)]
+    pub deleted_at: Option<i64>,
+    /// Organization metadata, managed through dedicated commands rather than
+    /// cluster saves.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub sort_order: Option<i64>,
+    /// Schema registries, Connect clusters and similar HTTP services used
+    /// alongside this cluster.
+    #[serde(default)]
+    pub services: Vec<AuxiliaryService>,
+    /// Sent as `client.id` by every client for this cluster so broker logs
+    /// and quotas can attribute its traffic. Defaults to
+    /// `kafkust/<hostname>/<user>` when unset. Changed through a dedicated
+    /// command rather than cluster saves.
+    #[serde(default)]
+    pub client_id: Option<String>,
+    /// SHA-256 fingerprint of the certificate brokers are expected to serve,
+    /// as printed by `openssl x509 -fingerprint -sha256`. Connection checks
+    /// accept a matching certificate even when CA validation fails and
+    /// reject any other. Changed through a dedicated command rather than
+    /// cluster saves.
+    #[serde(default)]
+    pub certificate_pin: Option<String>,
+    /// Every message produced through Kafkust is recorded in the signed
+    /// receipt log. Managed through a dedicated command rather than cluster
+    /// saves.
+    #[serde(default)]
+    pub production_receipts: bool,
+    /// How long topic lists and message counts fetched from this cluster are
+    /// reused before asking the brokers again; two seconds when unset.
+    /// Managed through a dedicated command rather than cluster saves.
+    #[serde(default)]
+    pub metadata_cache_ttl_ms: Option<u64>,
+    /// librdkafka `debug` contexts enabled for this cluster's clients, e.g.
+    /// `broker` or `security`. Managed through a dedicated command rather
+    /// than cluster saves.
+    #[serde(default)]
+    pub client_debug: Vec<String>,
+    /// Recorded by the app as the cluster is used; ignored by cluster saves.
+    #[serde(default)]
+    pub usage: ClusterUsage,
+}
+
+impl Cluster {
+    /// Brokers are an Azure Event Hubs namespace's Kafka endpoint.
+    pub fn is_event_hubs(&self) -> bool {
+        self.brokers.split(',').any(|broker| {
+            let host = broker.trim().split(':').next().unwrap_or_default();
+            let host = host.to_ascii_lowercase();
+            EVENT_HUBS_DOMAINS
+                .iter()
+                .any(|domain| host.ends_with(&format!(".{}", domain)))
+        })
+    }
+
+    /// Kafka APIs the cluster's endpoint does not implement.
+    pub fn unsupported_features(&self) -> Vec<ClusterFeature> {
+        if self.is_event_hubs() {
+            vec![
+                ClusterFeature::Acls,
+                ClusterFeature::DeleteRecords,
+                ClusterFeature::CreatePartitions,
+                ClusterFeature::Transactions,
+            ]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// Kafka APIs some managed endpoints lack. Operations needing one are
+/// refused upfront rather than left to time out against the brokers.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ClusterFeature {
+    Acls,
+    DeleteRecords,
+    CreatePartitions,
+    Transactions,
+}
+
+impl ClusterFeature {
+    pub fn label(self) -> &'static str {
+        match self {
+            ClusterFeature::Acls => "ACLs",
+            ClusterFeature::DeleteRecords => "deleting records",
+            ClusterFeature::CreatePartitions => "adding partitions",
+            ClusterFeature::Transactions => "transactions",
+        }
+    }
+}
+
+/// Service Bus domains of the Azure clouds hosting Event Hubs namespaces.
+const EVENT_HUBS_DOMAINS: &[&str] = &[
+    "servicebus.windows.net",
+    "servicebus.chinacloudapi.cn",
+    "servicebus.usgovcloudapi.net",
+    "servicebus.cloudapi.de",
+];
+
+/// SASL username Event Hubs expects when the password is a connection
+/// string.
+pub const EVENT_HUBS_USERNAME: &str = "$ConnectionString";
+
+/// The parts of an Event Hubs connection string a cluster is set up from.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EventHubsConnection {
+    pub namespace: String,
+    /// Kafka endpoint of the namespace, `<namespace host>:9093`.
+    pub bootstrap: String,
+    /// Set when the string is scoped to a single event hub rather than the
+    /// whole namespace.
+    pub entity_path: Option<String>,
+}
+
+/// Parses a connection string such as
+/// `Endpoint=sb://ns.servicebus.windows.net/;SharedAccessKeyName=...;SharedAccessKey=...`.
+pub fn parse_event_hubs_connection_string(
+    connection_string: &str,
+) -> Result<EventHubsConnection, String> {
+    let mut endpoint = None;
+    let mut has_key_name = false;
+    let mut has_key = false;
+    let mut entity_path = None;
+    for part in connection_string.trim().split(';') {
+        let Some((name, value)) = part.split_once('=') else {
+            continue;
+        };
+        let value = value.trim();
+        match name.trim().to_ascii_lowercase().as_str() {
+            "endpoint" => endpoint = Some(value),
+            "sharedaccesskeyname" => has_key_name = !value.is_empty(),
+            "sharedaccesskey" => has_key = !value.is_empty(),
+            "entitypath" if !value.is_empty() => entity_path = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    let endpoint = endpoint.ok_or("Connection string has no Endpoint")?;
+    if !has_key_name || !has_key {
+        return Err("Connection string has no SharedAccessKeyName and SharedAccessKey".into());
+    }
+    let host = endpoint
+        .strip_prefix("sb://")
+        .ok_or("Endpoint must start with sb://")?
+        .trim_end_matches('/');
+    let host = host
+        .split(':')
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let namespace = EVENT_HUBS_DOMAINS
+        .iter()
+        .find_map(|domain| host.strip_suffix(&format!(".{}", domain)))
+        .filter(|namespace| !namespace.is_empty() && !namespace.contains('.'))
+        .ok_or_else(|| format!("{} is not an Event Hubs namespace", host))?
+        .to_string();
+
+    Ok(EventHubsConnection {
+        namespace,
+        bootstrap: format!("{}:9093", host),
+        entity_path,
+    })
+}
+
+/// How recently and how much a saved cluster has been used, to spot stale
+/// entries worth cleaning up.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ClusterUsage {
+    /// Last time the app connected to the cluster, on selection or a
+    /// connection check.
+    pub last_connected_at: Option<i64>,
+    pub last_used_at: Option<i64>,
+    /// Operations run against the cluster since usage was first recorded.
+    pub operation_count: i64,
+}
+
+/// A cluster's cached OAUTHBEARER token, without the token itself.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TokenStatus {
+    pub cluster_id: Uuid,
+    pub principal: Option<String>,
+    pub fetched_at: Option<i64>,
+    pub expires_at: Option<i64>,
+    /// A token is cached and has not expired.
+    pub valid: bool,
+    /// Why the last fetch failed, cleared by the next successful one.
+    pub last_error: Option<String>,
+}
+
+/// A log line librdkafka emitted for one of a cluster's clients.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClientLogLine {
+    pub timestamp: i64,
+    /// `critical`, `error`, `warning`, `notice`, `info` or `debug`.
+    pub level: String,
+    /// librdkafka facility, e.g. `FAIL`, `SASL` or `METADATA`.
+    pub facility: String,
+    pub message: String,
+}
+
+/// Certificate a broker served during a connection check.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BrokerCertificate {
+    pub broker: String,
+    /// SHA-256 fingerprint, colon-separated uppercase hex.
+    pub fingerprint: String,
+    /// `None` when the cluster has no pin.
+    pub matches_pin: Option<bool>,
+}
+
+/// Fingerprint in colon-separated uppercase hex, the form `openssl` prints,
+/// whatever separators and case it was entered with.
+pub fn normalize_fingerprint(fingerprint: &str) -> String {
+    let digits: Vec<char> = fingerprint
+        .chars()
+        .filter(|c| c.is_ascii_hexdigit())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    digits
+        .chunks(2)
+        .map(|pair| pair.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AuxiliaryServiceKind {
+    SchemaRegistry,
+    Connect,
+    KsqlDb,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AuxiliaryService {
+    pub id: Uuid,
+    pub name: String,
+    pub kind: AuxiliaryServiceKind,
+    pub url: String,
+    pub username: Option<String>,
+    // Password is stored in keyring
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -21,9 +272,16 @@ pub enum SecurityConfig {
     },
     SaslSsl {
         mechanism: SaslMechanism,
+        /// Client id for OAUTHBEARER.
         username: String,
-        // Password is stored in keyring
+        // Password is stored in keyring; the client secret for OAUTHBEARER
         ca_location: Option<String>,
+        /// OAUTHBEARER token endpoint, called with the client credentials
+        /// grant.
+        #[serde(default)]
+        token_endpoint: Option<String>,
+        #[serde(default)]
+        oauth_scope: Option<String>,
     },
 }
 
@@ -0,0 +1,123 @@
+use crate::domain::topic::BulkTextFormat;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// A stored sequence of operations replayed against a cluster. String fields
+/// of the steps may contain `{{name}}` placeholders filled from the
+/// parameters given when the macro is run.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommandMacro {
+    pub id: Uuid,
+    pub name: String,
+    pub steps: Vec<MacroStep>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", content = "config")]
+pub enum MacroStep {
+    CreateTopic {
+        topic: String,
+        partitions: i32,
+        replication: i32,
+        #[serde(default)]
+        configs: HashMap<String, String>,
+    },
+    /// Sets the given configs, leaving the topic's other overrides in place.
+    SetTopicConfigs {
+        topic: String,
+        configs: HashMap<String, String>,
+    },
+    Publish {
+        topic: String,
+        key: Option<String>,
+        payload: String,
+    },
+    PublishBulk {
+        topic: String,
+        text: String,
+        format: BulkTextFormat,
+    },
+    DeleteTopic {
+        topic: String,
+    },
+}
+
+impl MacroStep {
+    /// Copy of the step with placeholders in its string fields filled in.
+    pub fn with_params(&self, params: &HashMap<String, String>) -> Result<MacroStep, String> {
+        let mut value = serde_json::to_value(self).map_err(|e| e.to_string())?;
+        fill_value(&mut value, params)?;
+        serde_json::from_value(value).map_err(|e| e.to_string())
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            MacroStep::CreateTopic { topic, .. } => format!("create topic {}", topic),
+            MacroStep::SetTopicConfigs { topic, .. } => format!("set configs of {}", topic),
+            MacroStep::Publish { topic, .. } => format!("publish to {}", topic),
+            MacroStep::PublishBulk { topic, .. } => format!("bulk publish to {}", topic),
+            MacroStep::DeleteTopic { topic } => format!("delete topic {}", topic),
+        }
+    }
+}
+
+/// Outcome of a macro run. Steps after a failed one are not attempted.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MacroRunResult {
+    pub completed_steps: usize,
+    pub failed_step: Option<MacroStepFailure>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MacroStepFailure {
+    /// 0-based index into the macro's steps.
+    pub index: usize,
+    pub description: String,
+    pub error: String,
+}
+
+fn fill_value(
+    value: &mut serde_json::Value,
+    params: &HashMap<String, String>,
+) -> Result<(), String> {
+    match value {
+        serde_json::Value::String(s) => *s = fill_placeholders(s, params)?,
+        serde_json::Value::Array(items) => {
+            for item in items {
+                fill_value(item, params)?;
+            }
+        }
+        serde_json::Value::Object(fields) => {
+            for field in fields.values_mut() {
+                fill_value(field, params)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Replaces `{{name}}` placeholders in `template`, failing on a placeholder
+/// without a parameter.
+pub fn fill_placeholders(
+    template: &str,
+    params: &HashMap<String, String>,
+) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = rest[start + 2..start + 2 + len].trim();
+        let value = params
+            .get(name)
+            .ok_or_else(|| format!("Missing macro parameter '{}'", name))?;
+        out.push_str(&rest[..start]);
+        out.push_str(value);
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
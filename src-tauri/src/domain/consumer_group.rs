@@ -0,0 +1,370 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommittedOffset {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConsumerGroup {
+    pub group_id: String,
+    /// Coordinator state, e.g. `Stable`, `Empty` or `PreparingRebalance`.
+    pub state: String,
+    pub protocol_type: String,
+    pub member_count: usize,
+    /// Set when served from the last successful listing because the
+    /// cluster was unreachable.
+    #[serde(default)]
+    pub cached_at: Option<i64>,
+}
+
+/// Keeps groups in one of `states` (all when empty) whose id contains
+/// `search`. Both comparisons ignore case.
+pub fn filter_groups(
+    groups: Vec<ConsumerGroup>,
+    states: &[String],
+    search: Option<&str>,
+) -> Vec<ConsumerGroup> {
+    let search = search.map(str::to_lowercase);
+    groups
+        .into_iter()
+        .filter(|g| states.is_empty() || states.iter().any(|s| s.eq_ignore_ascii_case(&g.state)))
+        .filter(|g| match &search {
+            Some(search) => g.group_id.to_lowercase().contains(search.as_str()),
+            None => true,
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MemberAssignment {
+    pub topic: String,
+    pub partitions: Vec<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConsumerGroupMember {
+    pub member_id: String,
+    pub client_id: String,
+    pub client_host: String,
+    /// Static membership id, set by consumers configured with
+    /// `group.instance.id`.
+    #[serde(default)]
+    pub group_instance_id: Option<String>,
+    /// Empty for groups that do not use the consumer protocol.
+    pub assignments: Vec<MemberAssignment>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConsumerGroupDetail {
+    pub group_id: String,
+    pub state: String,
+    pub protocol_type: String,
+    /// Assignor chosen by the group, e.g. `range` or `cooperative-sticky`.
+    pub protocol: String,
+    pub members: Vec<ConsumerGroupMember>,
+    /// Static ids claimed by more than one member. Instances sharing an id
+    /// keep fencing each other out of the group.
+    #[serde(default)]
+    pub duplicate_instance_ids: Vec<String>,
+}
+
+/// Static membership ids used by more than one member, sorted.
+pub fn duplicate_instance_ids(members: &[ConsumerGroupMember]) -> Vec<String> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for id in members
+        .iter()
+        .filter_map(|m| m.group_instance_id.as_deref())
+    {
+        *counts.entry(id).or_default() += 1;
+    }
+    let mut duplicates: Vec<String> = counts
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .map(|(id, _)| id.to_string())
+        .collect();
+    duplicates.sort();
+    duplicates
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PartitionLag {
+    pub topic: String,
+    pub partition: i32,
+    pub committed_offset: i64,
+    pub high_watermark: i64,
+    pub lag: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConsumerGroupLag {
+    pub group_id: String,
+    /// Partitions the group has committed offsets for.
+    pub partitions: Vec<PartitionLag>,
+    pub total_lag: i64,
+}
+
+/// One partition of a group's offset heatmap. Offsets and lag are unset
+/// when the group has no commit on the partition or its end offset could
+/// not be read; the member is unset when no member is assigned it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupPartitionCell {
+    pub topic: String,
+    pub partition: i32,
+    pub committed_offset: Option<i64>,
+    pub end_offset: Option<i64>,
+    pub lag: Option<i64>,
+    pub member_id: Option<String>,
+    pub client_id: Option<String>,
+}
+
+/// Lag summed over the partitions assigned to one member.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MemberLag {
+    pub member_id: String,
+    pub client_id: String,
+    pub client_host: String,
+    pub partitions: usize,
+    pub lag: i64,
+}
+
+/// Every partition a group has committed offsets for or is assigned, with
+/// the member reading it, for rendering as a member x partition heatmap.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupPartitionMatrix {
+    pub group_id: String,
+    pub state: String,
+    pub cells: Vec<GroupPartitionCell>,
+    /// Members by descending lag, so the bottleneck comes first.
+    pub members: Vec<MemberLag>,
+    pub total_lag: i64,
+    /// Largest lag of a single partition, to scale the heatmap.
+    pub max_lag: i64,
+}
+
+/// Joins a group's assignments with its lag. `end_offsets` supplies the end
+/// offset of assigned partitions the group has not committed on yet.
+pub fn group_partition_matrix(
+    detail: &ConsumerGroupDetail,
+    lag: &ConsumerGroupLag,
+    end_offsets: &HashMap<(String, i32), i64>,
+) -> GroupPartitionMatrix {
+    let mut cells: BTreeMap<(String, i32), GroupPartitionCell> = lag
+        .partitions
+        .iter()
+        .map(|p| {
+            let cell = GroupPartitionCell {
+                topic: p.topic.clone(),
+                partition: p.partition,
+                committed_offset: Some(p.committed_offset),
+                end_offset: Some(p.high_watermark),
+                lag: Some(p.lag),
+                member_id: None,
+                client_id: None,
+            };
+            ((p.topic.clone(), p.partition), cell)
+        })
+        .collect();
+
+    let mut members = Vec::with_capacity(detail.members.len());
+    for member in &detail.members {
+        let mut member_lag = MemberLag {
+            member_id: member.member_id.clone(),
+            client_id: member.client_id.clone(),
+            client_host: member.client_host.clone(),
+            partitions: 0,
+            lag: 0,
+        };
+        for assignment in &member.assignments {
+            for &partition in &assignment.partitions {
+                let key = (assignment.topic.clone(), partition);
+                let end_offset = end_offsets.get(&key).copied();
+                let cell = cells.entry(key).or_insert_with(|| GroupPartitionCell {
+                    topic: assignment.topic.clone(),
+                    partition,
+                    committed_offset: None,
+                    end_offset,
+                    lag: None,
+                    member_id: None,
+                    client_id: None,
+                });
+                cell.member_id = Some(member.member_id.clone());
+                cell.client_id = Some(member.client_id.clone());
+                member_lag.partitions += 1;
+                member_lag.lag += cell.lag.unwrap_or(0);
+            }
+        }
+        members.push(member_lag);
+    }
+    members.sort_by(|a, b| {
+        b.lag
+            .cmp(&a.lag)
+            .then_with(|| a.member_id.cmp(&b.member_id))
+    });
+
+    let cells: Vec<GroupPartitionCell> = cells.into_values().collect();
+    GroupPartitionMatrix {
+        group_id: detail.group_id.clone(),
+        state: detail.state.clone(),
+        total_lag: cells.iter().filter_map(|c| c.lag).sum(),
+        max_lag: cells.iter().filter_map(|c| c.lag).max().unwrap_or(0),
+        cells,
+        members,
+    }
+}
+
+/// A group reading a topic: it has committed offsets on it, members
+/// assigned to it, or both.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicConsumer {
+    pub group_id: String,
+    pub state: String,
+    /// Members currently assigned partitions of the topic.
+    pub active_members: usize,
+    pub assigned_partitions: Vec<i32>,
+    /// Partitions of the topic the group has committed offsets for.
+    pub partitions: Vec<PartitionLag>,
+    pub total_lag: i64,
+}
+
+/// Lag of one group on a topic, summed over its partitions.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupLagTotal {
+    pub group_id: String,
+    pub state: String,
+    pub total_lag: i64,
+    /// Lag of the group's furthest-behind partition.
+    pub max_partition_lag: i64,
+}
+
+/// Lag on a topic across every group reading it, worst first.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicLagOverview {
+    pub topic: String,
+    pub total_lag: i64,
+    pub groups: Vec<GroupLagTotal>,
+}
+
+/// Where a reset moves a group's committed offsets. Offsets outside a
+/// partition's current range are clamped to it.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(tag = "type", content = "value")]
+pub enum OffsetResetTarget {
+    Earliest,
+    Latest,
+    Offset(i64),
+    /// Earliest offset whose record timestamp (epoch millis) is at or after
+    /// the given time.
+    Timestamp(i64),
+    /// Moves the committed offset by N (negative rewinds). Partitions
+    /// without a committed offset shift from the earliest offset.
+    ShiftBy(i64),
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PartitionOffsetReset {
+    pub topic: String,
+    pub partition: i32,
+    /// Committed offset before the reset, if the group had one.
+    pub before: Option<i64>,
+    pub after: i64,
+    /// Timestamps of the records at `before` and `after`, filled in by
+    /// previews; `None` when the position is at the end of the partition.
+    #[serde(default)]
+    pub before_timestamp: Option<i64>,
+    #[serde(default)]
+    pub after_timestamp: Option<i64>,
+}
+
+/// A previewed offset reset, exported for review and applied as-is later.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OffsetResetPlan {
+    pub group_id: String,
+    pub topic: String,
+    pub created_at: i64,
+    pub offsets: Vec<PartitionOffsetReset>,
+}
+
+/// A group's committed offsets saved before a reset, for rolling it back.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OffsetSnapshot {
+    pub id: Uuid,
+    pub cluster_id: Uuid,
+    pub group_id: String,
+    pub created_at: i64,
+    pub offsets: Vec<CommittedOffset>,
+}
+
+/// Shape of an exported consumer group offsets file.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupOffsetDocument {
+    pub group_id: String,
+    pub exported_at: i64,
+    pub offsets: Vec<CommittedOffset>,
+}
+
+/// Lag of a group on one topic at a point in time, summed over partitions.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LagSample {
+    pub group_id: String,
+    pub topic: String,
+    pub lag: i64,
+    pub timestamp: i64,
+}
+
+/// A change in a group's state or membership between two polls. A `None`
+/// state means the group was not listed in that poll.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GroupEvent {
+    pub cluster_id: Uuid,
+    pub group_id: String,
+    pub from_state: Option<String>,
+    pub to_state: Option<String>,
+    pub members_before: usize,
+    pub members_after: usize,
+    pub timestamp: i64,
+}
+
+/// Groups whose state or member count differs between two polls, including
+/// groups that appeared or disappeared.
+pub fn diff_group_states(
+    cluster_id: Uuid,
+    previous: &[ConsumerGroup],
+    current: &[ConsumerGroup],
+    timestamp: i64,
+) -> Vec<GroupEvent> {
+    let before: HashMap<&str, &ConsumerGroup> =
+        previous.iter().map(|g| (g.group_id.as_str(), g)).collect();
+    let after: HashMap<&str, &ConsumerGroup> =
+        current.iter().map(|g| (g.group_id.as_str(), g)).collect();
+
+    let mut group_ids: Vec<&str> = before.keys().chain(after.keys()).copied().collect();
+    group_ids.sort_unstable();
+    group_ids.dedup();
+
+    group_ids
+        .into_iter()
+        .filter_map(|group_id| {
+            let old = before.get(group_id);
+            let new = after.get(group_id);
+            let unchanged = matches!((old, new), (Some(o), Some(n))
+                if o.state == n.state && o.member_count == n.member_count);
+            if unchanged {
+                return None;
+            }
+            Some(GroupEvent {
+                cluster_id,
+                group_id: group_id.to_string(),
+                from_state: old.map(|g| g.state.clone()),
+                to_state: new.map(|g| g.state.clone()),
+                members_before: old.map_or(0, |g| g.member_count),
+                members_after: new.map_or(0, |g| g.member_count),
+                timestamp,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+
+/// Name endings marking a dead-letter topic, compared case-insensitively.
+/// What precedes them is taken as the source topic's name.
+const DLQ_SUFFIXES: &[&str] = &[
+    ".dlt",
+    "-dlt",
+    "_dlt",
+    ".dlq",
+    "-dlq",
+    "_dlq",
+    ".deadletter",
+    "-deadletter",
+    "_deadletter",
+];
+
+/// Name beginnings marking a dead-letter topic, as Kafka Connect
+/// deployments commonly name them after the connector rather than a topic.
+const DLQ_PREFIXES: &[&str] = &["dlq.", "dlq-", "dlq_"];
+
+/// Headers carrying the topic a failed record was originally read from,
+/// written by Spring Kafka and Kafka Connect respectively.
+pub const ORIGINAL_TOPIC_HEADERS: &[&str] = &["kafka_dlt-original-topic", "__connect.errors.topic"];
+
+/// Headers carrying the error that sent a record to the dead-letter topic.
+pub const ERROR_HEADERS: &[&str] = &[
+    "kafka_dlt-exception-message",
+    "__connect.errors.exception.message",
+];
+
+/// Whether `topic` follows a dead-letter naming convention. Returns the
+/// source topic name the convention implies, if it implies one.
+pub fn dlq_naming(topic: &str) -> Option<Option<&str>> {
+    let lower = topic.to_ascii_lowercase();
+    if let Some(suffix) = DLQ_SUFFIXES.iter().find(|s| lower.ends_with(*s)) {
+        let source = &topic[..topic.len() - suffix.len()];
+        return Some(Some(source).filter(|s| !s.is_empty()));
+    }
+    if DLQ_PREFIXES.iter().any(|p| lower.starts_with(p)) {
+        return Some(None);
+    }
+    None
+}
+
+/// A record on a dead-letter topic, with what its headers say about why.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DlqFailure {
+    pub partition: i32,
+    pub offset: i64,
+    pub timestamp: Option<i64>,
+    pub original_topic: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DlqTopic {
+    pub topic: String,
+    /// Existing topic this one collects failures for, from its name or,
+    /// failing that, from the headers of its recent records.
+    pub source_topic: Option<String>,
+    /// Records currently retained.
+    pub depth: i64,
+    /// Most recent records, newest first.
+    pub recent_failures: Vec<DlqFailure>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DlqSummary {
+    pub topics: Vec<DlqTopic>,
+    pub total_depth: i64,
+}
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Payload of a record written by an exactly-once verification run.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EosRecord {
+    pub run: Uuid,
+    pub seq: u64,
+    /// Written in a transaction that was then aborted, so a read_committed
+    /// consumer must never see it.
+    #[serde(default)]
+    pub aborted: bool,
+}
+
+/// Emitted as `eos-verification-finished` when a verification run ends.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EosReport {
+    pub session_id: Uuid,
+    /// Records in committed transactions.
+    pub produced: u64,
+    /// Records in aborted transactions.
+    pub aborted: u64,
+    pub consumed: u64,
+    pub duplicates: u64,
+    pub missing: u64,
+    /// The first few missing sequence numbers.
+    pub missing_sample: Vec<u64>,
+    /// Records read after one with a higher sequence number.
+    pub reordered: u64,
+    /// Records from aborted transactions the consumer was handed.
+    pub aborted_visible: u64,
+    /// False when stopped before every committed record was read or the
+    /// read went idle.
+    pub completed: bool,
+    /// Completed with no duplicates, gaps, reordering or aborted reads.
+    pub passed: bool,
+    pub error: Option<String>,
+}
+
+const MISSING_SAMPLE_SIZE: usize = 20;
+
+/// Tallies the sequence numbers a verification consumer reads.
+#[derive(Debug, Default)]
+pub struct SequenceTracker {
+    seen: HashSet<u64>,
+    highest: Option<u64>,
+    consumed: u64,
+    duplicates: u64,
+    reordered: u64,
+    aborted_visible: u64,
+}
+
+impl SequenceTracker {
+    pub fn record(&mut self, record: &EosRecord) {
+        self.consumed += 1;
+        if record.aborted {
+            self.aborted_visible += 1;
+            return;
+        }
+        if !self.seen.insert(record.seq) {
+            self.duplicates += 1;
+            return;
+        }
+        match self.highest {
+            Some(highest) if record.seq < highest => self.reordered += 1,
+            _ => self.highest = Some(record.seq),
+        }
+    }
+
+    /// Whether every sequence number below `produced` has been read.
+    pub fn has_all(&self, produced: u64) -> bool {
+        (0..produced).all(|seq| self.seen.contains(&seq))
+    }
+
+    pub fn report(
+        &self,
+        session_id: Uuid,
+        produced: u64,
+        aborted: u64,
+        error: Option<String>,
+    ) -> EosReport {
+        let missing: Vec<u64> = (0..produced)
+            .filter(|seq| !self.seen.contains(seq))
+            .collect();
+        let completed = error.is_none() && missing.is_empty();
+        let passed =
+            completed && self.duplicates == 0 && self.reordered == 0 && self.aborted_visible == 0;
+        EosReport {
+            session_id,
+            produced,
+            aborted,
+            consumed: self.consumed,
+            duplicates: self.duplicates,
+            missing: missing.len() as u64,
+            missing_sample: missing.into_iter().take(MISSING_SAMPLE_SIZE).collect(),
+            reordered: self.reordered,
+            aborted_visible: self.aborted_visible,
+            completed,
+            passed,
+            error,
+        }
+    }
+}
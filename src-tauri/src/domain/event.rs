@@ -0,0 +1,7 @@
+/// Outbound port used by background work to push live updates to the UI.
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: &str, payload: serde_json::Value);
+
+    /// Raises a desktop notification, for events worth interrupting the user.
+    fn notify(&self, title: &str, body: &str);
+}
@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// File the manifest of an export is written to, next to its shards.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// How an export splits a topic's records into files.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", content = "config")]
+pub enum ExportSharding {
+    /// One file per partition.
+    Partition,
+    /// One file per window of record timestamps, e.g. an hour.
+    TimeBucket { bucket_ms: i64 },
+}
+
+impl ExportSharding {
+    /// Name of the file a record goes to.
+    pub fn shard_name(&self, partition: i32, timestamp: Option<i64>) -> String {
+        match self {
+            ExportSharding::Partition => format!("partition-{:05}.jsonl", partition),
+            ExportSharding::TimeBucket { bucket_ms } => match timestamp {
+                Some(ts) => format!("bucket-{}.jsonl", bucket_start(ts, *bucket_ms)),
+                None => "bucket-untimed.jsonl".to_string(),
+            },
+        }
+    }
+}
+
+/// Start of the bucket a timestamp falls in.
+pub fn bucket_start(timestamp: i64, bucket_ms: i64) -> i64 {
+    timestamp.div_euclid(bucket_ms) * bucket_ms
+}
+
+/// Offsets of one partition written to a shard.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ShardRange {
+    pub partition: i32,
+    pub first_offset: i64,
+    pub last_offset: i64,
+}
+
+/// One output file of an export, holding JSON lines of records.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportShard {
+    pub file: String,
+    pub records: u64,
+    pub ranges: Vec<ShardRange>,
+    /// False when the export stopped before the shard was fully written.
+    pub complete: bool,
+}
+
+impl ExportShard {
+    pub fn new(file: String) -> Self {
+        Self {
+            file,
+            records: 0,
+            ranges: Vec::new(),
+            complete: false,
+        }
+    }
+
+    /// Accounts for a record written to the shard.
+    pub fn add(&mut self, partition: i32, offset: i64) {
+        self.records += 1;
+        match self.ranges.iter_mut().find(|r| r.partition == partition) {
+            Some(range) => {
+                range.first_offset = range.first_offset.min(offset);
+                range.last_offset = range.last_offset.max(offset);
+            }
+            None => self.ranges.push(ShardRange {
+                partition,
+                first_offset: offset,
+                last_offset: offset,
+            }),
+        }
+    }
+}
+
+/// Written as `manifest.json` so shards can be processed in parallel and a
+/// failed one re-exported on its own.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportManifest {
+    pub cluster_id: Uuid,
+    pub topic: String,
+    pub sharding: ExportSharding,
+    pub exported_at: i64,
+    /// By file name.
+    pub shards: Vec<ExportShard>,
+}
+
+impl ExportManifest {
+    /// Replaces the entries of re-exported shards and adds new ones. A
+    /// manifest of a different topic or sharding is discarded.
+    pub fn merge(self, rerun: ExportManifest) -> ExportManifest {
+        if self.topic != rerun.topic || self.sharding != rerun.sharding {
+            return rerun;
+        }
+        let mut shards: Vec<ExportShard> = self
+            .shards
+            .into_iter()
+            .filter(|shard| !rerun.shards.iter().any(|s| s.file == shard.file))
+            .collect();
+        shards.extend(rerun.shards);
+        shards.sort_by(|a, b| a.file.cmp(&b.file));
+        ExportManifest { shards, ..rerun }
+    }
+}
+
+/// Emitted as `export-finished` when an export ends.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExportSummary {
+    pub session_id: Uuid,
+    pub directory: String,
+    pub records: u64,
+    pub manifest: ExportManifest,
+    /// False when the export was stopped before reaching the end offsets
+    /// captured at start.
+    pub completed: bool,
+}
@@ -0,0 +1,310 @@
+use crate::domain::topic::Partition;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum IsrChange {
+    Shrink,
+    Expand,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IsrEvent {
+    pub cluster_id: Uuid,
+    pub topic: String,
+    pub partition: i32,
+    pub change: IsrChange,
+    /// Brokers that left (shrink) or joined (expand) the ISR.
+    pub brokers: Vec<i32>,
+    /// ISR after the change.
+    pub isr: Vec<i32>,
+    pub timestamp: i64,
+}
+
+/// Compares the ISR of every partition present in both polls. A partition
+/// whose ISR both lost and gained brokers yields a shrink and an expand.
+pub fn diff_isr(
+    cluster_id: Uuid,
+    previous: &HashMap<String, Vec<Partition>>,
+    current: &HashMap<String, Vec<Partition>>,
+    timestamp: i64,
+) -> Vec<IsrEvent> {
+    let mut events = Vec::new();
+
+    for (topic, partitions) in current {
+        let Some(before) = previous.get(topic) else {
+            continue;
+        };
+        for partition in partitions {
+            let Some(old) = before.iter().find(|p| p.id == partition.id) else {
+                continue;
+            };
+
+            let left: Vec<i32> = old
+                .isrs
+                .iter()
+                .filter(|b| !partition.isrs.contains(b))
+                .copied()
+                .collect();
+            let joined: Vec<i32> = partition
+                .isrs
+                .iter()
+                .filter(|b| !old.isrs.contains(b))
+                .copied()
+                .collect();
+
+            for (change, brokers) in [(IsrChange::Shrink, left), (IsrChange::Expand, joined)] {
+                if !brokers.is_empty() {
+                    events.push(IsrEvent {
+                        cluster_id,
+                        topic: topic.clone(),
+                        partition: partition.id,
+                        change,
+                        brokers,
+                        isr: partition.isrs.clone(),
+                        timestamp,
+                    });
+                }
+            }
+        }
+    }
+
+    events
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TopicChange {
+    Created,
+    Deleted,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicEvent {
+    pub cluster_id: Uuid,
+    pub topic: String,
+    pub change: TopicChange,
+    /// Partition count when created, or last seen before deletion.
+    pub partitions: usize,
+    pub timestamp: i64,
+}
+
+/// Topics that appeared or disappeared between two polls.
+pub fn diff_topics(
+    cluster_id: Uuid,
+    previous: &HashMap<String, Vec<Partition>>,
+    current: &HashMap<String, Vec<Partition>>,
+    timestamp: i64,
+) -> Vec<TopicEvent> {
+    let created = current
+        .iter()
+        .filter(|(topic, _)| !previous.contains_key(*topic))
+        .map(|(topic, partitions)| (topic, partitions, TopicChange::Created));
+    let deleted = previous
+        .iter()
+        .filter(|(topic, _)| !current.contains_key(*topic))
+        .map(|(topic, partitions)| (topic, partitions, TopicChange::Deleted));
+
+    let mut events: Vec<TopicEvent> = created
+        .chain(deleted)
+        .map(|(topic, partitions, change)| TopicEvent {
+            cluster_id,
+            topic: topic.clone(),
+            change,
+            partitions: partitions.len(),
+            timestamp,
+        })
+        .collect();
+    events.sort_by(|a, b| a.topic.cmp(&b.topic));
+    events
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PartitionImpact {
+    pub topic: String,
+    pub partition: i32,
+    pub leader: i32,
+    pub replicas: Vec<i32>,
+    pub isr: Vec<i32>,
+    pub min_insync_replicas: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BrokerMaintenanceReport {
+    pub broker_id: i32,
+    /// Partitions with a replica on the broker.
+    pub hosted_replicas: usize,
+    /// Partitions whose leadership moves when the broker goes down.
+    pub led_partitions: Vec<PartitionImpact>,
+    /// Partitions left with fewer in-sync replicas than
+    /// `min.insync.replicas`, rejecting `acks=all` writes.
+    pub below_min_isr: Vec<PartitionImpact>,
+}
+
+/// Works out which partitions a broker outage would affect. Topics missing
+/// from `min_insync_replicas` are treated as using the broker default of 1.
+pub fn plan_broker_maintenance(
+    broker_id: i32,
+    partitions: &HashMap<String, Vec<Partition>>,
+    min_insync_replicas: &HashMap<String, i32>,
+) -> BrokerMaintenanceReport {
+    let mut report = BrokerMaintenanceReport {
+        broker_id,
+        hosted_replicas: 0,
+        led_partitions: Vec::new(),
+        below_min_isr: Vec::new(),
+    };
+
+    for (topic, partitions) in partitions {
+        let min_isr = min_insync_replicas.get(topic).copied().unwrap_or(1);
+        for partition in partitions {
+            if !partition.replicas.contains(&broker_id) {
+                continue;
+            }
+            report.hosted_replicas += 1;
+
+            let impact = PartitionImpact {
+                topic: topic.clone(),
+                partition: partition.id,
+                leader: partition.leader,
+                replicas: partition.replicas.clone(),
+                isr: partition.isrs.clone(),
+                min_insync_replicas: min_isr,
+            };
+            let remaining = partition.isrs.iter().filter(|b| **b != broker_id).count();
+
+            if remaining < min_isr as usize {
+                report.below_min_isr.push(impact.clone());
+            }
+            if partition.leader == broker_id {
+                report.led_partitions.push(impact);
+            }
+        }
+    }
+
+    for list in [&mut report.led_partitions, &mut report.below_min_isr] {
+        list.sort_by(|a, b| (&a.topic, a.partition).cmp(&(&b.topic, b.partition)));
+    }
+    report
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum MinIsrRiskReason {
+    /// The replication factor leaves no headroom above `min.insync.replicas`,
+    /// so any replica going down blocks writes even when fully in sync.
+    ReplicationFactor,
+    /// Replicas have already dropped out of the ISR, using up the headroom.
+    UnderReplicated,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MinIsrRisk {
+    pub topic: String,
+    pub partition: i32,
+    pub replication_factor: i32,
+    pub min_insync_replicas: i32,
+    pub isr: Vec<i32>,
+    pub reason: MinIsrRiskReason,
+}
+
+/// Partitions that would reject `acks=all` writes if any single in-sync
+/// broker failed. Topics missing from `min_insync_replicas` use the broker
+/// default of 1.
+pub fn analyze_min_isr_risk(
+    partitions: &HashMap<String, Vec<Partition>>,
+    min_insync_replicas: &HashMap<String, i32>,
+) -> Vec<MinIsrRisk> {
+    let mut risks = Vec::new();
+
+    for (topic, partitions) in partitions {
+        let min_isr = min_insync_replicas.get(topic).copied().unwrap_or(1);
+        for partition in partitions {
+            let replication_factor = partition.replicas.len() as i32;
+            let isr_size = partition.isrs.len() as i32;
+
+            let reason = if replication_factor <= min_isr {
+                MinIsrRiskReason::ReplicationFactor
+            } else if isr_size <= min_isr {
+                MinIsrRiskReason::UnderReplicated
+            } else {
+                continue;
+            };
+
+            risks.push(MinIsrRisk {
+                topic: topic.clone(),
+                partition: partition.id,
+                replication_factor,
+                min_insync_replicas: min_isr,
+                isr: partition.isrs.clone(),
+                reason,
+            });
+        }
+    }
+
+    risks.sort_by(|a, b| (&a.topic, a.partition).cmp(&(&b.topic, b.partition)));
+    risks
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PartitionHealthIssue {
+    /// No leader, so the partition can be neither read nor written.
+    Offline,
+    /// Fewer in-sync replicas than assigned replicas.
+    UnderReplicated,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct UnhealthyPartition {
+    pub partition: i32,
+    /// -1 when the partition has no leader.
+    pub leader: i32,
+    pub replicas: Vec<i32>,
+    pub isr: Vec<i32>,
+    pub issue: PartitionHealthIssue,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicHealth {
+    pub topic: String,
+    pub partitions: Vec<UnhealthyPartition>,
+}
+
+/// Offline and under-replicated partitions, grouped by topic. Healthy topics
+/// are left out.
+pub fn scan_partition_health(partitions: &HashMap<String, Vec<Partition>>) -> Vec<TopicHealth> {
+    let mut topics: Vec<TopicHealth> = partitions
+        .iter()
+        .filter_map(|(topic, partitions)| {
+            let mut unhealthy: Vec<UnhealthyPartition> = partitions
+                .iter()
+                .filter_map(|p| {
+                    let issue = if p.leader < 0 {
+                        PartitionHealthIssue::Offline
+                    } else if p.isrs.len() < p.replicas.len() {
+                        PartitionHealthIssue::UnderReplicated
+                    } else {
+                        return None;
+                    };
+                    Some(UnhealthyPartition {
+                        partition: p.id,
+                        leader: p.leader,
+                        replicas: p.replicas.clone(),
+                        isr: p.isrs.clone(),
+                        issue,
+                    })
+                })
+                .collect();
+            if unhealthy.is_empty() {
+                return None;
+            }
+            unhealthy.sort_by_key(|p| p.partition);
+            Some(TopicHealth {
+                topic: topic.clone(),
+                partitions: unhealthy,
+            })
+        })
+        .collect();
+
+    topics.sort_by(|a, b| a.topic.cmp(&b.topic));
+    topics
+}
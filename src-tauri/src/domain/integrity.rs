@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrityIssueKind {
+    /// `PRAGMA integrity_check` found damage; only a backup can fix it.
+    Corruption,
+    /// A cluster stored under an id that is not a UUID, which every lookup
+    /// silently misses.
+    InvalidClusterId,
+    InvalidServiceId,
+    /// Per-cluster data left behind by a cluster that no longer exists.
+    OrphanedRows,
+    /// A keyring entry belonging to data of a cluster that no longer exists.
+    OrphanedSecret,
+    /// A cluster or service that authenticates with a password the keyring
+    /// does not have.
+    MissingSecret,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IntegrityIssue {
+    pub kind: IntegrityIssueKind,
+    /// Table, cluster or keyring entry concerned.
+    pub subject: String,
+    pub message: String,
+    /// `repair_database` fixes it; otherwise it needs the user, e.g. to
+    /// enter a password again.
+    pub repairable: bool,
+}
+
+/// Outcome of checking the local database and keyring.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DatabaseReport {
+    pub checked_at: i64,
+    pub issues: Vec<IntegrityIssue>,
+}
+
+impl DatabaseReport {
+    pub fn is_healthy(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// What a repair did, and the check that followed it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DatabaseRepair {
+    pub actions: Vec<String>,
+    pub report: DatabaseReport,
+}
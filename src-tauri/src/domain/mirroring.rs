@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum Mm2TopicKind {
+    Heartbeats,
+    Checkpoints,
+    OffsetSyncs,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Mm2InternalTopic {
+    pub name: String,
+    pub kind: Mm2TopicKind,
+    /// Cluster alias embedded in the topic name, if any: the source alias for
+    /// checkpoints and replicated heartbeats, the target alias for offset syncs.
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Heartbeat {
+    pub source_cluster_alias: String,
+    pub target_cluster_alias: String,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Checkpoint {
+    pub consumer_group_id: String,
+    pub topic: String,
+    pub partition: i32,
+    pub upstream_offset: i64,
+    pub downstream_offset: i64,
+    pub metadata: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OffsetSync {
+    pub topic: String,
+    pub partition: i32,
+    pub upstream_offset: i64,
+    pub downstream_offset: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReplicationFlow {
+    pub source_cluster_alias: String,
+    pub target_cluster_alias: String,
+    pub last_heartbeat: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MirroringStatus {
+    pub internal_topics: Vec<Mm2InternalTopic>,
+    pub flows: Vec<ReplicationFlow>,
+    /// Latest checkpoint per group, topic and partition.
+    pub checkpoints: Vec<Checkpoint>,
+    /// Latest offset sync per topic and partition.
+    pub offset_syncs: Vec<OffsetSync>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TranslationMethod {
+    /// Downstream offsets taken from MirrorMaker 2 checkpoints.
+    Checkpoint,
+    /// Source record timestamps resolved with offsets-for-times on the
+    /// destination; approximate when timestamps are not unique.
+    Timestamp,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranslatedOffset {
+    pub source_topic: String,
+    pub destination_topic: String,
+    pub partition: i32,
+    pub source_offset: i64,
+    pub destination_offset: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OffsetTranslation {
+    pub group_id: String,
+    pub method: TranslationMethod,
+    pub offsets: Vec<TranslatedOffset>,
+    /// Source topics without a matching destination topic.
+    pub unmatched_topics: Vec<String>,
+}
+
+/// Recognizes the internal topics MirrorMaker 2 maintains with the default
+/// replication policy.
+pub fn classify_mm2_topic(name: &str) -> Option<Mm2InternalTopic> {
+    let (kind, alias) = if name == "heartbeats" {
+        (Mm2TopicKind::Heartbeats, None)
+    } else if let Some(alias) = name.strip_suffix(".heartbeats") {
+        (Mm2TopicKind::Heartbeats, Some(alias))
+    } else if let Some(alias) = name.strip_suffix(".checkpoints.internal") {
+        (Mm2TopicKind::Checkpoints, Some(alias))
+    } else if let Some(alias) = name
+        .strip_prefix("mm2-offset-syncs.")
+        .and_then(|rest| rest.strip_suffix(".internal"))
+    {
+        (Mm2TopicKind::OffsetSyncs, Some(alias))
+    } else {
+        return None;
+    };
+
+    Some(Mm2InternalTopic {
+        name: name.to_string(),
+        kind,
+        alias: alias.filter(|a| !a.is_empty()).map(str::to_string),
+    })
+}
@@ -1,2 +1,21 @@
+pub mod acl;
+pub mod alert;
 pub mod cluster;
+pub mod command_macro;
+pub mod consumer_group;
+pub mod dlq;
+pub mod eos;
+pub mod event;
+pub mod export;
+pub mod health;
+pub mod integrity;
+pub mod mirroring;
+pub mod plugin;
+pub mod quota;
+pub mod receipt;
+pub mod refresh;
+pub mod replay;
+pub mod session;
+pub mod streams;
 pub mod topic;
+pub mod workspace;
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+/// Turns a record payload in a custom wire format into display text.
+/// Implemented by plugins so proprietary formats can be decoded without
+/// changes to Kafkust itself.
+pub trait Deserializer: Send + Sync {
+    /// Name the plugin is selected by in topic preferences.
+    fn name(&self) -> &str;
+
+    fn deserialize(&self, topic: &str, payload: &[u8]) -> Result<String, String>;
+}
+
+/// A deserializer plugin found in the plugin directory.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeserializerPlugin {
+    pub name: String,
+    pub path: String,
+}
+
+/// A WebAssembly filter/transform module found in the plugin directory.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TransformPlugin {
+    pub name: String,
+    pub path: String,
+}
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+pub const PRODUCER_BYTE_RATE: &str = "producer_byte_rate";
+pub const CONSUMER_BYTE_RATE: &str = "consumer_byte_rate";
+
+/// Share of a byte-rate quota from which a client counts as near it.
+const NEAR_QUOTA: f64 = 0.8;
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct QuotaEntity {
+    /// `user`, `client-id` or `ip`.
+    pub entity_type: String,
+    /// `None` is the default entity of the type.
+    pub name: Option<String>,
+}
+
+/// Quota values set for one entity, as described by the brokers.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClientQuota {
+    pub entity: Vec<QuotaEntity>,
+    pub values: BTreeMap<String, f64>,
+}
+
+/// Throughput of the app's clients sharing a `client.id`. Quotas apply per
+/// broker, so the rates are those of the busiest broker.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClientThroughput {
+    pub client_id: String,
+    pub produce_bytes_per_sec: f64,
+    pub fetch_bytes_per_sec: f64,
+    /// Largest broker throttle time seen in the last sample.
+    pub throttle_time_ms: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaStatus {
+    /// No byte-rate quota applies and the brokers do not throttle.
+    Unlimited,
+    Ok,
+    Near,
+    Exceeding,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClientQuotaUsage {
+    pub client_id: String,
+    pub user: Option<String>,
+    pub produce_bytes_per_sec: f64,
+    pub fetch_bytes_per_sec: f64,
+    pub producer_byte_rate: Option<f64>,
+    pub consumer_byte_rate: Option<f64>,
+    pub throttle_time_ms: i64,
+    pub status: QuotaStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuotaUtilization {
+    pub cluster_id: Uuid,
+    pub sampled_at: i64,
+    pub quotas: Vec<ClientQuota>,
+    pub clients: Vec<ClientQuotaUsage>,
+}
+
+/// Value of `key` in force for a user and client id, following the brokers'
+/// precedence from the most specific user/client-id pair down to the default
+/// client id. An unknown user only matches default user quotas.
+pub fn effective_quota(
+    quotas: &[ClientQuota],
+    user: Option<&str>,
+    client_id: &str,
+    key: &str,
+) -> Option<f64> {
+    quotas
+        .iter()
+        .filter_map(|quota| {
+            let value = quota.values.get(key)?;
+            Some((precedence(quota, user, client_id)?, *value))
+        })
+        .min_by_key(|(rank, _)| *rank)
+        .map(|(_, value)| value)
+}
+
+/// Rank of a quota's entity for a user and client id, lowest first, or
+/// `None` when it does not apply to them.
+fn precedence(quota: &ClientQuota, user: Option<&str>, client_id: &str) -> Option<u8> {
+    enum Match {
+        Exact,
+        Default,
+        Absent,
+    }
+
+    let component = |entity_type: &str, name: Option<&str>| -> Option<Match> {
+        match quota.entity.iter().find(|e| e.entity_type == entity_type) {
+            None => Some(Match::Absent),
+            Some(QuotaEntity { name: None, .. }) => Some(Match::Default),
+            Some(QuotaEntity {
+                name: Some(entity), ..
+            }) => (name == Some(entity.as_str())).then_some(Match::Exact),
+        }
+    };
+
+    if quota
+        .entity
+        .iter()
+        .any(|e| e.entity_type != "user" && e.entity_type != "client-id")
+    {
+        return None;
+    }
+    let rank = match (
+        component("user", user)?,
+        component("client-id", Some(client_id))?,
+    ) {
+        (Match::Exact, Match::Exact) => 0,
+        (Match::Exact, Match::Default) => 1,
+        (Match::Exact, Match::Absent) => 2,
+        (Match::Default, Match::Exact) => 3,
+        (Match::Default, Match::Default) => 4,
+        (Match::Default, Match::Absent) => 5,
+        (Match::Absent, Match::Exact) => 6,
+        (Match::Absent, Match::Default) => 7,
+        (Match::Absent, Match::Absent) => return None,
+    };
+    Some(rank)
+}
+
+/// Compares a client's throughput with the byte-rate quotas in force for it.
+/// A client the brokers throttle counts as exceeding its quota.
+pub fn quota_usage(
+    quotas: &[ClientQuota],
+    user: Option<&str>,
+    throughput: &ClientThroughput,
+) -> ClientQuotaUsage {
+    let producer_byte_rate =
+        effective_quota(quotas, user, &throughput.client_id, PRODUCER_BYTE_RATE);
+    let consumer_byte_rate =
+        effective_quota(quotas, user, &throughput.client_id, CONSUMER_BYTE_RATE);
+
+    let utilization = [
+        (throughput.produce_bytes_per_sec, producer_byte_rate),
+        (throughput.fetch_bytes_per_sec, consumer_byte_rate),
+    ]
+    .iter()
+    .filter_map(|(rate, quota)| quota.map(|q| if q > 0.0 { rate / q } else { f64::INFINITY }))
+    .reduce(f64::max);
+
+    let status = match utilization {
+        _ if throughput.throttle_time_ms > 0 => QuotaStatus::Exceeding,
+        None => QuotaStatus::Unlimited,
+        Some(u) if u >= 1.0 => QuotaStatus::Exceeding,
+        Some(u) if u >= NEAR_QUOTA => QuotaStatus::Near,
+        Some(_) => QuotaStatus::Ok,
+    };
+
+    ClientQuotaUsage {
+        client_id: throughput.client_id.clone(),
+        user: user.map(str::to_string),
+        produce_bytes_per_sec: throughput.produce_bytes_per_sec,
+        fetch_bytes_per_sec: throughput.fetch_bytes_per_sec,
+        producer_byte_rate,
+        consumer_byte_rate,
+        throttle_time_ms: throughput.throttle_time_ms,
+        status,
+    }
+}
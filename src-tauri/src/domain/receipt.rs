@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Record of one message produced through Kafkust on a cluster with
+/// production receipts enabled. Receipts are signed and chained, so an edited,
+/// removed or reordered entry no longer verifies.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ProductionReceipt {
+    pub cluster_id: Uuid,
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    /// SHA-256 of the payload as submitted, before any encryption, in hex.
+    pub payload_sha256: String,
+    /// Operating system user that produced the message.
+    pub user: String,
+    pub timestamp: i64,
+    /// Signature of the receipt logged before this one; `None` for the first.
+    pub previous_signature: Option<String>,
+    /// HMAC-SHA256 of [`ProductionReceipt::signed_content`] in hex.
+    pub signature: String,
+}
+
+impl ProductionReceipt {
+    /// The fields covered by the signature, in a fixed order.
+    pub fn signed_content(&self) -> String {
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}",
+            self.cluster_id,
+            self.topic,
+            self.partition,
+            self.offset,
+            self.payload_sha256,
+            self.user,
+            self.timestamp,
+            self.previous_signature.as_deref().unwrap_or("")
+        )
+    }
+}
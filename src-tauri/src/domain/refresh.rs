@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// A read a refresh subscription re-runs on its interval.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "kind", content = "params")]
+pub enum RefreshTarget {
+    /// Per-partition lag of one consumer group.
+    GroupLag {
+        group_id: String,
+    },
+    TopicList,
+    /// Watermarks and replicas of each partition of a topic.
+    Watermarks {
+        topic: String,
+    },
+}
+
+impl RefreshTarget {
+    /// Topic shown for the subscription's session, if it has one.
+    pub fn topic(&self) -> &str {
+        match self {
+            RefreshTarget::Watermarks { topic } => topic,
+            _ => "",
+        }
+    }
+}
+
+/// One row of a refreshed result, e.g. a topic or a partition, identified by
+/// a key that is stable across refreshes.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct RefreshRow {
+    pub key: String,
+    pub value: serde_json::Value,
+}
+
+/// Emitted as `refresh-update` when a subscription's result changes. The
+/// first update of a subscription carries every row.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RefreshDiff {
+    pub subscription_id: Uuid,
+    pub target: RefreshTarget,
+    /// Rows that are new or whose value changed.
+    pub changed: Vec<RefreshRow>,
+    /// Keys of rows that disappeared.
+    pub removed: Vec<String>,
+}
+
+impl RefreshDiff {
+    pub fn between(
+        subscription_id: Uuid,
+        target: RefreshTarget,
+        previous: &BTreeMap<String, serde_json::Value>,
+        current: &BTreeMap<String, serde_json::Value>,
+    ) -> Self {
+        let changed = current
+            .iter()
+            .filter(|(key, value)| previous.get(*key) != Some(value))
+            .map(|(key, value)| RefreshRow {
+                key: key.clone(),
+                value: value.clone(),
+            })
+            .collect();
+        let removed = previous
+            .keys()
+            .filter(|key| !current.contains_key(*key))
+            .cloned()
+            .collect();
+        Self {
+            subscription_id,
+            target,
+            changed,
+            removed,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// How a replay keys the records it writes to the destination topic.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", content = "config")]
+pub enum RekeyStrategy {
+    /// Keep each record's original key.
+    Keep,
+    /// Use the value at a JSONPath into the payload, e.g. `$.customer.id`.
+    JsonPath(String),
+    /// Render a template whose `{...}` placeholders are JSONPaths into the
+    /// payload, e.g. `{$.tenant}:{$.order.id}`.
+    Template(String),
+}
+
+/// Emitted as `replay-finished` when a replay ends.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReplaySummary {
+    pub session_id: Uuid,
+    pub replayed: u64,
+    /// Records whose new key could not be computed, e.g. non-JSON payloads,
+    /// or whose transform failed.
+    pub skipped: u64,
+    /// Records dropped by a transform.
+    #[serde(default)]
+    pub filtered: u64,
+    /// False when the replay was stopped before reaching the end offsets
+    /// captured at start.
+    pub completed: bool,
+}
@@ -0,0 +1,146 @@
+use crate::domain::topic::KafkaMessage;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use uuid::Uuid;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SessionKind {
+    TestConsumer,
+    KeyWatch,
+    HealthMonitor,
+    Replay,
+    LagSampler,
+    LagAlert,
+    EosVerification,
+    Refresh,
+    Export,
+    WatermarkWatch,
+    Tail,
+    KeySearch,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    Running,
+    Paused,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionInfo {
+    pub id: Uuid,
+    pub kind: SessionKind,
+    pub cluster_id: Uuid,
+    pub topic: String,
+    pub partition: Option<i32>,
+    pub state: SessionState,
+    pub started_at: i64,
+    pub records_processed: u64,
+    /// Records a tail session discarded because its buffer was full.
+    #[serde(default)]
+    pub records_dropped: u64,
+}
+
+/// What a tail session does when its buffer of records not yet delivered to
+/// the UI is full.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BufferOverflow {
+    /// Discard the oldest buffered records to make room.
+    DropOldest,
+    /// Stop reading from the topic until the buffer has been delivered.
+    PauseUpstream,
+}
+
+/// Bounds on the records a tail session holds between deliveries to the UI.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+pub struct TailBufferLimits {
+    pub max_messages: usize,
+    pub max_bytes: usize,
+    pub overflow: BufferOverflow,
+}
+
+impl Default for TailBufferLimits {
+    fn default() -> Self {
+        Self {
+            max_messages: 1_000,
+            max_bytes: 8 * 1024 * 1024,
+            overflow: BufferOverflow::DropOldest,
+        }
+    }
+}
+
+/// Records waiting to be delivered to the UI, kept within `TailBufferLimits`.
+pub struct TailBuffer {
+    limits: TailBufferLimits,
+    messages: VecDeque<KafkaMessage>,
+    bytes: usize,
+}
+
+impl TailBuffer {
+    pub fn new(limits: TailBufferLimits) -> Self {
+        Self {
+            limits,
+            messages: VecDeque::new(),
+            bytes: 0,
+        }
+    }
+
+    /// Whether the session should stop reading until the next delivery.
+    pub fn is_full(&self) -> bool {
+        self.limits.overflow == BufferOverflow::PauseUpstream
+            && (self.messages.len() >= self.limits.max_messages
+                || self.bytes >= self.limits.max_bytes)
+    }
+
+    /// Buffers a record and returns how many older records were dropped to
+    /// fit it. The newest record is always kept.
+    pub fn push(&mut self, message: KafkaMessage) -> u64 {
+        self.bytes += message_size(&message);
+        self.messages.push_back(message);
+        let mut dropped = 0;
+        if self.limits.overflow == BufferOverflow::DropOldest {
+            while self.messages.len() > 1
+                && (self.messages.len() > self.limits.max_messages
+                    || self.bytes > self.limits.max_bytes)
+            {
+                if let Some(oldest) = self.messages.pop_front() {
+                    self.bytes -= message_size(&oldest);
+                    dropped += 1;
+                }
+            }
+        }
+        dropped
+    }
+
+    pub fn drain(&mut self) -> impl Iterator<Item = KafkaMessage> + '_ {
+        self.bytes = 0;
+        self.messages.drain(..)
+    }
+}
+
+fn message_size(message: &KafkaMessage) -> usize {
+    message.key.as_ref().map_or(0, String::len) + message.payload.as_ref().map_or(0, String::len)
+}
+
+/// A record delivered to the UI by a live session.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionMessage {
+    pub session_id: Uuid,
+    pub message: KafkaMessage,
+}
+
+/// Where a freshly created consumer group starts reading when it has no
+/// committed offsets yet (`auto.offset.reset`).
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum AutoOffsetReset {
+    Earliest,
+    Latest,
+}
+
+impl AutoOffsetReset {
+    pub fn as_config_value(&self) -> &'static str {
+        match self {
+            AutoOffsetReset::Earliest => "earliest",
+            AutoOffsetReset::Latest => "latest",
+        }
+    }
+}
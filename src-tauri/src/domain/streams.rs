@@ -0,0 +1,107 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum StreamsTopicRole {
+    Changelog,
+    Repartition,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreamsInternalTopic {
+    pub name: String,
+    pub role: StreamsTopicRole,
+    /// Store or processor name between the application id and the suffix.
+    pub store_name: String,
+    pub partitions: i32,
+    pub compacted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StreamsApplication {
+    pub application_id: String,
+    pub topics: Vec<StreamsInternalTopic>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StateStoreEntry {
+    pub key: Option<String>,
+    pub value: Option<String>,
+    pub partition: i32,
+    pub offset: i64,
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StateStoreSnapshot {
+    pub topic: String,
+    pub entries: Vec<StateStoreEntry>,
+    pub records_scanned: usize,
+    /// Keys whose latest record was a tombstone.
+    pub deleted_keys: usize,
+    /// False when the scan stopped before reaching the end of every partition.
+    pub complete: bool,
+}
+
+/// One page of the latest record per key of a compacted topic, ordered by
+/// key.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KeyedTablePage {
+    pub topic: String,
+    pub entries: Vec<StateStoreEntry>,
+    /// Index of the first entry within all matching keys.
+    pub offset: usize,
+    /// Keys matching the filter, across all pages.
+    pub total_keys: usize,
+    pub records_scanned: usize,
+    /// Keys whose latest record was a tombstone.
+    pub deleted_keys: usize,
+    /// False when the scan stopped before reaching the end of every partition.
+    pub complete: bool,
+}
+
+/// Splits a Kafka Streams internal topic name into application id, store or
+/// processor name and role.
+///
+/// Streams names its internal topics `<application.id>-<name>-changelog` and
+/// `<application.id>-<name>-repartition`. Because application ids may contain
+/// dashes, known ids (the consumer group ids of Streams apps) are tried first;
+/// otherwise generated `KSTREAM-`/`KTABLE-` processor names mark the boundary,
+/// and as a last resort the first dash does.
+pub fn classify_internal_topic(
+    name: &str,
+    known_application_ids: &[String],
+) -> Option<(String, String, StreamsTopicRole)> {
+    let (stem, role) = if let Some(stem) = name.strip_suffix("-changelog") {
+        (stem, StreamsTopicRole::Changelog)
+    } else if let Some(stem) = name.strip_suffix("-repartition") {
+        (stem, StreamsTopicRole::Repartition)
+    } else {
+        return None;
+    };
+
+    let known = known_application_ids
+        .iter()
+        .filter(|id| {
+            stem.len() > id.len() + 1
+                && stem.starts_with(id.as_str())
+                && stem.as_bytes()[id.len()] == b'-'
+        })
+        .max_by_key(|id| id.len());
+    if let Some(id) = known {
+        return Some((id.clone(), stem[id.len() + 1..].to_string(), role));
+    }
+
+    for marker in ["-KSTREAM-", "-KTABLE-"] {
+        if let Some(pos) = stem.find(marker) {
+            if pos > 0 {
+                return Some((stem[..pos].to_string(), stem[pos + 1..].to_string(), role));
+            }
+        }
+    }
+
+    let (app, store) = stem.split_once('-')?;
+    if app.is_empty() || store.is_empty() {
+        return None;
+    }
+    Some((app.to_string(), store.to_string(), role))
+}
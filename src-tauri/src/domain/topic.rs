@@ -1,19 +1,163 @@
+use crate::domain::mirroring::classify_mm2_topic;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use uuid::Uuid;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Topic {
     pub name: String,
     pub partitions: i32,
+    /// Largest replica count among the topic's partitions.
     pub replication_factor: i32,
+    #[serde(default)]
+    pub partition_ids: Vec<i32>,
+    /// Records between the watermarks of all partitions; compaction and
+    /// transaction markers make it an estimate. Only filled when the listing
+    /// asks for activity.
+    #[serde(default)]
+    pub message_count_estimate: Option<i64>,
+    /// Timestamp of the newest record, `None` when the topic is empty or the
+    /// brokers cannot report it.
+    #[serde(default)]
+    pub last_activity_ts: Option<i64>,
+    /// Maintained by Kafka or a platform component rather than holding
+    /// application data.
+    #[serde(default)]
+    pub is_internal: bool,
+    #[serde(default)]
+    pub internal_kind: Option<InternalTopicKind>,
+    /// Local favorite flag, notes and tags, when any are set.
+    #[serde(default)]
+    pub annotation: Option<TopicAnnotation>,
+    /// Marked locally as protected: deleting the topic or its records needs
+    /// an explicit override.
+    #[serde(default)]
+    pub protected: bool,
+    /// When the cluster was unreachable and this entry comes from the last
+    /// successful listing, the time that listing was taken.
+    #[serde(default)]
+    pub cached_at: Option<i64>,
+}
+
+/// Favorite flag, notes and tags kept locally for a topic.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TopicAnnotation {
+    pub topic: String,
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl TopicAnnotation {
+    /// Trimmed notes and tags, with tags deduplicated and sorted.
+    pub fn normalized(mut self) -> Self {
+        self.notes = self.notes.trim().to_string();
+        let tags: BTreeSet<String> = self
+            .tags
+            .iter()
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .map(str::to_string)
+            .collect();
+        self.tags = tags.into_iter().collect();
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.favorite && self.notes.is_empty() && self.tags.is_empty()
+    }
+}
+
+/// Component that owns an internal topic.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum InternalTopicKind {
+    /// Broker topics such as `__consumer_offsets` and `__transaction_state`.
+    Kafka,
+    Connect,
+    SchemaRegistry,
+    Ksql,
+    MirrorMaker,
+    /// Other Confluent Platform topics (`_confluent-*`).
+    Confluent,
+}
+
+/// Recognizes internal topics by the names their components use by default.
+pub fn internal_topic_kind(name: &str) -> Option<InternalTopicKind> {
+    if name.starts_with("__") {
+        Some(InternalTopicKind::Kafka)
+    } else if name == "_schemas" {
+        Some(InternalTopicKind::SchemaRegistry)
+    } else if name.starts_with("_confluent-ksql-") || name.starts_with("_confluent_ksql") {
+        Some(InternalTopicKind::Ksql)
+    } else if name.starts_with("_confluent") {
+        Some(InternalTopicKind::Confluent)
+    } else if name.contains("connect")
+        && ["-configs", "-offsets", "-status"]
+            .iter()
+            .any(|suffix| name.ends_with(suffix))
+    {
+        Some(InternalTopicKind::Connect)
+    } else if classify_mm2_topic(name).is_some() {
+        Some(InternalTopicKind::MirrorMaker)
+    } else {
+        None
+    }
 }
 
-#[allow(dead_code)]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Partition {
     pub id: i32,
     pub leader: i32,
     pub replicas: Vec<i32>,
     pub isrs: Vec<i32>,
+    /// Only fetched when describing a single topic's partitions.
+    #[serde(default)]
+    pub low_watermark: Option<i64>,
+    #[serde(default)]
+    pub high_watermark: Option<i64>,
+}
+
+/// Where browsing a topic starts reading.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(tag = "mode", content = "value")]
+pub enum SeekMode {
+    /// The most recent records, spread evenly over the partitions.
+    #[default]
+    Latest,
+    /// The oldest records still retained.
+    Earliest,
+    /// Absolute offsets by partition; partitions left out are not read.
+    Offsets(BTreeMap<i32, i64>),
+    /// The first record of each partition at or after a timestamp, in
+    /// milliseconds since the epoch.
+    Timestamp(i64),
+    /// The records just before these offsets by partition, i.e. the page
+    /// older than one whose `ConsumeResult::cursor` this is.
+    Before(BTreeMap<i32, i64>),
+}
+
+/// Watermarks of one partition as seen by a watermark watch.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PartitionWatermark {
+    pub partition: i32,
+    pub low: i64,
+    pub high: i64,
+    /// Records appended since the previous update; zero on the first.
+    pub appended: i64,
+}
+
+/// Emitted as `watermark-update` whenever a watched topic's watermarks
+/// move. The first update of a watch carries the starting watermarks.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WatermarkUpdate {
+    pub session_id: Uuid,
+    pub topic: String,
+    pub timestamp: i64,
+    pub partitions: Vec<PartitionWatermark>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -23,4 +167,836 @@ pub struct KafkaMessage {
     pub timestamp: Option<i64>,
     pub key: Option<String>,
     pub payload: Option<String>,
+    #[serde(default)]
+    pub headers: Vec<MessageHeader>,
+    /// Full payload length in bytes when `payload` was cut short for the
+    /// message browser.
+    #[serde(default)]
+    pub truncated_from: Option<usize>,
+}
+
+/// Payload bytes the message browser receives per record unless the topic's
+/// preferences say otherwise.
+pub const DEFAULT_MAX_PAYLOAD_BYTES: usize = 256 * 1024;
+
+impl KafkaMessage {
+    /// Cuts the payload to at most `max_bytes`, backing off to a character
+    /// boundary.
+    pub fn truncate_payload(&mut self, max_bytes: usize) {
+        let Some(payload) = self.payload.as_mut() else {
+            return;
+        };
+        if payload.len() <= max_bytes {
+            return;
+        }
+        let mut end = max_bytes;
+        while !payload.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.truncated_from = Some(payload.len());
+        payload.truncate(end);
+    }
+}
+
+/// A record header. `value` is the bytes read as lossy UTF-8, so binary
+/// values also come with their exact bytes in `value_base64`. When
+/// producing, `value_base64` wins over `value` if both are given.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MessageHeader {
+    pub key: String,
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default)]
+    pub value_base64: Option<String>,
+}
+
+/// Where a published record landed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PublishResult {
+    pub partition: i32,
+    pub offset: i64,
+    pub timestamp: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum FetchErrorKind {
+    /// Missing ACLs or failed authentication; retrying will not help.
+    Authorization,
+    PartitionEof,
+    UnknownTopicOrPartition,
+    /// Broker unavailable, leader moving or timeouts, usually short-lived.
+    Transient,
+    Other,
+}
+
+/// Errors of one kind seen while fetching, collapsed into one entry.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FetchError {
+    pub kind: FetchErrorKind,
+    /// Set when the error names a partition.
+    pub partition: Option<i32>,
+    pub message: String,
+    pub count: usize,
+}
+
+/// Records fetched by a consume, with the errors hit along the way. When
+/// `errors` is non-empty the records may be incomplete.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConsumeResult {
+    pub messages: Vec<KafkaMessage>,
+    pub errors: Vec<FetchError>,
+    /// Records dropped by the topic's de-duplication stage.
+    #[serde(default)]
+    pub duplicates_removed: usize,
+    /// Earliest offset read per partition, or the page's end offset for a
+    /// partition nothing was read from. Seeking `SeekMode::Before` it returns
+    /// the next older page.
+    #[serde(default)]
+    pub cursor: BTreeMap<i32, i64>,
+}
+
+/// Outcome of raising a topic's partition count.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PartitionIncrease {
+    pub topic: String,
+    pub previous_partitions: i32,
+    pub partitions: i32,
+    /// Recent records carry keys. Keys now hash to different partitions, so
+    /// per-key ordering does not hold across the change.
+    pub keyed_data: bool,
+}
+
+/// Start of a partition's log after DeleteRecords removed the records
+/// below it.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PartitionTruncation {
+    pub partition: i32,
+    pub low_watermark: i64,
+}
+
+/// A record fetched as part of a multi-cluster consume, tagged with its origin.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClusterMessage {
+    pub cluster_id: Uuid,
+    #[serde(flatten)]
+    pub message: KafkaMessage,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClusterDivergence {
+    pub cluster_id: Uuid,
+    pub fetched: usize,
+    /// Fetched records within the compared time range.
+    pub compared: usize,
+    /// Compared records (by key and payload) missing from at least one other
+    /// cluster.
+    pub unmatched: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MultiClusterConsumeResult {
+    pub messages: Vec<ClusterMessage>,
+    pub matched: usize,
+    pub divergence: Vec<ClusterDivergence>,
+    /// Time range, in milliseconds since the epoch, every cluster was read
+    /// whole over and records are compared in. `None` when no cluster
+    /// returned a timestamped record.
+    pub compared_from: Option<i64>,
+    pub compared_until: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KeyTimeline {
+    pub key: String,
+    pub partition: i32,
+    /// Newest matching records, oldest first.
+    pub records: Vec<KafkaMessage>,
+    /// Records with the key found in the partition, including any beyond the
+    /// limit.
+    pub matched: usize,
+    pub records_scanned: usize,
+    /// False when the scan stopped before reaching the end of the partition.
+    pub complete: bool,
+}
+
+/// Emitted as `key-search-progress` while a key search runs, and once more
+/// with `finished` set when it ends. Matches arrive as `key-search-match`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct KeySearchProgress {
+    pub session_id: Uuid,
+    pub key: String,
+    /// Partitions being scanned; just the key's own partition when the
+    /// search relies on the default partitioner.
+    pub partitions: Vec<i32>,
+    pub records_scanned: u64,
+    pub matches: u64,
+    /// Share of the offsets present at the start that has been scanned,
+    /// from 0 to 100.
+    pub percent: f64,
+    pub finished: bool,
+    /// False when the search was cancelled before reaching the end.
+    pub complete: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HistogramBucket {
+    pub start: i64,
+    pub end: i64,
+    pub count: i64,
+}
+
+/// What a topic sample is grouped by in a field breakdown.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", content = "config")]
+pub enum BreakdownSource {
+    Header { name: String },
+    JsonField { path: String },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FieldValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FieldBreakdown {
+    pub sampled: usize,
+    /// Sampled records without the header or field.
+    pub missing: usize,
+    pub values: Vec<FieldValueCount>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigEntrySource {
+    Unknown,
+    DynamicTopic,
+    DynamicBroker,
+    DynamicDefaultBroker,
+    StaticBroker,
+    Default,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicConfigEntry {
+    pub name: String,
+    pub value: Option<String>,
+    pub source: ConfigEntrySource,
+    pub is_read_only: bool,
+    pub is_default: bool,
+    pub is_sensitive: bool,
+    /// Set when served from the last successful fetch because the cluster
+    /// was unreachable.
+    #[serde(default)]
+    pub cached_at: Option<i64>,
+}
+
+/// One environment (column) of a drift matrix.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DriftCluster {
+    pub cluster_id: Uuid,
+    pub cluster_name: String,
+    pub topic_exists: bool,
+}
+
+/// One compared property (row) of a drift matrix, with a value per cluster
+/// in the order of `EnvironmentDrift::clusters`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DriftRow {
+    pub property: String,
+    pub values: Vec<Option<String>>,
+    pub drifted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EnvironmentDrift {
+    pub topic: String,
+    pub clusters: Vec<DriftCluster>,
+    pub rows: Vec<DriftRow>,
+    pub drifted: bool,
+}
+
+/// A config entry whose effective value differs between two clusters;
+/// `None` where the topic is missing or the value is not reported.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConfigEntryDiff {
+    pub name: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+/// Differences in one topic's layout and configuration between two
+/// clusters.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicConfigDiff {
+    pub topic: String,
+    pub left: DriftCluster,
+    pub right: DriftCluster,
+    pub left_partitions: Option<i32>,
+    pub right_partitions: Option<i32>,
+    pub left_replication_factor: Option<i32>,
+    pub right_replication_factor: Option<i32>,
+    /// Only the differing entries, by name.
+    pub configs: Vec<ConfigEntryDiff>,
+    pub identical: bool,
+}
+
+/// Entries of two config listings whose values differ, by name. Sensitive
+/// entries are skipped since brokers do not report their values.
+pub fn diff_config_entries(
+    left: &[TopicConfigEntry],
+    right: &[TopicConfigEntry],
+) -> Vec<ConfigEntryDiff> {
+    let values = |entries: &[TopicConfigEntry]| -> BTreeMap<String, Option<String>> {
+        entries
+            .iter()
+            .filter(|e| !e.is_sensitive)
+            .map(|e| (e.name.clone(), e.value.clone()))
+            .collect()
+    };
+    let mut left = values(left);
+    let mut right = values(right);
+    let names: BTreeSet<String> = left.keys().chain(right.keys()).cloned().collect();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let left = left.remove(&name).flatten();
+            let right = right.remove(&name).flatten();
+            (left != right).then_some(ConfigEntryDiff { name, left, right })
+        })
+        .collect()
+}
+
+/// Topics picked for bulk deletion: those whose whole name matches
+/// `pattern`, a regular expression, plus any listed explicitly. Internal
+/// topics are only picked when listed.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicSelection {
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub topics: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicDeletion {
+    pub topic: String,
+    pub message_count: i64,
+    /// Why deleting the topic failed.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BulkTopicDeletion {
+    pub topics: Vec<TopicDeletion>,
+    /// Listed topics the cluster does not have.
+    pub not_found: Vec<String>,
+    pub total_messages: i64,
+    /// Returned by the dry run and required to delete, so nothing is deleted
+    /// without first seeing what a selection matches. It changes whenever
+    /// the selection matches a different set of topics.
+    pub confirmation: String,
+    /// False for a dry run.
+    pub deleted: bool,
+}
+
+/// Longest topic name Kafka accepts.
+pub const MAX_TOPIC_NAME_LENGTH: usize = 249;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TopicField {
+    Name,
+    Partitions,
+    ReplicationFactor,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct TopicValidationIssue {
+    pub field: TopicField,
+    pub message: String,
+}
+
+/// Problems with a topic about to be created. Errors block creation;
+/// warnings do not.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TopicValidation {
+    pub errors: Vec<TopicValidationIssue>,
+    pub warnings: Vec<TopicValidationIssue>,
+}
+
+impl TopicValidation {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl std::fmt::Display for TopicValidation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages: Vec<&str> = self.errors.iter().map(|e| e.message.as_str()).collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for TopicValidation {}
+
+/// Checks a new topic against Kafka's naming rules, the topics the cluster
+/// already has and its live broker count. `-1` partitions or replication
+/// factor means the broker default.
+pub fn validate_new_topic(
+    name: &str,
+    partitions: i32,
+    replication: i32,
+    existing: &[String],
+    broker_count: usize,
+) -> TopicValidation {
+    let issue = |field, message: String| TopicValidationIssue { field, message };
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    if name.is_empty() {
+        errors.push(issue(
+            TopicField::Name,
+            "Topic name must not be empty".into(),
+        ));
+    } else if name == "." || name == ".." {
+        errors.push(issue(
+            TopicField::Name,
+            format!("Topic name cannot be '{}'", name),
+        ));
+    }
+    if name.len() > MAX_TOPIC_NAME_LENGTH {
+        errors.push(issue(
+            TopicField::Name,
+            format!(
+                "Topic name is {} characters long, at most {} are allowed",
+                name.len(),
+                MAX_TOPIC_NAME_LENGTH
+            ),
+        ));
+    }
+    let mut invalid: Vec<String> = Vec::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+            continue;
+        }
+        let quoted = format!("'{}'", c);
+        if !invalid.contains(&quoted) {
+            invalid.push(quoted);
+        }
+    }
+    if !invalid.is_empty() {
+        errors.push(issue(
+            TopicField::Name,
+            format!(
+                "Topic name contains {}; only ASCII letters, digits, '.', '_' and '-' are allowed",
+                invalid.join(", ")
+            ),
+        ));
+    }
+
+    // Kafka maps '.' to '_' in metric names, so topics differing only there
+    // cannot coexist.
+    let metric_name = name.replace('.', "_");
+    if existing.iter().any(|t| t == name) {
+        errors.push(issue(
+            TopicField::Name,
+            format!("Topic {} already exists", name),
+        ));
+    } else if let Some(other) = existing.iter().find(|t| t.replace('.', "_") == metric_name) {
+        errors.push(issue(
+            TopicField::Name,
+            format!(
+                "Topic name collides with {}; '.' and '_' are the same in metric names",
+                other
+            ),
+        ));
+    } else if name.contains('.') || name.contains('_') {
+        warnings.push(issue(
+            TopicField::Name,
+            "Topic names with '.' or '_' can collide in metric names; use one of them consistently"
+                .into(),
+        ));
+    }
+
+    if partitions == 0 || partitions < -1 {
+        errors.push(issue(
+            TopicField::Partitions,
+            "Partition count must be positive, or -1 for the broker default".into(),
+        ));
+    }
+    if replication == 0 || replication < -1 {
+        errors.push(issue(
+            TopicField::ReplicationFactor,
+            "Replication factor must be positive, or -1 for the broker default".into(),
+        ));
+    } else if replication > 0 && replication as usize > broker_count {
+        errors.push(issue(
+            TopicField::ReplicationFactor,
+            format!(
+                "Replication factor {} exceeds the {} live brokers",
+                replication, broker_count
+            ),
+        ));
+    }
+
+    TopicValidation { errors, warnings }
+}
+
+/// Confirmation token of a bulk deletion of exactly `topics`, in any order.
+pub fn deletion_token(topics: &[String]) -> String {
+    let mut sorted: Vec<&str> = topics.iter().map(String::as_str).collect();
+    sorted.sort_unstable();
+    let digest = Sha256::digest(sorted.join("\n").as_bytes());
+    digest[..8].iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Declarative topic file, YAML or JSON, listing the topics a cluster
+/// should have.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicSpecDocument {
+    pub topics: Vec<TopicSpec>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicSpec {
+    pub name: String,
+    pub partitions: i32,
+    /// Broker default when unset.
+    #[serde(default)]
+    pub replication_factor: Option<i32>,
+    #[serde(default)]
+    pub configs: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum TopicSpecAction {
+    Create,
+    Update,
+    Unchanged,
+}
+
+/// What applying a spec does to one topic, and how it went.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicSpecChange {
+    pub topic: String,
+    pub action: TopicSpecAction,
+    /// `None` when the topic does not exist yet.
+    pub current_partitions: Option<i32>,
+    pub partitions: i32,
+    /// Configs to set, with the current value on the left.
+    pub configs: Vec<ConfigEntryDiff>,
+    /// Differences the spec cannot apply, e.g. fewer partitions.
+    pub problems: Vec<String>,
+    /// Why applying the change failed.
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicSpecPlan {
+    pub changes: Vec<TopicSpecChange>,
+    /// False for a dry run.
+    pub applied: bool,
+}
+
+/// Line format accepted by bulk publishing.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BulkTextFormat {
+    /// One JSON document per line, produced as the payload without a key.
+    JsonLines,
+    /// `key<TAB>value` per line; an empty key produces a keyless record.
+    KeyTab,
+}
+
+#[derive(Debug, Clone)]
+pub struct BulkRecord {
+    /// 1-based line number in the pasted text.
+    pub line: usize,
+    pub key: Option<String>,
+    pub payload: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BulkLineError {
+    pub line: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BulkPublishResult {
+    pub produced: usize,
+    pub errors: Vec<BulkLineError>,
+}
+
+/// Splits pasted text into records, skipping blank lines and collecting a
+/// validation error for every malformed one.
+pub fn parse_bulk_text(
+    text: &str,
+    format: BulkTextFormat,
+) -> (Vec<BulkRecord>, Vec<BulkLineError>) {
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+
+    for (index, content) in text.lines().enumerate() {
+        let line = index + 1;
+        if content.trim().is_empty() {
+            continue;
+        }
+
+        let parsed = match format {
+            BulkTextFormat::JsonLines => serde_json::from_str::<serde_json::Value>(content)
+                .map(|_| (None, content.trim().to_string()))
+                .map_err(|e| format!("Invalid JSON: {}", e)),
+            BulkTextFormat::KeyTab => match content.split_once('\t') {
+                Some((key, value)) => Ok((
+                    Some(key.to_string()).filter(|k| !k.is_empty()),
+                    value.to_string(),
+                )),
+                None => Err("Expected key<TAB>value".to_string()),
+            },
+        };
+
+        match parsed {
+            Ok((key, payload)) => records.push(BulkRecord { line, key, payload }),
+            Err(message) => errors.push(BulkLineError { line, message }),
+        }
+    }
+
+    (records, errors)
+}
+
+/// Application-level payload encryption configured for a topic. Payloads are
+/// AES-256-GCM with the 12-byte nonce prepended; the key lives in the
+/// keyring.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicEncryption {
+    pub topic: String,
+    /// Encrypt payloads published from kafkust, not just decrypt on read.
+    pub encrypt_on_publish: bool,
+}
+
+/// Text encoding wrapped around a topic's payloads, undone before display.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PayloadEncoding {
+    /// Unwrap base64 or URL encoding only when the result is JSON.
+    #[default]
+    Auto,
+    /// Show payloads as stored.
+    Plain,
+    Base64,
+    UrlEncoded,
+}
+
+/// Where the message browser starts reading a topic.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(tag = "type", content = "value")]
+pub enum ConsumeStartPosition {
+    Latest,
+    Earliest,
+    Offset(i64),
+    /// Milliseconds since the epoch.
+    Timestamp(i64),
+}
+
+/// How the message browser renders keys and payloads.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum MessageDeserializer {
+    Auto,
+    String,
+    Json,
+    Hex,
+    /// A deserializer plugin, by name. Payloads are decoded by the plugin
+    /// before they reach the browser.
+    Plugin(String),
+}
+
+/// Consumption setup restored when a topic is reopened.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicPreferences {
+    pub start_position: ConsumeStartPosition,
+    pub limit: usize,
+    pub deserializer: MessageDeserializer,
+    /// Filter expressions as entered in the message browser.
+    pub filters: Vec<String>,
+    /// WebAssembly transforms applied to payloads, in order.
+    #[serde(default)]
+    pub transforms: Vec<String>,
+    #[serde(default)]
+    pub dedupe: Option<DedupeSettings>,
+    /// Payload bytes returned per record; longer payloads are truncated
+    /// and fetched whole on demand. Defaults to `DEFAULT_MAX_PAYLOAD_BYTES`.
+    #[serde(default)]
+    pub max_payload_bytes: Option<usize>,
+}
+
+impl Default for TopicPreferences {
+    fn default() -> Self {
+        Self {
+            start_position: ConsumeStartPosition::Latest,
+            limit: 50,
+            deserializer: MessageDeserializer::Auto,
+            filters: Vec::new(),
+            transforms: Vec::new(),
+            dedupe: None,
+            max_payload_bytes: None,
+        }
+    }
+}
+
+/// What makes two fetched records duplicates of each other.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", content = "config")]
+pub enum DedupeKey {
+    RecordKey,
+    /// An idempotency header set by the producer.
+    Header {
+        name: String,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum DedupeKeep {
+    First,
+    Last,
+}
+
+/// Conditions a record must meet to be returned by the message browser,
+/// checked while consuming so non-matching records never leave the backend.
+/// Every condition given must hold; payload conditions see the decoded
+/// payload.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MessageFilter {
+    /// Substring the payload contains.
+    #[serde(default)]
+    pub contains: Option<String>,
+    /// Regular expression the payload matches.
+    #[serde(default)]
+    pub pattern: Option<String>,
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub header: Option<HeaderMatch>,
+}
+
+/// A header a record must carry; without `value` any value matches.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct HeaderMatch {
+    pub key: String,
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+impl MessageFilter {
+    pub fn compile(self) -> Result<MessageMatcher, String> {
+        let pattern = self
+            .pattern
+            .as_deref()
+            .filter(|p| !p.is_empty())
+            .map(regex::Regex::new)
+            .transpose()
+            .map_err(|e| format!("Invalid payload pattern: {}", e))?;
+        Ok(MessageMatcher {
+            filter: self,
+            pattern,
+        })
+    }
+}
+
+/// A `MessageFilter` ready to test records against.
+#[derive(Debug, Clone)]
+pub struct MessageMatcher {
+    filter: MessageFilter,
+    pattern: Option<regex::Regex>,
+}
+
+impl MessageMatcher {
+    /// True when the filter sets no condition, so every record matches.
+    pub fn is_empty(&self) -> bool {
+        self.filter
+            .contains
+            .as_deref()
+            .unwrap_or_default()
+            .is_empty()
+            && self.pattern.is_none()
+            && self.filter.key.is_none()
+            && self.filter.header.is_none()
+    }
+
+    pub fn matches(&self, message: &KafkaMessage) -> bool {
+        let payload = message.payload.as_deref();
+        if let Some(contains) = self.filter.contains.as_deref().filter(|c| !c.is_empty()) {
+            if !payload.is_some_and(|p| p.contains(contains)) {
+                return false;
+            }
+        }
+        if let Some(pattern) = &self.pattern {
+            if !payload.is_some_and(|p| pattern.is_match(p)) {
+                return false;
+            }
+        }
+        if let Some(key) = &self.filter.key {
+            if message.key.as_ref() != Some(key) {
+                return false;
+            }
+        }
+        if let Some(wanted) = &self.filter.header {
+            let found = message.headers.iter().any(|h| {
+                h.key == wanted.key && (wanted.value.is_none() || h.value == wanted.value)
+            });
+            if !found {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Consume stage that collapses redelivered records within the fetched
+/// window, for topics written with at-least-once delivery.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DedupeSettings {
+    pub by: DedupeKey,
+    pub keep: DedupeKeep,
+}
+
+/// Keeps one record per identity, the earliest or latest by timestamp (then
+/// partition and offset). Records without an identity are always kept.
+/// Returns the kept records, in their original order, and how many were
+/// dropped.
+pub fn dedupe_messages(
+    messages: Vec<(Option<String>, KafkaMessage)>,
+    keep: DedupeKeep,
+) -> (Vec<KafkaMessage>, usize) {
+    let position = |m: &KafkaMessage| (m.timestamp.unwrap_or(i64::MIN), m.partition, m.offset);
+
+    let mut winners: HashMap<&str, usize> = HashMap::new();
+    for (index, (identity, message)) in messages.iter().enumerate() {
+        let Some(identity) = identity else {
+            continue;
+        };
+        winners
+            .entry(identity)
+            .and_modify(|winner| {
+                let current = position(&messages[*winner].1);
+                let replaces = match keep {
+                    DedupeKeep::First => position(message) < current,
+                    DedupeKeep::Last => position(message) > current,
+                };
+                if replaces {
+                    *winner = index;
+                }
+            })
+            .or_insert(index);
+    }
+
+    let kept: HashSet<usize> = winners.into_values().collect();
+    let total = messages.len();
+    let messages: Vec<KafkaMessage> = messages
+        .into_iter()
+        .enumerate()
+        .filter(|(index, (identity, _))| identity.is_none() || kept.contains(index))
+        .map(|(_, (_, message))| message)
+        .collect();
+    let removed = total - messages.len();
+    (messages, removed)
 }
@@ -0,0 +1,28 @@
+use crate::domain::command_macro::CommandMacro;
+use crate::domain::topic::TopicPreferences;
+use serde::{Deserialize, Serialize};
+
+/// Shape of an exported workspace file: the macros and per-topic views a
+/// team shares as a standard debugging setup.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkspaceBundle {
+    pub exported_at: i64,
+    #[serde(default)]
+    pub macros: Vec<CommandMacro>,
+    #[serde(default)]
+    pub views: Vec<TopicView>,
+}
+
+/// A topic's saved consumption setup: start position, limit, deserializer,
+/// filters and transforms.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopicView {
+    pub topic: String,
+    pub preferences: TopicPreferences,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkspaceImportResult {
+    pub macros: usize,
+    pub views: usize,
+}
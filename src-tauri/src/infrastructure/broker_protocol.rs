@@ -0,0 +1,511 @@
+//! Requests librdkafka has no API for, sent over a connection of our own to
+//! one of the cluster's bootstrap brokers. The connection follows the
+//! security settings of a client config from `create_config`: TLS through
+//! rustls and SASL with PLAIN, SCRAM-SHA-256/512 or OAUTHBEARER. GSSAPI and
+//! encrypted client keys are not supported and fail before connecting.
+//!
+//! Requests use the non-flexible versions of their APIs, so every message is
+//! an int32 size followed by a v1 request header or a v0 response header.
+
+use crate::domain::quota::{ClientQuota, QuotaEntity};
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::digest::core_api::BlockSizeUser;
+use hmac::{Mac, SimpleHmac};
+use rdkafka::config::ClientConfig;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{self, RootCertStore};
+use tokio_rustls::TlsConnector;
+use uuid::Uuid;
+
+use super::oauth_tokens;
+
+const SASL_HANDSHAKE: i16 = 17;
+const SASL_AUTHENTICATE: i16 = 36;
+const DESCRIBE_CLIENT_QUOTAS: i16 = 48;
+
+/// Time allowed for connecting, authenticating and one request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Responses larger than this are refused rather than buffered.
+const MAX_RESPONSE_BYTES: usize = 64 * 1024 * 1024;
+
+const SASL_MECHANISMS: [&str; 4] = ["PLAIN", "SCRAM-SHA-256", "SCRAM-SHA-512", "OAUTHBEARER"];
+
+/// Every client quota set on the cluster. Needs Kafka 2.6 or newer and
+/// DESCRIBE_CONFIGS on the cluster.
+pub async fn describe_client_quotas(config: &ClientConfig) -> Result<Vec<ClientQuota>> {
+    tokio::time::timeout(REQUEST_TIMEOUT, async {
+        let mut connection = BrokerConnection::open(config).await?;
+
+        let mut request = ProtocolWriter::default();
+        // No filter components, not strict: all entities.
+        request.i32(0);
+        request.i8(0);
+        let response = connection
+            .request(DESCRIBE_CLIENT_QUOTAS, 0, request.buf)
+            .await
+            .context("DescribeClientQuotas requires Kafka 2.6 or newer")?;
+
+        let mut reader = ProtocolReader { buf: &response };
+        reader.i32()?; // throttle_time_ms
+        let error_code = reader.i16()?;
+        let error_message = reader.nullable_string()?;
+        if error_code != 0 {
+            return Err(anyhow!(
+                "DescribeClientQuotas failed with error {}: {}",
+                error_code,
+                error_message.unwrap_or_else(|| error_name(error_code).to_string())
+            ));
+        }
+
+        let mut quotas = Vec::new();
+        for _ in 0..reader.count()? {
+            let entity = (0..reader.count()?)
+                .map(|_| {
+                    Ok(QuotaEntity {
+                        entity_type: reader.string()?,
+                        name: reader.nullable_string()?,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let values = (0..reader.count()?)
+                .map(|_| Ok((reader.string()?, reader.f64()?)))
+                .collect::<Result<BTreeMap<_, _>>>()?;
+            quotas.push(ClientQuota { entity, values });
+        }
+        Ok(quotas)
+    })
+    .await
+    .map_err(|_| anyhow!("Timed out describing client quotas"))?
+}
+
+fn error_name(code: i16) -> &'static str {
+    match code {
+        31 => "cluster authorization failed",
+        35 => "unsupported version",
+        42 => "invalid request",
+        _ => "unknown error",
+    }
+}
+
+trait BrokerStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> BrokerStream for T {}
+
+struct BrokerConnection {
+    stream: Box<dyn BrokerStream>,
+    client_id: String,
+    correlation_id: i32,
+}
+
+impl BrokerConnection {
+    /// Connects to the first reachable bootstrap broker and authenticates.
+    async fn open(config: &ClientConfig) -> Result<Self> {
+        let protocol = config
+            .get("security.protocol")
+            .unwrap_or("plaintext")
+            .to_ascii_lowercase();
+        let servers = config
+            .get("bootstrap.servers")
+            .ok_or_else(|| anyhow!("No bootstrap servers configured"))?;
+        check_supported(config, &protocol)?;
+
+        let mut last_error = anyhow!("No bootstrap servers configured");
+        for server in servers.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            // librdkafka accepts an optional `protocol://` prefix.
+            let server = server.rsplit("://").next().unwrap_or(server);
+            match connect(server, &protocol, config).await {
+                Ok(stream) => {
+                    let mut connection = Self {
+                        stream,
+                        client_id: config.get("client.id").unwrap_or("kafkust").to_string(),
+                        correlation_id: 0,
+                    };
+                    if protocol.starts_with("sasl") {
+                        connection.authenticate(config).await?;
+                    }
+                    return Ok(connection);
+                }
+                Err(e) => last_error = e.context(format!("Failed to connect to {}", server)),
+            }
+        }
+        Err(last_error)
+    }
+
+    async fn request(&mut self, api_key: i16, version: i16, body: Vec<u8>) -> Result<Vec<u8>> {
+        self.correlation_id += 1;
+        let mut frame = ProtocolWriter::default();
+        frame.i16(api_key);
+        frame.i16(version);
+        frame.i32(self.correlation_id);
+        frame.string(&self.client_id);
+        frame.buf.extend_from_slice(&body);
+
+        self.stream
+            .write_all(&(frame.buf.len() as i32).to_be_bytes())
+            .await?;
+        self.stream.write_all(&frame.buf).await?;
+        self.stream.flush().await?;
+
+        let mut size = [0u8; 4];
+        self.stream
+            .read_exact(&mut size)
+            .await
+            .map_err(|e| anyhow!("Broker closed the connection: {}", e))?;
+        let size = i32::from_be_bytes(size).max(0) as usize;
+        if size > MAX_RESPONSE_BYTES {
+            return Err(anyhow!("Broker response of {} bytes is too large", size));
+        }
+        let mut response = vec![0u8; size];
+        self.stream.read_exact(&mut response).await?;
+
+        let mut reader = ProtocolReader { buf: &response };
+        if reader.i32()? != self.correlation_id {
+            return Err(anyhow!("Broker response does not match the request"));
+        }
+        Ok(response[4..].to_vec())
+    }
+
+    async fn authenticate(&mut self, config: &ClientConfig) -> Result<()> {
+        let mechanism = config
+            .get("sasl.mechanism")
+            .unwrap_or("GSSAPI")
+            .to_uppercase();
+
+        let mut request = ProtocolWriter::default();
+        request.string(&mechanism);
+        let response = self.request(SASL_HANDSHAKE, 1, request.buf).await?;
+        let mut reader = ProtocolReader { buf: &response };
+        if reader.i16()? != 0 {
+            let enabled = (0..reader.count()?)
+                .map(|_| reader.string())
+                .collect::<Result<Vec<_>>>()?;
+            return Err(anyhow!(
+                "Broker does not accept SASL mechanism {} (enabled: {})",
+                mechanism,
+                enabled.join(", ")
+            ));
+        }
+
+        let username = config.get("sasl.username").unwrap_or_default();
+        let password = config.get("sasl.password").unwrap_or_default();
+        match mechanism.as_str() {
+            "PLAIN" => {
+                self.sasl_exchange(format!("\0{}\0{}", username, password).into_bytes())
+                    .await?;
+            }
+            "SCRAM-SHA-256" => self.scram::<Sha256>(username, password).await?,
+            "SCRAM-SHA-512" => self.scram::<Sha512>(username, password).await?,
+            "OAUTHBEARER" => {
+                let token = oauth_tokens::config_token(config)
+                    .ok_or_else(|| anyhow!("No OAuth token available for the cluster"))?;
+                let message = format!("n,,\x01auth=Bearer {}\x01\x01", token.token);
+                self.sasl_exchange(message.into_bytes()).await?;
+            }
+            other => return Err(anyhow!("SASL mechanism {} is not supported here", other)),
+        }
+        Ok(())
+    }
+
+    /// One SaslAuthenticate round trip, returning the server's message.
+    async fn sasl_exchange(&mut self, auth_bytes: Vec<u8>) -> Result<Vec<u8>> {
+        let mut request = ProtocolWriter::default();
+        request.bytes(&auth_bytes);
+        let response = self.request(SASL_AUTHENTICATE, 1, request.buf).await?;
+
+        let mut reader = ProtocolReader { buf: &response };
+        let error_code = reader.i16()?;
+        let error_message = reader.nullable_string()?;
+        if error_code != 0 {
+            return Err(anyhow!(
+                "SASL authentication failed: {}",
+                error_message.unwrap_or_else(|| format!("error {}", error_code))
+            ));
+        }
+        reader.bytes()
+    }
+
+    /// SCRAM exchange of RFC 5802, without channel binding.
+    async fn scram<D: Digest + BlockSizeUser>(
+        &mut self,
+        username: &str,
+        password: &str,
+    ) -> Result<()> {
+        // A v4 UUID carries 122 random bits.
+        let nonce = STANDARD.encode(Uuid::new_v4().as_bytes());
+        let username = username.replace('=', "=3D").replace(',', "=2C");
+        let client_first = format!("n={},r={}", username, nonce);
+
+        let server_first = self
+            .sasl_exchange(format!("n,,{}", client_first).into_bytes())
+            .await?;
+        let server_first = String::from_utf8(server_first)?;
+        let attribute = |name: &str| {
+            server_first
+                .split(',')
+                .find_map(|a| a.strip_prefix(name)?.strip_prefix('='))
+                .ok_or_else(|| anyhow!("Malformed SCRAM server message"))
+        };
+        let server_nonce = attribute("r")?;
+        if !server_nonce.starts_with(&nonce) {
+            return Err(anyhow!("SCRAM server nonce does not extend ours"));
+        }
+        let salt = STANDARD.decode(attribute("s")?)?;
+        let iterations: u32 = attribute("i")?.parse()?;
+
+        let salted = salted_password::<D>(password.as_bytes(), &salt, iterations);
+        let client_key = hmac::<D>(&salted, b"Client Key");
+        let stored_key = D::digest(&client_key);
+        let client_final = format!("c=biws,r={}", server_nonce);
+        let auth_message = format!("{},{},{}", client_first, server_first, client_final);
+        let signature = hmac::<D>(&stored_key, auth_message.as_bytes());
+        let proof: Vec<u8> = client_key
+            .iter()
+            .zip(&signature)
+            .map(|(k, s)| k ^ s)
+            .collect();
+
+        let server_final = self
+            .sasl_exchange(format!("{},p={}", client_final, STANDARD.encode(proof)).into_bytes())
+            .await?;
+        let server_final = String::from_utf8(server_final)?;
+        let server_key = hmac::<D>(&salted, b"Server Key");
+        let expected = STANDARD.encode(hmac::<D>(&server_key, auth_message.as_bytes()));
+        match server_final.strip_prefix("v=") {
+            Some(verifier) if verifier == expected => Ok(()),
+            Some(_) => Err(anyhow!("SCRAM server signature does not match")),
+            None => Err(anyhow!("SCRAM authentication failed: {}", server_final)),
+        }
+    }
+}
+
+/// Rejects setups the connection cannot follow before any broker is tried,
+/// so the error names the setting rather than a failed connection.
+fn check_supported(config: &ClientConfig, protocol: &str) -> Result<()> {
+    if protocol.starts_with("sasl") {
+        let mechanism = config
+            .get("sasl.mechanism")
+            .unwrap_or("GSSAPI")
+            .to_uppercase();
+        if !SASL_MECHANISMS.contains(&mechanism.as_str()) {
+            return Err(anyhow!(
+                "SASL mechanism {} is not supported for requests outside librdkafka",
+                mechanism
+            ));
+        }
+    }
+    if protocol.ends_with("ssl") && config.get("ssl.key.password").is_some() {
+        return Err(anyhow!(
+            "Encrypted client keys are not supported for requests outside librdkafka"
+        ));
+    }
+    Ok(())
+}
+
+async fn connect(
+    server: &str,
+    protocol: &str,
+    config: &ClientConfig,
+) -> Result<Box<dyn BrokerStream>> {
+    let tcp = TcpStream::connect(server).await?;
+    if !matches!(protocol, "ssl" | "sasl_ssl") {
+        return Ok(Box::new(tcp));
+    }
+
+    let host = server
+        .rsplit_once(':')
+        .map_or(server, |(host, _)| host)
+        .trim_start_matches('[')
+        .trim_end_matches(']');
+    let server_name = ServerName::try_from(host.to_string())?;
+    let tls = tls_connector(config)?.connect(server_name, tcp).await?;
+    Ok(Box::new(tls))
+}
+
+fn tls_connector(config: &ClientConfig) -> Result<TlsConnector> {
+    let roots = root_store(config.get("ssl.ca.location"))?;
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = rustls::ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots);
+    let tls = match (
+        config.get("ssl.certificate.location"),
+        config.get("ssl.key.location"),
+    ) {
+        (Some(cert), Some(key)) => {
+            let certs = rustls_pemfile::certs(&mut pem_reader(cert)?)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let key = rustls_pemfile::private_key(&mut pem_reader(key)?)?
+                .ok_or_else(|| anyhow!("No private key found in {}", key))?;
+            builder.with_client_auth_cert(certs, key)?
+        }
+        _ => builder.with_no_client_auth(),
+    };
+    Ok(TlsConnector::from(Arc::new(tls)))
+}
+
+/// CA certificates trusted for the brokers. Like librdkafka, `ca_location`
+/// names a PEM file or a directory of them, and without one (or with
+/// `probe`) the system's certificate store is used.
+fn root_store(ca_location: Option<&str>) -> Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    match ca_location {
+        None | Some("probe") => {
+            let native = rustls_native_certs::load_native_certs();
+            let (added, _) = roots.add_parsable_certificates(native.certs);
+            if added == 0 {
+                let reasons: Vec<String> = native.errors.iter().map(|e| e.to_string()).collect();
+                return Err(anyhow!(
+                    "No CA certificates found in the system store ({}); set ssl.ca.location",
+                    reasons.join("; ")
+                ));
+            }
+        }
+        Some(dir) if Path::new(dir).is_dir() => {
+            let entries =
+                std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir))?;
+            for entry in entries {
+                let path = entry?.path();
+                if !path.is_file() {
+                    continue;
+                }
+                // Hashed-name directories may hold CRLs and other files.
+                let mut reader = pem_reader(&path.to_string_lossy())?;
+                roots.add_parsable_certificates(rustls_pemfile::certs(&mut reader).flatten());
+            }
+            if roots.is_empty() {
+                return Err(anyhow!("No PEM CA certificates found in {}", dir));
+            }
+        }
+        Some(file) => {
+            for cert in rustls_pemfile::certs(&mut pem_reader(file)?) {
+                roots.add(cert?)?;
+            }
+            if roots.is_empty() {
+                return Err(anyhow!("No PEM CA certificates found in {}", file));
+            }
+        }
+    }
+    Ok(roots)
+}
+
+fn pem_reader(path: &str) -> Result<BufReader<File>> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path))?;
+    Ok(BufReader::new(file))
+}
+
+fn hmac<D: Digest + BlockSizeUser>(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac =
+        <SimpleHmac<D> as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// PBKDF2 with a single output block, SCRAM's `Hi`.
+fn salted_password<D: Digest + BlockSizeUser>(
+    password: &[u8],
+    salt: &[u8],
+    iterations: u32,
+) -> Vec<u8> {
+    let mut block = salt.to_vec();
+    block.extend_from_slice(&1u32.to_be_bytes());
+    let mut u = hmac::<D>(password, &block);
+    let mut result = u.clone();
+    for _ in 1..iterations {
+        u = hmac::<D>(password, &u);
+        result.iter_mut().zip(&u).for_each(|(r, x)| *r ^= x);
+    }
+    result
+}
+
+#[derive(Default)]
+struct ProtocolWriter {
+    buf: Vec<u8>,
+}
+
+impl ProtocolWriter {
+    fn i8(&mut self, value: i8) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn i16(&mut self, value: i16) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn i32(&mut self, value: i32) {
+        self.buf.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn string(&mut self, value: &str) {
+        self.i16(value.len() as i16);
+        self.buf.extend_from_slice(value.as_bytes());
+    }
+
+    fn bytes(&mut self, value: &[u8]) {
+        self.i32(value.len() as i32);
+        self.buf.extend_from_slice(value);
+    }
+}
+
+struct ProtocolReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> ProtocolReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.buf.len() < len {
+            return Err(anyhow!("Truncated broker response"));
+        }
+        let (head, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(head)
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    /// Array length; a null array decodes as empty.
+    fn count(&mut self) -> Result<usize> {
+        Ok(self.i32()?.max(0) as usize)
+    }
+
+    fn nullable_string(&mut self) -> Result<Option<String>> {
+        let len = self.i16()?;
+        if len < 0 {
+            return Ok(None);
+        }
+        Ok(Some(
+            String::from_utf8_lossy(self.take(len as usize)?).to_string(),
+        ))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.nullable_string()?
+            .ok_or_else(|| anyhow!("Unexpected null string in broker response"))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>> {
+        let len = self.i32()?.max(0) as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+}
@@ -0,0 +1,147 @@
+//! Broker certificate checks through librdkafka's certificate verify
+//! callback, which sees each certificate of the chain a broker serves.
+
+use crate::domain::cluster::cluster::{normalize_fingerprint, BrokerCertificate};
+use anyhow::{anyhow, Result};
+use rdkafka::bindings as rdsys;
+use rdkafka::client::{Client, ClientContext, OAuthToken};
+use rdkafka::config::{ClientConfig, RDKafkaLogLevel};
+use rdkafka::error::KafkaError;
+use rdkafka::types::RDKafkaType;
+use sha2::{Digest, Sha256};
+use std::error::Error;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::client_logs::{self, ClusterContext};
+use super::oauth_tokens;
+
+/// Records the certificates brokers serve. Logs, errors and OAUTHBEARER
+/// token renewal go through the cluster's context like any other client's.
+struct PinContext {
+    cluster: ClusterContext,
+    pin: Option<String>,
+    seen: Mutex<Vec<BrokerCertificate>>,
+}
+
+impl ClientContext for PinContext {
+    const ENABLE_REFRESH_OAUTH_TOKEN: bool = true;
+
+    fn log(&self, level: RDKafkaLogLevel, fac: &str, log_message: &str) {
+        self.cluster.log(level, fac, log_message);
+    }
+
+    fn error(&self, error: KafkaError, reason: &str) {
+        self.cluster.error(error, reason);
+    }
+
+    fn generate_oauth_token(
+        &self,
+        oauthbearer_config: Option<&str>,
+    ) -> Result<OAuthToken, Box<dyn Error>> {
+        self.cluster.generate_oauth_token(oauthbearer_config)
+    }
+}
+
+/// Connects to the cluster and returns the certificate each contacted broker
+/// served. With a pin, a matching leaf certificate is accepted even when CA
+/// validation fails and any other is rejected.
+pub fn inspect_certificates(
+    config: &ClientConfig,
+    pin: Option<&str>,
+) -> Result<Vec<BrokerCertificate>> {
+    // The cluster tag is not a librdkafka property.
+    let (config, cluster) = client_logs::client_context(config);
+    let native = config.create_native_config()?;
+    unsafe { rdsys::rd_kafka_conf_set_ssl_cert_verify_cb(native.ptr(), Some(verify_certificate)) };
+    let context = PinContext {
+        cluster,
+        pin: pin.map(normalize_fingerprint),
+        seen: Mutex::new(Vec::new()),
+    };
+    let client = Client::new(&config, native, RDKafkaType::RD_KAFKA_PRODUCER, context)?;
+    oauth_tokens::set_token(client.native_ptr(), &config)?;
+
+    let result = client.fetch_metadata(None, Duration::from_secs(5));
+    let seen = client.context().seen.lock().unwrap().clone();
+    if let Some(changed) = seen.iter().find(|c| c.matches_pin == Some(false)) {
+        return Err(anyhow!(
+            "Broker {} served certificate {}, which does not match the pinned {}. The certificate changed or the connection is being intercepted",
+            changed.broker,
+            changed.fingerprint,
+            client.context().pin.as_deref().unwrap_or_default()
+        ));
+    }
+    result.map_err(|e| anyhow!("Connection check failed: {}", e))?;
+    Ok(seen)
+}
+
+/// Called by librdkafka for each certificate in a broker's chain, root
+/// first. Returns 1 to accept the certificate.
+unsafe extern "C" fn verify_certificate(
+    _rk: *mut rdsys::rd_kafka_t,
+    broker_name: *const c_char,
+    _broker_id: i32,
+    x509_error: *mut c_int,
+    depth: c_int,
+    buf: *const c_char,
+    size: usize,
+    errstr: *mut c_char,
+    errstr_size: usize,
+    opaque: *mut c_void,
+) -> c_int {
+    let context = &*(opaque as *const PinContext);
+
+    // The leaf decides when pinned, so issuers need not validate.
+    if depth > 0 {
+        if context.pin.is_some() {
+            *x509_error = 0;
+            return 1;
+        }
+        return (*x509_error == 0) as c_int;
+    }
+
+    let der = std::slice::from_raw_parts(buf as *const u8, size);
+    let fingerprint = Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":");
+    let matches_pin = context.pin.as_ref().map(|pin| *pin == fingerprint);
+    if let Ok(mut seen) = context.seen.lock() {
+        seen.push(BrokerCertificate {
+            broker: CStr::from_ptr(broker_name).to_string_lossy().into_owned(),
+            fingerprint,
+            matches_pin,
+        });
+    }
+
+    match matches_pin {
+        Some(true) => {
+            *x509_error = 0;
+            1
+        }
+        Some(false) => {
+            write_errstr(
+                errstr,
+                errstr_size,
+                "Certificate does not match the pinned fingerprint",
+            );
+            0
+        }
+        None => (*x509_error == 0) as c_int,
+    }
+}
+
+fn write_errstr(errstr: *mut c_char, errstr_size: usize, message: &str) {
+    if errstr.is_null() || errstr_size == 0 {
+        return;
+    }
+    let len = message.len().min(errstr_size - 1);
+    unsafe {
+        std::ptr::copy_nonoverlapping(message.as_ptr() as *const c_char, errstr, len);
+        *errstr.add(len) = 0;
+    }
+}
@@ -0,0 +1,191 @@
+//! librdkafka log lines captured per cluster. Without a log callback they
+//! only reach the `log` crate, which this app never initializes, so broker
+//! connection and authentication errors vanish.
+//!
+//! Every client is created with a `ClusterContext` naming the cluster it
+//! belongs to. `create_config` records the cluster id under `CLUSTER_KEY`,
+//! which `client_context` removes again before librdkafka sees the config.
+
+use crate::domain::cluster::cluster::ClientLogLine;
+use rdkafka::client::{ClientContext, OAuthToken};
+use rdkafka::config::{ClientConfig, RDKafkaLogLevel};
+use rdkafka::consumer::{BaseConsumer, Consumer, ConsumerContext, Rebalance};
+use rdkafka::error::KafkaError;
+use rdkafka::producer::{DeliveryResult, ProducerContext};
+use rdkafka::statistics::Statistics;
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use uuid::Uuid;
+
+use super::client_stats;
+use super::oauth_tokens::OAuthTokenCache;
+use super::session_manager::now_millis;
+
+/// Lines kept per cluster; older ones are dropped first.
+const CLIENT_LOG_CAPACITY: usize = 1_000;
+
+const CLUSTER_KEY: &str = "kafkust.cluster.id";
+
+/// librdkafka `debug` contexts a cluster may enable.
+pub const DEBUG_CONTEXTS: &[&str] = &[
+    "generic",
+    "broker",
+    "topic",
+    "metadata",
+    "feature",
+    "queue",
+    "msg",
+    "protocol",
+    "cgrp",
+    "security",
+    "fetch",
+    "interceptor",
+    "plugin",
+    "consumer",
+    "admin",
+    "eos",
+    "mock",
+    "assignor",
+    "conf",
+    "all",
+];
+
+/// Tags a client config with the cluster its clients belong to.
+pub fn tag_config(config: &mut ClientConfig, cluster_id: Uuid) {
+    config.set(CLUSTER_KEY, cluster_id.to_string());
+}
+
+/// Splits the cluster tag off a config, returning the config to hand to
+/// librdkafka and the context for its client.
+pub fn client_context(config: &ClientConfig) -> (ClientConfig, ClusterContext) {
+    let cluster_id = config
+        .get(CLUSTER_KEY)
+        .and_then(|id| Uuid::parse_str(id).ok());
+    let mut config = config.clone();
+    config.remove(CLUSTER_KEY);
+    (
+        config,
+        ClusterContext {
+            cluster_id,
+            paused: AtomicBool::new(false),
+        },
+    )
+}
+
+/// Captured lines of a cluster, oldest first.
+pub fn client_logs(cluster_id: Uuid) -> Vec<ClientLogLine> {
+    buffers()
+        .lock()
+        .unwrap()
+        .get(&cluster_id)
+        .map(|lines| lines.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+pub fn clear_client_logs(cluster_id: Uuid) {
+    buffers().lock().unwrap().remove(&cluster_id);
+}
+
+fn buffers() -> &'static Mutex<HashMap<Uuid, VecDeque<ClientLogLine>>> {
+    static BUFFERS: OnceLock<Mutex<HashMap<Uuid, VecDeque<ClientLogLine>>>> = OnceLock::new();
+    BUFFERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Client context routing librdkafka logs and errors into the cluster's
+/// buffer. Warnings and worse also go to stderr. Statistics feed the
+/// cluster's throughput samples. OAUTHBEARER clients renew their token from
+/// the cluster's entry in the shared token cache.
+pub struct ClusterContext {
+    cluster_id: Option<Uuid>,
+    /// Set while a session is paused, so partitions a rebalance assigns to
+    /// its consumer start out paused too.
+    paused: AtomicBool,
+}
+
+impl ClusterContext {
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    fn record(&self, level: &str, facility: &str, message: &str) {
+        let Some(cluster_id) = self.cluster_id else {
+            return;
+        };
+        let mut buffers = buffers().lock().unwrap();
+        let lines = buffers.entry(cluster_id).or_default();
+        if lines.len() >= CLIENT_LOG_CAPACITY {
+            lines.pop_front();
+        }
+        lines.push_back(ClientLogLine {
+            timestamp: now_millis(),
+            level: level.to_string(),
+            facility: facility.to_string(),
+            message: message.to_string(),
+        });
+    }
+}
+
+impl ClientContext for ClusterContext {
+    const ENABLE_REFRESH_OAUTH_TOKEN: bool = true;
+
+    fn log(&self, level: RDKafkaLogLevel, fac: &str, log_message: &str) {
+        let level = match level {
+            RDKafkaLogLevel::Emerg | RDKafkaLogLevel::Alert | RDKafkaLogLevel::Critical => {
+                "critical"
+            }
+            RDKafkaLogLevel::Error => "error",
+            RDKafkaLogLevel::Warning => "warning",
+            RDKafkaLogLevel::Notice => "notice",
+            RDKafkaLogLevel::Info => "info",
+            RDKafkaLogLevel::Debug => "debug",
+        };
+        if matches!(level, "critical" | "error" | "warning") {
+            eprintln!("librdkafka {}: {} {}", level, fac, log_message);
+        }
+        self.record(level, fac, log_message);
+    }
+
+    fn error(&self, error: KafkaError, reason: &str) {
+        eprintln!("librdkafka: {}: {}", error, reason);
+        self.record("error", "ERROR", &format!("{}: {}", error, reason));
+    }
+
+    fn stats(&self, statistics: Statistics) {
+        if let Some(cluster_id) = self.cluster_id {
+            client_stats::record(cluster_id, &statistics);
+        }
+    }
+
+    fn generate_oauth_token(&self, _: Option<&str>) -> Result<OAuthToken, Box<dyn Error>> {
+        let cluster_id = self
+            .cluster_id
+            .ok_or("Client does not belong to a cluster")?;
+        let token = OAuthTokenCache::shared().renew(cluster_id)?;
+        Ok(OAuthToken {
+            token: token.token,
+            principal_name: token.principal,
+            lifetime_ms: token.expires_at,
+        })
+    }
+}
+
+impl ConsumerContext for ClusterContext {
+    fn post_rebalance(&self, base_consumer: &BaseConsumer<Self>, rebalance: &Rebalance<'_>) {
+        let Rebalance::Assign(assigned) = rebalance else {
+            return;
+        };
+        if self.paused.load(Ordering::Relaxed) {
+            if let Err(e) = base_consumer.pause(assigned) {
+                self.record("error", "REBALANCE", &format!("Failed to pause: {}", e));
+            }
+        }
+    }
+}
+
+impl ProducerContext for ClusterContext {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, _: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {}
+}
@@ -0,0 +1,77 @@
+use anyhow::Result;
+use rdkafka::admin::AdminClient;
+use rdkafka::config::ClientConfig;
+use rdkafka::consumer::BaseConsumer;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+use super::client_logs::ClusterContext;
+use super::oauth_tokens::create_client;
+
+struct PooledClients {
+    /// Settings the clients were created with; a cluster edit or password
+    /// change produces different settings and replaces them.
+    settings: HashMap<String, String>,
+    admin: Arc<AdminClient<ClusterContext>>,
+    consumer: Arc<BaseConsumer<ClusterContext>>,
+}
+
+/// One long-lived admin client and metadata consumer per cluster, so
+/// metadata requests reuse established broker connections instead of
+/// reconnecting and re-authenticating every time.
+pub struct ClientPool {
+    clients: Mutex<HashMap<Uuid, PooledClients>>,
+}
+
+impl ClientPool {
+    pub fn new() -> Self {
+        Self {
+            clients: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn admin(
+        &self,
+        cluster_id: Uuid,
+        config: &ClientConfig,
+    ) -> Result<Arc<AdminClient<ClusterContext>>> {
+        self.with_clients(cluster_id, config, |c| c.admin.clone())
+    }
+
+    pub fn consumer(
+        &self,
+        cluster_id: Uuid,
+        config: &ClientConfig,
+    ) -> Result<Arc<BaseConsumer<ClusterContext>>> {
+        self.with_clients(cluster_id, config, |c| c.consumer.clone())
+    }
+
+    /// Drops the cluster's clients, closing their connections.
+    pub fn release(&self, cluster_id: Uuid) {
+        self.clients.lock().unwrap().remove(&cluster_id);
+    }
+
+    fn with_clients<T>(
+        &self,
+        cluster_id: Uuid,
+        config: &ClientConfig,
+        get: impl FnOnce(&PooledClients) -> T,
+    ) -> Result<T> {
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(pooled) = clients.get(&cluster_id) {
+            if &pooled.settings == config.config_map() {
+                return Ok(get(pooled));
+            }
+        }
+
+        let pooled = PooledClients {
+            settings: config.config_map().clone(),
+            admin: Arc::new(create_client(config)?),
+            consumer: Arc::new(create_client(config)?),
+        };
+        let value = get(&pooled);
+        clients.insert(cluster_id, pooled);
+        Ok(value)
+    }
+}
@@ -0,0 +1,127 @@
+//! Throughput of the app's own clients, sampled from librdkafka statistics.
+//! `create_config` turns statistics on for every client, and `ClusterContext`
+//! hands each report here. Rates come from the byte counters of two
+//! consecutive reports of a client handle, per broker, so they include
+//! protocol overhead: bytes sent stand for produce traffic and bytes
+//! received for fetch traffic.
+
+use crate::domain::quota::ClientThroughput;
+use rdkafka::statistics::Statistics;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use uuid::Uuid;
+
+use super::session_manager::now_millis;
+
+/// How often every client reports its statistics.
+pub const STATS_INTERVAL_MS: i64 = 15_000;
+
+/// Handles silent for this long are closed and no longer counted.
+const STALE_AFTER_MS: i64 = 3 * STATS_INTERVAL_MS;
+
+/// Bytes per second sent and received, per broker id.
+type BrokerRates = HashMap<i32, (f64, f64)>;
+
+#[derive(Default)]
+struct HandleSample {
+    client_id: String,
+    /// librdkafka's monotonic clock at the report, in microseconds.
+    ts: i64,
+    received_at: i64,
+    /// Bytes sent and received per broker id since the handle was created.
+    totals: HashMap<i32, (u64, u64)>,
+    rates: BrokerRates,
+    throttle_time_ms: i64,
+}
+
+/// Records a statistics report of one of the cluster's clients.
+pub fn record(cluster_id: Uuid, stats: &Statistics) {
+    let totals: HashMap<i32, (u64, u64)> = stats
+        .brokers
+        .values()
+        // Bootstrap and logical brokers carry no node id.
+        .filter(|b| b.nodeid >= 0)
+        .map(|b| (b.nodeid, (b.txbytes, b.rxbytes)))
+        .collect();
+    let throttle_time_ms = stats
+        .brokers
+        .values()
+        .filter_map(|b| b.throttle.as_ref().map(|t| t.max))
+        .max()
+        .unwrap_or(0);
+
+    let mut samples = samples().lock().unwrap();
+    let handles = samples.entry(cluster_id).or_default();
+    let previous = handles.remove(&stats.name).unwrap_or_default();
+    let elapsed_secs = (stats.ts - previous.ts) as f64 / 1_000_000.0;
+    let rates = if elapsed_secs > 0.0 {
+        totals
+            .iter()
+            .filter_map(|(broker, (tx, rx))| {
+                let (prev_tx, prev_rx) = previous.totals.get(broker)?;
+                Some((
+                    *broker,
+                    (
+                        tx.saturating_sub(*prev_tx) as f64 / elapsed_secs,
+                        rx.saturating_sub(*prev_rx) as f64 / elapsed_secs,
+                    ),
+                ))
+            })
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    handles.insert(
+        stats.name.clone(),
+        HandleSample {
+            client_id: stats.client_id.clone(),
+            ts: stats.ts,
+            received_at: now_millis(),
+            totals,
+            rates,
+            throttle_time_ms,
+        },
+    );
+}
+
+/// Current throughput of the cluster's clients per `client.id`. Handles of
+/// one client id add up per broker, as the brokers see them.
+pub fn throughput(cluster_id: Uuid) -> Vec<ClientThroughput> {
+    let mut samples = samples().lock().unwrap();
+    let Some(handles) = samples.get_mut(&cluster_id) else {
+        return Vec::new();
+    };
+    let now = now_millis();
+    handles.retain(|_, sample| now - sample.received_at < STALE_AFTER_MS);
+
+    let mut clients: HashMap<&str, (BrokerRates, i64)> = HashMap::new();
+    for sample in handles.values() {
+        let (brokers, throttle) = clients.entry(&sample.client_id).or_default();
+        for (broker, (tx, rx)) in &sample.rates {
+            let rates = brokers.entry(*broker).or_default();
+            rates.0 += tx;
+            rates.1 += rx;
+        }
+        *throttle = (*throttle).max(sample.throttle_time_ms);
+    }
+
+    let mut throughput: Vec<ClientThroughput> = clients
+        .into_iter()
+        .map(
+            |(client_id, (brokers, throttle_time_ms))| ClientThroughput {
+                client_id: client_id.to_string(),
+                produce_bytes_per_sec: brokers.values().map(|r| r.0).fold(0.0, f64::max),
+                fetch_bytes_per_sec: brokers.values().map(|r| r.1).fold(0.0, f64::max),
+                throttle_time_ms,
+            },
+        )
+        .collect();
+    throughput.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+    throughput
+}
+
+fn samples() -> &'static Mutex<HashMap<Uuid, HashMap<String, HandleSample>>> {
+    static SAMPLES: OnceLock<Mutex<HashMap<Uuid, HashMap<String, HandleSample>>>> = OnceLock::new();
+    SAMPLES.get_or_init(|| Mutex::new(HashMap::new()))
+}
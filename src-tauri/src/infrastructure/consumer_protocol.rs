@@ -0,0 +1,62 @@
+//! Decoder for the member assignments of groups using the `consumer` protocol
+//! type. The layout is Kafka's `ConsumerProtocolAssignment`: an int16 version,
+//! an int32-counted array of topics (int16-length string followed by an
+//! int32-counted array of int32 partitions) and trailing user data.
+
+use crate::domain::consumer_group::MemberAssignment;
+use anyhow::{anyhow, Result};
+
+pub fn decode_assignment(buf: &[u8]) -> Result<Vec<MemberAssignment>> {
+    let mut reader = ProtocolReader { buf };
+    if reader.i16()? < 0 {
+        return Err(anyhow!("Unsupported consumer assignment version"));
+    }
+
+    let topics = reader.count()?;
+    let mut assignments = Vec::with_capacity(topics);
+    for _ in 0..topics {
+        let topic = reader.string()?;
+        let partitions = (0..reader.count()?)
+            .map(|_| reader.i32())
+            .collect::<Result<Vec<_>>>()?;
+        assignments.push(MemberAssignment { topic, partitions });
+    }
+
+    Ok(assignments)
+}
+
+struct ProtocolReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> ProtocolReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.buf.len() < len {
+            return Err(anyhow!("Truncated consumer assignment"));
+        }
+        let (head, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(head)
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    /// Array length; a null array decodes as empty.
+    fn count(&mut self) -> Result<usize> {
+        Ok(self.i32()?.max(0) as usize)
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.i16()?;
+        if len < 0 {
+            return Err(anyhow!("Unexpected null topic in consumer assignment"));
+        }
+        Ok(String::from_utf8_lossy(self.take(len as usize)?).to_string())
+    }
+}
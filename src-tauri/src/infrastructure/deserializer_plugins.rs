@@ -0,0 +1,191 @@
+//! Deserializer plugins loaded from shared libraries in the app data dir.
+//!
+//! A plugin exports these C symbols:
+//!
+//! ```c
+//! uint32_t kafkust_deserializer_abi_version(void);  // must return 1
+//! const char *kafkust_deserializer_name(void);      // static, NUL-terminated
+//! int32_t kafkust_deserialize(const char *topic,
+//!                             const uint8_t *payload, size_t payload_len,
+//!                             uint8_t **out, size_t *out_len);
+//! void kafkust_deserializer_free(uint8_t *buf, size_t len);
+//! ```
+//!
+//! `kafkust_deserialize` returns 0 and UTF-8 text in `out` on success, or
+//! non-zero and an optional UTF-8 error message. Buffers it hands out are
+//! released with `kafkust_deserializer_free`.
+
+use crate::domain::plugin::{Deserializer, DeserializerPlugin};
+use anyhow::{anyhow, Result};
+use libloading::Library;
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::sync::{Arc, RwLock};
+
+const ABI_VERSION: u32 = 1;
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type NameFn = unsafe extern "C" fn() -> *const c_char;
+type DeserializeFn =
+    unsafe extern "C" fn(*const c_char, *const u8, usize, *mut *mut u8, *mut usize) -> i32;
+type FreeFn = unsafe extern "C" fn(*mut u8, usize);
+
+/// A deserializer backed by a loaded shared library.
+struct DynamicDeserializer {
+    name: String,
+    path: PathBuf,
+    deserialize: DeserializeFn,
+    free: FreeFn,
+    // Keeps the functions above mapped; dropped last.
+    _library: Library,
+}
+
+impl DynamicDeserializer {
+    /// # Safety
+    /// Runs the library's initializers and trusts its exports to match the
+    /// plugin ABI.
+    unsafe fn load(path: &Path) -> Result<Self> {
+        let library = Library::new(path)?;
+
+        let abi_version = *library.get::<AbiVersionFn>(b"kafkust_deserializer_abi_version\0")?;
+        let version = abi_version();
+        if version != ABI_VERSION {
+            return Err(anyhow!(
+                "Unsupported plugin ABI version {} (expected {})",
+                version,
+                ABI_VERSION
+            ));
+        }
+
+        let name = *library.get::<NameFn>(b"kafkust_deserializer_name\0")?;
+        let name = name();
+        if name.is_null() {
+            return Err(anyhow!("Plugin has no name"));
+        }
+        let name = CStr::from_ptr(name).to_string_lossy().into_owned();
+
+        Ok(Self {
+            name,
+            path: path.to_path_buf(),
+            deserialize: *library.get::<DeserializeFn>(b"kafkust_deserialize\0")?,
+            free: *library.get::<FreeFn>(b"kafkust_deserializer_free\0")?,
+            _library: library,
+        })
+    }
+}
+
+impl Deserializer for DynamicDeserializer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn deserialize(&self, topic: &str, payload: &[u8]) -> Result<String, String> {
+        let topic = CString::new(topic).map_err(|e| e.to_string())?;
+        let mut out: *mut u8 = ptr::null_mut();
+        let mut out_len = 0;
+
+        let (status, text) = unsafe {
+            let status = (self.deserialize)(
+                topic.as_ptr(),
+                payload.as_ptr(),
+                payload.len(),
+                &mut out,
+                &mut out_len,
+            );
+            let text = if out.is_null() {
+                String::new()
+            } else {
+                let text =
+                    String::from_utf8_lossy(std::slice::from_raw_parts(out, out_len)).into_owned();
+                (self.free)(out, out_len);
+                text
+            };
+            (status, text)
+        };
+
+        if status == 0 {
+            Ok(text)
+        } else if text.is_empty() {
+            Err(format!("{} failed with status {}", self.name, status))
+        } else {
+            Err(format!("{}: {}", self.name, text))
+        }
+    }
+}
+
+/// Deserializer plugins by name, loaded from one directory.
+pub struct DeserializerRegistry {
+    dir: PathBuf,
+    plugins: RwLock<HashMap<String, Arc<DynamicDeserializer>>>,
+}
+
+impl DeserializerRegistry {
+    pub fn new(dir: PathBuf) -> Self {
+        let registry = Self {
+            dir,
+            plugins: RwLock::new(HashMap::new()),
+        };
+        registry.reload();
+        registry
+    }
+
+    /// Rescans the plugin directory. Libraries that fail to load are skipped
+    /// and logged. Plugins in use keep working until their last user is done.
+    pub fn reload(&self) {
+        let mut plugins = HashMap::new();
+        let entries = match std::fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            // No plugin directory means no plugins.
+            Err(_) => {
+                *self.plugins.write().unwrap() = plugins;
+                return;
+            }
+        };
+
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            if path.extension().and_then(|e| e.to_str()) != Some(std::env::consts::DLL_EXTENSION) {
+                continue;
+            }
+            match unsafe { DynamicDeserializer::load(&path) } {
+                Ok(plugin) => {
+                    if let Some(previous) = plugins.get(&plugin.name) {
+                        eprintln!(
+                            "Deserializer plugin {} in {:?} shadows {:?}",
+                            plugin.name, path, previous.path
+                        );
+                    }
+                    plugins.insert(plugin.name().to_string(), Arc::new(plugin));
+                }
+                Err(e) => eprintln!("Failed to load deserializer plugin {:?}: {}", path, e),
+            }
+        }
+
+        *self.plugins.write().unwrap() = plugins;
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Deserializer>> {
+        self.plugins
+            .read()
+            .unwrap()
+            .get(name)
+            .map(|p| p.clone() as Arc<dyn Deserializer>)
+    }
+
+    pub fn list(&self) -> Vec<DeserializerPlugin> {
+        let mut plugins: Vec<DeserializerPlugin> = self
+            .plugins
+            .read()
+            .unwrap()
+            .values()
+            .map(|p| DeserializerPlugin {
+                name: p.name().to_string(),
+                path: p.path.to_string_lossy().into_owned(),
+            })
+            .collect();
+        plugins.sort_by(|a, b| a.name.cmp(&b.name));
+        plugins
+    }
+}
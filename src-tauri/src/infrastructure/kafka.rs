@@ -1,24 +1,204 @@
-use crate::domain::cluster::cluster::{Cluster, SaslMechanism, SecurityConfig};
-use crate::domain::topic::{KafkaMessage, Topic};
+use crate::domain::acl::{AclBinding, PermissionCheck, PermissionProbe, PermissionStatus};
+use crate::domain::alert::{LagAlert, LagAlertRule};
+use crate::domain::cluster::cluster::{
+    BrokerCertificate, ClientLogLine, Cluster, SaslMechanism, SecurityConfig, TokenStatus,
+};
+use crate::domain::consumer_group::{
+    diff_group_states, duplicate_instance_ids, CommittedOffset, ConsumerGroup, ConsumerGroupDetail,
+    ConsumerGroupLag, ConsumerGroupMember, LagSample, OffsetResetTarget, PartitionLag,
+    PartitionOffsetReset, TopicConsumer,
+};
+use crate::domain::dlq::{DlqFailure, ERROR_HEADERS, ORIGINAL_TOPIC_HEADERS};
+use crate::domain::eos::{EosRecord, SequenceTracker};
+use crate::domain::event::EventSink;
+use crate::domain::export::{
+    ExportManifest, ExportShard, ExportSharding, ExportSummary, MANIFEST_FILE,
+};
+use crate::domain::health::{diff_isr, diff_topics, TopicChange};
+use crate::domain::mirroring::{MirroringStatus, Mm2InternalTopic, Mm2TopicKind, ReplicationFlow};
+use crate::domain::quota::{quota_usage, QuotaUtilization};
+use crate::domain::refresh::{RefreshDiff, RefreshTarget};
+use crate::domain::replay::{RekeyStrategy, ReplaySummary};
+use crate::domain::session::{
+    AutoOffsetReset, SessionMessage, SessionState, TailBuffer, TailBufferLimits,
+};
+use crate::domain::streams::{StateStoreEntry, StateStoreSnapshot};
+use crate::domain::topic::{
+    dedupe_messages, internal_topic_kind, BreakdownSource, BulkLineError, BulkRecord,
+    ConfigEntrySource, ConsumeResult, DedupeKey, DedupeSettings, FetchError, FetchErrorKind,
+    FieldBreakdown, FieldValueCount, HistogramBucket, KafkaMessage, KeySearchProgress, KeyTimeline,
+    MessageHeader, MessageMatcher, Partition, PartitionTruncation, PartitionWatermark,
+    PublishResult, SeekMode, Topic, TopicConfigEntry, WatermarkUpdate,
+};
 use anyhow::Result;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use rdkafka::admin::AdminClient;
-use rdkafka::client::DefaultClientContext;
 use rdkafka::config::ClientConfig;
-use rdkafka::consumer::{BaseConsumer, Consumer};
-use rdkafka::message::Message;
+use rdkafka::consumer::{BaseConsumer, CommitMode, Consumer, StreamConsumer};
+use rdkafka::message::{BorrowedMessage, Header, Headers, Message, OwnedHeaders};
+use rdkafka::producer::{BaseProducer, BaseRecord, FutureProducer, FutureRecord, Producer};
 use rdkafka::TopicPartitionList;
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::{BufWriter, Write};
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
+use uuid::Uuid;
 
-pub struct KafkaInfrastructure;
+use super::broker_protocol;
+use super::certificate_pin;
+use super::client_logs::{self, ClusterContext};
+use super::client_pool::ClientPool;
+use super::client_stats;
+use super::consumer_protocol;
+use super::json_path;
+use super::mirror_maker;
+use super::native_admin;
+use super::oauth_tokens::{self, create_client, OAuthTokenCache, TokenRequest};
+use super::partitioner;
+use super::payload_cipher::PayloadCipher;
+use super::payload_decoder::PayloadDecoder;
+use super::persistence::sqlite_cluster_repository::SqliteClusterRepository;
+use super::production_receipts::{payload_digest, ReceiptLog};
+use super::request_coalescer::RequestCoalescer;
+use super::session_manager::{now_millis, SessionContext};
+use super::wasm_transforms::TransformPipeline;
+
+/// Upper bound on histogram resolution; each boundary costs one
+/// offsets-for-times round-trip.
+const MAX_HISTOGRAM_BUCKETS: i64 = 500;
+
+/// Safety net for full-topic scans so a huge topic can't pin the backend.
+const MAX_SCAN_RECORDS: usize = 500_000;
+
+/// Records per window, across partitions, when a filtered browse reads back
+/// from the end of a topic.
+const BACKWARD_WINDOW_RECORDS: i64 = 10_000;
+
+/// Heartbeats are emitted every second per flow, so recent ones suffice.
+const HEARTBEAT_SAMPLE_SIZE: usize = 500;
+
+/// Recent records inspected to tell whether a topic carries keyed data.
+const KEY_SAMPLE_SIZE: usize = 100;
+
+/// How often the health monitor polls cluster metadata.
+const HEALTH_POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Identical metadata/watermark requests issued within this window share one
+/// broker round-trip, which bounds the load of views polling the same topic.
+const POLL_MIN_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Message counts and last activity shown in topic listings are reused for
+/// this long, since listing them costs three requests over every partition.
+const TOPIC_ACTIVITY_MIN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often a tail session hands its buffered records to the UI.
+const TAIL_DELIVERY_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How often a key search reports its progress.
+const KEY_SEARCH_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// A topic materialized by key is reused this long while the table is paged.
+const KEYED_TABLE_MIN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often the lag alert evaluator checks which rules are due.
+const LAG_ALERT_TICK: Duration = Duration::from_secs(1);
+
+/// Records per transaction in an exactly-once verification run.
+const EOS_BATCH_SIZE: u64 = 100;
+
+/// Every this many committed transactions the verifier also writes and aborts
+/// one, whose records must stay invisible to read_committed consumers.
+const EOS_ABORT_EVERY: u64 = 10;
+
+const EOS_TRANSACTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Once production is done, the verifier stops reading after this long
+/// without a record and reports whatever is still missing.
+const EOS_DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sessions that produce log receipts in batches of this many records.
+const RECEIPT_FLUSH_RECORDS: usize = 500;
+
+/// Rows of a refreshed result by key.
+type RefreshRows = BTreeMap<String, serde_json::Value>;
+
+/// Estimated message count and newest record timestamp, by topic.
+type TopicActivity = HashMap<String, (i64, Option<i64>)>;
+
+/// A produce request rejected by librdkafka or the broker, keeping the
+/// librdkafka error code so callers can tell failures apart.
+#[derive(Debug, thiserror::Error)]
+#[error("{code}: {message}")]
+pub struct DeliveryError {
+    pub code: String,
+    pub message: String,
+}
+
+pub struct KafkaInfrastructure {
+    clients: ClientPool,
+    topic_list_requests: RequestCoalescer<Uuid, Vec<Topic>>,
+    message_count_requests: RequestCoalescer<(Uuid, String), i64>,
+    topic_activity_requests: RequestCoalescer<Uuid, TopicActivity>,
+    refresh_requests: Arc<RequestCoalescer<(Uuid, RefreshTarget), RefreshRows>>,
+    keyed_table_requests: RequestCoalescer<(Uuid, String), Arc<KeyedTable>>,
+    oauth_tokens: Arc<OAuthTokenCache>,
+}
 
 impl KafkaInfrastructure {
     pub fn new() -> Self {
-        Self
+        Self {
+            clients: ClientPool::new(),
+            topic_list_requests: RequestCoalescer::new(POLL_MIN_INTERVAL),
+            message_count_requests: RequestCoalescer::new(POLL_MIN_INTERVAL),
+            topic_activity_requests: RequestCoalescer::new(TOPIC_ACTIVITY_MIN_INTERVAL),
+            refresh_requests: Arc::new(RequestCoalescer::new(POLL_MIN_INTERVAL)),
+            keyed_table_requests: RequestCoalescer::new(KEYED_TABLE_MIN_INTERVAL),
+            oauth_tokens: OAuthTokenCache::shared(),
+        }
+    }
+
+    /// Config for producers. Keyed records are partitioned like the Java
+    /// client does, which is what key watches and timelines look up through
+    /// `partitioner::default_partition`; librdkafka's own default hashes
+    /// keys with CRC32 instead.
+    fn create_producer_config(&self, cluster: &Cluster, password: Option<String>) -> ClientConfig {
+        let mut config = self.create_config(cluster, password);
+        config.set("partitioner", "murmur2_random");
+        config
     }
 
     fn create_config(&self, cluster: &Cluster, password: Option<String>) -> ClientConfig {
         let mut config = ClientConfig::new();
+        client_logs::tag_config(&mut config, cluster.id);
+        if !cluster.client_debug.is_empty() {
+            config.set("debug", cluster.client_debug.join(","));
+        }
         config.set("bootstrap.servers", &cluster.brokers);
+        config.set(
+            "statistics.interval.ms",
+            client_stats::STATS_INTERVAL_MS.to_string(),
+        );
+        config.set(
+            "client.id",
+            cluster
+                .client_id
+                .as_deref()
+                .filter(|id| !id.trim().is_empty())
+                .unwrap_or_else(|| default_client_id()),
+        );
+        if cluster.is_event_hubs() {
+            // Event Hubs drops connections idle for 240 s without telling the
+            // client; reconnect before that and keep metadata fresh enough to
+            // notice.
+            config.set("socket.keepalive.enable", "true");
+            config.set("connections.max.idle.ms", "180000");
+            config.set("metadata.max.age.ms", "180000");
+        }
 
         match &cluster.security {
             SecurityConfig::Plaintext => {
@@ -48,6 +228,7 @@ impl KafkaInfrastructure {
                 mechanism,
                 username,
                 ca_location,
+                ..
             } => {
                 config.set("security.protocol", "sasl_ssl");
                 let mech_str = match mechanism {
@@ -58,9 +239,13 @@ impl KafkaInfrastructure {
                     SaslMechanism::OAuthBearer => "OAUTHBEARER",
                 };
                 config.set("sasl.mechanism", mech_str);
-                config.set("sasl.username", username);
-                if let Some(p) = password {
-                    config.set("sasl.password", &p);
+                if let SaslMechanism::OAuthBearer = mechanism {
+                    self.oauth_tokens.apply(cluster.id, &mut config);
+                } else {
+                    config.set("sasl.username", username);
+                    if let Some(p) = password {
+                        config.set("sasl.password", &p);
+                    }
                 }
                 if let Some(ca) = ca_location {
                     config.set("ssl.ca.location", ca);
@@ -75,41 +260,175 @@ impl KafkaInfrastructure {
         cluster: &Cluster,
         password: Option<String>,
     ) -> Result<Vec<Topic>> {
-        let client: AdminClient<DefaultClientContext> =
-            self.create_config(cluster, password).create()?;
+        let client = self
+            .clients
+            .admin(cluster.id, &self.create_config(cluster, password))?;
+        let name = cluster.name.clone();
+        let brokers = cluster.brokers.clone();
 
-        println!(
-            "Fetching metadata for cluster: {} at {}",
-            cluster.name, cluster.brokers
-        );
-        let metadata = client
-            .inner()
-            .fetch_metadata(None, Duration::from_secs(5))
-            .map_err(|e| {
-                anyhow::anyhow!("Failed to fetch metadata from {}: {}", cluster.brokers, e)
-            })?;
+        self.topic_list_requests
+            .run_with_max_age(
+                cluster.id,
+                metadata_cache_ttl(cluster),
+                move || async move {
+                    tokio::task::spawn_blocking(move || fetch_topics(&client, &name, &brokers))
+                        .await?
+                },
+            )
+            .await
+    }
 
-        let topics = metadata
-            .topics()
+    /// The topic list with each topic's message count estimate and last
+    /// activity filled in from batched ListOffsets requests.
+    pub async fn list_topics_with_activity(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+    ) -> Result<Vec<Topic>> {
+        let mut topics = self.list_topics(cluster, password.clone()).await?;
+        let client = self
+            .clients
+            .admin(cluster.id, &self.create_config(cluster, password))?;
+        let partitions: Vec<(String, i32)> = topics
             .iter()
-            .map(|t| Topic {
-                name: t.name().to_string(),
-                partitions: t.partitions().len() as i32,
-                replication_factor: 1,
-            })
+            .flat_map(|t| t.partition_ids.iter().map(|p| (t.name.clone(), *p)))
             .collect();
 
-        println!("Successfully fetched {} topics", metadata.topics().len());
+        let activity = self
+            .topic_activity_requests
+            .run(cluster.id, move || async move {
+                tokio::task::spawn_blocking(move || fetch_topic_activity(&client, &partitions))
+                    .await?
+            })
+            .await?;
+        for topic in &mut topics {
+            if let Some(&(count, last_activity)) = activity.get(&topic.name) {
+                topic.message_count_estimate = Some(count);
+                topic.last_activity_ts = last_activity;
+            }
+        }
         Ok(topics)
     }
 
+    /// Current end offset of each listed partition, from one ListOffsets
+    /// request. Partitions the broker reports an error for are left out.
+    pub async fn get_end_offsets(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        partitions: Vec<(String, i32)>,
+    ) -> Result<HashMap<(String, i32), i64>> {
+        let client = self
+            .clients
+            .admin(cluster.id, &self.create_config(cluster, password))?;
+
+        let offsets = tokio::task::spawn_blocking(move || {
+            native_admin::list_offsets(&client, &partitions, native_admin::OffsetSpec::Latest)
+        })
+        .await??;
+        Ok(offsets
+            .into_iter()
+            .map(|(key, (offset, _))| (key, offset))
+            .collect())
+    }
+
+    /// librdkafka log lines captured for the cluster's clients, oldest first.
+    pub fn client_logs(&self, cluster_id: Uuid) -> Vec<ClientLogLine> {
+        client_logs::client_logs(cluster_id)
+    }
+
+    pub fn clear_client_logs(&self, cluster_id: Uuid) {
+        client_logs::clear_client_logs(cluster_id)
+    }
+
+    /// Drops the cluster's cached topic lists, message counts and activity,
+    /// so the next listing asks the brokers.
+    pub fn invalidate_topic_cache(&self, cluster_id: Uuid) {
+        self.topic_list_requests.invalidate(|id| *id == cluster_id);
+        self.message_count_requests
+            .invalidate(|(id, _)| *id == cluster_id);
+        self.topic_activity_requests
+            .invalidate(|id| *id == cluster_id);
+    }
+
+    /// Creates the cluster's pooled clients and fetches its metadata and
+    /// topic list, so the broker connections are established before the
+    /// first view needs them.
+    pub async fn warm_up(&self, cluster: &Cluster, password: Option<String>) -> Result<()> {
+        let config = self.create_config(cluster, password.clone());
+        let consumer = self.clients.consumer(cluster.id, &config)?;
+        tokio::task::spawn_blocking(move || {
+            consumer
+                .fetch_metadata(None, Duration::from_secs(5))
+                .map(|_| ())
+                .map_err(|e| anyhow::anyhow!("Failed to fetch metadata: {}", e))
+        })
+        .await??;
+
+        self.list_topics(cluster, password).await?;
+        Ok(())
+    }
+
+    /// Closes the cluster's pooled connections and drops its cached token.
+    pub fn release_clients(&self, cluster_id: Uuid) {
+        self.clients.release(cluster_id);
+        self.oauth_tokens.forget(cluster_id);
+    }
+
+    /// Makes sure an OAUTHBEARER cluster has a token that is not about to
+    /// expire, fetching one from its token endpoint if needed. `password` is
+    /// the client secret. Other clusters need nothing.
+    pub async fn refresh_oauth_token(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+    ) -> Result<()> {
+        let SecurityConfig::SaslSsl {
+            mechanism: SaslMechanism::OAuthBearer,
+            username,
+            token_endpoint,
+            oauth_scope,
+            ..
+        } = &cluster.security
+        else {
+            return Ok(());
+        };
+        let endpoint = token_endpoint.clone().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Cluster {} uses OAUTHBEARER but has no token endpoint",
+                cluster.name
+            )
+        })?;
+
+        self.oauth_tokens
+            .refresh(
+                cluster.id,
+                TokenRequest {
+                    endpoint,
+                    client_id: username.clone(),
+                    client_secret: password.unwrap_or_default(),
+                    scope: oauth_scope.clone(),
+                },
+            )
+            .await
+    }
+
+    pub fn oauth_token_status(&self, cluster_id: Uuid) -> TokenStatus {
+        self.oauth_tokens.status(cluster_id)
+    }
+
     pub async fn check_connection(
         &self,
         cluster: &Cluster,
         password: Option<String>,
     ) -> Result<()> {
-        let client: AdminClient<DefaultClientContext> =
-            self.create_config(cluster, password).create()?;
+        if cluster.certificate_pin.is_some() {
+            self.inspect_certificates(cluster, password).await?;
+            return Ok(());
+        }
+
+        let client: AdminClient<ClusterContext> =
+            create_client(&self.create_config(cluster, password))?;
 
         // Simple metadata fetch for a non-existent topic to test connectivity
         client
@@ -122,6 +441,45 @@ impl KafkaInfrastructure {
         Ok(())
     }
 
+    /// Certificates the cluster's brokers serve, checked against the
+    /// cluster's pin if it has one.
+    pub async fn inspect_certificates(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+    ) -> Result<Vec<BrokerCertificate>> {
+        if matches!(cluster.security, SecurityConfig::Plaintext) {
+            return Err(anyhow::anyhow!("Cluster {} does not use TLS", cluster.name));
+        }
+        let config = self.create_config(cluster, password);
+        let pin = cluster.certificate_pin.clone();
+
+        tokio::task::spawn_blocking(move || {
+            certificate_pin::inspect_certificates(&config, pin.as_deref())
+        })
+        .await?
+    }
+
+    /// Brokers currently in the cluster's metadata.
+    pub async fn count_live_brokers(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+    ) -> Result<usize> {
+        let client = self
+            .clients
+            .admin(cluster.id, &self.create_config(cluster, password))?;
+
+        tokio::task::spawn_blocking(move || {
+            let metadata = client
+                .inner()
+                .fetch_metadata(None, Duration::from_secs(5))
+                .map_err(|e| anyhow::anyhow!("Failed to fetch metadata: {}", e))?;
+            Ok(metadata.brokers().len())
+        })
+        .await?
+    }
+
     pub async fn create_topic(
         &self,
         cluster: &Cluster,
@@ -129,13 +487,17 @@ impl KafkaInfrastructure {
         name: String,
         partitions: i32,
         replication: i32,
+        configs: &HashMap<String, String>,
     ) -> Result<()> {
         use rdkafka::admin::{AdminOptions, NewTopic, TopicReplication};
 
-        let client: AdminClient<DefaultClientContext> =
-            self.create_config(cluster, password).create()?;
+        let client: AdminClient<ClusterContext> =
+            create_client(&self.create_config(cluster, password))?;
 
-        let new_topic = NewTopic::new(&name, partitions, TopicReplication::Fixed(replication));
+        let mut new_topic = NewTopic::new(&name, partitions, TopicReplication::Fixed(replication));
+        for (key, value) in configs {
+            new_topic = new_topic.set(key, value);
+        }
 
         let opts = AdminOptions::new().operation_timeout(Some(Duration::from_secs(30)));
 
@@ -157,6 +519,7 @@ impl KafkaInfrastructure {
             }
         }
 
+        self.invalidate_topic_cache(cluster.id);
         Ok(())
     }
 
@@ -168,8 +531,8 @@ impl KafkaInfrastructure {
     ) -> Result<()> {
         use rdkafka::admin::AdminOptions;
 
-        let client: AdminClient<DefaultClientContext> =
-            self.create_config(cluster, password).create()?;
+        let client: AdminClient<ClusterContext> =
+            create_client(&self.create_config(cluster, password))?;
 
         let opts = AdminOptions::new().operation_timeout(Some(Duration::from_secs(30)));
 
@@ -191,9 +554,11 @@ impl KafkaInfrastructure {
             }
         }
 
+        self.invalidate_topic_cache(cluster.id);
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn publish_message(
         &self,
         cluster: &Cluster,
@@ -201,145 +566,4492 @@ impl KafkaInfrastructure {
         topic: &str,
         key: Option<String>,
         payload: String,
-    ) -> Result<()> {
-        use rdkafka::producer::{FutureProducer, FutureRecord};
+        headers: &[MessageHeader],
+        cipher: Option<&PayloadCipher>,
+    ) -> Result<PublishResult> {
+        use rdkafka::error::KafkaError;
 
-        let producer: FutureProducer = self.create_config(cluster, password).create()?;
+        let producer: FutureProducer<ClusterContext> =
+            create_client(&self.create_producer_config(cluster, password))?;
 
+        let payload = match cipher {
+            Some(cipher) => cipher.encrypt(payload.as_bytes())?,
+            None => payload.into_bytes(),
+        };
         let mut record = FutureRecord::to(topic).payload(&payload);
 
         if let Some(ref k) = key {
             record = record.key(k);
         }
+        if !headers.is_empty() {
+            record = record.headers(to_owned_headers(headers)?);
+        }
 
-        producer
+        let delivery = producer
             .send(record, Duration::from_secs(5))
             .await
-            .map_err(|(e, _)| anyhow::anyhow!("Failed to publish message: {}", e))?;
+            .map_err(|(e, _)| match e {
+                KafkaError::MessageProduction(code) => anyhow::Error::new(DeliveryError {
+                    code: format!("{:?}", code),
+                    message: code.to_string(),
+                }),
+                e => anyhow::anyhow!("Failed to publish message: {}", e),
+            })?;
 
-        Ok(())
+        Ok(PublishResult {
+            partition: delivery.partition,
+            offset: delivery.offset,
+            timestamp: delivery.timestamp.to_millis(),
+        })
+    }
+
+    /// Produces the records concurrently through one producer and returns the
+    /// outcome of each, in record order. Payloads are encrypted with `cipher`
+    /// when given.
+    pub async fn publish_batch(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        records: &[BulkRecord],
+        cipher: Option<&PayloadCipher>,
+    ) -> Result<Vec<std::result::Result<PublishResult, BulkLineError>>> {
+        let producer: FutureProducer<ClusterContext> =
+            create_client(&self.create_producer_config(cluster, password))?;
+
+        let payloads = records
+            .iter()
+            .map(|r| match cipher {
+                Some(cipher) => cipher.encrypt(r.payload.as_bytes()),
+                None => Ok(r.payload.clone().into_bytes()),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let deliveries = records.iter().zip(&payloads).map(|(r, payload)| {
+            let mut record = FutureRecord::to(topic).payload(payload);
+            if let Some(ref k) = r.key {
+                record = record.key(k);
+            }
+            producer.send(record, Duration::from_secs(5))
+        });
+
+        Ok(futures::future::join_all(deliveries)
+            .await
+            .into_iter()
+            .zip(records)
+            .map(|(delivery, r)| {
+                delivery
+                    .map(|delivery| PublishResult {
+                        partition: delivery.partition,
+                        offset: delivery.offset,
+                        timestamp: delivery.timestamp.to_millis(),
+                    })
+                    .map_err(|(e, _)| BulkLineError {
+                        line: r.line,
+                        message: format!("Failed to publish message: {}", e),
+                    })
+            })
+            .collect())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub async fn consume_messages(
         &self,
         cluster: &Cluster,
         password: Option<String>,
         topic: &str,
+        partition: Option<i32>,
+        seek: &SeekMode,
         max_messages: usize,
-    ) -> Result<Vec<KafkaMessage>> {
-        let mut config = self.create_config(cluster, password);
-        config.set("group.id", format!("kafkust-consumer-{}", uuid::Uuid::new_v4()));
-        config.set("auto.offset.reset", "latest");
-        config.set("enable.auto.commit", "false");
+        decoder: &PayloadDecoder,
+        transforms: &TransformPipeline,
+        dedupe: Option<&DedupeSettings>,
+        matcher: &MessageMatcher,
+    ) -> Result<ConsumeResult> {
+        let mut messages = Vec::new();
+        let mut earliest = BTreeMap::new();
+        let visit = |msg: &BorrowedMessage<'_>| {
+            earliest
+                .entry(msg.partition())
+                .and_modify(|offset: &mut i64| *offset = (*offset).min(msg.offset()))
+                .or_insert(msg.offset());
+            let mut message = to_kafka_message(msg);
+            let identity = dedupe.and_then(|dedupe| match &dedupe.by {
+                DedupeKey::RecordKey => message.key.clone(),
+                DedupeKey::Header { name } => msg.headers().and_then(|headers| {
+                    headers
+                        .iter()
+                        .find(|h| h.key == name)
+                        .and_then(|h| h.value)
+                        .map(|v| String::from_utf8_lossy(v).to_string())
+                }),
+            });
+            if let Some(payload) = msg.payload() {
+                let decoded = decoder.decode(payload).and_then(|decoded| {
+                    if transforms.is_empty() {
+                        Ok(Some(decoded.into_owned()))
+                    } else {
+                        transforms.apply(&decoded)
+                    }
+                });
+                match decoded {
+                    Ok(Some(decoded)) => {
+                        message.payload = Some(String::from_utf8_lossy(&decoded).to_string())
+                    }
+                    // Filtered out by a transform.
+                    Ok(None) => return false,
+                    Err(e) => eprintln!(
+                        "Offset {} of partition {}: {}",
+                        msg.offset(),
+                        msg.partition(),
+                        e
+                    ),
+                }
+            }
+            if !matcher.matches(&message) {
+                return false;
+            }
+            messages.push((identity, message));
+            true
+        };
+        let outcome = if *seek == SeekMode::Latest && !matcher.is_empty() {
+            self.read_back(cluster, password, topic, partition, max_messages, visit)?
+        } else {
+            read_from(
+                self.create_config(cluster, password),
+                topic,
+                partition,
+                seek,
+                max_messages,
+                visit,
+            )?
+        };
 
-        let consumer: BaseConsumer = config.create()?;
+        let (mut messages, duplicates_removed) = match dedupe {
+            Some(dedupe) => dedupe_messages(messages, dedupe.keep),
+            None => (messages.into_iter().map(|(_, m)| m).collect(), 0),
+        };
+        messages.sort_by_key(|m| std::cmp::Reverse(m.offset));
 
-        let metadata = consumer
-            .fetch_metadata(Some(topic), Duration::from_secs(5))
-            .map_err(|e| anyhow::anyhow!("Failed to fetch topic metadata: {}", e))?;
+        // Partitions no record was read from keep their end, so the older
+        // page still covers them.
+        let mut cursor = outcome.ends;
+        for (partition, offset) in earliest {
+            let entry = cursor.entry(partition).or_insert(offset);
+            *entry = (*entry).min(offset);
+        }
+        // Reading back can find more matches than fit the page; the older
+        // page picks up from just above the newest one left out.
+        if messages.len() > max_messages {
+            let mut resume = BTreeMap::new();
+            for left_out in messages.drain(max_messages..) {
+                resume
+                    .entry(left_out.partition)
+                    .or_insert(left_out.offset + 1);
+            }
+            cursor.extend(resume);
+        }
 
-        let topic_metadata = metadata
-            .topics()
-            .iter()
-            .find(|t| t.name() == topic)
-            .ok_or_else(|| anyhow::anyhow!("Topic not found"))?;
+        Ok(ConsumeResult {
+            messages,
+            errors: outcome.errors,
+            duplicates_removed,
+            cursor,
+        })
+    }
 
-        let partition_count = topic_metadata.partitions().len() as i32;
+    /// The most recent records of a dead-letter topic with the source topic
+    /// and error their headers record, newest first.
+    pub async fn read_dlq_failures(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        limit: usize,
+    ) -> Result<Vec<DlqFailure>> {
+        let config = self.create_config(cluster, password);
+        let topic = topic.to_string();
 
-        let mut tpl = TopicPartitionList::new();
-        for p in 0..partition_count {
-            tpl.add_partition(topic, p);
-        }
+        tokio::task::spawn_blocking(move || -> Result<_> {
+            let mut failures = Vec::new();
+            read_latest(config, &topic, limit, |msg| {
+                let header = |names: &[&str]| {
+                    msg.headers().and_then(|headers| {
+                        headers
+                            .iter()
+                            .find(|h| names.contains(&h.key))
+                            .and_then(|h| h.value)
+                            .map(|v| String::from_utf8_lossy(v).to_string())
+                    })
+                };
+                failures.push(DlqFailure {
+                    partition: msg.partition(),
+                    offset: msg.offset(),
+                    timestamp: msg.timestamp().to_millis(),
+                    original_topic: header(ORIGINAL_TOPIC_HEADERS),
+                    error: header(ERROR_HEADERS),
+                });
+            })?;
 
-        let watermarks_result: Result<Vec<(i32, i64, i64)>, _> = (0..partition_count)
-            .map(|p| {
-                consumer
-                    .fetch_watermarks(topic, p, Duration::from_secs(5))
-                    .map(|(low, high)| (p, low, high))
-            })
-            .collect();
+            failures.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then(b.offset.cmp(&a.offset)));
+            failures.truncate(limit);
+            Ok(failures)
+        })
+        .await?
+    }
 
-        let watermarks = watermarks_result
-            .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+    /// Whether any of the topic's most recent records has a key.
+    pub async fn has_keyed_records(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+    ) -> Result<bool> {
+        let config = self.create_config(cluster, password);
+        let topic = topic.to_string();
 
-        let mut offset_tpl = TopicPartitionList::new();
-        for (partition, _low, high) in &watermarks {
-            let start_offset = (*high as usize).saturating_sub(max_messages / partition_count as usize);
-            offset_tpl
-                .add_partition_offset(topic, *partition, rdkafka::Offset::Offset(start_offset as i64))
-                .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
+        tokio::task::spawn_blocking(move || {
+            let mut keyed = false;
+            read_latest(config, &topic, KEY_SAMPLE_SIZE, |msg| {
+                keyed |= msg.key().is_some_and(|k| !k.is_empty());
+            })?;
+            Ok(keyed)
+        })
+        .await?
+    }
+
+    /// Raises the topic's partition count to `total`.
+    pub async fn create_partitions(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        total: usize,
+    ) -> Result<()> {
+        use rdkafka::admin::{AdminOptions, NewPartitions};
+
+        let client: AdminClient<ClusterContext> =
+            create_client(&self.create_config(cluster, password))?;
+
+        let opts = AdminOptions::new().operation_timeout(Some(Duration::from_secs(30)));
+
+        let results = client
+            .create_partitions(&[NewPartitions::new(topic, total)], &opts)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to add partitions: {}", e))?;
+
+        for result in results {
+            if let Err((topic, code)) = result {
+                return Err(anyhow::anyhow!(
+                    "Failed to add partitions to '{}': {:?}",
+                    topic,
+                    code
+                ));
+            }
         }
 
-        consumer
-            .assign(&offset_tpl)
-            .map_err(|e| anyhow::anyhow!("Failed to assign partitions: {}", e))?;
+        self.invalidate_topic_cache(cluster.id);
+        Ok(())
+    }
 
-        let mut messages = Vec::new();
-        let timeout = Duration::from_millis(100);
-        let max_attempts = 50;
+    /// Reads `topic` backwards from its end in windows until `visit` has kept
+    /// `max_messages` records or `MAX_SCAN_RECORDS` were scanned, so a
+    /// filtered browse finds matches older than the newest `max_messages`
+    /// records. Windows are read whole, so `visit` may keep more than asked.
+    fn read_back<F>(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        partition: Option<i32>,
+        max_messages: usize,
+        mut visit: F,
+    ) -> Result<ReadOutcome>
+    where
+        F: FnMut(&BorrowedMessage<'_>) -> bool,
+    {
+        let mut config = self.create_config(cluster, password);
+        config.set("enable.partition.eof", "true");
+        let (consumer, watermarks) = open_reader(config, topic, partition)?;
 
-        for _ in 0..max_attempts {
-            if messages.len() >= max_messages {
-                break;
+        let lows: BTreeMap<i32, i64> = watermarks.iter().map(|&(p, low, _)| (p, low)).collect();
+        let mut ends: BTreeMap<i32, i64> =
+            watermarks.iter().map(|&(p, _, high)| (p, high)).collect();
+        let per_partition = (BACKWARD_WINDOW_RECORDS / watermarks.len().max(1) as i64).max(1);
+
+        let mut errors = Vec::new();
+        walk_back(&lows, &mut ends, per_partition, max_messages, |windows| {
+            let mut tpl = TopicPartitionList::new();
+            for (&partition, &(start, _)) in windows {
+                tpl.add_partition_offset(topic, partition, rdkafka::Offset::Offset(start))
+                    .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
             }
+            consumer
+                .assign(&tpl)
+                .map_err(|e| anyhow::anyhow!("Failed to assign partitions: {}", e))?;
 
-            match consumer.poll(timeout) {
-                Some(Ok(msg)) => {
-                    let kafka_msg = KafkaMessage {
-                        partition: msg.partition(),
-                        offset: msg.offset(),
-                        timestamp: msg.timestamp().to_millis(),
-                        key: msg.key().map(|k| String::from_utf8_lossy(k).to_string()),
-                        payload: msg.payload().map(|p| String::from_utf8_lossy(p).to_string()),
-                    };
-                    messages.push(kafka_msg);
-                }
-                Some(Err(e)) => {
-                    eprintln!("Error consuming message: {}", e);
-                }
-                None => {
-                    if messages.is_empty() {
-                        continue;
+            let mut pending: HashMap<i32, i64> =
+                windows.iter().map(|(&p, &(_, end))| (p, end)).collect();
+            let mut kept = 0;
+            let mut scanned = 0;
+            let mut idle_polls = 0;
+            while !pending.is_empty() && idle_polls < SCAN_MAX_IDLE_POLLS {
+                match consumer.poll(Duration::from_millis(100)) {
+                    Some(Ok(msg)) => {
+                        idle_polls = 0;
+                        let Some(&end) = pending.get(&msg.partition()) else {
+                            continue;
+                        };
+                        if msg.offset() + 1 >= end {
+                            pending.remove(&msg.partition());
+                        }
+                        if msg.offset() >= end {
+                            continue;
+                        }
+                        scanned += 1;
+                        if visit(&msg) {
+                            kept += 1;
+                        }
                     }
-                    break;
+                    Some(Err(rdkafka::error::KafkaError::PartitionEOF(partition))) => {
+                        pending.remove(&partition);
+                    }
+                    Some(Err(e)) => {
+                        if record_fetch_error(&mut errors, &e) {
+                            return Ok(ControlFlow::Break(()));
+                        }
+                    }
+                    None => idle_polls += 1,
                 }
             }
-        }
+            Ok(ControlFlow::Continue((kept, scanned)))
+        })?;
 
-        messages.sort_by(|a, b| b.offset.cmp(&a.offset));
+        Ok(ReadOutcome { errors, ends })
+    }
 
-        Ok(messages)
+    /// Samples the latest records of a topic and counts them by the value of a
+    /// header or JSON field, showing the composition of mixed-event topics.
+    pub async fn get_field_breakdown(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        source: &BreakdownSource,
+        sample_size: usize,
+    ) -> Result<FieldBreakdown> {
+        let config = self.create_config(cluster, password);
+        let topic = topic.to_string();
+        let source = source.clone();
+
+        tokio::task::spawn_blocking(move || {
+            sample_field_breakdown(config, &topic, &source, sample_size)
+        })
+        .await?
     }
 
-    pub async fn get_topic_message_count(
+    pub fn create_test_consumer(
         &self,
         cluster: &Cluster,
         password: Option<String>,
         topic: &str,
-    ) -> Result<i64> {
+        group_id: &str,
+        from: AutoOffsetReset,
+    ) -> Result<TestConsumer> {
+        let mut config = self.create_config(cluster, password);
+        config.set("group.id", group_id);
+        config.set("auto.offset.reset", from.as_config_value());
+        config.set("enable.auto.commit", "false");
+
+        let consumer: StreamConsumer<ClusterContext> = create_client(&config)?;
+        consumer
+            .subscribe(&[topic])
+            .map_err(|e| anyhow::anyhow!("Failed to subscribe to {}: {}", topic, e))?;
+
+        Ok(TestConsumer { consumer })
+    }
+
+    pub fn create_health_monitor(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        repo: SqliteClusterRepository,
+        events: Arc<dyn EventSink>,
+    ) -> Result<HealthMonitor> {
+        let consumer: BaseConsumer<ClusterContext> =
+            create_client(&self.create_config(cluster, password))?;
+
+        Ok(HealthMonitor {
+            cluster_id: cluster.id,
+            consumer: Arc::new(consumer),
+            repo,
+            events,
+        })
+    }
+
+    /// Prepares a sampler recording the lag of `groups`, or of every group
+    /// on the cluster when empty.
+    pub fn create_lag_sampler(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        groups: Vec<String>,
+        interval: Duration,
+        retention_ms: i64,
+        repo: SqliteClusterRepository,
+    ) -> LagSampler {
+        LagSampler {
+            cluster_id: cluster.id,
+            config: self.create_config(cluster, password),
+            groups,
+            interval,
+            retention_ms,
+            repo,
+        }
+    }
+
+    /// Prepares an evaluator for the lag alert rules stored for `cluster`.
+    /// Rules are re-read on every tick so edits apply without a restart.
+    pub fn create_lag_alert_evaluator(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        repo: SqliteClusterRepository,
+        events: Arc<dyn EventSink>,
+    ) -> LagAlertEvaluator {
+        LagAlertEvaluator {
+            cluster_id: cluster.id,
+            cluster_name: cluster.name.clone(),
+            config: self.create_config(cluster, password),
+            repo,
+            events,
+        }
+    }
+
+    /// Prepares a poller that re-runs `target` every `interval`. Pollers of
+    /// the same read share one broker request per refresh window.
+    pub fn create_refresh_poller(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        target: RefreshTarget,
+        interval: Duration,
+        events: Arc<dyn EventSink>,
+    ) -> Result<RefreshPoller> {
         let config = self.create_config(cluster, password);
-        let consumer: BaseConsumer = config.create()?;
+        Ok(RefreshPoller {
+            cluster_id: cluster.id,
+            cluster_name: cluster.name.clone(),
+            brokers: cluster.brokers.clone(),
+            admin: self.clients.admin(cluster.id, &config)?,
+            consumer: self.clients.consumer(cluster.id, &config)?,
+            config,
+            target,
+            interval,
+            requests: self.refresh_requests.clone(),
+            events,
+        })
+    }
 
-        let metadata = consumer
-            .fetch_metadata(Some(topic), Duration::from_secs(5))
-            .map_err(|e| anyhow::anyhow!("Failed to fetch topic metadata: {}", e))?;
+    /// Prepares a watcher polling the watermarks of `topic` every `interval`.
+    pub fn create_watermark_watcher(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        interval: Duration,
+        events: Arc<dyn EventSink>,
+    ) -> Result<WatermarkWatcher> {
+        let config = self.create_config(cluster, password);
+        Ok(WatermarkWatcher {
+            consumer: self.clients.consumer(cluster.id, &config)?,
+            topic: topic.to_string(),
+            interval,
+            events,
+        })
+    }
 
-        let topic_metadata = metadata
-            .topics()
-            .iter()
-            .find(|t| t.name() == topic)
-            .ok_or_else(|| anyhow::anyhow!("Topic not found"))?;
+    pub fn create_key_watcher(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        key: &str,
+        limits: TailBufferLimits,
+    ) -> Result<KeyWatcher> {
+        let mut config = self.create_config(cluster, password);
+        config.set(
+            "group.id",
+            format!("kafkust-watch-{}", uuid::Uuid::new_v4()),
+        );
+        config.set("enable.auto.commit", "false");
+        // Keep librdkafka's own prefetch queue in line with the session's
+        // buffer, or pausing upstream would just move the backlog there.
+        config.set(
+            "queued.max.messages.kbytes",
+            (limits.max_bytes / 1024).max(1).to_string(),
+        );
 
-        let partition_count = topic_metadata.partitions().len() as i32;
+        let consumer: StreamConsumer<ClusterContext> = create_client(&config)?;
 
-        let mut total_messages: i64 = 0;
-        for p in 0..partition_count {
-            let (low, high) = consumer
-                .fetch_watermarks(topic, p, Duration::from_secs(5))
-                .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
-            total_messages += high - low;
+        let partition_count = fetch_partition_ids(&consumer, topic)?.len() as i32;
+        if partition_count == 0 {
+            return Err(anyhow::anyhow!("Topic not found"));
         }
 
-        Ok(total_messages)
+        let partition = partitioner::default_partition(key.as_bytes(), partition_count);
+
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition_offset(topic, partition, rdkafka::Offset::End)
+            .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
+        consumer
+            .assign(&tpl)
+            .map_err(|e| anyhow::anyhow!("Failed to assign partitions: {}", e))?;
+
+        Ok(KeyWatcher {
+            consumer,
+            key: key.as_bytes().to_vec(),
+            partition,
+            limits,
+        })
+    }
+
+    /// Prepares a tail of every partition of `topic`, starting at the end.
+    /// Tails read without a consumer group and commit nothing.
+    pub fn create_topic_tail(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        limits: TailBufferLimits,
+    ) -> Result<TopicTail> {
+        let mut config = self.create_config(cluster, password);
+        config.set("group.id", format!("kafkust-tail-{}", uuid::Uuid::new_v4()));
+        config.set("enable.auto.commit", "false");
+        config.set(
+            "queued.max.messages.kbytes",
+            (limits.max_bytes / 1024).max(1).to_string(),
+        );
+
+        let consumer: StreamConsumer<ClusterContext> = create_client(&config)?;
+
+        let partitions = fetch_partition_ids(&consumer, topic)?;
+        if partitions.is_empty() {
+            return Err(anyhow::anyhow!("Topic not found"));
+        }
+        let mut tpl = TopicPartitionList::new();
+        for partition in partitions {
+            tpl.add_partition_offset(topic, partition, rdkafka::Offset::End)
+                .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
+        }
+        consumer
+            .assign(&tpl)
+            .map_err(|e| anyhow::anyhow!("Failed to assign partitions: {}", e))?;
+
+        Ok(TopicTail { consumer, limits })
+    }
+
+    /// Prepares a scan of `topic` up to its current end offsets for records
+    /// keyed `key`. With `hashed_partition_only`, only the partition the
+    /// default partitioner maps the key to is read.
+    pub fn create_key_search(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        key: &str,
+        hashed_partition_only: bool,
+    ) -> Result<KeySearch> {
+        let mut config = self.create_config(cluster, password);
+        config.set(
+            "group.id",
+            format!("kafkust-search-{}", uuid::Uuid::new_v4()),
+        );
+        config.set("enable.auto.commit", "false");
+        config.set("enable.partition.eof", "true");
+        let consumer: StreamConsumer<ClusterContext> = create_client(&config)?;
+
+        let mut partitions = fetch_partition_ids(&consumer, topic)?;
+        if partitions.is_empty() {
+            return Err(anyhow::anyhow!("Topic not found"));
+        }
+        if hashed_partition_only {
+            partitions = vec![partitioner::default_partition(
+                key.as_bytes(),
+                partitions.len() as i32,
+            )];
+        }
+
+        let mut start_offsets = HashMap::new();
+        let mut end_offsets = HashMap::new();
+        let mut tpl = TopicPartitionList::new();
+        for &p in &partitions {
+            let (low, high) = consumer
+                .fetch_watermarks(topic, p, Duration::from_secs(5))
+                .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+            if high > low {
+                start_offsets.insert(p, low);
+                end_offsets.insert(p, high);
+                tpl.add_partition_offset(topic, p, rdkafka::Offset::Offset(low))
+                    .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
+            }
+        }
+        consumer
+            .assign(&tpl)
+            .map_err(|e| anyhow::anyhow!("Failed to assign partitions: {}", e))?;
+
+        Ok(KeySearch {
+            consumer,
+            key: key.to_string(),
+            partitions,
+            start_offsets,
+            end_offsets,
+        })
+    }
+
+    /// Prepares an export of `topic` up to its current end offsets into shard
+    /// files under `directory`. With `only`, just the named shards are
+    /// written again.
+    pub fn create_topic_export(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        directory: PathBuf,
+        sharding: ExportSharding,
+        only: Option<HashSet<String>>,
+    ) -> Result<TopicExport> {
+        let mut config = self.create_config(cluster, password);
+        config.set(
+            "group.id",
+            format!("kafkust-export-{}", uuid::Uuid::new_v4()),
+        );
+        config.set("enable.auto.commit", "false");
+        config.set("enable.partition.eof", "true");
+        let consumer: StreamConsumer<ClusterContext> = create_client(&config)?;
+
+        let partitions = fetch_partition_ids(&consumer, topic)?;
+        if partitions.is_empty() {
+            return Err(anyhow::anyhow!("Topic not found"));
+        }
+
+        let mut end_offsets = HashMap::new();
+        let mut tpl = TopicPartitionList::new();
+        for p in partitions {
+            // Partition shards can be re-run without reading the others.
+            if sharding == ExportSharding::Partition
+                && only
+                    .as_ref()
+                    .is_some_and(|only| !only.contains(&sharding.shard_name(p, None)))
+            {
+                continue;
+            }
+            let (low, high) = consumer
+                .fetch_watermarks(topic, p, Duration::from_secs(5))
+                .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+            if high > low {
+                end_offsets.insert(p, high);
+                tpl.add_partition_offset(topic, p, rdkafka::Offset::Beginning)
+                    .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
+            }
+        }
+        consumer
+            .assign(&tpl)
+            .map_err(|e| anyhow::anyhow!("Failed to assign partitions: {}", e))?;
+
+        std::fs::create_dir_all(&directory)
+            .map_err(|e| anyhow::anyhow!("Failed to create export directory: {}", e))?;
+
+        Ok(TopicExport {
+            consumer,
+            cluster_id: cluster.id,
+            topic: topic.to_string(),
+            directory,
+            sharding,
+            only,
+            end_offsets,
+        })
+    }
+
+    /// Prepares a copy of `source` into `destination` up to the source's
+    /// current end offsets, re-keying records on the way. Copied records are
+    /// logged to `receipts` when given.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_replay(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        source: &str,
+        destination: &str,
+        rekey: RekeyStrategy,
+        transforms: TransformPipeline,
+        receipts: Option<ReceiptLog>,
+    ) -> Result<Replay> {
+        if source == destination {
+            return Err(anyhow::anyhow!("Source and destination topics must differ"));
+        }
+
+        let mut config = self.create_config(cluster, password.clone());
+        config.set(
+            "group.id",
+            format!("kafkust-replay-{}", uuid::Uuid::new_v4()),
+        );
+        config.set("enable.auto.commit", "false");
+        config.set("enable.partition.eof", "true");
+        let consumer: StreamConsumer<ClusterContext> = create_client(&config)?;
+
+        let partitions = fetch_partition_ids(&consumer, source)?;
+        if partitions.is_empty() {
+            return Err(anyhow::anyhow!("Topic not found"));
+        }
+        if fetch_partition_ids(&consumer, destination)?.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Destination topic {} not found",
+                destination
+            ));
+        }
+
+        let mut end_offsets = HashMap::new();
+        let mut tpl = TopicPartitionList::new();
+        for p in partitions {
+            let (low, high) = consumer
+                .fetch_watermarks(source, p, Duration::from_secs(5))
+                .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+            if high > low {
+                end_offsets.insert(p, high);
+                tpl.add_partition_offset(source, p, rdkafka::Offset::Beginning)
+                    .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
+            }
+        }
+        consumer
+            .assign(&tpl)
+            .map_err(|e| anyhow::anyhow!("Failed to assign partitions: {}", e))?;
+
+        let producer: FutureProducer<ClusterContext> =
+            create_client(&self.create_producer_config(cluster, password))?;
+
+        Ok(Replay {
+            consumer,
+            producer,
+            destination: destination.to_string(),
+            rekey,
+            transforms,
+            end_offsets,
+            receipts,
+        })
+    }
+
+    /// Prepares an exactly-once check against `topic`: a transactional
+    /// producer and a read_committed consumer positioned at the current end
+    /// of every partition, so only records written by this run are read.
+    /// Committed records are logged to `receipts` as they are read back.
+    pub fn create_eos_verification(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        message_count: u64,
+        receipts: Option<ReceiptLog>,
+    ) -> Result<EosVerification> {
+        let run = Uuid::new_v4();
+
+        let mut config = self.create_config(cluster, password.clone());
+        config.set("group.id", format!("kafkust-eos-{}", run));
+        config.set("enable.auto.commit", "false");
+        config.set("isolation.level", "read_committed");
+        let consumer: StreamConsumer<ClusterContext> = create_client(&config)?;
+
+        let partitions = fetch_partition_ids(&consumer, topic)?;
+        if partitions.is_empty() {
+            return Err(anyhow::anyhow!("Topic not found"));
+        }
+        let mut tpl = TopicPartitionList::new();
+        for p in partitions {
+            let (_, high) = consumer
+                .fetch_watermarks(topic, p, Duration::from_secs(5))
+                .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+            tpl.add_partition_offset(topic, p, rdkafka::Offset::Offset(high))
+                .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
+        }
+        consumer
+            .assign(&tpl)
+            .map_err(|e| anyhow::anyhow!("Failed to assign partitions: {}", e))?;
+
+        let mut config = self.create_producer_config(cluster, password);
+        config.set("transactional.id", format!("kafkust-eos-{}", run));
+        config.set("enable.idempotence", "true");
+        let producer: BaseProducer<ClusterContext> = create_client(&config)?;
+
+        Ok(EosVerification {
+            consumer,
+            producer,
+            topic: topic.to_string(),
+            run,
+            message_count,
+            receipts,
+        })
+    }
+
+    /// Counts records per time bucket by resolving the offset at every bucket
+    /// boundary, so no payloads are transferred.
+    pub async fn get_topic_histogram(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        from_ts: i64,
+        to_ts: i64,
+        bucket_ms: i64,
+    ) -> Result<Vec<HistogramBucket>> {
+        if bucket_ms <= 0 || to_ts <= from_ts {
+            return Err(anyhow::anyhow!(
+                "Invalid histogram range: bucket size and time range must be positive"
+            ));
+        }
+        let bucket_count = (to_ts - from_ts + bucket_ms - 1) / bucket_ms;
+        if bucket_count > MAX_HISTOGRAM_BUCKETS {
+            return Err(anyhow::anyhow!(
+                "Histogram would have {} buckets (max {}); use a larger bucket size",
+                bucket_count,
+                MAX_HISTOGRAM_BUCKETS
+            ));
+        }
+
+        let boundaries: Vec<i64> = (0..=bucket_count)
+            .map(|i| (from_ts + i * bucket_ms).min(to_ts))
+            .collect();
+        let config = self.create_config(cluster, password);
+        let topic = topic.to_string();
+
+        tokio::task::spawn_blocking(move || count_between_boundaries(config, &topic, &boundaries))
+            .await?
+    }
+
+    /// Partition count and replication factor of a single topic, or `None`
+    /// when the topic does not exist.
+    pub async fn describe_topic(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+    ) -> Result<Option<Topic>> {
+        let client = self
+            .clients
+            .admin(cluster.id, &self.create_config(cluster, password))?;
+
+        let metadata = client
+            .inner()
+            .fetch_metadata(Some(topic), Duration::from_secs(5))
+            .map_err(|e| anyhow::anyhow!("Failed to fetch metadata: {}", e))?;
+
+        Ok(metadata
+            .topics()
+            .iter()
+            .find(|t| t.name() == topic && t.error().is_none())
+            .map(to_topic))
+    }
+
+    /// Leader, replicas and ISR of every partition in the cluster.
+    pub async fn describe_partitions(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+    ) -> Result<HashMap<String, Vec<Partition>>> {
+        let consumer = self
+            .clients
+            .consumer(cluster.id, &self.create_config(cluster, password))?;
+        tokio::task::spawn_blocking(move || fetch_partitions(&*consumer)).await?
+    }
+
+    /// The record at `offset` of one partition with up to `context` records on
+    /// each side, in offset order. Compacted or deleted records leave gaps.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_messages_around(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        partition: i32,
+        offset: i64,
+        context: usize,
+        decoder: &PayloadDecoder,
+    ) -> Result<Vec<KafkaMessage>> {
+        let mut config = self.create_config(cluster, password);
+        config.set(
+            "group.id",
+            format!("kafkust-lookup-{}", uuid::Uuid::new_v4()),
+        );
+        config.set("enable.auto.commit", "false");
+        let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+
+        if !fetch_partition_ids(&consumer, topic)?.contains(&partition) {
+            return Err(anyhow::anyhow!(
+                "Partition {} does not exist in {}",
+                partition,
+                topic
+            ));
+        }
+        let (low, high) = consumer
+            .fetch_watermarks(topic, partition, Duration::from_secs(5))
+            .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+        if offset < low || offset >= high {
+            return Err(anyhow::anyhow!(
+                "Offset {} is outside partition {} ({}..{})",
+                offset,
+                partition,
+                low,
+                high
+            ));
+        }
+
+        let context = context as i64;
+        let start = offset.saturating_sub(context).max(low);
+        let end = offset.saturating_add(context + 1).min(high);
+
+        let mut tpl = TopicPartitionList::new();
+        tpl.add_partition_offset(topic, partition, rdkafka::Offset::Offset(start))
+            .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
+        consumer
+            .assign(&tpl)
+            .map_err(|e| anyhow::anyhow!("Failed to assign partitions: {}", e))?;
+
+        let mut messages = Vec::new();
+        let mut idle_polls = 0;
+        while idle_polls < SCAN_MAX_IDLE_POLLS {
+            match consumer.poll(Duration::from_millis(200)) {
+                Some(Ok(msg)) => {
+                    idle_polls = 0;
+                    if msg.offset() >= end {
+                        break;
+                    }
+                    let mut message = to_kafka_message(&msg);
+                    if let Some(payload) = msg.payload() {
+                        match decoder.decode(payload) {
+                            Ok(decoded) => {
+                                message.payload =
+                                    Some(String::from_utf8_lossy(&decoded).to_string())
+                            }
+                            Err(e) => eprintln!(
+                                "Offset {} of partition {}: {}",
+                                msg.offset(),
+                                msg.partition(),
+                                e
+                            ),
+                        }
+                    }
+                    messages.push(message);
+                    if msg.offset() + 1 >= end {
+                        break;
+                    }
+                }
+                Some(Err(e)) => eprintln!("Error reading around offset {}: {}", offset, e),
+                None => idle_polls += 1,
+            }
+        }
+
+        Ok(messages)
+    }
+
+    /// Leader, replicas, ISR and watermarks of each partition of `topic`.
+    pub async fn describe_topic_partitions(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+    ) -> Result<Vec<Partition>> {
+        let consumer = self
+            .clients
+            .consumer(cluster.id, &self.create_config(cluster, password))?;
+        let topic = topic.to_string();
+
+        tokio::task::spawn_blocking(move || fetch_partition_watermarks(&consumer, &topic)).await?
+    }
+
+    pub async fn describe_topic_configs(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topics: &[String],
+    ) -> Result<HashMap<String, Vec<TopicConfigEntry>>> {
+        use rdkafka::admin::{AdminOptions, OwnedResourceSpecifier, ResourceSpecifier};
+
+        if topics.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let client: AdminClient<ClusterContext> =
+            create_client(&self.create_config(cluster, password))?;
+
+        let specifiers: Vec<ResourceSpecifier> = topics
+            .iter()
+            .map(|t| ResourceSpecifier::Topic(t.as_str()))
+            .collect();
+        let opts = AdminOptions::new().request_timeout(Some(Duration::from_secs(10)));
+
+        let results = client
+            .describe_configs(&specifiers, &opts)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to describe topic configs: {}", e))?;
+
+        let mut configs = HashMap::new();
+        for result in results {
+            let resource = result
+                .map_err(|code| anyhow::anyhow!("Failed to describe topic config: {:?}", code))?;
+            if let OwnedResourceSpecifier::Topic(name) = resource.specifier {
+                let entries = resource
+                    .entries
+                    .into_iter()
+                    .map(|entry| TopicConfigEntry {
+                        source: config_entry_source(&entry.source),
+                        name: entry.name,
+                        value: entry.value,
+                        is_read_only: entry.is_read_only,
+                        is_default: entry.is_default,
+                        is_sensitive: entry.is_sensitive,
+                        cached_at: None,
+                    })
+                    .collect();
+                configs.insert(name, entries);
+            }
+        }
+
+        Ok(configs)
+    }
+
+    /// Sets `configs` on a topic. AlterConfigs replaces the whole set of
+    /// overrides, so the topic's current dynamic configs are carried over.
+    pub async fn alter_topic_configs(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        configs: &HashMap<String, String>,
+    ) -> Result<()> {
+        use rdkafka::admin::{AdminOptions, AlterConfig, ResourceSpecifier};
+
+        let current = self
+            .describe_topic_configs(cluster, password.clone(), &[topic.to_string()])
+            .await?
+            .remove(topic)
+            .unwrap_or_default();
+        let mut merged: HashMap<&str, &str> = current
+            .iter()
+            .filter(|entry| entry.source == ConfigEntrySource::DynamicTopic)
+            .filter_map(|entry| Some((entry.name.as_str(), entry.value.as_deref()?)))
+            .collect();
+        for (name, value) in configs {
+            merged.insert(name, value);
+        }
+
+        let client: AdminClient<ClusterContext> =
+            create_client(&self.create_config(cluster, password))?;
+        let mut alter = AlterConfig::new(ResourceSpecifier::Topic(topic));
+        for (name, value) in merged {
+            alter = alter.set(name, value);
+        }
+        let opts = AdminOptions::new().request_timeout(Some(Duration::from_secs(10)));
+
+        let results = client
+            .alter_configs(&[alter], &opts)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to alter topic configs: {}", e))?;
+        for result in results {
+            result.map_err(|(_, code)| {
+                anyhow::anyhow!("Failed to alter configs of '{}': {:?}", topic, code)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Tries each `PermissionCheck` as the cluster's principal. Topic creation
+    /// and config changes are sent validate-only, so nothing is modified;
+    /// brokers authorize those requests before validating them, so any
+    /// error other than an authorization failure counts as allowed.
+    pub async fn probe_permissions(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+    ) -> Result<Vec<PermissionProbe>> {
+        use rdkafka::admin::{
+            AdminOptions, AlterConfig, NewTopic, ResourceSpecifier, TopicReplication,
+        };
+
+        let config = self.create_config(cluster, password);
+        let blocking_config = config.clone();
+        let (topics, groups) = tokio::task::spawn_blocking(move || -> Result<_> {
+            let consumer: BaseConsumer<ClusterContext> = create_client(&blocking_config)?;
+            let metadata = consumer
+                .fetch_metadata(None, Duration::from_secs(10))
+                .map_err(|e| anyhow::anyhow!("Failed to fetch metadata: {}", e))?;
+            let topics: Vec<String> = metadata
+                .topics()
+                .iter()
+                .filter(|t| t.error().is_none())
+                .map(|t| t.name().to_string())
+                .collect();
+            let groups = consumer
+                .fetch_group_list(None, Duration::from_secs(10))
+                .map(|groups| groups.groups().len());
+            Ok((topics, groups))
+        })
+        .await??;
+
+        let probe = |check, status, detail: Option<String>| PermissionProbe {
+            check,
+            status,
+            detail,
+        };
+        let mut probes = Vec::new();
+
+        probes.push(if topics.is_empty() {
+            probe(
+                PermissionCheck::DescribeTopics,
+                PermissionStatus::Unknown,
+                Some("No topics visible; the cluster is empty or Describe is denied".into()),
+            )
+        } else {
+            probe(
+                PermissionCheck::DescribeTopics,
+                PermissionStatus::Allowed,
+                Some(format!("{} topics visible", topics.len())),
+            )
+        });
+
+        probes.push(match groups {
+            Ok(0) => probe(
+                PermissionCheck::ReadGroups,
+                PermissionStatus::Unknown,
+                Some("No groups visible; none exist or Describe is denied".into()),
+            ),
+            Ok(count) => probe(
+                PermissionCheck::ReadGroups,
+                PermissionStatus::Allowed,
+                Some(format!("{} groups visible", count)),
+            ),
+            Err(e) => {
+                let status = match e.rdkafka_error_code() {
+                    Some(code) if is_authorization_failure(code) => PermissionStatus::Denied,
+                    _ => PermissionStatus::Unknown,
+                };
+                probe(PermissionCheck::ReadGroups, status, Some(e.to_string()))
+            }
+        });
+
+        let client: AdminClient<ClusterContext> = create_client(&config)?;
+        let opts = AdminOptions::new()
+            .validate_only(true)
+            .request_timeout(Some(Duration::from_secs(10)));
+
+        let probe_topic = format!("kafkust-permission-probe-{}", Uuid::new_v4().simple());
+        let new_topic = NewTopic::new(&probe_topic, 1, TopicReplication::Fixed(1));
+        probes.push(match client.create_topics(&[new_topic], &opts).await {
+            Ok(results) => match results.into_iter().next() {
+                Some(Err((_, code))) if is_authorization_failure(code) => probe(
+                    PermissionCheck::CreateTopics,
+                    PermissionStatus::Denied,
+                    Some(format!("{:?}", code)),
+                ),
+                Some(Err((_, code))) => probe(
+                    PermissionCheck::CreateTopics,
+                    PermissionStatus::Allowed,
+                    Some(format!("Authorized; validation reported {:?}", code)),
+                ),
+                _ => probe(
+                    PermissionCheck::CreateTopics,
+                    PermissionStatus::Allowed,
+                    None,
+                ),
+            },
+            Err(e) => probe(
+                PermissionCheck::CreateTopics,
+                PermissionStatus::Unknown,
+                Some(e.to_string()),
+            ),
+        });
+
+        // Altering configs is authorized per topic, so it is tried on the
+        // first visible application topic with an empty change.
+        probes.push(
+            match topics.iter().find(|t| internal_topic_kind(t).is_none()) {
+                None => probe(
+                    PermissionCheck::AlterConfigs,
+                    PermissionStatus::Unknown,
+                    Some("No topic to try on".into()),
+                ),
+                Some(topic) => {
+                    let alter = AlterConfig::new(ResourceSpecifier::Topic(topic));
+                    match client.alter_configs(&[alter], &opts).await {
+                        Ok(results) => match results.into_iter().next() {
+                            Some(Err((_, code))) if is_authorization_failure(code) => probe(
+                                PermissionCheck::AlterConfigs,
+                                PermissionStatus::Denied,
+                                Some(format!("{:?} on {}", code, topic)),
+                            ),
+                            Some(Err((_, code))) => probe(
+                                PermissionCheck::AlterConfigs,
+                                PermissionStatus::Allowed,
+                                Some(format!(
+                                    "Authorized on {}; validation reported {:?}",
+                                    topic, code
+                                )),
+                            ),
+                            _ => probe(
+                                PermissionCheck::AlterConfigs,
+                                PermissionStatus::Allowed,
+                                Some(format!("Tried on {}", topic)),
+                            ),
+                        },
+                        Err(e) => probe(
+                            PermissionCheck::AlterConfigs,
+                            PermissionStatus::Unknown,
+                            Some(e.to_string()),
+                        ),
+                    }
+                }
+            },
+        );
+
+        Ok(probes)
+    }
+
+    pub async fn list_group_ids(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+    ) -> Result<Vec<String>> {
+        let consumer: BaseConsumer<ClusterContext> =
+            create_client(&self.create_config(cluster, password))?;
+
+        let groups = consumer
+            .fetch_group_list(None, Duration::from_secs(10))
+            .map_err(|e| anyhow::anyhow!("Failed to list consumer groups: {}", e))?;
+
+        Ok(groups
+            .groups()
+            .iter()
+            .map(|g| g.name().to_string())
+            .collect())
+    }
+
+    pub async fn list_consumer_groups(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+    ) -> Result<Vec<ConsumerGroup>> {
+        let consumer: BaseConsumer<ClusterContext> =
+            create_client(&self.create_config(cluster, password))?;
+
+        fetch_consumer_groups(&consumer)
+    }
+
+    pub async fn describe_consumer_group(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        group_id: &str,
+    ) -> Result<ConsumerGroupDetail> {
+        let config = self.create_config(cluster, password);
+        let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+
+        let groups = consumer
+            .fetch_group_list(Some(group_id), Duration::from_secs(10))
+            .map_err(|e| anyhow::anyhow!("Failed to describe consumer group: {}", e))?;
+        let group = groups
+            .groups()
+            .iter()
+            .find(|g| g.name() == group_id)
+            .ok_or_else(|| anyhow::anyhow!("Consumer group {} not found", group_id))?;
+
+        // ListGroups does not carry static membership ids. Brokers older than
+        // DescribeGroups v4 cannot report them, so a failure leaves them unset.
+        let name = group_id.to_string();
+        let instance_ids = tokio::task::spawn_blocking(move || {
+            let client: AdminClient<ClusterContext> = create_client(&config)?;
+            native_admin::describe_group_instance_ids(&client, &name)
+        })
+        .await?
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to fetch static membership of {}: {}", group_id, e);
+            HashMap::new()
+        });
+
+        let is_consumer = group.protocol_type() == "consumer";
+        let members = group
+            .members()
+            .iter()
+            .map(|m| {
+                let assignments = match m.assignment() {
+                    Some(bytes) if is_consumer && !bytes.is_empty() => {
+                        consumer_protocol::decode_assignment(bytes)?
+                    }
+                    _ => Vec::new(),
+                };
+                Ok(ConsumerGroupMember {
+                    member_id: m.id().to_string(),
+                    client_id: m.client_id().to_string(),
+                    client_host: m.client_host().to_string(),
+                    group_instance_id: instance_ids.get(m.id()).cloned().flatten(),
+                    assignments,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(ConsumerGroupDetail {
+            group_id: group.name().to_string(),
+            state: group.state().to_string(),
+            protocol_type: group.protocol_type().to_string(),
+            protocol: group.protocol().to_string(),
+            duplicate_instance_ids: duplicate_instance_ids(&members),
+            members,
+        })
+    }
+
+    /// All records with `key` in the partition the default partitioner maps
+    /// it to, keeping the newest `limit` in offset order.
+    pub async fn get_key_timeline(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        key: &str,
+        limit: usize,
+    ) -> Result<KeyTimeline> {
+        let config = self.create_config(cluster, password);
+        let topic = topic.to_string();
+        let key = key.to_string();
+
+        tokio::task::spawn_blocking(move || scan_key_timeline(config, &topic, &key, limit)).await?
+    }
+
+    /// Replays a changelog topic from the beginning and keeps the latest value
+    /// per key, dropping keys whose latest record is a tombstone.
+    pub async fn read_state_store(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        limit: usize,
+    ) -> Result<StateStoreSnapshot> {
+        let table = self
+            .materialize_topic_by_key(cluster, password, topic, true)
+            .await?;
+
+        Ok(StateStoreSnapshot {
+            topic: topic.to_string(),
+            entries: table.entries.iter().take(limit).cloned().collect(),
+            records_scanned: table.records_scanned,
+            deleted_keys: table.deleted_keys,
+            complete: table.complete,
+        })
+    }
+
+    /// Decodes MirrorMaker 2 internal topics into replication flows and the
+    /// latest checkpoint/offset-sync per partition.
+    pub async fn get_mirroring_status(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        internal_topics: Vec<Mm2InternalTopic>,
+    ) -> Result<MirroringStatus> {
+        let config = self.create_config(cluster, password);
+
+        tokio::task::spawn_blocking(move || read_mirroring_status(config, internal_topics)).await?
+    }
+
+    /// Client quotas described by the brokers, and how close the app's own
+    /// clients are to the quotas in force for them.
+    pub async fn get_quota_utilization(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+    ) -> Result<QuotaUtilization> {
+        let config = self.create_config(cluster, password);
+        let quotas = broker_protocol::describe_client_quotas(&config).await?;
+
+        // Principal the brokers match user quotas against. The name in a
+        // client certificate is not known here.
+        let user = match &cluster.security {
+            SecurityConfig::Plaintext => Some("ANONYMOUS".to_string()),
+            SecurityConfig::Ssl { .. } => None,
+            SecurityConfig::SaslSsl {
+                mechanism: SaslMechanism::OAuthBearer,
+                ..
+            } => oauth_tokens::config_token(&config).map(|t| t.principal),
+            SecurityConfig::SaslSsl { username, .. } => Some(username.clone()),
+        };
+        let clients = client_stats::throughput(cluster.id)
+            .iter()
+            .map(|t| quota_usage(&quotas, user.as_deref(), t))
+            .collect();
+
+        Ok(QuotaUtilization {
+            cluster_id: cluster.id,
+            sampled_at: now_millis(),
+            quotas,
+            clients,
+        })
+    }
+
+    pub async fn describe_acls(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+    ) -> Result<Vec<AclBinding>> {
+        let config = self.create_config(cluster, password);
+
+        tokio::task::spawn_blocking(move || {
+            let client: AdminClient<ClusterContext> = create_client(&config)?;
+            native_admin::describe_acls(&client)
+        })
+        .await?
+    }
+
+    pub async fn create_acls(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        acls: Vec<AclBinding>,
+    ) -> Result<()> {
+        let config = self.create_config(cluster, password);
+
+        tokio::task::spawn_blocking(move || {
+            let client: AdminClient<ClusterContext> = create_client(&config)?;
+            native_admin::create_acls(&client, &acls)
+        })
+        .await?
+    }
+
+    pub async fn delete_acls(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        acls: Vec<AclBinding>,
+    ) -> Result<()> {
+        let config = self.create_config(cluster, password);
+
+        tokio::task::spawn_blocking(move || {
+            let client: AdminClient<ClusterContext> = create_client(&config)?;
+            native_admin::delete_acls(&client, &acls)
+        })
+        .await?
+    }
+
+    /// Removes a group's committed offsets for the topic's partitions (or
+    /// only `partitions`), leaving its other offsets and the group itself in
+    /// place.
+    pub async fn delete_group_topic_offsets(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        group_id: &str,
+        topic: &str,
+        partitions: Option<Vec<i32>>,
+    ) -> Result<()> {
+        let config = self.create_config(cluster, password);
+        let group_id = group_id.to_string();
+        let topic = topic.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let client: AdminClient<ClusterContext> = create_client(&config)?;
+            let partitions = match partitions {
+                Some(partitions) => partitions,
+                None => client
+                    .inner()
+                    .fetch_metadata(Some(&topic), Duration::from_secs(5))
+                    .map_err(|e| anyhow::anyhow!("Failed to fetch metadata: {}", e))?
+                    .topics()
+                    .iter()
+                    .find(|t| t.name() == topic && t.error().is_none())
+                    .ok_or_else(|| anyhow::anyhow!("Topic {} not found", topic))?
+                    .partitions()
+                    .iter()
+                    .map(|p| p.id())
+                    .collect(),
+            };
+
+            native_admin::delete_group_offsets(&client, &group_id, &topic, &partitions)
+        })
+        .await?
+    }
+
+    /// Deletes the records of each listed partition below its offset; `None`
+    /// empties the partition.
+    pub async fn delete_records(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: String,
+        offsets: Vec<(i32, Option<i64>)>,
+    ) -> Result<Vec<PartitionTruncation>> {
+        let config = self.create_config(cluster, password);
+        let offsets: Vec<(i32, rdkafka::Offset)> = offsets
+            .into_iter()
+            .map(|(partition, offset)| {
+                (
+                    partition,
+                    offset.map_or(rdkafka::Offset::End, rdkafka::Offset::Offset),
+                )
+            })
+            .collect();
+
+        tokio::task::spawn_blocking(move || {
+            let client: AdminClient<ClusterContext> = create_client(&config)?;
+            native_admin::delete_records(&client, &topic, &offsets)
+        })
+        .await?
+    }
+
+    pub async fn scram_user_exists(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        user: String,
+    ) -> Result<bool> {
+        let config = self.create_config(cluster, password);
+
+        tokio::task::spawn_blocking(move || {
+            let client: AdminClient<ClusterContext> = create_client(&config)?;
+            native_admin::scram_user_exists(&client, &user)
+        })
+        .await?
+    }
+
+    pub async fn upsert_scram_user(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        user: String,
+        mechanism: SaslMechanism,
+        user_password: String,
+    ) -> Result<()> {
+        let config = self.create_config(cluster, password);
+
+        tokio::task::spawn_blocking(move || {
+            let client: AdminClient<ClusterContext> = create_client(&config)?;
+            native_admin::upsert_scram_credential(&client, &user, &mechanism, &user_password)
+        })
+        .await?
+    }
+
+    pub async fn delete_scram_user(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        user: String,
+        mechanism: SaslMechanism,
+    ) -> Result<()> {
+        let config = self.create_config(cluster, password);
+
+        tokio::task::spawn_blocking(move || {
+            let client: AdminClient<ClusterContext> = create_client(&config)?;
+            native_admin::delete_scram_credential(&client, &user, &mechanism)
+        })
+        .await?
+    }
+
+    /// Committed offsets of a group across all topics. The group need not be
+    /// active; offsets are read from the group coordinator.
+    pub async fn fetch_committed_offsets(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        group_id: &str,
+    ) -> Result<Vec<CommittedOffset>> {
+        let mut config = self.create_config(cluster, password);
+        config.set("group.id", group_id);
+        config.set("enable.auto.commit", "false");
+        let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+
+        fetch_group_offsets(&consumer)
+    }
+
+    /// Groups with committed offsets on `topic` or members assigned to it.
+    pub async fn find_topic_consumers(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+    ) -> Result<Vec<TopicConsumer>> {
+        let config = self.create_config(cluster, password);
+        let topic = topic.to_string();
+
+        tokio::task::spawn_blocking(move || fetch_topic_consumers(config, &topic)).await?
+    }
+
+    pub async fn get_consumer_group_lag(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        group_id: &str,
+    ) -> Result<ConsumerGroupLag> {
+        let config = self.create_config(cluster, password);
+        let group_id = group_id.to_string();
+
+        tokio::task::spawn_blocking(move || fetch_group_lag(config, &group_id)).await?
+    }
+
+    /// Deletes a group and its committed offsets. The broker only deletes
+    /// groups without active members.
+    pub async fn delete_consumer_group(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        group_id: &str,
+    ) -> Result<()> {
+        use rdkafka::admin::AdminOptions;
+        use rdkafka::types::RDKafkaErrorCode;
+
+        let client: AdminClient<ClusterContext> =
+            create_client(&self.create_config(cluster, password))?;
+
+        let opts = AdminOptions::new().operation_timeout(Some(Duration::from_secs(30)));
+
+        let results = client
+            .delete_groups(&[group_id], &opts)
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to delete consumer group: {}", e))?;
+
+        for result in results {
+            match result {
+                Ok(_) => {}
+                Err((group, RDKafkaErrorCode::NonEmptyGroup)) => {
+                    return Err(anyhow::anyhow!(
+                        "Consumer group '{}' still has active members; stop them before deleting it",
+                        group
+                    ));
+                }
+                Err((group, RDKafkaErrorCode::GroupIdNotFound)) => {
+                    return Err(anyhow::anyhow!("Consumer group '{}' not found", group));
+                }
+                Err((group, code)) => {
+                    return Err(anyhow::anyhow!(
+                        "Failed to delete consumer group '{}': {:?}",
+                        group,
+                        code
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Computes the offsets a reset would commit for the topic's partitions
+    /// (or only `partitions`), without committing anything.
+    pub async fn plan_offset_reset(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        group_id: &str,
+        topic: &str,
+        partitions: Option<Vec<i32>>,
+        target: OffsetResetTarget,
+    ) -> Result<Vec<PartitionOffsetReset>> {
+        let mut config = self.create_config(cluster, password);
+        config.set("group.id", group_id);
+        config.set("enable.auto.commit", "false");
+        let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+
+        let existing = fetch_partition_ids(&consumer, topic)?;
+        if existing.is_empty() {
+            return Err(anyhow::anyhow!("Topic not found"));
+        }
+        let partitions = match partitions {
+            Some(partitions) => {
+                if let Some(p) = partitions.iter().find(|p| !existing.contains(p)) {
+                    return Err(anyhow::anyhow!(
+                        "Partition {} does not exist in {}",
+                        p,
+                        topic
+                    ));
+                }
+                partitions
+            }
+            None => existing,
+        };
+
+        let mut tpl = TopicPartitionList::new();
+        for p in &partitions {
+            tpl.add_partition(topic, *p);
+        }
+        let committed = consumer
+            .committed_offsets(tpl, Duration::from_secs(10))
+            .map_err(|e| anyhow::anyhow!("Failed to fetch committed offsets: {}", e))?;
+
+        let for_times = match target {
+            OffsetResetTarget::Timestamp(ts) => {
+                let mut tpl = TopicPartitionList::new();
+                for p in &partitions {
+                    tpl.add_partition_offset(topic, *p, rdkafka::Offset::Offset(ts))
+                        .map_err(|e| anyhow::anyhow!("Failed to set timestamp: {}", e))?;
+                }
+                Some(
+                    consumer
+                        .offsets_for_times(tpl, Duration::from_secs(10))
+                        .map_err(|e| {
+                            anyhow::anyhow!("Failed to resolve offsets for times: {}", e)
+                        })?,
+                )
+            }
+            _ => None,
+        };
+
+        let mut plan = Vec::with_capacity(partitions.len());
+        for p in partitions {
+            let (low, high) = consumer
+                .fetch_watermarks(topic, p, Duration::from_secs(5))
+                .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+            let before = match committed.find_partition(topic, p).map(|e| e.offset()) {
+                Some(rdkafka::Offset::Offset(offset)) => Some(offset),
+                _ => None,
+            };
+
+            let after = match target {
+                OffsetResetTarget::Earliest => low,
+                OffsetResetTarget::Latest => high,
+                OffsetResetTarget::Offset(offset) => offset,
+                OffsetResetTarget::Timestamp(_) => match for_times
+                    .as_ref()
+                    .and_then(|t| t.find_partition(topic, p))
+                    .map(|e| e.offset())
+                {
+                    Some(rdkafka::Offset::Offset(offset)) => offset,
+                    // No record at or after the time.
+                    _ => high,
+                },
+                OffsetResetTarget::ShiftBy(n) => before.unwrap_or(low).saturating_add(n),
+            };
+
+            plan.push(PartitionOffsetReset {
+                topic: topic.to_string(),
+                partition: p,
+                before,
+                after: after.clamp(low, high),
+                before_timestamp: None,
+                after_timestamp: None,
+            });
+        }
+
+        Ok(plan)
+    }
+
+    /// Commits offsets on behalf of a group. The group must have no active
+    /// members, or the coordinator rejects the commit.
+    pub async fn commit_group_offsets(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        group_id: &str,
+        offsets: &[PartitionOffsetReset],
+    ) -> Result<()> {
+        let mut config = self.create_config(cluster, password);
+        config.set("group.id", group_id);
+        config.set("enable.auto.commit", "false");
+        let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+
+        let mut tpl = TopicPartitionList::new();
+        for reset in offsets {
+            tpl.add_partition_offset(
+                &reset.topic,
+                reset.partition,
+                rdkafka::Offset::Offset(reset.after),
+            )
+            .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
+        }
+
+        consumer
+            .commit(&tpl, CommitMode::Sync)
+            .map_err(|e| anyhow::anyhow!("Failed to commit offsets: {}", e))
+    }
+
+    /// Timestamp of the record at each position, or `None` when the position
+    /// is at the end of the partition (nothing to read yet).
+    pub async fn fetch_record_timestamps(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        positions: &[CommittedOffset],
+    ) -> Result<HashMap<(String, i32), Option<i64>>> {
+        let mut config = self.create_config(cluster, password);
+        config.set(
+            "group.id",
+            format!("kafkust-lookup-{}", uuid::Uuid::new_v4()),
+        );
+        config.set("enable.auto.commit", "false");
+        let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+
+        let mut timestamps = HashMap::new();
+        let mut tpl = TopicPartitionList::new();
+        for pos in positions {
+            let (_low, high) = consumer
+                .fetch_watermarks(&pos.topic, pos.partition, Duration::from_secs(5))
+                .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+            if pos.offset >= high {
+                timestamps.insert((pos.topic.clone(), pos.partition), None);
+            } else {
+                tpl.add_partition_offset(
+                    &pos.topic,
+                    pos.partition,
+                    rdkafka::Offset::Offset(pos.offset),
+                )
+                .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
+            }
+        }
+
+        let mut pending = tpl.count();
+        if pending > 0 {
+            consumer
+                .assign(&tpl)
+                .map_err(|e| anyhow::anyhow!("Failed to assign partitions: {}", e))?;
+
+            let mut idle_polls = 0;
+            while pending > 0 && idle_polls < SCAN_MAX_IDLE_POLLS {
+                match consumer.poll(Duration::from_millis(200)) {
+                    Some(Ok(msg)) => {
+                        idle_polls = 0;
+                        let key = (msg.topic().to_string(), msg.partition());
+                        if let Entry::Vacant(slot) = timestamps.entry(key) {
+                            slot.insert(msg.timestamp().to_millis());
+                            pending -= 1;
+                        }
+                    }
+                    Some(Err(e)) => eprintln!("Error reading record timestamps: {}", e),
+                    None => idle_polls += 1,
+                }
+            }
+        }
+
+        Ok(timestamps)
+    }
+
+    /// Earliest offset per partition whose timestamp is at or after the given
+    /// time; `None` timestamps and times past the newest record map to the end
+    /// of the partition.
+    pub async fn resolve_offsets_for_times(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        timestamps: &[(i32, Option<i64>)],
+    ) -> Result<HashMap<i32, i64>> {
+        let consumer: BaseConsumer<ClusterContext> =
+            create_client(&self.create_config(cluster, password))?;
+
+        let mut high_watermarks = HashMap::new();
+        let mut tpl = TopicPartitionList::new();
+        for (partition, ts) in timestamps {
+            let (_low, high) = consumer
+                .fetch_watermarks(topic, *partition, Duration::from_secs(5))
+                .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+            high_watermarks.insert(*partition, high);
+            if let Some(ts) = ts {
+                tpl.add_partition_offset(topic, *partition, rdkafka::Offset::Offset(*ts))
+                    .map_err(|e| anyhow::anyhow!("Failed to set timestamp: {}", e))?;
+            }
+        }
+
+        let mut resolved = high_watermarks.clone();
+        if tpl.count() > 0 {
+            let offsets = consumer
+                .offsets_for_times(tpl, Duration::from_secs(10))
+                .map_err(|e| anyhow::anyhow!("Failed to resolve offsets for times: {}", e))?;
+            for elem in offsets.elements_for_topic(topic) {
+                if let rdkafka::Offset::Offset(offset) = elem.offset() {
+                    resolved.insert(elem.partition(), offset);
+                }
+            }
+        }
+
+        Ok(resolved)
+    }
+
+    /// The latest record of each key of `topic`, by key, from a full scan
+    /// that is reused for a minute so paging through the table scans once.
+    /// `refresh` forces a new scan.
+    pub async fn materialize_topic_by_key(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+        refresh: bool,
+    ) -> Result<Arc<KeyedTable>> {
+        let key = (cluster.id, topic.to_string());
+        if refresh {
+            self.keyed_table_requests.invalidate(|k| *k == key);
+        }
+        let config = self.create_config(cluster, password);
+        let topic = topic.to_string();
+
+        self.keyed_table_requests
+            .run(key, move || async move {
+                tokio::task::spawn_blocking(move || scan_latest_by_key(config, &topic))
+                    .await?
+                    .map(Arc::new)
+            })
+            .await
+    }
+
+    pub async fn get_topic_message_count(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topic: &str,
+    ) -> Result<i64> {
+        let consumer = self
+            .clients
+            .consumer(cluster.id, &self.create_config(cluster, password))?;
+        let topic = topic.to_string();
+
+        self.message_count_requests
+            .run_with_max_age(
+                (cluster.id, topic.clone()),
+                metadata_cache_ttl(cluster),
+                move || async move {
+                    tokio::task::spawn_blocking(move || fetch_message_count(&consumer, &topic))
+                        .await?
+                },
+            )
+            .await
+    }
+}
+
+/// What a `read_from` or `read_back` covered.
+struct ReadOutcome {
+    errors: Vec<FetchError>,
+    /// Where an older page ends per assigned partition, before lowering it to
+    /// the earliest record read: the high watermark or `SeekMode::Before`
+    /// bound for `read_from`, the start of the oldest window for `read_back`.
+    ends: BTreeMap<i32, i64>,
+}
+
+struct ScanOutcome {
+    scanned: usize,
+    complete: bool,
+}
+
+const SCAN_MAX_IDLE_POLLS: u32 = 15;
+
+/// Sessions reading up to fixed end offsets learn a partition is done from
+/// its end-of-partition event: on transactional topics the last offset is a
+/// commit marker that is never delivered. Receives that time out in a row
+/// end the session in case an event never comes.
+const SESSION_RECEIVE_TIMEOUT: Duration = Duration::from_millis(200);
+const SESSION_MAX_IDLE_RECEIVES: u32 = 15;
+
+/// A consumer that participates in a real consumer group and commits what it
+/// reads, standing in for a downstream application during producer testing.
+pub struct TestConsumer {
+    consumer: StreamConsumer<ClusterContext>,
+}
+
+impl TestConsumer {
+    pub async fn run(self, mut ctx: SessionContext) -> Result<()> {
+        loop {
+            tokio::select! {
+                state = ctx.changed() => match state {
+                    Some(state) => {
+                        apply_session_state(&self.consumer, state)?;
+                    }
+                    None => break,
+                },
+                // Keeps polling while paused: the partitions are paused
+                // instead, and a group member that stops polling is evicted
+                // after max.poll.interval.ms.
+                msg = self.consumer.recv() => match msg {
+                    Ok(msg) => {
+                        self.consumer
+                            .commit_message(&msg, CommitMode::Async)
+                            .map_err(|e| anyhow::anyhow!("Failed to commit offset: {}", e))?;
+                        ctx.record_processed(1);
+                    }
+                    Err(e) => eprintln!("Test consumer {} error: {}", ctx.id, e),
+                },
+            }
+        }
+
+        // Flush the latest position before leaving the group.
+        let _ = self.consumer.commit_consumer_state(CommitMode::Sync);
+        Ok(())
+    }
+}
+
+/// Writes a topic's records up to the end offsets seen when it was created
+/// to shard files and a manifest, then finishes on its own.
+pub struct TopicExport {
+    consumer: StreamConsumer<ClusterContext>,
+    cluster_id: Uuid,
+    topic: String,
+    directory: PathBuf,
+    sharding: ExportSharding,
+    only: Option<HashSet<String>>,
+    end_offsets: HashMap<i32, i64>,
+}
+
+impl TopicExport {
+    pub async fn run(self, mut ctx: SessionContext, events: Arc<dyn EventSink>) -> Result<()> {
+        let mut remaining = self.end_offsets.clone();
+        let mut shards: HashMap<String, (BufWriter<std::fs::File>, ExportShard)> = HashMap::new();
+        let mut records = 0;
+        let mut paused = false;
+        let mut idle_receives = 0;
+
+        while !remaining.is_empty() {
+            tokio::select! {
+                state = ctx.changed() => match state {
+                    Some(state) => {
+                        paused = apply_session_state(&self.consumer, state)?;
+                        if paused {
+                            // Checkpoint what was written so far while the
+                            // export sleeps.
+                            for (writer, shard) in shards.values_mut() {
+                                writer.flush().map_err(|e| {
+                                    anyhow::anyhow!("Failed to write {}: {}", shard.file, e)
+                                })?;
+                            }
+                        }
+                    }
+                    None => break,
+                },
+                msg = tokio::time::timeout(SESSION_RECEIVE_TIMEOUT, self.consumer.recv()),
+                    if !paused => match msg {
+                    Ok(Ok(msg)) => {
+                        idle_receives = 0;
+                        let Some(&end) = remaining.get(&msg.partition()) else {
+                            continue;
+                        };
+                        if msg.offset() + 1 >= end {
+                            remaining.remove(&msg.partition());
+                        }
+                        if msg.offset() >= end {
+                            continue;
+                        }
+
+                        let file = self
+                            .sharding
+                            .shard_name(msg.partition(), msg.timestamp().to_millis());
+                        if self.only.as_ref().is_some_and(|only| !only.contains(&file)) {
+                            continue;
+                        }
+                        let (writer, shard) = match shards.entry(file) {
+                            Entry::Occupied(entry) => entry.into_mut(),
+                            Entry::Vacant(entry) => {
+                                let path = self.directory.join(entry.key());
+                                let out = std::fs::File::create(&path).map_err(|e| {
+                                    anyhow::anyhow!("Failed to create {:?}: {}", path, e)
+                                })?;
+                                let shard = ExportShard::new(entry.key().clone());
+                                entry.insert((BufWriter::new(out), shard))
+                            }
+                        };
+
+                        serde_json::to_writer(&mut *writer, &to_kafka_message(&msg))?;
+                        writer
+                            .write_all(b"\n")
+                            .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", shard.file, e))?;
+                        shard.add(msg.partition(), msg.offset());
+                        records += 1;
+                        ctx.record_processed(1);
+                    }
+                    Ok(Err(rdkafka::error::KafkaError::PartitionEOF(partition))) => {
+                        remaining.remove(&partition);
+                    }
+                    Ok(Err(e)) => eprintln!("Export {} error: {}", ctx.id, e),
+                    Err(_) => {
+                        idle_receives += 1;
+                        if idle_receives >= SESSION_MAX_IDLE_RECEIVES {
+                            break;
+                        }
+                    }
+                },
+            }
+        }
+
+        let completed = remaining.is_empty();
+        let mut written = Vec::with_capacity(shards.len());
+        for (_, (mut writer, mut shard)) in shards {
+            writer
+                .flush()
+                .map_err(|e| anyhow::anyhow!("Failed to write {}: {}", shard.file, e))?;
+            // A partition shard is whole once its partition reached the end,
+            // even if the export was stopped while reading another.
+            shard.complete = completed
+                || (self.sharding == ExportSharding::Partition
+                    && shard
+                        .ranges
+                        .iter()
+                        .all(|r| !remaining.contains_key(&r.partition)));
+            written.push(shard);
+        }
+        written.sort_by(|a, b| a.file.cmp(&b.file));
+
+        let mut manifest = ExportManifest {
+            cluster_id: self.cluster_id,
+            topic: self.topic.clone(),
+            sharding: self.sharding.clone(),
+            exported_at: now_millis(),
+            shards: written,
+        };
+        let manifest_path = self.directory.join(MANIFEST_FILE);
+        if self.only.is_some() {
+            let previous = std::fs::read(&manifest_path)
+                .ok()
+                .and_then(|json| serde_json::from_slice::<ExportManifest>(&json).ok());
+            if let Some(previous) = previous {
+                manifest = previous.merge(manifest);
+            }
+        }
+        std::fs::write(&manifest_path, serde_json::to_vec_pretty(&manifest)?)
+            .map_err(|e| anyhow::anyhow!("Failed to write export manifest: {}", e))?;
+
+        let summary = ExportSummary {
+            session_id: ctx.id,
+            directory: self.directory.to_string_lossy().into_owned(),
+            records,
+            manifest,
+            completed,
+        };
+        events.emit("export-finished", serde_json::to_value(summary)?);
+        Ok(())
+    }
+}
+
+/// Copies a topic's records up to the end offsets seen when it was created,
+/// then finishes on its own.
+pub struct Replay {
+    consumer: StreamConsumer<ClusterContext>,
+    producer: FutureProducer<ClusterContext>,
+    destination: String,
+    rekey: RekeyStrategy,
+    transforms: TransformPipeline,
+    end_offsets: HashMap<i32, i64>,
+    receipts: Option<ReceiptLog>,
+}
+
+impl Replay {
+    pub async fn run(self, mut ctx: SessionContext, events: Arc<dyn EventSink>) -> Result<()> {
+        let mut remaining = self.end_offsets.clone();
+        let mut replayed = 0;
+        let mut skipped = 0;
+        let mut filtered = 0;
+        let mut paused = false;
+        let mut idle_receives = 0;
+        let mut produced = Vec::new();
+
+        while !remaining.is_empty() {
+            tokio::select! {
+                state = ctx.changed() => match state {
+                    Some(state) => paused = apply_session_state(&self.consumer, state)?,
+                    None => break,
+                },
+                msg = tokio::time::timeout(SESSION_RECEIVE_TIMEOUT, self.consumer.recv()),
+                    if !paused => match msg {
+                    Ok(Ok(msg)) => {
+                        idle_receives = 0;
+                        let Some(&end) = remaining.get(&msg.partition()) else {
+                            continue;
+                        };
+                        if msg.offset() + 1 >= end {
+                            remaining.remove(&msg.partition());
+                        }
+                        if msg.offset() >= end {
+                            continue;
+                        }
+
+                        let payload = match msg.payload() {
+                            Some(payload) if !self.transforms.is_empty() => {
+                                match self.transforms.apply(payload) {
+                                    Ok(Some(out)) => Some(Cow::Owned(out)),
+                                    Ok(None) => {
+                                        filtered += 1;
+                                        continue;
+                                    }
+                                    Err(e) => {
+                                        eprintln!(
+                                            "Replay {} skipped offset {} of partition {}: {}",
+                                            ctx.id,
+                                            msg.offset(),
+                                            msg.partition(),
+                                            e
+                                        );
+                                        skipped += 1;
+                                        continue;
+                                    }
+                                }
+                            }
+                            payload => payload.map(Cow::Borrowed),
+                        };
+
+                        let key = match rekey(&self.rekey, msg.key(), payload.as_deref()) {
+                            Ok(key) => key,
+                            Err(e) => {
+                                eprintln!(
+                                    "Replay {} skipped offset {} of partition {}: {}",
+                                    ctx.id,
+                                    msg.offset(),
+                                    msg.partition(),
+                                    e
+                                );
+                                skipped += 1;
+                                continue;
+                            }
+                        };
+
+                        let mut record = FutureRecord::<[u8], [u8]>::to(&self.destination);
+                        if let Some(ref payload) = payload {
+                            record = record.payload(payload.as_ref());
+                        }
+                        if let Some(ref key) = key {
+                            record = record.key(key.as_slice());
+                        }
+                        if let Some(timestamp) = msg.timestamp().to_millis() {
+                            record = record.timestamp(timestamp);
+                        }
+                        if let Some(headers) = msg.headers() {
+                            record = record.headers(headers.detach());
+                        }
+
+                        let sent = self.producer.send(record, Duration::from_secs(10)).await;
+                        let delivery = match sent {
+                            Ok(delivery) => delivery,
+                            Err((e, _)) => {
+                                flush_receipts(self.receipts.as_ref(), &mut produced).await?;
+                                return Err(anyhow::anyhow!("Failed to replay record: {}", e));
+                            }
+                        };
+                        replayed += 1;
+                        ctx.record_processed(1);
+                        if self.receipts.is_some() {
+                            produced.push((
+                                payload_digest(payload.as_deref().unwrap_or_default()),
+                                PublishResult {
+                                    partition: delivery.partition,
+                                    offset: delivery.offset,
+                                    timestamp: delivery.timestamp.to_millis(),
+                                },
+                            ));
+                            if produced.len() >= RECEIPT_FLUSH_RECORDS {
+                                flush_receipts(self.receipts.as_ref(), &mut produced).await?;
+                            }
+                        }
+                    }
+                    Ok(Err(rdkafka::error::KafkaError::PartitionEOF(partition))) => {
+                        remaining.remove(&partition);
+                    }
+                    Ok(Err(e)) => eprintln!("Replay {} error: {}", ctx.id, e),
+                    Err(_) => {
+                        idle_receives += 1;
+                        if idle_receives >= SESSION_MAX_IDLE_RECEIVES {
+                            break;
+                        }
+                    }
+                },
+            }
+        }
+        flush_receipts(self.receipts.as_ref(), &mut produced).await?;
+
+        let summary = ReplaySummary {
+            session_id: ctx.id,
+            replayed,
+            skipped,
+            filtered,
+            completed: remaining.is_empty(),
+        };
+        events.emit("replay-finished", serde_json::to_value(summary)?);
+        Ok(())
+    }
+}
+
+pub struct EosVerification {
+    consumer: StreamConsumer<ClusterContext>,
+    producer: BaseProducer<ClusterContext>,
+    topic: String,
+    run: Uuid,
+    message_count: u64,
+    receipts: Option<ReceiptLog>,
+}
+
+/// Records written by a verification run, split by transaction outcome.
+#[derive(Default)]
+struct EosProduction {
+    committed: u64,
+    aborted: u64,
+}
+
+impl EosVerification {
+    pub async fn run(self, mut ctx: SessionContext, events: Arc<dyn EventSink>) -> Result<()> {
+        let producer = self.producer;
+        let topic = self.topic.clone();
+        let run = self.run;
+        let count = self.message_count;
+        let mut production = tokio::task::spawn_blocking(move || {
+            produce_eos_sequence(&producer, &topic, run, count)
+        });
+
+        let key = run.to_string();
+        let mut tracker = SequenceTracker::default();
+        let mut produced: Option<Result<EosProduction>> = None;
+        let mut paused = false;
+        let mut committed = Vec::new();
+
+        loop {
+            if let Some(Ok(ref stats)) = produced {
+                if tracker.has_all(stats.committed) {
+                    break;
+                }
+            }
+            tokio::select! {
+                state = ctx.changed() => match state {
+                    Some(state) => paused = apply_session_state(&self.consumer, state)?,
+                    None => break,
+                },
+                result = &mut production, if produced.is_none() => {
+                    let result = result
+                        .map_err(|e| anyhow::anyhow!("Producer task failed: {}", e))
+                        .and_then(|r| r);
+                    let failed = result.is_err();
+                    produced = Some(result);
+                    if failed {
+                        break;
+                    }
+                },
+                msg = self.consumer.recv(), if !paused => match msg {
+                    Ok(msg) => {
+                        if msg.key() != Some(key.as_bytes()) {
+                            continue;
+                        }
+                        let Some(payload) = msg.payload() else {
+                            continue;
+                        };
+                        let Ok(record) = serde_json::from_slice::<EosRecord>(payload) else {
+                            continue;
+                        };
+                        tracker.record(&record);
+                        ctx.record_processed(1);
+                        if self.receipts.is_some() {
+                            committed.push((
+                                payload_digest(payload),
+                                PublishResult {
+                                    partition: msg.partition(),
+                                    offset: msg.offset(),
+                                    timestamp: msg.timestamp().to_millis(),
+                                },
+                            ));
+                            if committed.len() >= RECEIPT_FLUSH_RECORDS {
+                                flush_receipts(self.receipts.as_ref(), &mut committed).await?;
+                            }
+                        }
+                    }
+                    Err(e) => eprintln!("EOS verification {} error: {}", ctx.id, e),
+                },
+                _ = tokio::time::sleep(EOS_DRAIN_TIMEOUT), if produced.is_some() && !paused => break,
+            }
+        }
+        flush_receipts(self.receipts.as_ref(), &mut committed).await?;
+
+        let report = match produced {
+            Some(Ok(stats)) => tracker.report(ctx.id, stats.committed, stats.aborted, None),
+            Some(Err(e)) => tracker.report(ctx.id, 0, 0, Some(e.to_string())),
+            None => tracker.report(
+                ctx.id,
+                0,
+                0,
+                Some("Stopped before production finished".to_string()),
+            ),
+        };
+        events.emit("eos-verification-finished", serde_json::to_value(report)?);
+        Ok(())
+    }
+}
+
+/// Writes sequence numbers `0..count` in committed transactions of
+/// `EOS_BATCH_SIZE`, interleaving an aborted copy of every
+/// `EOS_ABORT_EVERY`th batch. Every record shares the run's key, so they all
+/// land on one partition and their order is meaningful.
+fn produce_eos_sequence(
+    producer: &BaseProducer<ClusterContext>,
+    topic: &str,
+    run: Uuid,
+    count: u64,
+) -> Result<EosProduction> {
+    producer
+        .init_transactions(EOS_TRANSACTION_TIMEOUT)
+        .map_err(|e| anyhow::anyhow!("Failed to initialize transactions: {}", e))?;
+
+    let key = run.to_string();
+    let mut stats = EosProduction::default();
+    let mut batch = 0;
+    let mut start = 0;
+    while start < count {
+        let end = (start + EOS_BATCH_SIZE).min(count);
+
+        if batch % EOS_ABORT_EVERY == EOS_ABORT_EVERY - 1 {
+            producer
+                .begin_transaction()
+                .map_err(|e| anyhow::anyhow!("Failed to begin transaction: {}", e))?;
+            for seq in start..end {
+                send_eos_record(producer, topic, &key, run, seq, true)?;
+            }
+            producer
+                .abort_transaction(EOS_TRANSACTION_TIMEOUT)
+                .map_err(|e| anyhow::anyhow!("Failed to abort transaction: {}", e))?;
+            stats.aborted += end - start;
+        }
+
+        producer
+            .begin_transaction()
+            .map_err(|e| anyhow::anyhow!("Failed to begin transaction: {}", e))?;
+        for seq in start..end {
+            send_eos_record(producer, topic, &key, run, seq, false)?;
+        }
+        producer
+            .commit_transaction(EOS_TRANSACTION_TIMEOUT)
+            .map_err(|e| anyhow::anyhow!("Failed to commit transaction: {}", e))?;
+        stats.committed = end;
+
+        batch += 1;
+        start = end;
+    }
+    Ok(stats)
+}
+
+fn send_eos_record(
+    producer: &BaseProducer<ClusterContext>,
+    topic: &str,
+    key: &str,
+    run: Uuid,
+    seq: u64,
+    aborted: bool,
+) -> Result<()> {
+    let payload = serde_json::to_vec(&EosRecord { run, seq, aborted })?;
+    loop {
+        match producer.send(BaseRecord::to(topic).key(key).payload(&payload)) {
+            Ok(()) => return Ok(()),
+            Err((
+                rdkafka::error::KafkaError::MessageProduction(
+                    rdkafka::types::RDKafkaErrorCode::QueueFull,
+                ),
+                _,
+            )) => {
+                producer.poll(Duration::from_millis(100));
+            }
+            Err((e, _)) => return Err(anyhow::anyhow!("Failed to produce record: {}", e)),
+        }
+    }
+}
+
+/// Logs and clears the receipts collected by a session, if it keeps any.
+async fn flush_receipts(
+    receipts: Option<&ReceiptLog>,
+    produced: &mut Vec<(String, PublishResult)>,
+) -> Result<()> {
+    if let Some(receipts) = receipts {
+        receipts.record(produced).await?;
+    }
+    produced.clear();
+    Ok(())
+}
+
+/// New key for a replayed record; `None` produces it without a key.
+fn rekey(
+    strategy: &RekeyStrategy,
+    key: Option<&[u8]>,
+    payload: Option<&[u8]>,
+) -> Result<Option<Vec<u8>>> {
+    let payload = || -> Result<serde_json::Value> {
+        let bytes = payload.ok_or_else(|| anyhow::anyhow!("Record has no payload"))?;
+        serde_json::from_slice(bytes).map_err(|e| anyhow::anyhow!("Payload is not JSON: {}", e))
+    };
+    let select = |value: &serde_json::Value, path: &str| -> Result<String> {
+        json_path::select(value, path)
+            .map(json_path::display)
+            .ok_or_else(|| anyhow::anyhow!("Path {} not found in payload", path))
+    };
+
+    match strategy {
+        RekeyStrategy::Keep => Ok(key.map(<[u8]>::to_vec)),
+        RekeyStrategy::JsonPath(path) => Ok(Some(select(&payload()?, path)?.into_bytes())),
+        RekeyStrategy::Template(template) => {
+            let value = payload()?;
+            let mut key = String::new();
+            let mut rest = template.as_str();
+            while let Some(start) = rest.find('{') {
+                let end = rest[start..]
+                    .find('}')
+                    .map(|i| start + i)
+                    .ok_or_else(|| anyhow::anyhow!("Unclosed placeholder in key template"))?;
+                key.push_str(&rest[..start]);
+                key.push_str(&select(&value, &rest[start + 1..end])?);
+                rest = &rest[end + 1..];
+            }
+            key.push_str(rest);
+            Ok(Some(key.into_bytes()))
+        }
+    }
+}
+
+/// Records per-topic group lag on an interval and prunes samples past the
+/// retention period.
+pub struct LagSampler {
+    cluster_id: Uuid,
+    config: ClientConfig,
+    groups: Vec<String>,
+    interval: Duration,
+    retention_ms: i64,
+    repo: SqliteClusterRepository,
+}
+
+impl LagSampler {
+    pub async fn run(self, mut ctx: SessionContext) -> Result<()> {
+        let mut paused = false;
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                state = ctx.changed() => match state {
+                    Some(state) => paused = state == SessionState::Paused,
+                    None => break,
+                },
+                _ = ticker.tick(), if !paused => {
+                    let config = self.config.clone();
+                    let groups = self.groups.clone();
+                    let session_id = ctx.id;
+                    let lags = tokio::task::spawn_blocking(move || {
+                        sample_group_lags(config, groups, session_id)
+                    })
+                    .await?;
+
+                    let timestamp = now_millis();
+                    let samples: Vec<LagSample> = match lags {
+                        Ok(lags) => lags.iter().flat_map(|lag| topic_lag_samples(lag, timestamp)).collect(),
+                        Err(e) => {
+                            eprintln!("Lag sampler {} poll failed: {}", ctx.id, e);
+                            continue;
+                        }
+                    };
+
+                    self.repo.record_lag_samples(&self.cluster_id, &samples).await?;
+                    self.repo
+                        .prune_lag_samples(timestamp - self.retention_ms)
+                        .await?;
+                    ctx.record_processed(samples.len() as u64);
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Re-runs one read on an interval and emits `refresh-update` with what
+/// changed since the previous run.
+pub struct RefreshPoller {
+    cluster_id: Uuid,
+    cluster_name: String,
+    brokers: String,
+    config: ClientConfig,
+    admin: Arc<AdminClient<ClusterContext>>,
+    consumer: Arc<BaseConsumer<ClusterContext>>,
+    target: RefreshTarget,
+    interval: Duration,
+    requests: Arc<RequestCoalescer<(Uuid, RefreshTarget), RefreshRows>>,
+    events: Arc<dyn EventSink>,
+}
+
+impl RefreshPoller {
+    pub async fn run(self, mut ctx: SessionContext) -> Result<()> {
+        let mut paused = false;
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut previous: Option<RefreshRows> = None;
+
+        loop {
+            tokio::select! {
+                state = ctx.changed() => match state {
+                    Some(state) => paused = state == SessionState::Paused,
+                    None => break,
+                },
+                _ = ticker.tick(), if !paused => {
+                    let rows = match self.fetch().await {
+                        Ok(rows) => rows,
+                        Err(e) => {
+                            eprintln!("Refresh {} failed: {}", ctx.id, e);
+                            continue;
+                        }
+                    };
+
+                    let diff = RefreshDiff::between(
+                        ctx.id,
+                        self.target.clone(),
+                        previous.as_ref().unwrap_or(&RefreshRows::new()),
+                        &rows,
+                    );
+                    // The first update goes out even when empty so the view
+                    // knows the initial result.
+                    if previous.is_none() || !diff.is_empty() {
+                        self.events.emit("refresh-update", serde_json::to_value(diff)?);
+                        ctx.record_processed(1);
+                    }
+                    previous = Some(rows);
+                },
+            }
+        }
+        Ok(())
+    }
+
+    async fn fetch(&self) -> Result<RefreshRows> {
+        let target = self.target.clone();
+        let config = self.config.clone();
+        let admin = self.admin.clone();
+        let consumer = self.consumer.clone();
+        let name = self.cluster_name.clone();
+        let brokers = self.brokers.clone();
+
+        self.requests
+            .run((self.cluster_id, self.target.clone()), move || async move {
+                tokio::task::spawn_blocking(move || match target {
+                    RefreshTarget::GroupLag { group_id } => {
+                        keyed_rows(fetch_group_lag(config, &group_id)?.partitions, |p| {
+                            format!("{}-{}", p.topic, p.partition)
+                        })
+                    }
+                    RefreshTarget::TopicList => {
+                        keyed_rows(fetch_topics(&admin, &name, &brokers)?, |t| t.name.clone())
+                    }
+                    RefreshTarget::Watermarks { topic } => {
+                        keyed_rows(fetch_partition_watermarks(&consumer, &topic)?, |p| {
+                            p.id.to_string()
+                        })
+                    }
+                })
+                .await?
+            })
+            .await
+    }
+}
+
+/// Polls a topic's watermarks and emits `watermark-update` when they move.
+pub struct WatermarkWatcher {
+    consumer: Arc<BaseConsumer<ClusterContext>>,
+    topic: String,
+    interval: Duration,
+    events: Arc<dyn EventSink>,
+}
+
+impl WatermarkWatcher {
+    pub async fn run(self, mut ctx: SessionContext) -> Result<()> {
+        let mut paused = false;
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut previous: Option<HashMap<i32, (i64, i64)>> = None;
+
+        loop {
+            tokio::select! {
+                state = ctx.changed() => match state {
+                    Some(state) => paused = state == SessionState::Paused,
+                    None => break,
+                },
+                _ = ticker.tick(), if !paused => {
+                    let consumer = self.consumer.clone();
+                    let topic = self.topic.clone();
+                    let partitions = match tokio::task::spawn_blocking(move || {
+                        fetch_partition_watermarks(&consumer, &topic)
+                    })
+                    .await?
+                    {
+                        Ok(partitions) => partitions,
+                        Err(e) => {
+                            eprintln!("Watermark watch {} failed: {}", ctx.id, e);
+                            continue;
+                        }
+                    };
+
+                    let current: HashMap<i32, (i64, i64)> = partitions
+                        .iter()
+                        .map(|p| {
+                            (
+                                p.id,
+                                (
+                                    p.low_watermark.unwrap_or_default(),
+                                    p.high_watermark.unwrap_or_default(),
+                                ),
+                            )
+                        })
+                        .collect();
+                    if previous.as_ref() == Some(&current) {
+                        continue;
+                    }
+
+                    let watermarks = partitions
+                        .iter()
+                        .map(|p| {
+                            let (low, high) = current[&p.id];
+                            let appended = previous
+                                .as_ref()
+                                .and_then(|previous| previous.get(&p.id))
+                                .map(|&(_, before)| (high - before).max(0))
+                                .unwrap_or(0);
+                            PartitionWatermark {
+                                partition: p.id,
+                                low,
+                                high,
+                                appended,
+                            }
+                        })
+                        .collect();
+                    let update = WatermarkUpdate {
+                        session_id: ctx.id,
+                        topic: self.topic.clone(),
+                        timestamp: now_millis(),
+                        partitions: watermarks,
+                    };
+                    self.events.emit("watermark-update", serde_json::to_value(update)?);
+                    ctx.record_processed(1);
+                    previous = Some(current);
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+fn keyed_rows<T: serde::Serialize>(
+    items: Vec<T>,
+    key: impl Fn(&T) -> String,
+) -> Result<RefreshRows> {
+    items
+        .iter()
+        .map(|item| Ok((key(item), serde_json::to_value(item)?)))
+        .collect()
+}
+
+/// Checks each lag alert rule on its own interval and raises an alert when
+/// lag goes above the threshold. A rule fires once per crossing and re-arms
+/// after lag drops back to or below the threshold.
+pub struct LagAlertEvaluator {
+    cluster_id: Uuid,
+    cluster_name: String,
+    config: ClientConfig,
+    repo: SqliteClusterRepository,
+    events: Arc<dyn EventSink>,
+}
+
+impl LagAlertEvaluator {
+    pub async fn run(self, mut ctx: SessionContext) -> Result<()> {
+        let mut paused = false;
+        let mut ticker = tokio::time::interval(LAG_ALERT_TICK);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut last_evaluated: HashMap<Uuid, i64> = HashMap::new();
+        let mut breached: HashSet<Uuid> = HashSet::new();
+
+        loop {
+            tokio::select! {
+                state = ctx.changed() => match state {
+                    Some(state) => paused = state == SessionState::Paused,
+                    None => break,
+                },
+                _ = ticker.tick(), if !paused => {
+                    let now = now_millis();
+                    let rules = self.repo.list_lag_alert_rules(&self.cluster_id).await?;
+                    last_evaluated.retain(|id, _| rules.iter().any(|r| &r.id == id));
+                    breached.retain(|id| rules.iter().any(|r| &r.id == id));
+
+                    let due: Vec<LagAlertRule> = rules
+                        .into_iter()
+                        .filter(|rule| match last_evaluated.get(&rule.id) {
+                            Some(last) => now - last >= rule.interval_secs as i64 * 1000,
+                            None => true,
+                        })
+                        .collect();
+                    if due.is_empty() {
+                        continue;
+                    }
+
+                    let groups: HashSet<String> = due.iter().map(|r| r.group_id.clone()).collect();
+                    let config = self.config.clone();
+                    let session_id = ctx.id;
+                    let lags = tokio::task::spawn_blocking(move || {
+                        sample_group_lags(config, groups.into_iter().collect(), session_id)
+                    })
+                    .await??;
+
+                    for rule in &due {
+                        last_evaluated.insert(rule.id, now);
+                        let Some(group_lag) = lags.iter().find(|l| l.group_id == rule.group_id) else {
+                            continue;
+                        };
+                        let lag: i64 = group_lag
+                            .partitions
+                            .iter()
+                            .filter(|p| rule.topic.is_none() || rule.topic.as_ref() == Some(&p.topic))
+                            .map(|p| p.lag)
+                            .sum();
+
+                        if lag <= rule.threshold {
+                            breached.remove(&rule.id);
+                            continue;
+                        }
+                        if !breached.insert(rule.id) {
+                            continue;
+                        }
+
+                        let alert = LagAlert {
+                            rule_id: rule.id,
+                            cluster_id: self.cluster_id,
+                            group_id: rule.group_id.clone(),
+                            topic: rule.topic.clone(),
+                            lag,
+                            threshold: rule.threshold,
+                            timestamp: now,
+                        };
+                        let target = match &rule.topic {
+                            Some(topic) => format!("{} on {}", rule.group_id, topic),
+                            None => rule.group_id.clone(),
+                        };
+                        self.events.notify(
+                            &format!("Consumer lag alert: {}", self.cluster_name),
+                            &format!("{} is {} messages behind (threshold {})", target, lag, rule.threshold),
+                        );
+                        self.events.emit("alert-triggered", serde_json::to_value(alert)?);
+                        ctx.record_processed(1);
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Lag of each group; groups whose lag cannot be read are logged and
+/// skipped so one bad group does not stop the others being sampled.
+fn sample_group_lags(
+    config: ClientConfig,
+    groups: Vec<String>,
+    session_id: Uuid,
+) -> Result<Vec<ConsumerGroupLag>> {
+    let groups = if groups.is_empty() {
+        let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+        consumer
+            .fetch_group_list(None, Duration::from_secs(10))
+            .map_err(|e| anyhow::anyhow!("Failed to list consumer groups: {}", e))?
+            .groups()
+            .iter()
+            .map(|g| g.name().to_string())
+            .collect()
+    } else {
+        groups
+    };
+
+    Ok(groups
+        .iter()
+        .filter_map(|group| match fetch_group_lag(config.clone(), group) {
+            Ok(lag) => Some(lag),
+            Err(e) => {
+                eprintln!("Lag sampler {} skipped group {}: {}", session_id, group, e);
+                None
+            }
+        })
+        .collect())
+}
+
+fn topic_lag_samples(lag: &ConsumerGroupLag, timestamp: i64) -> Vec<LagSample> {
+    let mut per_topic: HashMap<&str, i64> = HashMap::new();
+    for p in &lag.partitions {
+        *per_topic.entry(&p.topic).or_default() += p.lag;
+    }
+    per_topic
+        .into_iter()
+        .map(|(topic, total)| LagSample {
+            group_id: lag.group_id.clone(),
+            topic: topic.to_string(),
+            lag: total,
+            timestamp,
+        })
+        .collect()
+}
+
+/// Polls cluster metadata and consumer groups on an interval and records ISR
+/// changes, topic creations and deletions, and group state transitions
+/// between consecutive polls.
+pub struct HealthMonitor {
+    cluster_id: Uuid,
+    consumer: Arc<BaseConsumer<ClusterContext>>,
+    repo: SqliteClusterRepository,
+    events: Arc<dyn EventSink>,
+}
+
+impl HealthMonitor {
+    pub async fn run(self, mut ctx: SessionContext) -> Result<()> {
+        let mut previous = None;
+        let mut previous_groups: Option<Vec<ConsumerGroup>> = None;
+        let mut paused = false;
+        let mut ticker = tokio::time::interval(HEALTH_POLL_INTERVAL);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                state = ctx.changed() => match state {
+                    Some(state) => paused = state == SessionState::Paused,
+                    None => break,
+                },
+                _ = ticker.tick(), if !paused => {
+                    let consumer = self.consumer.clone();
+                    let current =
+                        match tokio::task::spawn_blocking(move || fetch_partitions(&*consumer))
+                            .await?
+                        {
+                            Ok(current) => current,
+                            Err(e) => {
+                                eprintln!("Health monitor {} poll failed: {}", ctx.id, e);
+                                continue;
+                            }
+                        };
+                    ctx.record_processed(1);
+
+                    if let Some(previous) = &previous {
+                        let timestamp = now_millis();
+                        let changes = diff_isr(self.cluster_id, previous, &current, timestamp);
+                        if !changes.is_empty() {
+                            self.repo.record_isr_events(&changes).await?;
+                            for change in &changes {
+                                self.events.emit("isr-change", serde_json::to_value(change)?);
+                            }
+                        }
+
+                        let topic_events = diff_topics(self.cluster_id, previous, &current, timestamp);
+                        if !topic_events.is_empty() {
+                            self.repo.record_topic_events(&topic_events).await?;
+                            for event in &topic_events {
+                                let name = match event.change {
+                                    TopicChange::Created => "topic-created",
+                                    TopicChange::Deleted => "topic-deleted",
+                                };
+                                self.events.emit(name, serde_json::to_value(event)?);
+                            }
+                        }
+                    }
+                    previous = Some(current);
+
+                    let consumer = self.consumer.clone();
+                    let groups =
+                        match tokio::task::spawn_blocking(move || fetch_consumer_groups(&*consumer))
+                            .await?
+                        {
+                            Ok(groups) => groups,
+                            Err(e) => {
+                                eprintln!("Health monitor {} group poll failed: {}", ctx.id, e);
+                                continue;
+                            }
+                        };
+                    if let Some(previous_groups) = &previous_groups {
+                        let events =
+                            diff_group_states(self.cluster_id, previous_groups, &groups, now_millis());
+                        if !events.is_empty() {
+                            self.repo.record_group_events(&events).await?;
+                            for event in &events {
+                                self.events.emit("group-state-change", serde_json::to_value(event)?);
+                            }
+                        }
+                    }
+                    previous_groups = Some(groups);
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Tails the single partition a key hashes to and forwards only the records
+/// carrying that key.
+pub struct KeyWatcher {
+    consumer: StreamConsumer<ClusterContext>,
+    key: Vec<u8>,
+    partition: i32,
+    limits: TailBufferLimits,
+}
+
+impl KeyWatcher {
+    pub fn partition(&self) -> i32 {
+        self.partition
+    }
+
+    pub async fn run(self, mut ctx: SessionContext, events: Arc<dyn EventSink>) -> Result<()> {
+        let mut paused = false;
+        let mut buffer = TailBuffer::new(self.limits);
+        let mut delivery = tokio::time::interval(TAIL_DELIVERY_INTERVAL);
+        delivery.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                state = ctx.changed() => match state {
+                    Some(state) => paused = apply_session_state(&self.consumer, state)?,
+                    None => break,
+                },
+                _ = delivery.tick(), if !paused => {
+                    for message in buffer.drain() {
+                        let event = SessionMessage {
+                            session_id: ctx.id,
+                            message,
+                        };
+                        events.emit("key-watch-message", serde_json::to_value(event)?);
+                    }
+                },
+                msg = self.consumer.recv(), if !paused && !buffer.is_full() => match msg {
+                    Ok(msg) if msg.key() == Some(self.key.as_slice()) => {
+                        let dropped = buffer.push(to_kafka_message(&msg));
+                        if dropped > 0 {
+                            ctx.record_dropped(dropped);
+                        }
+                        ctx.record_processed(1);
+                    }
+                    Ok(_) => {}
+                    Err(e) => eprintln!("Key watch {} error: {}", ctx.id, e),
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Streams every new record of a topic as `message-received` events.
+pub struct TopicTail {
+    consumer: StreamConsumer<ClusterContext>,
+    limits: TailBufferLimits,
+}
+
+impl TopicTail {
+    pub async fn run(self, mut ctx: SessionContext, events: Arc<dyn EventSink>) -> Result<()> {
+        let mut paused = false;
+        let mut buffer = TailBuffer::new(self.limits);
+        let mut delivery = tokio::time::interval(TAIL_DELIVERY_INTERVAL);
+        delivery.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                state = ctx.changed() => match state {
+                    Some(state) => paused = apply_session_state(&self.consumer, state)?,
+                    None => break,
+                },
+                _ = delivery.tick(), if !paused => {
+                    for message in buffer.drain() {
+                        let event = SessionMessage {
+                            session_id: ctx.id,
+                            message,
+                        };
+                        events.emit("message-received", serde_json::to_value(event)?);
+                    }
+                },
+                msg = self.consumer.recv(), if !paused && !buffer.is_full() => match msg {
+                    Ok(msg) => {
+                        let dropped = buffer.push(to_kafka_message(&msg));
+                        if dropped > 0 {
+                            ctx.record_dropped(dropped);
+                        }
+                        ctx.record_processed(1);
+                    }
+                    Err(e) => eprintln!("Tail {} error: {}", ctx.id, e),
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Scans a topic up to the end offsets seen when it was created, emitting
+/// each record with the searched key, then finishes on its own.
+pub struct KeySearch {
+    consumer: StreamConsumer<ClusterContext>,
+    key: String,
+    partitions: Vec<i32>,
+    start_offsets: HashMap<i32, i64>,
+    end_offsets: HashMap<i32, i64>,
+}
+
+impl KeySearch {
+    pub async fn run(self, mut ctx: SessionContext, events: Arc<dyn EventSink>) -> Result<()> {
+        let mut remaining = self.end_offsets.clone();
+        let mut positions = self.start_offsets.clone();
+        let mut records_scanned = 0;
+        let mut matches = 0;
+        let mut paused = false;
+        let mut idle_receives = 0;
+        let mut report = tokio::time::interval(KEY_SEARCH_PROGRESS_INTERVAL);
+        report.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        while !remaining.is_empty() {
+            tokio::select! {
+                state = ctx.changed() => match state {
+                    Some(state) => paused = apply_session_state(&self.consumer, state)?,
+                    None => break,
+                },
+                _ = report.tick(), if !paused => {
+                    let progress =
+                        self.progress(ctx.id, &positions, records_scanned, matches, false, false);
+                    events.emit("key-search-progress", serde_json::to_value(progress)?);
+                },
+                msg = tokio::time::timeout(SESSION_RECEIVE_TIMEOUT, self.consumer.recv()),
+                    if !paused => match msg {
+                    Ok(Ok(msg)) => {
+                        idle_receives = 0;
+                        let Some(&end) = remaining.get(&msg.partition()) else {
+                            continue;
+                        };
+                        if msg.offset() + 1 >= end {
+                            remaining.remove(&msg.partition());
+                        }
+                        if msg.offset() >= end {
+                            continue;
+                        }
+                        positions.insert(msg.partition(), msg.offset() + 1);
+                        records_scanned += 1;
+                        ctx.record_processed(1);
+
+                        if msg.key() == Some(self.key.as_bytes()) {
+                            matches += 1;
+                            let event = SessionMessage {
+                                session_id: ctx.id,
+                                message: to_kafka_message(&msg),
+                            };
+                            events.emit("key-search-match", serde_json::to_value(event)?);
+                        }
+                    }
+                    Ok(Err(rdkafka::error::KafkaError::PartitionEOF(partition))) => {
+                        if let Some(end) = remaining.remove(&partition) {
+                            positions.insert(partition, end);
+                        }
+                    }
+                    Ok(Err(e)) => eprintln!("Key search {} error: {}", ctx.id, e),
+                    Err(_) => {
+                        idle_receives += 1;
+                        if idle_receives >= SESSION_MAX_IDLE_RECEIVES {
+                            break;
+                        }
+                    }
+                },
+            }
+        }
+
+        let progress = self.progress(
+            ctx.id,
+            &positions,
+            records_scanned,
+            matches,
+            true,
+            remaining.is_empty(),
+        );
+        events.emit("key-search-progress", serde_json::to_value(progress)?);
+        Ok(())
+    }
+
+    fn progress(
+        &self,
+        session_id: Uuid,
+        positions: &HashMap<i32, i64>,
+        records_scanned: u64,
+        matches: u64,
+        finished: bool,
+        complete: bool,
+    ) -> KeySearchProgress {
+        let mut total = 0;
+        let mut done = 0;
+        for (partition, &end) in &self.end_offsets {
+            let start = self.start_offsets[partition];
+            total += end - start;
+            done += positions.get(partition).map_or(0, |&p| p.min(end) - start);
+        }
+        let percent = if complete || total == 0 {
+            100.0
+        } else {
+            done as f64 * 100.0 / total as f64
+        };
+        KeySearchProgress {
+            session_id,
+            key: self.key.clone(),
+            partitions: self.partitions.clone(),
+            records_scanned,
+            matches,
+            percent,
+            finished,
+            complete,
+        }
+    }
+}
+
+/// Pauses or resumes the consumer's partitions, including any a later
+/// rebalance assigns. Returns whether the session is now paused.
+fn apply_session_state<C: Consumer<ClusterContext>>(
+    consumer: &C,
+    state: SessionState,
+) -> Result<bool> {
+    let paused = state == SessionState::Paused;
+    consumer.context().set_paused(paused);
+    let assignment = consumer.assignment()?;
+    if paused {
+        consumer.pause(&assignment)?;
+    } else {
+        consumer.resume(&assignment)?;
+    }
+    Ok(paused)
+}
+
+/// Committed offsets of the consumer's group across all topics, skipping
+/// partitions without a commit.
+fn fetch_group_offsets<C: Consumer<ClusterContext>>(consumer: &C) -> Result<Vec<CommittedOffset>> {
+    let metadata = consumer
+        .fetch_metadata(None, Duration::from_secs(5))
+        .map_err(|e| anyhow::anyhow!("Failed to fetch metadata: {}", e))?;
+
+    let mut tpl = TopicPartitionList::new();
+    for topic in metadata.topics() {
+        for partition in topic.partitions() {
+            tpl.add_partition(topic.name(), partition.id());
+        }
+    }
+
+    let committed = consumer
+        .committed_offsets(tpl, Duration::from_secs(10))
+        .map_err(|e| anyhow::anyhow!("Failed to fetch committed offsets: {}", e))?;
+
+    Ok(committed
+        .elements()
+        .iter()
+        .filter_map(|elem| match elem.offset() {
+            rdkafka::Offset::Offset(offset) => Some(CommittedOffset {
+                topic: elem.topic().to_string(),
+                partition: elem.partition(),
+                offset,
+            }),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Lag of every partition `group_id` has committed offsets for.
+fn fetch_group_lag(mut config: ClientConfig, group_id: &str) -> Result<ConsumerGroupLag> {
+    config.set("group.id", group_id);
+    config.set("enable.auto.commit", "false");
+    let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+
+    let mut partitions = Vec::new();
+    for c in fetch_group_offsets(&consumer)? {
+        let (_low, high) = consumer
+            .fetch_watermarks(&c.topic, c.partition, Duration::from_secs(5))
+            .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+        partitions.push(PartitionLag {
+            lag: (high - c.offset).max(0),
+            topic: c.topic,
+            partition: c.partition,
+            committed_offset: c.offset,
+            high_watermark: high,
+        });
+    }
+    partitions.sort_by(|a, b| (&a.topic, a.partition).cmp(&(&b.topic, b.partition)));
+
+    Ok(ConsumerGroupLag {
+        group_id: group_id.to_string(),
+        total_lag: partitions.iter().map(|p| p.lag).sum(),
+        partitions,
+    })
+}
+
+fn fetch_topic_consumers(config: ClientConfig, topic: &str) -> Result<Vec<TopicConsumer>> {
+    let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+    let metadata = consumer
+        .fetch_metadata(Some(topic), Duration::from_secs(5))
+        .map_err(|e| anyhow::anyhow!("Failed to fetch metadata: {}", e))?;
+    let partitions: Vec<i32> = metadata
+        .topics()
+        .iter()
+        .find(|t| t.name() == topic && t.error().is_none())
+        .ok_or_else(|| anyhow::anyhow!("Topic {} not found", topic))?
+        .partitions()
+        .iter()
+        .map(|p| p.id())
+        .collect();
+
+    let mut high_watermarks = HashMap::new();
+    for &partition in &partitions {
+        let (_low, high) = consumer
+            .fetch_watermarks(topic, partition, Duration::from_secs(5))
+            .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+        high_watermarks.insert(partition, high);
+    }
+
+    let groups = consumer
+        .fetch_group_list(None, Duration::from_secs(10))
+        .map_err(|e| anyhow::anyhow!("Failed to list consumer groups: {}", e))?;
+
+    let mut consumers = Vec::new();
+    for group in groups.groups() {
+        let mut active_members = 0;
+        let mut assigned_partitions = Vec::new();
+        if group.protocol_type() == "consumer" {
+            for member in group.members() {
+                let Some(bytes) = member.assignment().filter(|b| !b.is_empty()) else {
+                    continue;
+                };
+                let assigned: Vec<i32> = consumer_protocol::decode_assignment(bytes)?
+                    .into_iter()
+                    .filter(|a| a.topic == topic)
+                    .flat_map(|a| a.partitions)
+                    .collect();
+                if !assigned.is_empty() {
+                    active_members += 1;
+                    assigned_partitions.extend(assigned);
+                }
+            }
+        }
+        assigned_partitions.sort_unstable();
+
+        let mut group_config = config.clone();
+        group_config.set("group.id", group.name());
+        group_config.set("enable.auto.commit", "false");
+        let group_consumer: BaseConsumer<ClusterContext> = create_client(&group_config)?;
+        let mut tpl = TopicPartitionList::new();
+        for &partition in &partitions {
+            tpl.add_partition(topic, partition);
+        }
+        let committed = match group_consumer.committed_offsets(tpl, Duration::from_secs(10)) {
+            Ok(committed) => committed,
+            Err(e) => {
+                eprintln!("Skipped group {} reading {}: {}", group.name(), topic, e);
+                continue;
+            }
+        };
+        let lags: Vec<PartitionLag> = committed
+            .elements()
+            .iter()
+            .filter_map(|elem| match elem.offset() {
+                rdkafka::Offset::Offset(offset) => {
+                    let high = high_watermarks.get(&elem.partition()).copied().unwrap_or(0);
+                    Some(PartitionLag {
+                        topic: topic.to_string(),
+                        partition: elem.partition(),
+                        committed_offset: offset,
+                        high_watermark: high,
+                        lag: (high - offset).max(0),
+                    })
+                }
+                _ => None,
+            })
+            .collect();
+
+        if lags.is_empty() && assigned_partitions.is_empty() {
+            continue;
+        }
+        consumers.push(TopicConsumer {
+            group_id: group.name().to_string(),
+            state: group.state().to_string(),
+            active_members,
+            assigned_partitions,
+            total_lag: lags.iter().map(|p| p.lag).sum(),
+            partitions: lags,
+        });
+    }
+    consumers.sort_by(|a, b| a.group_id.cmp(&b.group_id));
+
+    Ok(consumers)
+}
+
+fn fetch_consumer_groups<C: Consumer<ClusterContext>>(consumer: &C) -> Result<Vec<ConsumerGroup>> {
+    let groups = consumer
+        .fetch_group_list(None, Duration::from_secs(10))
+        .map_err(|e| anyhow::anyhow!("Failed to list consumer groups: {}", e))?;
+
+    let mut groups: Vec<ConsumerGroup> = groups
+        .groups()
+        .iter()
+        .map(|g| ConsumerGroup {
+            group_id: g.name().to_string(),
+            state: g.state().to_string(),
+            protocol_type: g.protocol_type().to_string(),
+            member_count: g.members().len(),
+            cached_at: None,
+        })
+        .collect();
+    groups.sort_by(|a, b| a.group_id.cmp(&b.group_id));
+
+    Ok(groups)
+}
+
+/// `kafkust/<hostname>/<user>`, identifying this installation to brokers.
+fn default_client_id() -> &'static str {
+    static CLIENT_ID: OnceLock<String> = OnceLock::new();
+    CLIENT_ID.get_or_init(|| {
+        let host = gethostname::gethostname().to_string_lossy().into_owned();
+        format!("kafkust/{}/{}", host, local_user())
+    })
+}
+
+/// Operating system user running the app.
+pub fn local_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+/// Leader, replicas and ISR of every partition, keyed by topic.
+fn fetch_partitions<C: Consumer<ClusterContext>>(
+    consumer: &C,
+) -> Result<HashMap<String, Vec<Partition>>> {
+    let metadata = consumer
+        .fetch_metadata(None, Duration::from_secs(5))
+        .map_err(|e| anyhow::anyhow!("Failed to fetch metadata: {}", e))?;
+
+    Ok(metadata
+        .topics()
+        .iter()
+        .map(|t| {
+            let partitions = t
+                .partitions()
+                .iter()
+                .map(|p| Partition {
+                    id: p.id(),
+                    leader: p.leader(),
+                    replicas: p.replicas().to_vec(),
+                    isrs: p.isr().to_vec(),
+                    low_watermark: None,
+                    high_watermark: None,
+                })
+                .collect();
+            (t.name().to_string(), partitions)
+        })
+        .collect())
+}
+
+/// Offset each partition is read from for `seek`, given its `(partition,
+/// low, high)` watermarks. Starts are kept within the retained range.
+fn start_offsets<C: Consumer<ClusterContext>>(
+    consumer: &C,
+    topic: &str,
+    seek: &SeekMode,
+    watermarks: &[(i32, i64, i64)],
+    max_messages: usize,
+) -> Result<Vec<(i32, i64)>> {
+    let clamp = |offset: i64, low: i64, high: i64| offset.max(low).min(high);
+    match seek {
+        SeekMode::Latest => {
+            let per_partition = (max_messages / watermarks.len().max(1)) as i64;
+            Ok(watermarks
+                .iter()
+                .map(|&(partition, low, high)| (partition, clamp(high - per_partition, low, high)))
+                .collect())
+        }
+        SeekMode::Earliest => Ok(watermarks
+            .iter()
+            .map(|&(partition, low, _)| (partition, low))
+            .collect()),
+        SeekMode::Offsets(offsets) => offsets
+            .iter()
+            .map(|(&partition, &offset)| {
+                let &(_, low, high) = watermarks
+                    .iter()
+                    .find(|(p, _, _)| *p == partition)
+                    .ok_or_else(|| anyhow::anyhow!("Partition {} not found", partition))?;
+                Ok((partition, clamp(offset, low, high)))
+            })
+            .collect(),
+        SeekMode::Before(before) => {
+            let per_partition = (max_messages / before.len().max(1)) as i64;
+            Ok(before
+                .iter()
+                .filter_map(|(&partition, &offset)| {
+                    let &(_, low, high) = watermarks.iter().find(|(p, _, _)| *p == partition)?;
+                    let end = clamp(offset, low, high);
+                    let start = clamp(end - per_partition, low, high);
+                    (start < end).then_some((partition, start))
+                })
+                .collect())
+        }
+        SeekMode::Timestamp(timestamp) => {
+            let mut tpl = TopicPartitionList::new();
+            for &(partition, _, _) in watermarks {
+                tpl.add_partition_offset(topic, partition, rdkafka::Offset::Offset(*timestamp))
+                    .map_err(|e| anyhow::anyhow!("Failed to set timestamp: {}", e))?;
+            }
+            let resolved = consumer
+                .offsets_for_times(tpl, Duration::from_secs(10))
+                .map_err(|e| anyhow::anyhow!("Failed to look up offsets for time: {}", e))?;
+            Ok(watermarks
+                .iter()
+                .map(|&(partition, low, high)| {
+                    // No record at or after the timestamp leaves the partition
+                    // at its end.
+                    let offset = match resolved
+                        .find_partition(topic, partition)
+                        .map(|elem| elem.offset())
+                    {
+                        Some(rdkafka::Offset::Offset(offset)) => clamp(offset, low, high),
+                        _ => high,
+                    };
+                    (partition, offset)
+                })
+                .collect())
+        }
+    }
+}
+
+fn fetch_partition_ids<C: Consumer<ClusterContext>>(consumer: &C, topic: &str) -> Result<Vec<i32>> {
+    let metadata = consumer
+        .fetch_metadata(Some(topic), Duration::from_secs(5))
+        .map_err(|e| anyhow::anyhow!("Failed to fetch topic metadata: {}", e))?;
+
+    let topic_metadata = metadata
+        .topics()
+        .iter()
+        .find(|t| t.name() == topic)
+        .ok_or_else(|| anyhow::anyhow!("Topic not found"))?;
+
+    Ok(topic_metadata.partitions().iter().map(|p| p.id()).collect())
+}
+
+fn is_authorization_failure(code: rdkafka::types::RDKafkaErrorCode) -> bool {
+    use rdkafka::types::RDKafkaErrorCode as Code;
+
+    matches!(
+        code,
+        Code::TopicAuthorizationFailed
+            | Code::GroupAuthorizationFailed
+            | Code::ClusterAuthorizationFailed
+    )
+}
+
+/// Reads up to `max_messages` of the most recent records, spread evenly
+/// over the topic's partitions, and hands each one to `visit`. Poll errors
+/// do not abort the read; they are returned alongside whatever was read,
+/// and authorization or unknown-topic errors end it early.
+fn read_latest<F>(
+    config: ClientConfig,
+    topic: &str,
+    max_messages: usize,
+    mut visit: F,
+) -> Result<Vec<FetchError>>
+where
+    F: FnMut(&BorrowedMessage<'_>),
+{
+    read_from(
+        config,
+        topic,
+        None,
+        &SeekMode::Latest,
+        max_messages,
+        |msg| {
+            visit(msg);
+            true
+        },
+    )
+    .map(|outcome| outcome.errors)
+}
+
+/// Reads records of `topic` starting where `seek` points, passing each to
+/// `visit`. `partition` limits reading to one partition; otherwise every
+/// partition is read. Only records `visit` keeps count towards
+/// `max_messages`. `SeekMode::Latest` starts `max_messages` records back,
+/// so filtering the newest records goes through `read_back` instead.
+#[allow(clippy::too_many_arguments)]
+fn read_from<F>(
+    config: ClientConfig,
+    topic: &str,
+    partition: Option<i32>,
+    seek: &SeekMode,
+    max_messages: usize,
+    mut visit: F,
+) -> Result<ReadOutcome>
+where
+    F: FnMut(&BorrowedMessage<'_>) -> bool,
+{
+    let (consumer, watermarks) = open_reader(config, topic, partition)?;
+
+    let mut offset_tpl = TopicPartitionList::new();
+    let mut bounds = BTreeMap::new();
+    for (partition, start_offset) in
+        start_offsets(&consumer, topic, seek, &watermarks, max_messages)?
+    {
+        offset_tpl
+            .add_partition_offset(topic, partition, rdkafka::Offset::Offset(start_offset))
+            .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
+        let high = watermarks
+            .iter()
+            .find(|(p, _, _)| *p == partition)
+            .map_or(start_offset, |&(_, _, high)| high);
+        let end = match seek {
+            SeekMode::Before(before) => before.get(&partition).map_or(high, |&b| b.min(high)),
+            _ => high,
+        };
+        bounds.insert(partition, end);
+    }
+    if offset_tpl.count() == 0 {
+        return Ok(ReadOutcome {
+            errors: Vec::new(),
+            ends: bounds,
+        });
+    }
+
+    consumer
+        .assign(&offset_tpl)
+        .map_err(|e| anyhow::anyhow!("Failed to assign partitions: {}", e))?;
+
+    // Paging backwards stops each partition at the offset the newer page
+    // started from.
+    let ends = match seek {
+        SeekMode::Before(before) => before.clone(),
+        _ => BTreeMap::new(),
+    };
+    let mut finished = HashSet::new();
+
+    let mut read = 0;
+    let mut errors: Vec<FetchError> = Vec::new();
+    let timeout = Duration::from_millis(100);
+    let max_attempts = 50;
+    // Records `visit` skips don't use up an attempt.
+    let mut attempts = 0;
+    let mut scanned = 0;
+
+    while attempts < max_attempts && read < max_messages && scanned < MAX_SCAN_RECORDS {
+        match consumer.poll(timeout) {
+            Some(Ok(msg)) => {
+                if ends
+                    .get(&msg.partition())
+                    .is_some_and(|end| msg.offset() >= *end)
+                {
+                    attempts += 1;
+                    finished.insert(msg.partition());
+                    if finished.len() == offset_tpl.count() {
+                        break;
+                    }
+                    continue;
+                }
+                scanned += 1;
+                if visit(&msg) {
+                    attempts += 1;
+                    read += 1;
+                }
+            }
+            Some(Err(e)) => {
+                attempts += 1;
+                if record_fetch_error(&mut errors, &e) {
+                    break;
+                }
+            }
+            None => {
+                attempts += 1;
+                if read == 0 {
+                    continue;
+                }
+                break;
+            }
+        }
+    }
+
+    Ok(ReadOutcome {
+        errors,
+        ends: bounds,
+    })
+}
+
+/// `(partition, low, high)` watermarks.
+type PartitionBounds = (i32, i64, i64);
+
+/// A consumer without a group for reading `topic`, with the `(partition,
+/// low, high)` watermarks of `partition`, or of every partition.
+fn open_reader(
+    mut config: ClientConfig,
+    topic: &str,
+    partition: Option<i32>,
+) -> Result<(BaseConsumer<ClusterContext>, Vec<PartitionBounds>)> {
+    config.set(
+        "group.id",
+        format!("kafkust-consumer-{}", uuid::Uuid::new_v4()),
+    );
+    config.set("auto.offset.reset", "latest");
+    config.set("enable.auto.commit", "false");
+
+    let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+
+    let metadata = consumer
+        .fetch_metadata(Some(topic), Duration::from_secs(5))
+        .map_err(|e| anyhow::anyhow!("Failed to fetch topic metadata: {}", e))?;
+
+    let topic_metadata = metadata
+        .topics()
+        .iter()
+        .find(|t| t.name() == topic)
+        .ok_or_else(|| anyhow::anyhow!("Topic not found"))?;
+
+    let partition_count = topic_metadata.partitions().len() as i32;
+    let partitions = match partition {
+        Some(p) if (0..partition_count).contains(&p) => p..p + 1,
+        Some(p) => return Err(anyhow::anyhow!("Partition {} not found", p)),
+        None => 0..partition_count,
+    };
+
+    let watermarks = partitions
+        .map(|p| {
+            consumer
+                .fetch_watermarks(topic, p, Duration::from_secs(5))
+                .map(|(low, high)| (p, low, high))
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+
+    Ok((consumer, watermarks))
+}
+
+/// The next older window of each partition below `ends`: up to
+/// `per_partition` offsets, but not below the partition's low watermark.
+/// Partitions already read down to their low watermark get none.
+fn older_windows(
+    ends: &BTreeMap<i32, i64>,
+    lows: &BTreeMap<i32, i64>,
+    per_partition: i64,
+) -> BTreeMap<i32, (i64, i64)> {
+    ends.iter()
+        .filter_map(|(&partition, &end)| {
+            let low = lows.get(&partition).copied().unwrap_or(0);
+            let start = end.saturating_sub(per_partition).max(low);
+            (start < end).then_some((partition, (start, end)))
+        })
+        .collect()
+}
+
+/// Hands `read_window` ever older windows below `ends` until it has kept
+/// `max_messages` records, `MAX_SCAN_RECORDS` were scanned or every
+/// partition is read down to its low watermark. `read_window` returns how
+/// many records of the windows it kept and scanned, or breaks to end the
+/// read early. `ends` is left at the start of the last window read whole.
+fn walk_back<R>(
+    lows: &BTreeMap<i32, i64>,
+    ends: &mut BTreeMap<i32, i64>,
+    per_partition: i64,
+    max_messages: usize,
+    mut read_window: R,
+) -> Result<()>
+where
+    R: FnMut(&BTreeMap<i32, (i64, i64)>) -> Result<ControlFlow<(), (usize, usize)>>,
+{
+    let mut kept = 0;
+    let mut scanned = 0;
+    while kept < max_messages && scanned < MAX_SCAN_RECORDS {
+        let windows = older_windows(ends, lows, per_partition);
+        if windows.is_empty() {
+            break;
+        }
+        let ControlFlow::Continue((window_kept, window_scanned)) = read_window(&windows)? else {
+            break;
+        };
+        kept += window_kept;
+        scanned += window_scanned;
+        for (partition, (start, _)) in windows {
+            ends.insert(partition, start);
+        }
+    }
+    Ok(())
+}
+
+/// Adds a poll error to `errors`, counting repeats, and returns whether it
+/// should end the read.
+fn record_fetch_error(errors: &mut Vec<FetchError>, e: &rdkafka::error::KafkaError) -> bool {
+    eprintln!("Error consuming message: {}", e);
+    let error = classify_fetch_error(e);
+    let fatal = matches!(
+        error.kind,
+        FetchErrorKind::Authorization | FetchErrorKind::UnknownTopicOrPartition
+    );
+    match errors.iter_mut().find(|seen| {
+        seen.kind == error.kind
+            && seen.partition == error.partition
+            && seen.message == error.message
+    }) {
+        Some(seen) => seen.count += 1,
+        None => errors.push(error),
+    }
+    fatal
+}
+
+fn classify_fetch_error(error: &rdkafka::error::KafkaError) -> FetchError {
+    use rdkafka::error::KafkaError;
+    use rdkafka::types::RDKafkaErrorCode as Code;
+
+    let (kind, partition) = match error {
+        KafkaError::PartitionEOF(partition) => (FetchErrorKind::PartitionEof, Some(*partition)),
+        _ => {
+            let kind = match error.rdkafka_error_code() {
+                Some(
+                    Code::TopicAuthorizationFailed
+                    | Code::GroupAuthorizationFailed
+                    | Code::ClusterAuthorizationFailed
+                    | Code::SaslAuthenticationFailed
+                    | Code::Authentication,
+                ) => FetchErrorKind::Authorization,
+                Some(Code::PartitionEOF) => FetchErrorKind::PartitionEof,
+                Some(
+                    Code::UnknownTopicOrPartition | Code::UnknownTopic | Code::UnknownPartition,
+                ) => FetchErrorKind::UnknownTopicOrPartition,
+                Some(
+                    Code::BrokerTransportFailure
+                    | Code::AllBrokersDown
+                    | Code::OperationTimedOut
+                    | Code::RequestTimedOut
+                    | Code::LeaderNotAvailable
+                    | Code::NotLeaderForPartition
+                    | Code::BrokerNotAvailable
+                    | Code::NetworkException,
+                ) => FetchErrorKind::Transient,
+                _ => FetchErrorKind::Other,
+            };
+            (kind, None)
+        }
+    };
+
+    FetchError {
+        kind,
+        partition,
+        message: error.to_string(),
+        count: 1,
+    }
+}
+
+fn config_entry_source(source: &rdkafka::admin::ConfigSource) -> ConfigEntrySource {
+    use rdkafka::admin::ConfigSource;
+
+    match source {
+        ConfigSource::Unknown => ConfigEntrySource::Unknown,
+        ConfigSource::DynamicTopic => ConfigEntrySource::DynamicTopic,
+        ConfigSource::DynamicBroker => ConfigEntrySource::DynamicBroker,
+        ConfigSource::DynamicDefaultBroker => ConfigEntrySource::DynamicDefaultBroker,
+        ConfigSource::StaticBroker => ConfigEntrySource::StaticBroker,
+        ConfigSource::Default => ConfigEntrySource::Default,
+    }
+}
+
+/// Reads every partition (or only `partition`) from its low watermark up
+/// to the high watermark observed when the scan starts. `visit` returns
+/// `false` to stop early.
+fn scan_topic<F>(
+    mut config: ClientConfig,
+    topic: &str,
+    partition: Option<i32>,
+    max_records: usize,
+    mut visit: F,
+) -> Result<ScanOutcome>
+where
+    F: FnMut(&BorrowedMessage<'_>) -> bool,
+{
+    config.set("group.id", format!("kafkust-scan-{}", uuid::Uuid::new_v4()));
+    config.set("enable.auto.commit", "false");
+    config.set("enable.partition.eof", "false");
+
+    let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+
+    let mut end_offsets = HashMap::new();
+    let mut tpl = TopicPartitionList::new();
+    let partitions = match partition {
+        Some(p) => vec![p],
+        None => fetch_partition_ids(&consumer, topic)?,
+    };
+    for p in partitions {
+        let (low, high) = consumer
+            .fetch_watermarks(topic, p, Duration::from_secs(5))
+            .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+        if high > low {
+            tpl.add_partition_offset(topic, p, rdkafka::Offset::Offset(low))
+                .map_err(|e| anyhow::anyhow!("Failed to set offset: {}", e))?;
+            end_offsets.insert(p, high);
+        }
+    }
+
+    let mut outcome = ScanOutcome {
+        scanned: 0,
+        complete: true,
+    };
+    if end_offsets.is_empty() {
+        return Ok(outcome);
+    }
+
+    consumer
+        .assign(&tpl)
+        .map_err(|e| anyhow::anyhow!("Failed to assign partitions: {}", e))?;
+
+    // Transaction markers occupy offsets that are never delivered, so a
+    // partition may never report its last offset; idle polls end the scan.
+    let mut idle_polls = 0;
+    while !end_offsets.is_empty() && idle_polls < SCAN_MAX_IDLE_POLLS {
+        if outcome.scanned >= max_records {
+            outcome.complete = false;
+            break;
+        }
+
+        match consumer.poll(Duration::from_millis(200)) {
+            Some(Ok(msg)) => {
+                idle_polls = 0;
+                let Some(end) = end_offsets.get(&msg.partition()).copied() else {
+                    continue;
+                };
+                if msg.offset() >= end {
+                    end_offsets.remove(&msg.partition());
+                    continue;
+                }
+                outcome.scanned += 1;
+                if msg.offset() + 1 >= end {
+                    end_offsets.remove(&msg.partition());
+                }
+                if !visit(&msg) {
+                    outcome.complete = false;
+                    break;
+                }
+            }
+            Some(Err(e)) => {
+                eprintln!("Error scanning topic {}: {}", topic, e);
+            }
+            None => idle_polls += 1,
+        }
+    }
+
+    Ok(outcome)
+}
+
+/// Latest record of every key of a topic, sorted by key.
+pub struct KeyedTable {
+    pub entries: Vec<StateStoreEntry>,
+    pub records_scanned: usize,
+    /// Keys whose latest record was a tombstone.
+    pub deleted_keys: usize,
+    pub complete: bool,
+}
+
+fn scan_latest_by_key(config: ClientConfig, topic: &str) -> Result<KeyedTable> {
+    let mut latest: HashMap<Option<Vec<u8>>, StateStoreEntry> = HashMap::new();
+    let mut deleted = HashSet::new();
+
+    let outcome = scan_topic(config, topic, None, MAX_SCAN_RECORDS, |msg| {
+        let key = msg.key().map(|k| k.to_vec());
+        match msg.payload() {
+            Some(_) => {
+                deleted.remove(&key);
+                latest.insert(key, state_store_entry(msg));
+            }
+            None => {
+                latest.remove(&key);
+                deleted.insert(key);
+            }
+        }
+        true
+    })?;
+
+    let mut entries: Vec<StateStoreEntry> = latest.into_values().collect();
+    entries.sort_by(|a, b| a.key.cmp(&b.key));
+    Ok(KeyedTable {
+        entries,
+        records_scanned: outcome.scanned,
+        deleted_keys: deleted.len(),
+        complete: outcome.complete,
+    })
+}
+
+/// Counts the latest records of `topic` by the value `source` picks out.
+fn sample_field_breakdown(
+    config: ClientConfig,
+    topic: &str,
+    source: &BreakdownSource,
+    sample_size: usize,
+) -> Result<FieldBreakdown> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut sampled = 0;
+    let mut missing = 0;
+
+    read_latest(config, topic, sample_size, |msg| {
+        sampled += 1;
+        let value = match source {
+            BreakdownSource::Header { name } => msg.headers().and_then(|headers| {
+                headers
+                    .iter()
+                    .find(|h| h.key == name)
+                    .map(|h| h.value.map(|v| String::from_utf8_lossy(v).to_string()))
+                    .map(|v| v.unwrap_or_else(|| "null".to_string()))
+            }),
+            BreakdownSource::JsonField { path } => msg
+                .payload()
+                .and_then(|p| serde_json::from_slice::<serde_json::Value>(p).ok())
+                .and_then(|json| json_path::select(&json, path).map(json_path::display)),
+        };
+        match value {
+            Some(value) => *counts.entry(value).or_default() += 1,
+            None => missing += 1,
+        }
+    })?;
+
+    let mut values: Vec<FieldValueCount> = counts
+        .into_iter()
+        .map(|(value, count)| FieldValueCount { value, count })
+        .collect();
+    values.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+
+    Ok(FieldBreakdown {
+        sampled,
+        missing,
+        values,
+    })
+}
+
+/// Record counts between consecutive `boundaries`, summed over partitions.
+fn count_between_boundaries(
+    config: ClientConfig,
+    topic: &str,
+    boundaries: &[i64],
+) -> Result<Vec<HistogramBucket>> {
+    let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+    let partitions = fetch_partition_ids(&consumer, topic)?;
+
+    let mut high_watermarks = HashMap::new();
+    for p in &partitions {
+        let (_low, high) = consumer
+            .fetch_watermarks(topic, *p, Duration::from_secs(5))
+            .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+        high_watermarks.insert(*p, high);
+    }
+
+    let mut boundary_offsets = Vec::with_capacity(boundaries.len());
+    for ts in boundaries {
+        let mut tpl = TopicPartitionList::new();
+        for p in &partitions {
+            tpl.add_partition_offset(topic, *p, rdkafka::Offset::Offset(*ts))
+                .map_err(|e| anyhow::anyhow!("Failed to set timestamp: {}", e))?;
+        }
+        let resolved = consumer
+            .offsets_for_times(tpl, Duration::from_secs(10))
+            .map_err(|e| anyhow::anyhow!("Failed to resolve offsets for times: {}", e))?;
+
+        // A boundary past the newest record resolves to the end of the log.
+        let total: i64 = resolved
+            .elements_for_topic(topic)
+            .iter()
+            .map(|elem| match elem.offset() {
+                rdkafka::Offset::Offset(o) => o,
+                _ => high_watermarks.get(&elem.partition()).copied().unwrap_or(0),
+            })
+            .sum();
+        boundary_offsets.push(total);
+    }
+
+    Ok(boundaries
+        .windows(2)
+        .zip(boundary_offsets.windows(2))
+        .map(|(ts, offsets)| HistogramBucket {
+            start: ts[0],
+            end: ts[1],
+            count: (offsets[1] - offsets[0]).max(0),
+        })
+        .collect())
+}
+
+/// Scans the partition `key` maps to for its records.
+fn scan_key_timeline(
+    config: ClientConfig,
+    topic: &str,
+    key: &str,
+    limit: usize,
+) -> Result<KeyTimeline> {
+    let consumer: BaseConsumer<ClusterContext> = create_client(&config)?;
+    let partition_count = fetch_partition_ids(&consumer, topic)?.len() as i32;
+    if partition_count == 0 {
+        return Err(anyhow::anyhow!("Topic not found"));
+    }
+    let partition = partitioner::default_partition(key.as_bytes(), partition_count);
+
+    let mut records = VecDeque::new();
+    let mut matched = 0;
+    let outcome = scan_topic(config, topic, Some(partition), MAX_SCAN_RECORDS, |msg| {
+        if msg.key() == Some(key.as_bytes()) {
+            matched += 1;
+            records.push_back(to_kafka_message(msg));
+            if records.len() > limit {
+                records.pop_front();
+            }
+        }
+        true
+    })?;
+
+    Ok(KeyTimeline {
+        key: key.to_string(),
+        partition,
+        records: records.into(),
+        matched,
+        records_scanned: outcome.scanned,
+        complete: outcome.complete,
+    })
+}
+
+/// Reads MirrorMaker 2's internal topics into a `MirroringStatus`.
+fn read_mirroring_status(
+    config: ClientConfig,
+    internal_topics: Vec<Mm2InternalTopic>,
+) -> Result<MirroringStatus> {
+    let mut flows: HashMap<(String, String), i64> = HashMap::new();
+    let mut checkpoints = HashMap::new();
+    let mut offset_syncs = HashMap::new();
+
+    for topic in &internal_topics {
+        match topic.kind {
+            Mm2TopicKind::Heartbeats => {
+                read_latest(config.clone(), &topic.name, HEARTBEAT_SAMPLE_SIZE, |msg| {
+                    let (Some(key), Some(value)) = (msg.key(), msg.payload()) else {
+                        return;
+                    };
+                    match mirror_maker::decode_heartbeat(key, value) {
+                        Ok(hb) => {
+                            let last = flows
+                                .entry((hb.source_cluster_alias, hb.target_cluster_alias))
+                                .or_insert(hb.timestamp);
+                            *last = (*last).max(hb.timestamp);
+                        }
+                        Err(e) => eprintln!("Skipping heartbeat in {}: {}", topic.name, e),
+                    }
+                })?;
+            }
+            Mm2TopicKind::Checkpoints => {
+                scan_topic(config.clone(), &topic.name, None, MAX_SCAN_RECORDS, |msg| {
+                    if let (Some(key), Some(value)) = (msg.key(), msg.payload()) {
+                        match mirror_maker::decode_checkpoint(key, value) {
+                            Ok(cp) => {
+                                checkpoints.insert(
+                                    (cp.consumer_group_id.clone(), cp.topic.clone(), cp.partition),
+                                    cp,
+                                );
+                            }
+                            Err(e) => {
+                                eprintln!("Skipping checkpoint in {}: {}", topic.name, e)
+                            }
+                        }
+                    }
+                    true
+                })?;
+            }
+            Mm2TopicKind::OffsetSyncs => {
+                scan_topic(config.clone(), &topic.name, None, MAX_SCAN_RECORDS, |msg| {
+                    if let (Some(key), Some(value)) = (msg.key(), msg.payload()) {
+                        match mirror_maker::decode_offset_sync(key, value) {
+                            Ok(sync) => {
+                                offset_syncs.insert((sync.topic.clone(), sync.partition), sync);
+                            }
+                            Err(e) => {
+                                eprintln!("Skipping offset sync in {}: {}", topic.name, e)
+                            }
+                        }
+                    }
+                    true
+                })?;
+            }
+        }
+    }
+
+    let mut flows: Vec<ReplicationFlow> = flows
+        .into_iter()
+        .map(|((source, target), last_heartbeat)| ReplicationFlow {
+            source_cluster_alias: source,
+            target_cluster_alias: target,
+            last_heartbeat,
+        })
+        .collect();
+    flows.sort_by(|a, b| {
+        (&a.source_cluster_alias, &a.target_cluster_alias)
+            .cmp(&(&b.source_cluster_alias, &b.target_cluster_alias))
+    });
+
+    let mut checkpoints: Vec<_> = checkpoints.into_values().collect();
+    checkpoints.sort_by(|a, b| {
+        (&a.consumer_group_id, &a.topic, a.partition).cmp(&(
+            &b.consumer_group_id,
+            &b.topic,
+            b.partition,
+        ))
+    });
+    let mut offset_syncs: Vec<_> = offset_syncs.into_values().collect();
+    offset_syncs.sort_by(|a, b| (&a.topic, a.partition).cmp(&(&b.topic, b.partition)));
+
+    Ok(MirroringStatus {
+        internal_topics,
+        flows,
+        checkpoints,
+        offset_syncs,
+    })
+}
+
+fn state_store_entry<M: Message>(msg: &M) -> StateStoreEntry {
+    StateStoreEntry {
+        key: msg.key().map(|k| String::from_utf8_lossy(k).to_string()),
+        value: msg
+            .payload()
+            .map(|p| String::from_utf8_lossy(p).to_string()),
+        partition: msg.partition(),
+        offset: msg.offset(),
+        timestamp: msg.timestamp().to_millis(),
+    }
+}
+
+fn to_kafka_message<M: Message>(msg: &M) -> KafkaMessage {
+    KafkaMessage {
+        partition: msg.partition(),
+        offset: msg.offset(),
+        timestamp: msg.timestamp().to_millis(),
+        key: msg.key().map(|k| String::from_utf8_lossy(k).to_string()),
+        payload: msg
+            .payload()
+            .map(|p| String::from_utf8_lossy(p).to_string()),
+        headers: msg
+            .headers()
+            .map(|headers| {
+                headers
+                    .iter()
+                    .map(|h| MessageHeader {
+                        key: h.key.to_string(),
+                        value: h.value.map(|v| String::from_utf8_lossy(v).to_string()),
+                        value_base64: h.value.map(|v| BASE64.encode(v)),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+        truncated_from: None,
+    }
+}
+
+fn to_owned_headers(headers: &[MessageHeader]) -> Result<OwnedHeaders> {
+    let mut owned = OwnedHeaders::new_with_capacity(headers.len());
+    for header in headers {
+        let value = match (&header.value_base64, &header.value) {
+            (Some(encoded), _) => Some(BASE64.decode(encoded).map_err(|e| {
+                anyhow::anyhow!("Header {} is not valid base64: {}", header.key, e)
+            })?),
+            (None, Some(value)) => Some(value.as_bytes().to_vec()),
+            (None, None) => None,
+        };
+        owned = owned.insert(Header {
+            key: &header.key,
+            value: value.as_ref(),
+        });
+    }
+    Ok(owned)
+}
+
+fn metadata_cache_ttl(cluster: &Cluster) -> Duration {
+    cluster
+        .metadata_cache_ttl_ms
+        .map(Duration::from_millis)
+        .unwrap_or(POLL_MIN_INTERVAL)
+}
+
+fn to_topic(t: &rdkafka::metadata::MetadataTopic) -> Topic {
+    let mut partition_ids: Vec<i32> = t.partitions().iter().map(|p| p.id()).collect();
+    partition_ids.sort_unstable();
+    let internal_kind = internal_topic_kind(t.name());
+    Topic {
+        name: t.name().to_string(),
+        partitions: t.partitions().len() as i32,
+        replication_factor: t
+            .partitions()
+            .iter()
+            .map(|p| p.replicas().len() as i32)
+            .max()
+            .unwrap_or(0),
+        partition_ids,
+        message_count_estimate: None,
+        last_activity_ts: None,
+        is_internal: internal_kind.is_some(),
+        internal_kind,
+        annotation: None,
+        protected: false,
+        cached_at: None,
+    }
+}
+
+fn fetch_topics(
+    client: &AdminClient<ClusterContext>,
+    cluster_name: &str,
+    brokers: &str,
+) -> Result<Vec<Topic>> {
+    println!(
+        "Fetching metadata for cluster: {} at {}",
+        cluster_name, brokers
+    );
+    let metadata = client
+        .inner()
+        .fetch_metadata(None, Duration::from_secs(5))
+        .map_err(|e| anyhow::anyhow!("Failed to fetch metadata from {}: {}", brokers, e))?;
+
+    let topics = metadata.topics().iter().map(to_topic).collect();
+
+    println!("Successfully fetched {} topics", metadata.topics().len());
+    Ok(topics)
+}
+
+fn fetch_topic_activity(
+    client: &AdminClient<ClusterContext>,
+    partitions: &[(String, i32)],
+) -> Result<TopicActivity> {
+    use native_admin::OffsetSpec;
+
+    let earliest = native_admin::list_offsets(client, partitions, OffsetSpec::Earliest)?;
+    let latest = native_admin::list_offsets(client, partitions, OffsetSpec::Latest)?;
+    // Brokers before Kafka 3.0 reject the max-timestamp spec; the counts
+    // are still worth showing without it.
+    let newest = native_admin::list_offsets(client, partitions, OffsetSpec::MaxTimestamp)
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to fetch newest record timestamps: {}", e);
+            HashMap::new()
+        });
+
+    let mut activity = TopicActivity::new();
+    for key in partitions {
+        let (Some(&(low, _)), Some(&(high, _))) = (earliest.get(key), latest.get(key)) else {
+            continue;
+        };
+        let (count, last_activity) = activity.entry(key.0.clone()).or_insert((0, None));
+        *count += (high - low).max(0);
+        if let Some(&(_, timestamp)) = newest.get(key).filter(|(_, ts)| *ts >= 0) {
+            *last_activity = Some(last_activity.map_or(timestamp, |t: i64| t.max(timestamp)));
+        }
+    }
+    Ok(activity)
+}
+
+fn fetch_partition_watermarks(
+    consumer: &BaseConsumer<ClusterContext>,
+    topic: &str,
+) -> Result<Vec<Partition>> {
+    let metadata = consumer
+        .fetch_metadata(Some(topic), Duration::from_secs(5))
+        .map_err(|e| anyhow::anyhow!("Failed to fetch topic metadata: {}", e))?;
+    let topic_metadata = metadata
+        .topics()
+        .iter()
+        .find(|t| t.name() == topic && t.error().is_none())
+        .ok_or_else(|| anyhow::anyhow!("Topic not found"))?;
+
+    let mut partitions = Vec::with_capacity(topic_metadata.partitions().len());
+    for p in topic_metadata.partitions() {
+        let (low, high) = consumer
+            .fetch_watermarks(topic, p.id(), Duration::from_secs(5))
+            .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+        partitions.push(Partition {
+            id: p.id(),
+            leader: p.leader(),
+            replicas: p.replicas().to_vec(),
+            isrs: p.isr().to_vec(),
+            low_watermark: Some(low),
+            high_watermark: Some(high),
+        });
+    }
+    partitions.sort_by_key(|p| p.id);
+    Ok(partitions)
+}
+
+fn fetch_message_count(consumer: &BaseConsumer<ClusterContext>, topic: &str) -> Result<i64> {
+    let metadata = consumer
+        .fetch_metadata(Some(topic), Duration::from_secs(5))
+        .map_err(|e| anyhow::anyhow!("Failed to fetch topic metadata: {}", e))?;
+
+    let topic_metadata = metadata
+        .topics()
+        .iter()
+        .find(|t| t.name() == topic)
+        .ok_or_else(|| anyhow::anyhow!("Topic not found"))?;
+
+    let partition_count = topic_metadata.partitions().len() as i32;
+
+    let mut total_messages: i64 = 0;
+    for p in 0..partition_count {
+        let (low, high) = consumer
+            .fetch_watermarks(topic, p, Duration::from_secs(5))
+            .map_err(|e| anyhow::anyhow!("Failed to fetch watermarks: {}", e))?;
+        total_messages += high - low;
+    }
+
+    Ok(total_messages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::topic::MessageFilter;
+
+    fn record(offset: i64, payload: &str) -> KafkaMessage {
+        KafkaMessage {
+            partition: 0,
+            offset,
+            timestamp: None,
+            key: None,
+            payload: Some(payload.to_string()),
+            headers: Vec::new(),
+            truncated_from: None,
+        }
+    }
+
+    #[test]
+    fn reading_back_finds_sparse_matches_beyond_the_newest_window() {
+        let matcher = MessageFilter {
+            contains: Some("needle".to_string()),
+            ..Default::default()
+        }
+        .compile()
+        .unwrap();
+        // One match every 700 offsets, none among the newest 100 records.
+        let log: Vec<KafkaMessage> = (0..5_000)
+            .map(|offset| record(offset, if offset % 700 == 0 { "needle" } else { "hay" }))
+            .collect();
+
+        let lows = BTreeMap::from([(0, 0)]);
+        let mut ends = BTreeMap::from([(0, 5_000)]);
+        let mut matches = Vec::new();
+        let mut windows_read = 0;
+        walk_back(&lows, &mut ends, 1_000, 3, |windows| {
+            let (start, end) = windows[&0];
+            let window = &log[start as usize..end as usize];
+            let before = matches.len();
+            matches.extend(
+                window
+                    .iter()
+                    .filter(|m| matcher.matches(m))
+                    .map(|m| m.offset),
+            );
+            windows_read += 1;
+            Ok(ControlFlow::Continue((
+                matches.len() - before,
+                window.len(),
+            )))
+        })
+        .unwrap();
+        matches.sort_unstable();
+        matches.reverse();
+
+        assert_eq!(matches, vec![4_900, 4_200, 3_500]);
+        assert_eq!(windows_read, 2);
+        assert_eq!(ends, BTreeMap::from([(0, 3_000)]));
+    }
+
+    #[test]
+    fn walking_back_stops_where_a_window_breaks() {
+        let lows = BTreeMap::from([(0, 0)]);
+        let mut ends = BTreeMap::from([(0, 5_000)]);
+        let mut windows_read = 0;
+        walk_back(&lows, &mut ends, 1_000, 10, |_| {
+            windows_read += 1;
+            Ok(if windows_read == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue((0, 1_000))
+            })
+        })
+        .unwrap();
+
+        assert_eq!(windows_read, 2);
+        assert_eq!(ends, BTreeMap::from([(0, 4_000)]));
+    }
+
+    #[test]
+    fn older_windows_stop_at_the_low_watermark() {
+        let lows = BTreeMap::from([(0, 100), (1, 0)]);
+        let ends = BTreeMap::from([(0, 150), (1, 0)]);
+
+        assert_eq!(
+            older_windows(&ends, &lows, 1_000),
+            BTreeMap::from([(0, (100, 150))])
+        );
     }
 }
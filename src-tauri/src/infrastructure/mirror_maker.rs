@@ -0,0 +1,108 @@
+//! Decoders for the records MirrorMaker 2 writes to its internal topics. They
+//! use Kafka's `Struct` wire format: big-endian integers and strings prefixed
+//! with an int16 length. Heartbeat and checkpoint values start with an int16
+//! schema version; offset-sync records are unversioned.
+
+use crate::domain::mirroring::{Checkpoint, Heartbeat, OffsetSync};
+use anyhow::{anyhow, Result};
+
+pub fn decode_heartbeat(key: &[u8], value: &[u8]) -> Result<Heartbeat> {
+    let mut key = StructReader::new(key);
+    let source_cluster_alias = key.string()?;
+    let target_cluster_alias = key.string()?;
+
+    let mut value = StructReader::new(value);
+    value.version()?;
+    let timestamp = value.i64()?;
+
+    Ok(Heartbeat {
+        source_cluster_alias,
+        target_cluster_alias,
+        timestamp,
+    })
+}
+
+pub fn decode_checkpoint(key: &[u8], value: &[u8]) -> Result<Checkpoint> {
+    let mut key = StructReader::new(key);
+    let consumer_group_id = key.string()?;
+    let topic = key.string()?;
+    let partition = key.i32()?;
+
+    let mut value = StructReader::new(value);
+    value.version()?;
+    let upstream_offset = value.i64()?;
+    let downstream_offset = value.i64()?;
+    let metadata = value.string()?;
+
+    Ok(Checkpoint {
+        consumer_group_id,
+        topic,
+        partition,
+        upstream_offset,
+        downstream_offset,
+        metadata,
+    })
+}
+
+pub fn decode_offset_sync(key: &[u8], value: &[u8]) -> Result<OffsetSync> {
+    let mut key = StructReader::new(key);
+    let topic = key.string()?;
+    let partition = key.i32()?;
+
+    let mut value = StructReader::new(value);
+    let upstream_offset = value.i64()?;
+    let downstream_offset = value.i64()?;
+
+    Ok(OffsetSync {
+        topic,
+        partition,
+        upstream_offset,
+        downstream_offset,
+    })
+}
+
+struct StructReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> StructReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        if self.buf.len() < len {
+            return Err(anyhow!("Truncated MirrorMaker record"));
+        }
+        let (head, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(head)
+    }
+
+    fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn i64(&mut self) -> Result<i64> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.i16()?;
+        if len < 0 {
+            return Err(anyhow!("Unexpected null string in MirrorMaker record"));
+        }
+        Ok(String::from_utf8_lossy(self.take(len as usize)?).to_string())
+    }
+
+    fn version(&mut self) -> Result<()> {
+        match self.i16()? {
+            0 => Ok(()),
+            v => Err(anyhow!("Unsupported MirrorMaker record version {}", v)),
+        }
+    }
+}
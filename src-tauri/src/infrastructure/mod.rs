@@ -1,2 +1,20 @@
+pub mod broker_protocol;
+pub mod certificate_pin;
+pub mod client_logs;
+pub mod client_pool;
+pub mod client_stats;
+pub mod consumer_protocol;
+pub mod deserializer_plugins;
+pub mod json_path;
 pub mod kafka;
+pub mod mirror_maker;
+pub mod native_admin;
+pub mod oauth_tokens;
+pub mod partitioner;
+pub mod payload_cipher;
+pub mod payload_decoder;
 pub mod persistence;
+pub mod production_receipts;
+pub mod request_coalescer;
+pub mod session_manager;
+pub mod wasm_transforms;
@@ -0,0 +1,733 @@
+//! Admin requests that rdkafka's `AdminClient` does not wrap, issued through
+//! librdkafka's C API. Every call blocks until the broker answers, so callers
+//! run them on a blocking thread.
+
+use crate::domain::acl::{
+    AclBinding, AclOperation, AclPatternType, AclPermission, AclResourceType,
+};
+use crate::domain::cluster::cluster::SaslMechanism;
+use crate::domain::topic::PartitionTruncation;
+use anyhow::{anyhow, Result};
+use rdkafka::admin::AdminClient;
+use rdkafka::bindings as rdsys;
+use rdkafka::{Offset, TopicPartitionList};
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+use std::time::Duration;
+
+use super::client_logs::ClusterContext;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Kafka rejects SCRAM credentials with fewer than 4096 iterations.
+const SCRAM_ITERATIONS: i32 = 8192;
+
+/// Extra time the local wait allows on top of the broker request timeout.
+const QUEUE_GRACE: Duration = Duration::from_secs(5);
+
+struct Event(*mut rdsys::rd_kafka_event_t);
+
+impl Drop for Event {
+    fn drop(&mut self) {
+        unsafe { rdsys::rd_kafka_event_destroy(self.0) }
+    }
+}
+
+pub fn describe_acls(client: &AdminClient<ClusterContext>) -> Result<Vec<AclBinding>> {
+    let mut errstr = [0 as c_char; 512];
+    let filter = unsafe {
+        rdsys::rd_kafka_AclBindingFilter_new(
+            rdsys::rd_kafka_ResourceType_t::RD_KAFKA_RESOURCE_ANY,
+            ptr::null(),
+            rdsys::rd_kafka_ResourcePatternType_t::RD_KAFKA_RESOURCE_PATTERN_ANY,
+            ptr::null(),
+            ptr::null(),
+            rdsys::rd_kafka_AclOperation_t::RD_KAFKA_ACL_OPERATION_ANY,
+            rdsys::rd_kafka_AclPermissionType_t::RD_KAFKA_ACL_PERMISSION_TYPE_ANY,
+            errstr.as_mut_ptr(),
+            errstr.len(),
+        )
+    };
+    if filter.is_null() {
+        return Err(anyhow!(
+            "Failed to build ACL filter: {}",
+            from_errstr(&errstr)
+        ));
+    }
+
+    let event = request(
+        client,
+        rdsys::rd_kafka_admin_op_t::RD_KAFKA_ADMIN_OP_DESCRIBEACLS,
+        |rk, options, queue| unsafe { rdsys::rd_kafka_DescribeAcls(rk, filter, options, queue) },
+    );
+    unsafe { rdsys::rd_kafka_AclBinding_destroy(filter) };
+    let event = event.map_err(|e| anyhow!("Failed to describe ACLs: {}", e))?;
+
+    unsafe {
+        let result = rdsys::rd_kafka_event_DescribeAcls_result(event.0);
+        let mut count = 0;
+        let acls = rdsys::rd_kafka_DescribeAcls_result_acls(result, &mut count);
+        (0..count)
+            .map(|i| acl_binding_from_native(*acls.add(i)))
+            .collect()
+    }
+}
+
+/// Creates the given bindings; creating a binding that already exists is a
+/// no-op on the broker.
+pub fn create_acls(client: &AdminClient<ClusterContext>, acls: &[AclBinding]) -> Result<()> {
+    if acls.is_empty() {
+        return Ok(());
+    }
+
+    let mut natives = Vec::with_capacity(acls.len());
+    let built: Result<()> = acls.iter().try_for_each(|acl| {
+        natives.push(acl_binding_to_native(acl, rdsys::rd_kafka_AclBinding_new)?);
+        Ok(())
+    });
+
+    let event = built.and_then(|_| {
+        request(
+            client,
+            rdsys::rd_kafka_admin_op_t::RD_KAFKA_ADMIN_OP_CREATEACLS,
+            |rk, options, queue| unsafe {
+                rdsys::rd_kafka_CreateAcls(rk, natives.as_mut_ptr(), natives.len(), options, queue)
+            },
+        )
+    });
+    for native in natives {
+        unsafe { rdsys::rd_kafka_AclBinding_destroy(native) };
+    }
+    let event = event.map_err(|e| anyhow!("Failed to create ACLs: {}", e))?;
+
+    unsafe {
+        let result = rdsys::rd_kafka_event_CreateAcls_result(event.0);
+        let mut count = 0;
+        let results = rdsys::rd_kafka_CreateAcls_result_acls(result, &mut count);
+        for (i, acl) in acls.iter().enumerate().take(count) {
+            let error = rdsys::rd_kafka_acl_result_error(*results.add(i));
+            if !error.is_null() {
+                return Err(anyhow!(
+                    "Failed to create ACL {:?}: {}",
+                    acl,
+                    from_cstr(rdsys::rd_kafka_error_string(error))
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deletes exactly the given bindings.
+pub fn delete_acls(client: &AdminClient<ClusterContext>, acls: &[AclBinding]) -> Result<()> {
+    if acls.is_empty() {
+        return Ok(());
+    }
+
+    let mut filters = Vec::with_capacity(acls.len());
+    let built: Result<()> = acls.iter().try_for_each(|acl| {
+        filters.push(acl_binding_to_native(
+            acl,
+            rdsys::rd_kafka_AclBindingFilter_new,
+        )?);
+        Ok(())
+    });
+
+    let event = built.and_then(|_| {
+        request(
+            client,
+            rdsys::rd_kafka_admin_op_t::RD_KAFKA_ADMIN_OP_DELETEACLS,
+            |rk, options, queue| unsafe {
+                rdsys::rd_kafka_DeleteAcls(rk, filters.as_mut_ptr(), filters.len(), options, queue)
+            },
+        )
+    });
+    for filter in filters {
+        unsafe { rdsys::rd_kafka_AclBinding_destroy(filter) };
+    }
+    let event = event.map_err(|e| anyhow!("Failed to delete ACLs: {}", e))?;
+
+    unsafe {
+        let result = rdsys::rd_kafka_event_DeleteAcls_result(event.0);
+        let mut count = 0;
+        let responses = rdsys::rd_kafka_DeleteAcls_result_responses(result, &mut count);
+        for (i, acl) in acls.iter().enumerate().take(count) {
+            let error = rdsys::rd_kafka_DeleteAcls_result_response_error(*responses.add(i));
+            if !error.is_null() {
+                return Err(anyhow!(
+                    "Failed to delete ACL {:?}: {}",
+                    acl,
+                    from_cstr(rdsys::rd_kafka_error_string(error))
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deletes a group's committed offsets for the given partitions of `topic`
+/// (OffsetDelete). The broker refuses while the group is subscribed to it.
+pub fn delete_group_offsets(
+    client: &AdminClient<ClusterContext>,
+    group_id: &str,
+    topic: &str,
+    partitions: &[i32],
+) -> Result<()> {
+    let group = CString::new(group_id)?;
+    let mut tpl = TopicPartitionList::new();
+    for &partition in partitions {
+        tpl.add_partition(topic, partition);
+    }
+
+    let mut offsets =
+        [unsafe { rdsys::rd_kafka_DeleteConsumerGroupOffsets_new(group.as_ptr(), tpl.ptr()) }];
+    let event = request(
+        client,
+        rdsys::rd_kafka_admin_op_t::RD_KAFKA_ADMIN_OP_DELETECONSUMERGROUPOFFSETS,
+        |rk, options, queue| unsafe {
+            rdsys::rd_kafka_DeleteConsumerGroupOffsets(
+                rk,
+                offsets.as_mut_ptr(),
+                offsets.len(),
+                options,
+                queue,
+            )
+        },
+    );
+    unsafe { rdsys::rd_kafka_DeleteConsumerGroupOffsets_destroy(offsets[0]) };
+    let event = event.map_err(|e| anyhow!("Failed to delete committed offsets: {}", e))?;
+
+    unsafe {
+        let result = rdsys::rd_kafka_event_DeleteConsumerGroupOffsets_result(event.0);
+        let mut count = 0;
+        let groups = rdsys::rd_kafka_DeleteConsumerGroupOffsets_result_groups(result, &mut count);
+        for i in 0..count {
+            let group = *groups.add(i);
+            let error = rdsys::rd_kafka_group_result_error(group);
+            if !error.is_null() {
+                return Err(anyhow!(
+                    "Failed to delete committed offsets: {}",
+                    from_cstr(rdsys::rd_kafka_error_string(error))
+                ));
+            }
+
+            let list = rdsys::rd_kafka_group_result_partitions(group);
+            if list.is_null() {
+                continue;
+            }
+            for j in 0..(*list).cnt as usize {
+                let partition = &*(*list).elems.add(j);
+                if partition.err != rdsys::rd_kafka_resp_err_t::RD_KAFKA_RESP_ERR_NO_ERROR {
+                    return Err(anyhow!(
+                        "Failed to delete committed offset of {} partition {}: {}",
+                        topic,
+                        partition.partition,
+                        from_cstr(rdsys::rd_kafka_err2str(partition.err))
+                    ));
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the records of each partition below the given offset
+/// (DeleteRecords); `Offset::End` empties the partition. Returns the new low
+/// watermark of each partition.
+pub fn delete_records(
+    client: &AdminClient<ClusterContext>,
+    topic: &str,
+    offsets: &[(i32, Offset)],
+) -> Result<Vec<PartitionTruncation>> {
+    let mut tpl = TopicPartitionList::new();
+    for &(partition, offset) in offsets {
+        tpl.add_partition_offset(topic, partition, offset)
+            .map_err(|e| anyhow!("Invalid offset for partition {}: {}", partition, e))?;
+    }
+
+    let mut records = [unsafe { rdsys::rd_kafka_DeleteRecords_new(tpl.ptr()) }];
+    let event = request(
+        client,
+        rdsys::rd_kafka_admin_op_t::RD_KAFKA_ADMIN_OP_DELETERECORDS,
+        |rk, options, queue| unsafe {
+            rdsys::rd_kafka_DeleteRecords(rk, records.as_mut_ptr(), records.len(), options, queue)
+        },
+    );
+    unsafe { rdsys::rd_kafka_DeleteRecords_destroy(records[0]) };
+    let event = event.map_err(|e| anyhow!("Failed to delete records: {}", e))?;
+
+    let mut truncations = Vec::new();
+    unsafe {
+        let result = rdsys::rd_kafka_event_DeleteRecords_result(event.0);
+        let list = rdsys::rd_kafka_DeleteRecords_result_offsets(result);
+        if list.is_null() {
+            return Ok(truncations);
+        }
+        for i in 0..(*list).cnt as usize {
+            let partition = &*(*list).elems.add(i);
+            if partition.err != rdsys::rd_kafka_resp_err_t::RD_KAFKA_RESP_ERR_NO_ERROR {
+                return Err(anyhow!(
+                    "Failed to delete records of {} partition {}: {}",
+                    topic,
+                    partition.partition,
+                    from_cstr(rdsys::rd_kafka_err2str(partition.err))
+                ));
+            }
+            truncations.push(PartitionTruncation {
+                partition: partition.partition,
+                low_watermark: partition.offset,
+            });
+        }
+    }
+    truncations.sort_by_key(|t| t.partition);
+    Ok(truncations)
+}
+
+/// Which offset of each partition ListOffsets resolves.
+#[derive(Debug, Clone, Copy)]
+pub enum OffsetSpec {
+    Earliest,
+    Latest,
+    /// The record with the largest timestamp (Kafka 3.0+).
+    MaxTimestamp,
+}
+
+/// Resolves `spec` for every listed partition in one ListOffsets request,
+/// returning the offset and timestamp of each by `(topic, partition)`.
+/// Partitions the broker reports an error for are left out.
+pub fn list_offsets(
+    client: &AdminClient<ClusterContext>,
+    partitions: &[(String, i32)],
+    spec: OffsetSpec,
+) -> Result<HashMap<(String, i32), (i64, i64)>> {
+    let spec = match spec {
+        OffsetSpec::Earliest => rdsys::rd_kafka_OffsetSpec_t::RD_KAFKA_OFFSET_SPEC_EARLIEST,
+        OffsetSpec::Latest => rdsys::rd_kafka_OffsetSpec_t::RD_KAFKA_OFFSET_SPEC_LATEST,
+        OffsetSpec::MaxTimestamp => {
+            rdsys::rd_kafka_OffsetSpec_t::RD_KAFKA_OFFSET_SPEC_MAX_TIMESTAMP
+        }
+    };
+    let mut offsets = HashMap::new();
+    if partitions.is_empty() {
+        return Ok(offsets);
+    }
+
+    let mut tpl = TopicPartitionList::new();
+    for (topic, partition) in partitions {
+        tpl.add_partition_offset(topic, *partition, Offset::Offset(spec as i64))
+            .map_err(|e| anyhow!("Invalid offset for partition {}: {}", partition, e))?;
+    }
+
+    let event = request(
+        client,
+        rdsys::rd_kafka_admin_op_t::RD_KAFKA_ADMIN_OP_LISTOFFSETS,
+        |rk, options, queue| unsafe { rdsys::rd_kafka_ListOffsets(rk, tpl.ptr(), options, queue) },
+    )
+    .map_err(|e| anyhow!("Failed to list offsets: {}", e))?;
+
+    unsafe {
+        let result = rdsys::rd_kafka_event_ListOffsets_result(event.0);
+        let mut count = 0;
+        let infos = rdsys::rd_kafka_ListOffsets_result_infos(result, &mut count);
+        for i in 0..count {
+            let info = *infos.add(i);
+            let partition = &*rdsys::rd_kafka_ListOffsetsResultInfo_topic_partition(info);
+            if partition.err != rdsys::rd_kafka_resp_err_t::RD_KAFKA_RESP_ERR_NO_ERROR {
+                continue;
+            }
+            offsets.insert(
+                (from_cstr(partition.topic), partition.partition),
+                (
+                    partition.offset,
+                    rdsys::rd_kafka_ListOffsetsResultInfo_timestamp(info),
+                ),
+            );
+        }
+    }
+    Ok(offsets)
+}
+
+/// Static membership ids (`group.instance.id`) of a group's members, keyed
+/// by member id. Dynamic members map to `None`.
+pub fn describe_group_instance_ids(
+    client: &AdminClient<ClusterContext>,
+    group_id: &str,
+) -> Result<HashMap<String, Option<String>>> {
+    let group = CString::new(group_id)?;
+    let mut groups = [group.as_ptr()];
+    let event = request(
+        client,
+        rdsys::rd_kafka_admin_op_t::RD_KAFKA_ADMIN_OP_DESCRIBECONSUMERGROUPS,
+        |rk, options, queue| unsafe {
+            rdsys::rd_kafka_DescribeConsumerGroups(
+                rk,
+                groups.as_mut_ptr(),
+                groups.len(),
+                options,
+                queue,
+            )
+        },
+    )
+    .map_err(|e| anyhow!("Failed to describe consumer group: {}", e))?;
+
+    let mut instance_ids = HashMap::new();
+    unsafe {
+        let result = rdsys::rd_kafka_event_DescribeConsumerGroups_result(event.0);
+        let mut count = 0;
+        let descriptions = rdsys::rd_kafka_DescribeConsumerGroups_result_groups(result, &mut count);
+        for i in 0..count {
+            let description = *descriptions.add(i);
+            let error = rdsys::rd_kafka_ConsumerGroupDescription_error(description);
+            if !error.is_null() {
+                return Err(anyhow!(
+                    "Failed to describe consumer group: {}",
+                    from_cstr(rdsys::rd_kafka_error_string(error))
+                ));
+            }
+
+            for j in 0..rdsys::rd_kafka_ConsumerGroupDescription_member_count(description) {
+                let member = rdsys::rd_kafka_ConsumerGroupDescription_member(description, j);
+                let instance_id = rdsys::rd_kafka_MemberDescription_group_instance_id(member);
+                instance_ids.insert(
+                    from_cstr(rdsys::rd_kafka_MemberDescription_consumer_id(member)),
+                    (!instance_id.is_null()).then(|| from_cstr(instance_id)),
+                );
+            }
+        }
+    }
+    Ok(instance_ids)
+}
+
+/// Whether the user has SCRAM credentials for any mechanism.
+pub fn scram_user_exists(client: &AdminClient<ClusterContext>, user: &str) -> Result<bool> {
+    let name = CString::new(user)?;
+    let mut users = [name.as_ptr()];
+
+    let event = request(
+        client,
+        rdsys::rd_kafka_admin_op_t::RD_KAFKA_ADMIN_OP_DESCRIBEUSERSCRAMCREDENTIALS,
+        |rk, options, queue| unsafe {
+            rdsys::rd_kafka_DescribeUserScramCredentials(
+                rk,
+                users.as_mut_ptr(),
+                users.len(),
+                options,
+                queue,
+            )
+        },
+    )
+    .map_err(|e| anyhow!("Failed to describe SCRAM credentials: {}", e))?;
+
+    unsafe {
+        let result = rdsys::rd_kafka_event_DescribeUserScramCredentials_result(event.0);
+        let mut count = 0;
+        let descriptions =
+            rdsys::rd_kafka_DescribeUserScramCredentials_result_descriptions(result, &mut count);
+        if count == 0 {
+            return Ok(false);
+        }
+
+        let error = rdsys::rd_kafka_UserScramCredentialsDescription_error(*descriptions);
+        if error.is_null() {
+            return Ok(true);
+        }
+        match rdsys::rd_kafka_error_code(error) {
+            rdsys::rd_kafka_resp_err_t::RD_KAFKA_RESP_ERR_NO_ERROR => Ok(true),
+            rdsys::rd_kafka_resp_err_t::RD_KAFKA_RESP_ERR_RESOURCE_NOT_FOUND => Ok(false),
+            _ => Err(anyhow!(
+                "Failed to describe SCRAM credentials: {}",
+                from_cstr(rdsys::rd_kafka_error_string(error))
+            )),
+        }
+    }
+}
+
+/// Creates or replaces the user's credential for a SCRAM mechanism. The salt
+/// is generated by librdkafka.
+pub fn upsert_scram_credential(
+    client: &AdminClient<ClusterContext>,
+    user: &str,
+    mechanism: &SaslMechanism,
+    password: &str,
+) -> Result<()> {
+    let name = CString::new(user)?;
+    let alteration = unsafe {
+        rdsys::rd_kafka_UserScramCredentialUpsertion_new(
+            name.as_ptr(),
+            scram_mechanism(mechanism)?,
+            SCRAM_ITERATIONS,
+            password.as_ptr(),
+            password.len(),
+            ptr::null(),
+            0,
+        )
+    };
+    alter_scram_credential(client, alteration)
+}
+
+pub fn delete_scram_credential(
+    client: &AdminClient<ClusterContext>,
+    user: &str,
+    mechanism: &SaslMechanism,
+) -> Result<()> {
+    let name = CString::new(user)?;
+    let alteration = unsafe {
+        rdsys::rd_kafka_UserScramCredentialDeletion_new(name.as_ptr(), scram_mechanism(mechanism)?)
+    };
+    alter_scram_credential(client, alteration)
+}
+
+fn alter_scram_credential(
+    client: &AdminClient<ClusterContext>,
+    alteration: *mut rdsys::rd_kafka_UserScramCredentialAlteration_t,
+) -> Result<()> {
+    let mut alterations = [alteration];
+    let event = request(
+        client,
+        rdsys::rd_kafka_admin_op_t::RD_KAFKA_ADMIN_OP_ALTERUSERSCRAMCREDENTIALS,
+        |rk, options, queue| unsafe {
+            rdsys::rd_kafka_AlterUserScramCredentials(
+                rk,
+                alterations.as_mut_ptr(),
+                alterations.len(),
+                options,
+                queue,
+            )
+        },
+    );
+    unsafe { rdsys::rd_kafka_UserScramCredentialAlteration_destroy(alteration) };
+    let event = event.map_err(|e| anyhow!("Failed to alter SCRAM credentials: {}", e))?;
+
+    unsafe {
+        let result = rdsys::rd_kafka_event_AlterUserScramCredentials_result(event.0);
+        let mut count = 0;
+        let responses =
+            rdsys::rd_kafka_AlterUserScramCredentials_result_responses(result, &mut count);
+        for i in 0..count {
+            let error =
+                rdsys::rd_kafka_AlterUserScramCredentials_result_response_error(*responses.add(i));
+            if !error.is_null()
+                && rdsys::rd_kafka_error_code(error)
+                    != rdsys::rd_kafka_resp_err_t::RD_KAFKA_RESP_ERR_NO_ERROR
+            {
+                return Err(anyhow!(
+                    "Failed to alter SCRAM credentials: {}",
+                    from_cstr(rdsys::rd_kafka_error_string(error))
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn scram_mechanism(mechanism: &SaslMechanism) -> Result<rdsys::rd_kafka_ScramMechanism_t> {
+    match mechanism {
+        SaslMechanism::ScramSha256 => {
+            Ok(rdsys::rd_kafka_ScramMechanism_t::RD_KAFKA_SCRAM_MECHANISM_SHA_256)
+        }
+        SaslMechanism::ScramSha512 => {
+            Ok(rdsys::rd_kafka_ScramMechanism_t::RD_KAFKA_SCRAM_MECHANISM_SHA_512)
+        }
+        other => Err(anyhow!("{:?} is not a SCRAM mechanism", other)),
+    }
+}
+
+/// Issues one admin request on a private queue and waits for its result event.
+fn request<F>(
+    client: &AdminClient<ClusterContext>,
+    op: rdsys::rd_kafka_admin_op_t,
+    start: F,
+) -> Result<Event>
+where
+    F: FnOnce(
+        *mut rdsys::rd_kafka_t,
+        *const rdsys::rd_kafka_AdminOptions_t,
+        *mut rdsys::rd_kafka_queue_t,
+    ),
+{
+    let rk = client.inner().native_ptr();
+    let mut errstr = [0 as c_char; 512];
+
+    let event = unsafe {
+        let queue = rdsys::rd_kafka_queue_new(rk);
+        let options = rdsys::rd_kafka_AdminOptions_new(rk, op);
+        rdsys::rd_kafka_AdminOptions_set_request_timeout(
+            options,
+            REQUEST_TIMEOUT.as_millis() as i32,
+            errstr.as_mut_ptr(),
+            errstr.len(),
+        );
+
+        start(rk, options, queue);
+        let event =
+            rdsys::rd_kafka_queue_poll(queue, (REQUEST_TIMEOUT + QUEUE_GRACE).as_millis() as i32);
+
+        rdsys::rd_kafka_AdminOptions_destroy(options);
+        rdsys::rd_kafka_queue_destroy(queue);
+        event
+    };
+
+    if event.is_null() {
+        return Err(anyhow!("Request timed out"));
+    }
+    let event = Event(event);
+
+    let code = unsafe { rdsys::rd_kafka_event_error(event.0) };
+    if code != rdsys::rd_kafka_resp_err_t::RD_KAFKA_RESP_ERR_NO_ERROR {
+        return Err(anyhow!(from_cstr(unsafe {
+            rdsys::rd_kafka_event_error_string(event.0)
+        })));
+    }
+    Ok(event)
+}
+
+unsafe fn acl_binding_from_native(acl: *const rdsys::rd_kafka_AclBinding_t) -> Result<AclBinding> {
+    use rdsys::rd_kafka_AclOperation_t as Op;
+
+    let resource_type = match rdsys::rd_kafka_AclBinding_restype(acl) {
+        rdsys::rd_kafka_ResourceType_t::RD_KAFKA_RESOURCE_TOPIC => AclResourceType::Topic,
+        rdsys::rd_kafka_ResourceType_t::RD_KAFKA_RESOURCE_GROUP => AclResourceType::Group,
+        rdsys::rd_kafka_ResourceType_t::RD_KAFKA_RESOURCE_BROKER => AclResourceType::Cluster,
+        rdsys::rd_kafka_ResourceType_t::RD_KAFKA_RESOURCE_TRANSACTIONAL_ID => {
+            AclResourceType::TransactionalId
+        }
+        other => return Err(anyhow!("Unsupported ACL resource type {:?}", other)),
+    };
+    let pattern_type = match rdsys::rd_kafka_AclBinding_resource_pattern_type(acl) {
+        rdsys::rd_kafka_ResourcePatternType_t::RD_KAFKA_RESOURCE_PATTERN_LITERAL => {
+            AclPatternType::Literal
+        }
+        rdsys::rd_kafka_ResourcePatternType_t::RD_KAFKA_RESOURCE_PATTERN_PREFIXED => {
+            AclPatternType::Prefixed
+        }
+        other => return Err(anyhow!("Unsupported ACL pattern type {:?}", other)),
+    };
+    let operation = match rdsys::rd_kafka_AclBinding_operation(acl) {
+        Op::RD_KAFKA_ACL_OPERATION_ALL => AclOperation::All,
+        Op::RD_KAFKA_ACL_OPERATION_READ => AclOperation::Read,
+        Op::RD_KAFKA_ACL_OPERATION_WRITE => AclOperation::Write,
+        Op::RD_KAFKA_ACL_OPERATION_CREATE => AclOperation::Create,
+        Op::RD_KAFKA_ACL_OPERATION_DELETE => AclOperation::Delete,
+        Op::RD_KAFKA_ACL_OPERATION_ALTER => AclOperation::Alter,
+        Op::RD_KAFKA_ACL_OPERATION_DESCRIBE => AclOperation::Describe,
+        Op::RD_KAFKA_ACL_OPERATION_CLUSTER_ACTION => AclOperation::ClusterAction,
+        Op::RD_KAFKA_ACL_OPERATION_DESCRIBE_CONFIGS => AclOperation::DescribeConfigs,
+        Op::RD_KAFKA_ACL_OPERATION_ALTER_CONFIGS => AclOperation::AlterConfigs,
+        Op::RD_KAFKA_ACL_OPERATION_IDEMPOTENT_WRITE => AclOperation::IdempotentWrite,
+        other => return Err(anyhow!("Unsupported ACL operation {:?}", other)),
+    };
+    let permission = match rdsys::rd_kafka_AclBinding_permission_type(acl) {
+        rdsys::rd_kafka_AclPermissionType_t::RD_KAFKA_ACL_PERMISSION_TYPE_ALLOW => {
+            AclPermission::Allow
+        }
+        rdsys::rd_kafka_AclPermissionType_t::RD_KAFKA_ACL_PERMISSION_TYPE_DENY => {
+            AclPermission::Deny
+        }
+        other => return Err(anyhow!("Unsupported ACL permission {:?}", other)),
+    };
+
+    Ok(AclBinding {
+        resource_type,
+        resource_name: from_cstr(rdsys::rd_kafka_AclBinding_name(acl)),
+        pattern_type,
+        principal: from_cstr(rdsys::rd_kafka_AclBinding_principal(acl)),
+        host: from_cstr(rdsys::rd_kafka_AclBinding_host(acl)),
+        operation,
+        permission,
+    })
+}
+
+/// Signature shared by `rd_kafka_AclBinding_new` and
+/// `rd_kafka_AclBindingFilter_new`.
+type NativeAclConstructor = unsafe extern "C" fn(
+    rdsys::rd_kafka_ResourceType_t,
+    *const c_char,
+    rdsys::rd_kafka_ResourcePatternType_t,
+    *const c_char,
+    *const c_char,
+    rdsys::rd_kafka_AclOperation_t,
+    rdsys::rd_kafka_AclPermissionType_t,
+    *mut c_char,
+    usize,
+) -> *mut rdsys::rd_kafka_AclBinding_t;
+
+fn acl_binding_to_native(
+    acl: &AclBinding,
+    new: NativeAclConstructor,
+) -> Result<*mut rdsys::rd_kafka_AclBinding_t> {
+    use rdsys::rd_kafka_AclOperation_t as Op;
+
+    let resource_type = match acl.resource_type {
+        AclResourceType::Topic => rdsys::rd_kafka_ResourceType_t::RD_KAFKA_RESOURCE_TOPIC,
+        AclResourceType::Group => rdsys::rd_kafka_ResourceType_t::RD_KAFKA_RESOURCE_GROUP,
+        // librdkafka calls the cluster resource a broker.
+        AclResourceType::Cluster => rdsys::rd_kafka_ResourceType_t::RD_KAFKA_RESOURCE_BROKER,
+        AclResourceType::TransactionalId => {
+            rdsys::rd_kafka_ResourceType_t::RD_KAFKA_RESOURCE_TRANSACTIONAL_ID
+        }
+    };
+    let pattern_type = match acl.pattern_type {
+        AclPatternType::Literal => {
+            rdsys::rd_kafka_ResourcePatternType_t::RD_KAFKA_RESOURCE_PATTERN_LITERAL
+        }
+        AclPatternType::Prefixed => {
+            rdsys::rd_kafka_ResourcePatternType_t::RD_KAFKA_RESOURCE_PATTERN_PREFIXED
+        }
+    };
+    let operation = match acl.operation {
+        AclOperation::All => Op::RD_KAFKA_ACL_OPERATION_ALL,
+        AclOperation::Read => Op::RD_KAFKA_ACL_OPERATION_READ,
+        AclOperation::Write => Op::RD_KAFKA_ACL_OPERATION_WRITE,
+        AclOperation::Create => Op::RD_KAFKA_ACL_OPERATION_CREATE,
+        AclOperation::Delete => Op::RD_KAFKA_ACL_OPERATION_DELETE,
+        AclOperation::Alter => Op::RD_KAFKA_ACL_OPERATION_ALTER,
+        AclOperation::Describe => Op::RD_KAFKA_ACL_OPERATION_DESCRIBE,
+        AclOperation::ClusterAction => Op::RD_KAFKA_ACL_OPERATION_CLUSTER_ACTION,
+        AclOperation::DescribeConfigs => Op::RD_KAFKA_ACL_OPERATION_DESCRIBE_CONFIGS,
+        AclOperation::AlterConfigs => Op::RD_KAFKA_ACL_OPERATION_ALTER_CONFIGS,
+        AclOperation::IdempotentWrite => Op::RD_KAFKA_ACL_OPERATION_IDEMPOTENT_WRITE,
+    };
+    let permission = match acl.permission {
+        AclPermission::Allow => {
+            rdsys::rd_kafka_AclPermissionType_t::RD_KAFKA_ACL_PERMISSION_TYPE_ALLOW
+        }
+        AclPermission::Deny => {
+            rdsys::rd_kafka_AclPermissionType_t::RD_KAFKA_ACL_PERMISSION_TYPE_DENY
+        }
+    };
+
+    let name = CString::new(acl.resource_name.as_str())?;
+    let principal = CString::new(acl.principal.as_str())?;
+    let host = CString::new(acl.host.as_str())?;
+    let mut errstr = [0 as c_char; 512];
+    let native = unsafe {
+        new(
+            resource_type,
+            name.as_ptr(),
+            pattern_type,
+            principal.as_ptr(),
+            host.as_ptr(),
+            operation,
+            permission,
+            errstr.as_mut_ptr(),
+            errstr.len(),
+        )
+    };
+    if native.is_null() {
+        return Err(anyhow!("Invalid ACL {:?}: {}", acl, from_errstr(&errstr)));
+    }
+    Ok(native)
+}
+
+fn from_cstr(s: *const c_char) -> String {
+    if s.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned()
+}
+
+fn from_errstr(buf: &[c_char]) -> String {
+    from_cstr(buf.as_ptr())
+}
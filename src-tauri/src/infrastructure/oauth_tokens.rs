@@ -0,0 +1,349 @@
+//! OAUTHBEARER tokens fetched from a cluster's token endpoint with the client
+//! credentials grant. Tokens are cached per cluster and handed to every client
+//! created for it, so opening a client costs no request to the identity
+//! provider. Clients outliving their token get a new one through librdkafka's
+//! refresh callback, which `ClusterContext` answers from the same cache.
+//!
+//! librdkafka leaves the format of `sasl.oauthbearer.config` to the
+//! application's token handler. Ours carries the cached token there as
+//! `token=<jwt> principal=<name> expires_at=<ms>`, so client creation only
+//! needs the config.
+
+use crate::domain::cluster::cluster::TokenStatus;
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use rdkafka::admin::AdminClient;
+use rdkafka::bindings as rdsys;
+use rdkafka::client::ClientContext;
+use rdkafka::config::{ClientConfig, FromClientConfigAndContext};
+use rdkafka::consumer::{BaseConsumer, Consumer, ConsumerContext, StreamConsumer};
+use rdkafka::producer::{BaseProducer, FutureProducer, Producer, ProducerContext};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::ptr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use uuid::Uuid;
+
+use super::client_logs::{client_context, ClusterContext};
+use super::request_coalescer::RequestCoalescer;
+use super::session_manager::now_millis;
+
+/// A cached token is replaced once less than this is left before it expires.
+const REFRESH_MARGIN_MS: i64 = 60_000;
+
+/// Lifetime assumed when the token endpoint does not report one.
+const DEFAULT_LIFETIME_MS: i64 = 5 * 60_000;
+
+const CONFIG_KEY: &str = "sasl.oauthbearer.config";
+
+#[derive(Debug, Clone)]
+pub struct OAuthToken {
+    pub token: String,
+    pub principal: String,
+    pub fetched_at: i64,
+    pub expires_at: i64,
+}
+
+impl OAuthToken {
+    fn to_config(&self) -> String {
+        format!(
+            "token={} principal={} expires_at={}",
+            self.token, self.principal, self.expires_at
+        )
+    }
+
+    fn from_config(config: &str) -> Option<Self> {
+        let fields: HashMap<&str, &str> = config
+            .split_whitespace()
+            .filter_map(|field| field.split_once('='))
+            .collect();
+        Some(Self {
+            token: fields.get("token")?.to_string(),
+            principal: fields.get("principal")?.to_string(),
+            fetched_at: 0,
+            expires_at: fields.get("expires_at")?.parse().ok()?,
+        })
+    }
+
+    fn is_fresh(&self) -> bool {
+        self.expires_at - now_millis() > REFRESH_MARGIN_MS
+    }
+}
+
+/// Client credentials for a cluster's token endpoint.
+#[derive(Debug, Clone)]
+pub struct TokenRequest {
+    pub endpoint: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<i64>,
+}
+
+#[derive(Default)]
+struct CacheEntry {
+    token: Option<OAuthToken>,
+    last_error: Option<String>,
+    /// Last request made for the cluster, repeated when a client asks for a
+    /// new token.
+    request: Option<TokenRequest>,
+}
+
+pub struct OAuthTokenCache {
+    http: reqwest::Client,
+    entries: Mutex<HashMap<Uuid, CacheEntry>>,
+    // Concurrent refreshes of one cluster share a single token request.
+    requests: RequestCoalescer<Uuid, OAuthToken>,
+    // Runtime that refreshes run on when started from librdkafka's threads.
+    runtime: OnceLock<tokio::runtime::Handle>,
+}
+
+impl OAuthTokenCache {
+    /// The process-wide cache. Client contexts are built from a config
+    /// alone, so they reach the cache through here.
+    pub fn shared() -> Arc<Self> {
+        static CACHE: OnceLock<Arc<OAuthTokenCache>> = OnceLock::new();
+        CACHE
+            .get_or_init(|| {
+                Arc::new(Self {
+                    http: reqwest::Client::new(),
+                    entries: Mutex::new(HashMap::new()),
+                    requests: RequestCoalescer::new(Duration::ZERO),
+                    runtime: OnceLock::new(),
+                })
+            })
+            .clone()
+    }
+
+    /// Fetches a token unless the cached one is still fresh.
+    pub async fn refresh(&self, cluster_id: Uuid, request: TokenRequest) -> Result<()> {
+        self.runtime.get_or_init(tokio::runtime::Handle::current);
+        self.entries
+            .lock()
+            .unwrap()
+            .entry(cluster_id)
+            .or_default()
+            .request = Some(request.clone());
+        if self.cached(cluster_id).is_some_and(|t| t.is_fresh()) {
+            return Ok(());
+        }
+
+        let http = self.http.clone();
+        let result = self
+            .requests
+            .run(cluster_id, move || fetch_token(http, request))
+            .await;
+
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.entry(cluster_id).or_default();
+        match result {
+            Ok(token) => {
+                entry.token = Some(token);
+                entry.last_error = None;
+                Ok(())
+            }
+            Err(e) => {
+                entry.last_error = Some(e.to_string());
+                Err(anyhow!("Failed to fetch OAuth token: {}", e))
+            }
+        }
+    }
+
+    /// Token for a client whose current one is due for renewal, asked for by
+    /// librdkafka's refresh callback. A fresh cached token is returned as is.
+    /// Otherwise a refresh starts in the background and this fails, so the
+    /// client keeps its current token and asks again shortly.
+    pub fn renew(self: &Arc<Self>, cluster_id: Uuid) -> Result<OAuthToken> {
+        if let Some(token) = self.cached(cluster_id).filter(OAuthToken::is_fresh) {
+            return Ok(token);
+        }
+        let request = self
+            .entries
+            .lock()
+            .unwrap()
+            .get(&cluster_id)
+            .and_then(|e| e.request.clone())
+            .ok_or_else(|| anyhow!("No token endpoint known for cluster {}", cluster_id))?;
+        let runtime = self
+            .runtime
+            .get()
+            .ok_or_else(|| anyhow!("No runtime to refresh OAuth tokens on"))?;
+
+        let cache = self.clone();
+        runtime.spawn(async move {
+            if let Err(e) = cache.refresh(cluster_id, request).await {
+                eprintln!(
+                    "Failed to refresh OAuth token of cluster {}: {}",
+                    cluster_id, e
+                );
+            }
+        });
+        Err(anyhow!(
+            "OAuth token of cluster {} is being refreshed",
+            cluster_id
+        ))
+    }
+
+    /// Sets the cluster's cached token, if any, on a client config.
+    pub fn apply(&self, cluster_id: Uuid, config: &mut ClientConfig) {
+        if let Some(token) = self.cached(cluster_id) {
+            config.set(CONFIG_KEY, token.to_config());
+        }
+    }
+
+    pub fn status(&self, cluster_id: Uuid) -> TokenStatus {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(&cluster_id);
+        let token = entry.and_then(|e| e.token.as_ref());
+        TokenStatus {
+            cluster_id,
+            principal: token.map(|t| t.principal.clone()),
+            fetched_at: token.map(|t| t.fetched_at),
+            expires_at: token.map(|t| t.expires_at),
+            valid: token.is_some_and(|t| t.expires_at > now_millis()),
+            last_error: entry.and_then(|e| e.last_error.clone()),
+        }
+    }
+
+    pub fn forget(&self, cluster_id: Uuid) {
+        self.entries.lock().unwrap().remove(&cluster_id);
+    }
+
+    fn cached(&self, cluster_id: Uuid) -> Option<OAuthToken> {
+        self.entries
+            .lock()
+            .unwrap()
+            .get(&cluster_id)
+            .and_then(|e| e.token.clone())
+    }
+}
+
+async fn fetch_token(http: reqwest::Client, request: TokenRequest) -> Result<OAuthToken> {
+    let mut form = vec![("grant_type", "client_credentials".to_string())];
+    if let Some(scope) = request.scope {
+        form.push(("scope", scope));
+    }
+
+    let response: TokenResponse = http
+        .post(&request.endpoint)
+        .basic_auth(&request.client_id, Some(&request.client_secret))
+        .form(&form)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    let fetched_at = now_millis();
+    let lifetime_ms = response
+        .expires_in
+        .map(|secs| secs * 1000)
+        .unwrap_or(DEFAULT_LIFETIME_MS);
+    Ok(OAuthToken {
+        principal: jwt_subject(&response.access_token).unwrap_or(request.client_id),
+        token: response.access_token,
+        fetched_at,
+        expires_at: fetched_at + lifetime_ms,
+    })
+}
+
+/// The `sub` claim of a JWT, read without verifying the signature.
+fn jwt_subject(token: &str) -> Option<String> {
+    let claims = token.split('.').nth(1)?;
+    let claims = base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .decode(claims.trim_end_matches('='))
+        .ok()?;
+    let claims: serde_json::Value = serde_json::from_slice(&claims).ok()?;
+    claims.get("sub")?.as_str().map(str::to_string)
+}
+
+/// A librdkafka client whose handle can be given a token.
+pub trait NativeClient {
+    fn native_ptr(&self) -> *mut rdsys::rd_kafka_t;
+}
+
+impl<C: ClientContext> NativeClient for AdminClient<C> {
+    fn native_ptr(&self) -> *mut rdsys::rd_kafka_t {
+        self.inner().native_ptr()
+    }
+}
+
+impl<C: ConsumerContext> NativeClient for BaseConsumer<C> {
+    fn native_ptr(&self) -> *mut rdsys::rd_kafka_t {
+        self.client().native_ptr()
+    }
+}
+
+impl<C: ConsumerContext + 'static> NativeClient for StreamConsumer<C> {
+    fn native_ptr(&self) -> *mut rdsys::rd_kafka_t {
+        self.client().native_ptr()
+    }
+}
+
+impl<C: ProducerContext> NativeClient for BaseProducer<C> {
+    fn native_ptr(&self) -> *mut rdsys::rd_kafka_t {
+        self.client().native_ptr()
+    }
+}
+
+impl<C: ClientContext + 'static> NativeClient for FutureProducer<C> {
+    fn native_ptr(&self) -> *mut rdsys::rd_kafka_t {
+        self.client().native_ptr()
+    }
+}
+
+/// Creates a client logging into its cluster's buffer and, for OAUTHBEARER
+/// clusters, hands it the token carried by the config before it connects.
+pub fn create_client<T>(config: &ClientConfig) -> Result<T>
+where
+    T: FromClientConfigAndContext<ClusterContext> + NativeClient,
+{
+    let (config, context) = client_context(config);
+    let client: T = config.create_with_context(context)?;
+    set_token(client.native_ptr(), &config)?;
+    Ok(client)
+}
+
+/// The token carried by a client config, if any.
+pub fn config_token(config: &ClientConfig) -> Option<OAuthToken> {
+    config.get(CONFIG_KEY).and_then(OAuthToken::from_config)
+}
+
+/// Hands a client handle the token carried by its config, if any.
+pub fn set_token(rk: *mut rdsys::rd_kafka_t, config: &ClientConfig) -> Result<()> {
+    let Some(token) = config_token(config) else {
+        return Ok(());
+    };
+
+    let value = CString::new(token.token)?;
+    let principal = CString::new(token.principal)?;
+    let mut errstr = [0 as c_char; 512];
+    let code = unsafe {
+        rdsys::rd_kafka_oauthbearer_set_token(
+            rk,
+            value.as_ptr(),
+            token.expires_at,
+            principal.as_ptr(),
+            ptr::null_mut(),
+            0,
+            errstr.as_mut_ptr(),
+            errstr.len(),
+        )
+    };
+    if code != rdsys::rd_kafka_resp_err_t::RD_KAFKA_RESP_ERR_NO_ERROR {
+        let message = unsafe { std::ffi::CStr::from_ptr(errstr.as_ptr()) };
+        return Err(anyhow!(
+            "Failed to set OAuth token: {}",
+            message.to_string_lossy()
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,73 @@
+/// Partition that the Java client's default partitioner (and librdkafka's
+/// `murmur2_random`) assigns to a keyed record.
+pub fn default_partition(key: &[u8], partition_count: i32) -> i32 {
+    (murmur2(key) & 0x7fff_ffff) % partition_count
+}
+
+fn murmur2(data: &[u8]) -> i32 {
+    const SEED: u32 = 0x9747_b28c;
+    const M: u32 = 0x5bd1_e995;
+    const R: u32 = 24;
+
+    let mut h: u32 = SEED ^ data.len() as u32;
+
+    let mut chunks = data.chunks_exact(4);
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(M);
+        k ^= k >> R;
+        k = k.wrapping_mul(M);
+        h = h.wrapping_mul(M);
+        h ^= k;
+    }
+
+    let rest = chunks.remainder();
+    if rest.len() >= 3 {
+        h ^= (rest[2] as u32) << 16;
+    }
+    if rest.len() >= 2 {
+        h ^= (rest[1] as u32) << 8;
+    }
+    if !rest.is_empty() {
+        h ^= rest[0] as u32;
+        h = h.wrapping_mul(M);
+    }
+
+    h ^= h >> 13;
+    h = h.wrapping_mul(M);
+    h ^= h >> 15;
+    h as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Vectors from the Java client's `Utils.murmur2` tests.
+    #[test]
+    fn murmur2_matches_the_java_client() {
+        let cases: &[(&[u8], i32)] = &[
+            (b"21", -973_932_308),
+            (b"foobar", -790_332_482),
+            (b"a-little-bit-long-string", -985_981_536),
+            (b"a-little-bit-longer-string", -1_486_304_829),
+            (
+                b"lkjh234lh9fiuh90y23oiuhsafujhadof229phr9h19h89h8",
+                -58_897_971,
+            ),
+            (b"abc", 479_470_107),
+        ];
+        for (key, expected) in cases {
+            assert_eq!(murmur2(key), *expected, "{}", String::from_utf8_lossy(key));
+        }
+    }
+
+    #[test]
+    fn default_partition_masks_the_sign_bit() {
+        assert_eq!(
+            default_partition(b"21", 10),
+            (-973_932_308 & 0x7fff_ffff) % 10
+        );
+        assert_eq!(default_partition(b"abc", 7), 479_470_107 % 7);
+    }
+}
@@ -0,0 +1,55 @@
+//! AES-256-GCM for payloads that applications encrypt before producing. A
+//! ciphertext is the 12-byte nonce followed by the sealed payload and tag.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+const NONCE_LEN: usize = 12;
+
+pub struct PayloadCipher {
+    cipher: Aes256Gcm,
+}
+
+impl PayloadCipher {
+    /// Builds a cipher from a base64-encoded 256-bit key.
+    pub fn from_base64_key(key: &str) -> Result<Self> {
+        let bytes = STANDARD
+            .decode(key.trim())
+            .map_err(|e| anyhow!("Encryption key is not valid base64: {}", e))?;
+        if bytes.len() != 32 {
+            return Err(anyhow!(
+                "Encryption key must be 32 bytes, got {}",
+                bytes.len()
+            ));
+        }
+        Ok(Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&bytes)),
+        })
+    }
+
+    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let sealed = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow!("Failed to encrypt payload"))?;
+
+        let mut out = Vec::with_capacity(NONCE_LEN + sealed.len());
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&sealed);
+        Ok(out)
+    }
+
+    pub fn decrypt(&self, ciphertext: &[u8]) -> Result<Vec<u8>> {
+        if ciphertext.len() < NONCE_LEN {
+            return Err(anyhow!("Encrypted payload is too short"));
+        }
+        let (nonce, sealed) = ciphertext.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .map_err(|_| anyhow!("Failed to decrypt payload: wrong key or corrupted data"))
+    }
+}
@@ -0,0 +1,220 @@
+//! Decode pipeline turning stored payload bytes into what the message browser
+//! shows: application-level decryption, unwrapping of base64 or URL encoding,
+//! then decompression of payloads that producers compressed themselves
+//! (independent of Kafka's transport compression), and finally a deserializer
+//! plugin when the topic uses one. A step that does not apply leaves the
+//! bytes untouched.
+
+use super::payload_cipher::PayloadCipher;
+use crate::domain::plugin::Deserializer;
+use crate::domain::topic::PayloadEncoding;
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE};
+use base64::Engine;
+use std::borrow::Cow;
+use std::io::Read;
+use std::sync::Arc;
+
+/// Upper bound on a decompressed payload, guarding against compression bombs.
+const MAX_DECOMPRESSED_BYTES: u64 = 16 * 1024 * 1024;
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b, 0x08];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+/// Stream identifier chunk of the snappy framing format.
+const SNAPPY_FRAMED_MAGIC: &[u8] = b"\xff\x06\x00\x00sNaPpY";
+/// Header written by snappy-java's `SnappyOutputStream`.
+const SNAPPY_XERIAL_MAGIC: &[u8] = b"\x82SNAPPY\x00";
+
+/// How many nested text encodings auto-detection unwraps.
+const MAX_AUTO_LAYERS: usize = 3;
+
+pub struct PayloadDecoder {
+    cipher: Option<PayloadCipher>,
+    encoding: PayloadEncoding,
+    /// Plugin and the topic it decodes.
+    plugin: Option<(Arc<dyn Deserializer>, String)>,
+}
+
+impl PayloadDecoder {
+    pub fn new(cipher: Option<PayloadCipher>, encoding: PayloadEncoding) -> Self {
+        Self {
+            cipher,
+            encoding,
+            plugin: None,
+        }
+    }
+
+    /// Hands the decoded bytes of `topic` to `plugin` as the last step.
+    pub fn with_plugin(mut self, plugin: Arc<dyn Deserializer>, topic: &str) -> Self {
+        self.plugin = Some((plugin, topic.to_string()));
+        self
+    }
+
+    pub fn decode<'a>(&self, payload: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        let mut bytes = Cow::Borrowed(payload);
+        if let Some(cipher) = &self.cipher {
+            bytes = Cow::Owned(cipher.decrypt(&bytes)?);
+        }
+        let unwrapped = match self.encoding {
+            PayloadEncoding::Auto => auto_unwrap(&bytes, MAX_AUTO_LAYERS),
+            PayloadEncoding::Plain => None,
+            PayloadEncoding::Base64 => {
+                Some(decode_base64(&bytes).ok_or_else(|| anyhow!("Payload is not valid base64"))?)
+            }
+            PayloadEncoding::UrlEncoded => Some(
+                decode_url(&bytes).ok_or_else(|| anyhow!("Payload is not valid URL encoding"))?,
+            ),
+        };
+        if let Some(unwrapped) = unwrapped {
+            bytes = Cow::Owned(unwrapped);
+        }
+        if let Some(decompressed) = decompress(&bytes)? {
+            bytes = Cow::Owned(decompressed);
+        }
+        if let Some((plugin, topic)) = &self.plugin {
+            let text = plugin.deserialize(topic, &bytes).map_err(|e| anyhow!(e))?;
+            bytes = Cow::Owned(text.into_bytes());
+        }
+        Ok(bytes)
+    }
+}
+
+/// Decompresses gzip, zstd and framed or snappy-java payloads recognized by
+/// their magic bytes. Raw snappy has no header and is not detected.
+fn decompress(bytes: &[u8]) -> Result<Option<Vec<u8>>> {
+    let out = if bytes.starts_with(GZIP_MAGIC) {
+        read_limited(flate2::read::MultiGzDecoder::new(bytes))
+            .map_err(|e| anyhow!("Failed to gunzip payload: {}", e))?
+    } else if bytes.starts_with(ZSTD_MAGIC) {
+        read_limited(zstd::stream::read::Decoder::new(bytes)?)
+            .map_err(|e| anyhow!("Failed to decompress zstd payload: {}", e))?
+    } else if bytes.starts_with(SNAPPY_FRAMED_MAGIC) {
+        read_limited(snap::read::FrameDecoder::new(bytes))
+            .map_err(|e| anyhow!("Failed to decompress snappy payload: {}", e))?
+    } else if bytes.starts_with(SNAPPY_XERIAL_MAGIC) {
+        decompress_xerial_snappy(bytes)?
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(out))
+}
+
+fn read_limited<R: Read>(reader: R) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    reader
+        .take(MAX_DECOMPRESSED_BYTES + 1)
+        .read_to_end(&mut out)?;
+    if out.len() as u64 > MAX_DECOMPRESSED_BYTES {
+        return Err(std::io::Error::other("decompressed payload is too large"));
+    }
+    Ok(out)
+}
+
+/// snappy-java blocks: the magic, two int32 version fields, then
+/// int32-length-prefixed raw snappy blocks.
+fn decompress_xerial_snappy(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut rest = bytes
+        .get(SNAPPY_XERIAL_MAGIC.len() + 8..)
+        .ok_or_else(|| anyhow!("Truncated snappy-java header"))?;
+    let mut out = Vec::new();
+    let mut decoder = snap::raw::Decoder::new();
+
+    while !rest.is_empty() {
+        let (len, tail) = rest
+            .split_first_chunk::<4>()
+            .ok_or_else(|| anyhow!("Truncated snappy-java block"))?;
+        let len = u32::from_be_bytes(*len) as usize;
+        let block = tail
+            .get(..len)
+            .ok_or_else(|| anyhow!("Truncated snappy-java block"))?;
+
+        let block_len = snap::raw::decompress_len(block)?;
+        if (out.len() + block_len) as u64 > MAX_DECOMPRESSED_BYTES {
+            return Err(anyhow!("Decompressed payload is too large"));
+        }
+        out.extend_from_slice(&decoder.decompress_vec(block)?);
+        rest = &tail[len..];
+    }
+    Ok(out)
+}
+
+/// Peels base64 and URL encoding layers while that leads to JSON. Payloads
+/// that are already JSON, or that only look encoded, are left alone.
+fn auto_unwrap(bytes: &[u8], layers: usize) -> Option<Vec<u8>> {
+    if layers == 0 || is_json(bytes) {
+        return None;
+    }
+    [decode_base64(bytes), decode_url(bytes)]
+        .into_iter()
+        .flatten()
+        .find_map(|decoded| {
+            if is_json(&decoded) {
+                Some(decoded)
+            } else {
+                auto_unwrap(&decoded, layers - 1)
+            }
+        })
+}
+
+fn is_json(bytes: &[u8]) -> bool {
+    let trimmed = trim_whitespace(bytes);
+    matches!(trimmed.first(), Some(b'{') | Some(b'['))
+        && serde_json::from_slice::<serde_json::Value>(trimmed).is_ok()
+}
+
+/// Standard or URL-safe base64, with or without padding.
+fn decode_base64(bytes: &[u8]) -> Option<Vec<u8>> {
+    let trimmed = trim_whitespace(bytes);
+    if trimmed.len() < 4 {
+        return None;
+    }
+    let unpadded: Vec<u8> = trimmed.iter().copied().take_while(|b| *b != b'=').collect();
+    let padded = |mut raw: Vec<u8>| {
+        raw.resize(raw.len().div_ceil(4) * 4, b'=');
+        raw
+    };
+    STANDARD
+        .decode(padded(unpadded.clone()))
+        .or_else(|_| URL_SAFE.decode(padded(unpadded)))
+        .ok()
+}
+
+/// Percent-decoding with `+` as a space, as HTML forms and most HTTP bridges
+/// encode. Requires at least one escape so plain text is not mistaken for it.
+fn decode_url(bytes: &[u8]) -> Option<Vec<u8>> {
+    if !bytes.contains(&b'%') {
+        return None;
+    }
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    Some(out)
+}
+
+fn trim_whitespace(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|b| !b.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let end = bytes
+        .iter()
+        .rposition(|b| !b.is_ascii_whitespace())
+        .map_or(start, |i| i + 1);
+    &bytes[start..end]
+}
@@ -29,6 +29,18 @@ impl KeyringSecretRepository {
             .map_err(|e| anyhow!("Failed to retrieve password from keyring: {}", e))
     }
 
+    /// Like `get_password`, but a missing entry is `None` rather than an
+    /// error, so callers can tell it apart from an unavailable keyring.
+    pub fn find_password(&self, cluster_id: &str) -> Result<Option<String>> {
+        let entry = Entry::new(&self.service_name, cluster_id)
+            .map_err(|e| anyhow!("Failed to create keyring entry: {}", e))?;
+        match entry.get_password() {
+            Ok(password) => Ok(Some(password)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(anyhow!("Failed to retrieve password from keyring: {}", e)),
+        }
+    }
+
     pub fn delete_password(&self, cluster_id: &str) -> Result<()> {
         let entry = Entry::new(&self.service_name, cluster_id)
             .map_err(|e| anyhow!("Failed to create keyring entry: {}", e))?;
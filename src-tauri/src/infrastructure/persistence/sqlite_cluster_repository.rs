@@ -1,8 +1,47 @@
-use crate::domain::cluster::cluster::{Cluster, SaslMechanism, SecurityConfig};
+use crate::domain::alert::LagAlertRule;
+use crate::domain::cluster::cluster::{
+    AuxiliaryService, AuxiliaryServiceKind, Cluster, ClusterUsage, SaslMechanism, SecurityConfig,
+};
+use crate::domain::command_macro::CommandMacro;
+use crate::domain::consumer_group::{GroupEvent, LagSample, OffsetSnapshot};
+use crate::domain::health::{IsrChange, IsrEvent, TopicChange, TopicEvent};
+use crate::domain::receipt::ProductionReceipt;
+use crate::domain::topic::{PayloadEncoding, TopicAnnotation, TopicEncryption, TopicPreferences};
+use crate::infrastructure::session_manager::now_millis;
 use anyhow::Result;
 use sqlx::{sqlite::SqlitePoolOptions, Pool, Sqlite};
+use std::collections::HashMap;
 use uuid::Uuid;
 
+/// A cluster update based on a version that is no longer the stored one.
+#[derive(Debug, thiserror::Error)]
+#[error("cluster was modified elsewhere (expected version {expected}, found {current})")]
+pub struct VersionConflict {
+    pub expected: i64,
+    pub current: i64,
+}
+
+/// Tables holding per-cluster data that goes with its cluster, including
+/// history such as lag samples and ISR events. Production receipts are left
+/// out: they are append-only, sign their cluster id and outlive the cluster
+/// as an audit trail.
+const CLUSTER_DATA_TABLES: &[&str] = &[
+    "cluster_services",
+    "topic_encryption",
+    "topic_payload_encoding",
+    "lag_alert_rules",
+    "topic_preferences",
+    "topic_annotations",
+    "protected_topics",
+    "metadata_snapshots",
+    "offset_snapshots",
+    "topic_events",
+    "group_events",
+    "lag_samples",
+    "isr_events",
+];
+
+#[derive(Clone)]
 pub struct SqliteClusterRepository {
     pool: Pool<Sqlite>,
 }
@@ -36,6 +75,268 @@ impl SqliteClusterRepository {
         .execute(&pool)
         .await?;
 
+        add_column_if_missing(&pool, "clusters", "version", "INTEGER NOT NULL DEFAULT 1").await?;
+        add_column_if_missing(&pool, "clusters", "updated_at", "INTEGER").await?;
+        add_column_if_missing(&pool, "clusters", "deleted_at", "INTEGER").await?;
+        add_column_if_missing(&pool, "clusters", "tags", "TEXT NOT NULL DEFAULT '[]'").await?;
+        add_column_if_missing(&pool, "clusters", "group_name", "TEXT").await?;
+        add_column_if_missing(&pool, "clusters", "sort_order", "INTEGER").await?;
+        add_column_if_missing(&pool, "clusters", "client_id", "TEXT").await?;
+        add_column_if_missing(&pool, "clusters", "certificate_pin", "TEXT").await?;
+        add_column_if_missing(&pool, "clusters", "oauth_token_endpoint", "TEXT").await?;
+        add_column_if_missing(&pool, "clusters", "oauth_scope", "TEXT").await?;
+        add_column_if_missing(
+            &pool,
+            "clusters",
+            "production_receipts",
+            "INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+        add_column_if_missing(&pool, "clusters", "last_connected_at", "INTEGER").await?;
+        add_column_if_missing(&pool, "clusters", "metadata_cache_ttl_ms", "INTEGER").await?;
+        add_column_if_missing(
+            &pool,
+            "clusters",
+            "client_debug",
+            "TEXT NOT NULL DEFAULT '[]'",
+        )
+        .await?;
+        add_column_if_missing(&pool, "clusters", "last_used_at", "INTEGER").await?;
+        add_column_if_missing(
+            &pool,
+            "clusters",
+            "operation_count",
+            "INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS cluster_services (
+                id TEXT PRIMARY KEY,
+                cluster_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                url TEXT NOT NULL,
+                username TEXT
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS topic_encryption (
+                cluster_id TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                encrypt_on_publish INTEGER NOT NULL,
+                PRIMARY KEY (cluster_id, topic)
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS topic_payload_encoding (
+                cluster_id TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                encoding TEXT NOT NULL,
+                PRIMARY KEY (cluster_id, topic)
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS lag_samples (
+                cluster_id TEXT NOT NULL,
+                group_id TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                lag INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS lag_samples_by_group ON lag_samples (cluster_id, group_id, timestamp)",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS offset_snapshots (
+                id TEXT PRIMARY KEY,
+                cluster_id TEXT NOT NULL,
+                group_id TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                offsets TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS offset_snapshots_by_group ON offset_snapshots (cluster_id, group_id, created_at)",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS topic_preferences (
+                cluster_id TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                preferences TEXT NOT NULL,
+                PRIMARY KEY (cluster_id, topic)
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS topic_annotations (
+                cluster_id TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                favorite INTEGER NOT NULL DEFAULT 0,
+                notes TEXT NOT NULL DEFAULT '',
+                tags TEXT NOT NULL DEFAULT '[]',
+                PRIMARY KEY (cluster_id, topic)
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS protected_topics (
+                cluster_id TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                protected_at INTEGER NOT NULL,
+                PRIMARY KEY (cluster_id, topic)
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS metadata_snapshots (
+                cluster_id TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                name TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                fetched_at INTEGER NOT NULL,
+                PRIMARY KEY (cluster_id, kind, name)
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS command_macros (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                steps TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS lag_alert_rules (
+                id TEXT PRIMARY KEY,
+                cluster_id TEXT NOT NULL,
+                group_id TEXT NOT NULL,
+                topic TEXT,
+                threshold INTEGER NOT NULL,
+                interval_secs INTEGER NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS isr_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                cluster_id TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                partition INTEGER NOT NULL,
+                change TEXT NOT NULL,
+                brokers TEXT NOT NULL,
+                isr TEXT NOT NULL,
+                timestamp INTEGER NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS isr_events_by_topic ON isr_events (cluster_id, topic, timestamp)",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS topic_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                cluster_id TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                change TEXT NOT NULL,
+                partitions INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS topic_events_by_cluster ON topic_events (cluster_id, timestamp)",
+        )
+        .execute(&pool)
+        .await?;
+
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS group_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                cluster_id TEXT NOT NULL,
+                group_id TEXT NOT NULL,
+                from_state TEXT,
+                to_state TEXT,
+                members_before INTEGER NOT NULL,
+                members_after INTEGER NOT NULL,
+                timestamp INTEGER NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS group_events_by_group ON group_events (cluster_id, group_id, timestamp)",
+        )
+        .execute(&pool)
+        .await?;
+
+        // Receipts are kept when their cluster is purged and can never be
+        // changed or removed.
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS production_receipts (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                cluster_id TEXT NOT NULL,
+                topic TEXT NOT NULL,
+                partition INTEGER NOT NULL,
+                offset INTEGER NOT NULL,
+                payload_sha256 TEXT NOT NULL,
+                user TEXT NOT NULL,
+                timestamp INTEGER NOT NULL,
+                previous_signature TEXT,
+                signature TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await?;
+        for operation in ["UPDATE", "DELETE"] {
+            sqlx::query(&format!(
+                "CREATE TRIGGER IF NOT EXISTS production_receipts_no_{} BEFORE {} ON production_receipts
+                 BEGIN SELECT RAISE(ABORT, 'production receipts are append-only'); END",
+                operation.to_lowercase(),
+                operation
+            ))
+            .execute(&pool)
+            .await?;
+        }
+
         Ok(Self { pool })
     }
 
@@ -59,6 +360,7 @@ impl SqliteClusterRepository {
                 mechanism,
                 username,
                 ca_location,
+                ..
             } => {
                 let m = match mechanism {
                     SaslMechanism::Plain => "PLAIN",
@@ -77,10 +379,37 @@ impl SqliteClusterRepository {
                 )
             }
         };
+        let (token_endpoint, oauth_scope) = match &cluster.security {
+            SecurityConfig::SaslSsl {
+                token_endpoint,
+                oauth_scope,
+                ..
+            } => (token_endpoint.as_deref(), oauth_scope.as_deref()),
+            _ => (None, None),
+        };
 
-        sqlx::query(
-            "INSERT OR REPLACE INTO clusters (id, name, brokers, security_type, sasl_mechanism, sasl_username, ca_location, cert_location, key_location)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        // Upsert; an existing row is only overwritten when the caller's
+        // version (if any) still matches, and its version is bumped. An
+        // OAUTHBEARER cluster saved without token settings keeps its own.
+        let result = sqlx::query(
+            "INSERT INTO clusters (id, name, brokers, security_type, sasl_mechanism, sasl_username, ca_location, cert_location, key_location, client_id, certificate_pin, oauth_token_endpoint, oauth_scope, version, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 1, ?)
+             ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                brokers = excluded.brokers,
+                security_type = excluded.security_type,
+                sasl_mechanism = excluded.sasl_mechanism,
+                sasl_username = excluded.sasl_username,
+                ca_location = excluded.ca_location,
+                cert_location = excluded.cert_location,
+                key_location = excluded.key_location,
+                oauth_token_endpoint = CASE WHEN excluded.sasl_mechanism = 'OAUTHBEARER'
+                    THEN COALESCE(excluded.oauth_token_endpoint, clusters.oauth_token_endpoint) END,
+                oauth_scope = CASE WHEN excluded.sasl_mechanism = 'OAUTHBEARER'
+                    THEN COALESCE(excluded.oauth_scope, clusters.oauth_scope) END,
+                version = clusters.version + 1,
+                updated_at = excluded.updated_at
+             WHERE ? IS NULL OR clusters.version = ?"
         )
         .bind(cluster.id.to_string())
         .bind(&cluster.name)
@@ -91,16 +420,44 @@ impl SqliteClusterRepository {
         .bind(ca)
         .bind(cert)
         .bind(key)
+        .bind(&cluster.client_id)
+        .bind(&cluster.certificate_pin)
+        .bind(token_endpoint)
+        .bind(oauth_scope)
+        .bind(now_millis())
+        .bind(cluster.version)
+        .bind(cluster.version)
         .execute(&self.pool)
         .await?;
 
+        if result.rows_affected() == 0 {
+            let current: i64 = sqlx::query_scalar("SELECT version FROM clusters WHERE id = ?")
+                .bind(cluster.id.to_string())
+                .fetch_one(&self.pool)
+                .await?;
+            return Err(VersionConflict {
+                expected: cluster.version.unwrap_or_default(),
+                current,
+            }
+            .into());
+        }
+
         Ok(())
     }
 
     pub async fn list_clusters(&self) -> Result<Vec<Cluster>> {
-        let rows = sqlx::query("SELECT id, name, brokers, security_type, sasl_mechanism, sasl_username, ca_location, cert_location, key_location FROM clusters")
+        self.select_clusters("deleted_at IS NULL").await
+    }
+
+    pub async fn list_deleted_clusters(&self) -> Result<Vec<Cluster>> {
+        self.select_clusters("deleted_at IS NOT NULL").await
+    }
+
+    async fn select_clusters(&self, condition: &str) -> Result<Vec<Cluster>> {
+        let rows = sqlx::query(&format!("SELECT id, name, brokers, security_type, sasl_mechanism, sasl_username, ca_location, cert_location, key_location, version, updated_at, deleted_at, tags, group_name, sort_order, client_id, certificate_pin, oauth_token_endpoint, oauth_scope, production_receipts, last_connected_at, last_used_at, operation_count, metadata_cache_ttl_ms, client_debug FROM clusters WHERE {} ORDER BY group_name IS NULL, group_name, sort_order IS NULL, sort_order, name", condition))
             .fetch_all(&self.pool)
             .await?;
+        let mut services = self.list_services().await?;
 
         let clusters = rows
             .into_iter()
@@ -114,6 +471,24 @@ impl SqliteClusterRepository {
                 let ca_location: Option<String> = sqlx::Row::get(&row, 6);
                 let cert_location: Option<String> = sqlx::Row::get(&row, 7);
                 let key_location: Option<String> = sqlx::Row::get(&row, 8);
+                let version: i64 = sqlx::Row::get(&row, 9);
+                let updated_at: Option<i64> = sqlx::Row::get(&row, 10);
+                let deleted_at: Option<i64> = sqlx::Row::get(&row, 11);
+                let tags: String = sqlx::Row::get(&row, 12);
+                let group: Option<String> = sqlx::Row::get(&row, 13);
+                let sort_order: Option<i64> = sqlx::Row::get(&row, 14);
+                let client_id: Option<String> = sqlx::Row::get(&row, 15);
+                let certificate_pin: Option<String> = sqlx::Row::get(&row, 16);
+                let token_endpoint: Option<String> = sqlx::Row::get(&row, 17);
+                let oauth_scope: Option<String> = sqlx::Row::get(&row, 18);
+                let production_receipts: bool = sqlx::Row::get(&row, 19);
+                let usage = ClusterUsage {
+                    last_connected_at: sqlx::Row::get(&row, 20),
+                    last_used_at: sqlx::Row::get(&row, 21),
+                    operation_count: sqlx::Row::get(&row, 22),
+                };
+                let metadata_cache_ttl_ms: Option<i64> = sqlx::Row::get(&row, 23);
+                let client_debug: String = sqlx::Row::get(&row, 24);
 
                 let security = match st.as_str() {
                     "plaintext" => SecurityConfig::Plaintext,
@@ -135,6 +510,8 @@ impl SqliteClusterRepository {
                             mechanism,
                             username: username.unwrap_or_default(),
                             ca_location,
+                            token_endpoint,
+                            oauth_scope,
                         }
                     }
                     _ => SecurityConfig::Plaintext,
@@ -145,6 +522,19 @@ impl SqliteClusterRepository {
                     name,
                     brokers,
                     security,
+                    version: Some(version),
+                    updated_at,
+                    deleted_at,
+                    tags: serde_json::from_str(&tags).unwrap_or_default(),
+                    group,
+                    sort_order,
+                    services: services.remove(&id).unwrap_or_default(),
+                    client_id,
+                    certificate_pin,
+                    production_receipts,
+                    metadata_cache_ttl_ms: metadata_cache_ttl_ms.map(|ttl| ttl as u64),
+                    client_debug: serde_json::from_str(&client_debug).unwrap_or_default(),
+                    usage,
                 }
             })
             .collect();
@@ -152,11 +542,1174 @@ impl SqliteClusterRepository {
         Ok(clusters)
     }
 
+    pub async fn set_cluster_tags(&self, id: &Uuid, tags: &[String]) -> Result<()> {
+        let result = sqlx::query("UPDATE clusters SET tags = ? WHERE id = ?")
+            .bind(serde_json::to_string(tags)?)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Cluster not found"));
+        }
+        Ok(())
+    }
+
+    pub async fn set_production_receipts(&self, id: &Uuid, enabled: bool) -> Result<()> {
+        let result = sqlx::query("UPDATE clusters SET production_receipts = ? WHERE id = ?")
+            .bind(enabled)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Cluster not found"));
+        }
+        Ok(())
+    }
+
+    pub async fn set_certificate_pin(&self, id: &Uuid, pin: Option<&str>) -> Result<()> {
+        let result = sqlx::query("UPDATE clusters SET certificate_pin = ? WHERE id = ?")
+            .bind(pin)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Cluster not found"));
+        }
+        Ok(())
+    }
+
+    pub async fn set_client_debug(&self, id: &Uuid, contexts: &[String]) -> Result<()> {
+        let result = sqlx::query("UPDATE clusters SET client_debug = ? WHERE id = ?")
+            .bind(serde_json::to_string(contexts)?)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Cluster not found"));
+        }
+        Ok(())
+    }
+
+    pub async fn set_metadata_cache_ttl(&self, id: &Uuid, ttl_ms: Option<u64>) -> Result<()> {
+        let result = sqlx::query("UPDATE clusters SET metadata_cache_ttl_ms = ? WHERE id = ?")
+            .bind(ttl_ms.map(|ttl| ttl as i64))
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Cluster not found"));
+        }
+        Ok(())
+    }
+
+    /// Counts an operation against the cluster.
+    pub async fn record_cluster_use(&self, id: &Uuid) -> Result<()> {
+        sqlx::query(
+            "UPDATE clusters SET operation_count = operation_count + 1, last_used_at = ? WHERE id = ?",
+        )
+        .bind(now_millis())
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn record_cluster_connected(&self, id: &Uuid) -> Result<()> {
+        sqlx::query("UPDATE clusters SET last_connected_at = ? WHERE id = ?")
+            .bind(now_millis())
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_cluster_group(&self, id: &Uuid, group: Option<&str>) -> Result<()> {
+        let result = sqlx::query("UPDATE clusters SET group_name = ? WHERE id = ?")
+            .bind(group)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Cluster not found"));
+        }
+        Ok(())
+    }
+
+    pub async fn set_client_id(&self, id: &Uuid, client_id: Option<&str>) -> Result<()> {
+        let result = sqlx::query("UPDATE clusters SET client_id = ? WHERE id = ?")
+            .bind(client_id)
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Cluster not found"));
+        }
+        Ok(())
+    }
+
+    /// Stores the position of each cluster in `ids`; clusters not listed keep
+    /// theirs.
+    pub async fn reorder_clusters(&self, ids: &[Uuid]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (position, id) in ids.iter().enumerate() {
+            sqlx::query("UPDATE clusters SET sort_order = ? WHERE id = ?")
+                .bind(position as i64)
+                .bind(id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Services keyed by cluster id, in name order.
+    async fn list_services(&self) -> Result<HashMap<String, Vec<AuxiliaryService>>> {
+        let rows = sqlx::query(
+            "SELECT id, cluster_id, name, kind, url, username FROM cluster_services ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut services: HashMap<String, Vec<AuxiliaryService>> = HashMap::new();
+        for row in rows {
+            let id: String = sqlx::Row::get(&row, 0);
+            let cluster_id: String = sqlx::Row::get(&row, 1);
+            let kind: String = sqlx::Row::get(&row, 3);
+            let kind = match kind.as_str() {
+                "connect" => AuxiliaryServiceKind::Connect,
+                "ksqldb" => AuxiliaryServiceKind::KsqlDb,
+                _ => AuxiliaryServiceKind::SchemaRegistry,
+            };
+            services
+                .entry(cluster_id)
+                .or_default()
+                .push(AuxiliaryService {
+                    id: Uuid::parse_str(&id).unwrap_or_default(),
+                    name: sqlx::Row::get(&row, 2),
+                    kind,
+                    url: sqlx::Row::get(&row, 4),
+                    username: sqlx::Row::get(&row, 5),
+                });
+        }
+        Ok(services)
+    }
+
+    pub async fn save_service(&self, cluster_id: &Uuid, service: &AuxiliaryService) -> Result<()> {
+        let kind = match service.kind {
+            AuxiliaryServiceKind::SchemaRegistry => "schema_registry",
+            AuxiliaryServiceKind::Connect => "connect",
+            AuxiliaryServiceKind::KsqlDb => "ksqldb",
+        };
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO cluster_services (id, cluster_id, name, kind, url, username)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(service.id.to_string())
+        .bind(cluster_id.to_string())
+        .bind(&service.name)
+        .bind(kind)
+        .bind(&service.url)
+        .bind(&service.username)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn delete_service(&self, cluster_id: &Uuid, service_id: &Uuid) -> Result<()> {
+        let result = sqlx::query("DELETE FROM cluster_services WHERE id = ? AND cluster_id = ?")
+            .bind(service_id.to_string())
+            .bind(cluster_id.to_string())
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Service not found"));
+        }
+        Ok(())
+    }
+
+    pub async fn save_topic_encryption(
+        &self,
+        cluster_id: &Uuid,
+        encryption: &TopicEncryption,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO topic_encryption (cluster_id, topic, encrypt_on_publish)
+             VALUES (?, ?, ?)",
+        )
+        .bind(cluster_id.to_string())
+        .bind(&encryption.topic)
+        .bind(encryption.encrypt_on_publish)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn list_topic_encryption(&self, cluster_id: &Uuid) -> Result<Vec<TopicEncryption>> {
+        let rows = sqlx::query(
+            "SELECT topic, encrypt_on_publish FROM topic_encryption WHERE cluster_id = ? ORDER BY topic",
+        )
+        .bind(cluster_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| TopicEncryption {
+                topic: sqlx::Row::get(row, 0),
+                encrypt_on_publish: sqlx::Row::get(row, 1),
+            })
+            .collect())
+    }
+
+    pub async fn get_topic_encryption(
+        &self,
+        cluster_id: &Uuid,
+        topic: &str,
+    ) -> Result<Option<TopicEncryption>> {
+        let row = sqlx::query(
+            "SELECT topic, encrypt_on_publish FROM topic_encryption WHERE cluster_id = ? AND topic = ?",
+        )
+        .bind(cluster_id.to_string())
+        .bind(topic)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| TopicEncryption {
+            topic: sqlx::Row::get(&row, 0),
+            encrypt_on_publish: sqlx::Row::get(&row, 1),
+        }))
+    }
+
+    pub async fn delete_topic_encryption(&self, cluster_id: &Uuid, topic: &str) -> Result<()> {
+        let result = sqlx::query("DELETE FROM topic_encryption WHERE cluster_id = ? AND topic = ?")
+            .bind(cluster_id.to_string())
+            .bind(topic)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Topic has no encryption configured"));
+        }
+        Ok(())
+    }
+
+    /// Encoding override of a topic; `Auto` when none is stored.
+    pub async fn get_payload_encoding(
+        &self,
+        cluster_id: &Uuid,
+        topic: &str,
+    ) -> Result<PayloadEncoding> {
+        let encoding: Option<String> = sqlx::query_scalar(
+            "SELECT encoding FROM topic_payload_encoding WHERE cluster_id = ? AND topic = ?",
+        )
+        .bind(cluster_id.to_string())
+        .bind(topic)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(match encoding.as_deref() {
+            Some("plain") => PayloadEncoding::Plain,
+            Some("base64") => PayloadEncoding::Base64,
+            Some("url_encoded") => PayloadEncoding::UrlEncoded,
+            _ => PayloadEncoding::Auto,
+        })
+    }
+
+    /// Stores an override; setting `Auto` removes it.
+    pub async fn set_payload_encoding(
+        &self,
+        cluster_id: &Uuid,
+        topic: &str,
+        encoding: PayloadEncoding,
+    ) -> Result<()> {
+        let encoding = match encoding {
+            PayloadEncoding::Auto => {
+                sqlx::query(
+                    "DELETE FROM topic_payload_encoding WHERE cluster_id = ? AND topic = ?",
+                )
+                .bind(cluster_id.to_string())
+                .bind(topic)
+                .execute(&self.pool)
+                .await?;
+                return Ok(());
+            }
+            PayloadEncoding::Plain => "plain",
+            PayloadEncoding::Base64 => "base64",
+            PayloadEncoding::UrlEncoded => "url_encoded",
+        };
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO topic_payload_encoding (cluster_id, topic, encoding)
+             VALUES (?, ?, ?)",
+        )
+        .bind(cluster_id.to_string())
+        .bind(topic)
+        .bind(encoding)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn record_lag_samples(&self, cluster_id: &Uuid, samples: &[LagSample]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for sample in samples {
+            sqlx::query(
+                "INSERT INTO lag_samples (cluster_id, group_id, topic, lag, timestamp)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(cluster_id.to_string())
+            .bind(&sample.group_id)
+            .bind(&sample.topic)
+            .bind(sample.lag)
+            .bind(sample.timestamp)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Samples of a group within `[from, to]`, oldest first.
+    pub async fn list_lag_samples(
+        &self,
+        cluster_id: &Uuid,
+        group_id: &str,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<LagSample>> {
+        let rows = sqlx::query(
+            "SELECT topic, lag, timestamp FROM lag_samples
+             WHERE cluster_id = ? AND group_id = ? AND timestamp BETWEEN ? AND ?
+             ORDER BY timestamp, topic",
+        )
+        .bind(cluster_id.to_string())
+        .bind(group_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| LagSample {
+                group_id: group_id.to_string(),
+                topic: sqlx::Row::get(row, 0),
+                lag: sqlx::Row::get(row, 1),
+                timestamp: sqlx::Row::get(row, 2),
+            })
+            .collect())
+    }
+
+    /// Deletes samples taken before `before`. Returns how many were removed.
+    pub async fn prune_lag_samples(&self, before: i64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM lag_samples WHERE timestamp < ?")
+            .bind(before)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected())
+    }
+
+    pub async fn save_offset_snapshot(&self, snapshot: &OffsetSnapshot) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO offset_snapshots (id, cluster_id, group_id, created_at, offsets)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(snapshot.id.to_string())
+        .bind(snapshot.cluster_id.to_string())
+        .bind(&snapshot.group_id)
+        .bind(snapshot.created_at)
+        .bind(serde_json::to_string(&snapshot.offsets)?)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn list_offset_snapshots(
+        &self,
+        cluster_id: &Uuid,
+        group_id: &str,
+    ) -> Result<Vec<OffsetSnapshot>> {
+        let rows = sqlx::query(
+            "SELECT id, created_at, offsets FROM offset_snapshots
+             WHERE cluster_id = ? AND group_id = ?
+             ORDER BY created_at DESC",
+        )
+        .bind(cluster_id.to_string())
+        .bind(group_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let id: String = sqlx::Row::get(row, 0);
+                let offsets: String = sqlx::Row::get(row, 2);
+                Ok(OffsetSnapshot {
+                    id: Uuid::parse_str(&id)?,
+                    cluster_id: *cluster_id,
+                    group_id: group_id.to_string(),
+                    created_at: sqlx::Row::get(row, 1),
+                    offsets: serde_json::from_str(&offsets)?,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn get_offset_snapshot(&self, id: &Uuid) -> Result<Option<OffsetSnapshot>> {
+        let row = sqlx::query(
+            "SELECT cluster_id, group_id, created_at, offsets FROM offset_snapshots WHERE id = ?",
+        )
+        .bind(id.to_string())
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(|row| {
+            let cluster_id: String = sqlx::Row::get(&row, 0);
+            let offsets: String = sqlx::Row::get(&row, 3);
+            Ok(OffsetSnapshot {
+                id: *id,
+                cluster_id: Uuid::parse_str(&cluster_id)?,
+                group_id: sqlx::Row::get(&row, 1),
+                created_at: sqlx::Row::get(&row, 2),
+                offsets: serde_json::from_str(&offsets)?,
+            })
+        })
+        .transpose()
+    }
+
+    pub async fn get_topic_preferences(
+        &self,
+        cluster_id: &Uuid,
+        topic: &str,
+    ) -> Result<Option<TopicPreferences>> {
+        let preferences: Option<String> = sqlx::query_scalar(
+            "SELECT preferences FROM topic_preferences WHERE cluster_id = ? AND topic = ?",
+        )
+        .bind(cluster_id.to_string())
+        .bind(topic)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(preferences.map(|p| serde_json::from_str(&p)).transpose()?)
+    }
+
+    /// Saved preferences of every topic of a cluster, by topic name.
+    pub async fn list_topic_preferences(
+        &self,
+        cluster_id: &Uuid,
+    ) -> Result<Vec<(String, TopicPreferences)>> {
+        let rows = sqlx::query(
+            "SELECT topic, preferences FROM topic_preferences WHERE cluster_id = ? ORDER BY topic",
+        )
+        .bind(cluster_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let topic: String = sqlx::Row::get(&row, 0);
+                let preferences: String = sqlx::Row::get(&row, 1);
+                Ok((topic, serde_json::from_str(&preferences)?))
+            })
+            .collect()
+    }
+
+    pub async fn save_topic_preferences(
+        &self,
+        cluster_id: &Uuid,
+        topic: &str,
+        preferences: &TopicPreferences,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO topic_preferences (cluster_id, topic, preferences)
+             VALUES (?, ?, ?)",
+        )
+        .bind(cluster_id.to_string())
+        .bind(topic)
+        .bind(serde_json::to_string(preferences)?)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Annotations of a cluster's topics, by topic name.
+    pub async fn list_topic_annotations(&self, cluster_id: &Uuid) -> Result<Vec<TopicAnnotation>> {
+        let rows = sqlx::query(
+            "SELECT topic, favorite, notes, tags FROM topic_annotations
+             WHERE cluster_id = ? ORDER BY topic",
+        )
+        .bind(cluster_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let tags: String = sqlx::Row::get(&row, 3);
+                Ok(TopicAnnotation {
+                    topic: sqlx::Row::get(&row, 0),
+                    favorite: sqlx::Row::get(&row, 1),
+                    notes: sqlx::Row::get(&row, 2),
+                    tags: serde_json::from_str(&tags)?,
+                })
+            })
+            .collect()
+    }
+
+    /// Stores a topic's annotation, or removes it once nothing is set.
+    pub async fn save_topic_annotation(
+        &self,
+        cluster_id: &Uuid,
+        annotation: &TopicAnnotation,
+    ) -> Result<()> {
+        if annotation.is_empty() {
+            sqlx::query("DELETE FROM topic_annotations WHERE cluster_id = ? AND topic = ?")
+                .bind(cluster_id.to_string())
+                .bind(&annotation.topic)
+                .execute(&self.pool)
+                .await?;
+            return Ok(());
+        }
+        sqlx::query(
+            "INSERT OR REPLACE INTO topic_annotations (cluster_id, topic, favorite, notes, tags)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(cluster_id.to_string())
+        .bind(&annotation.topic)
+        .bind(annotation.favorite)
+        .bind(&annotation.notes)
+        .bind(serde_json::to_string(&annotation.tags)?)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Topics of a cluster marked as protected, by name.
+    pub async fn list_protected_topics(&self, cluster_id: &Uuid) -> Result<Vec<String>> {
+        let rows =
+            sqlx::query("SELECT topic FROM protected_topics WHERE cluster_id = ? ORDER BY topic")
+                .bind(cluster_id.to_string())
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(rows.iter().map(|row| sqlx::Row::get(row, 0)).collect())
+    }
+
+    pub async fn is_topic_protected(&self, cluster_id: &Uuid, topic: &str) -> Result<bool> {
+        let row = sqlx::query("SELECT 1 FROM protected_topics WHERE cluster_id = ? AND topic = ?")
+            .bind(cluster_id.to_string())
+            .bind(topic)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(row.is_some())
+    }
+
+    pub async fn set_topic_protected(
+        &self,
+        cluster_id: &Uuid,
+        topic: &str,
+        protected: bool,
+        now: i64,
+    ) -> Result<()> {
+        if protected {
+            sqlx::query(
+                "INSERT OR IGNORE INTO protected_topics (cluster_id, topic, protected_at)
+                 VALUES (?, ?, ?)",
+            )
+            .bind(cluster_id.to_string())
+            .bind(topic)
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+        } else {
+            sqlx::query("DELETE FROM protected_topics WHERE cluster_id = ? AND topic = ?")
+                .bind(cluster_id.to_string())
+                .bind(topic)
+                .execute(&self.pool)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Stores the latest successful fetch of some cluster metadata, e.g. the
+    /// topic list, to serve while the cluster is unreachable.
+    pub async fn save_metadata_snapshot(
+        &self,
+        cluster_id: &Uuid,
+        kind: &str,
+        name: &str,
+        payload: &str,
+        fetched_at: i64,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO metadata_snapshots (cluster_id, kind, name, payload, fetched_at)
+             VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(cluster_id.to_string())
+        .bind(kind)
+        .bind(name)
+        .bind(payload)
+        .bind(fetched_at)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Payload and fetch time of a stored snapshot.
+    pub async fn get_metadata_snapshot(
+        &self,
+        cluster_id: &Uuid,
+        kind: &str,
+        name: &str,
+    ) -> Result<Option<(String, i64)>> {
+        let row = sqlx::query(
+            "SELECT payload, fetched_at FROM metadata_snapshots
+             WHERE cluster_id = ? AND kind = ? AND name = ?",
+        )
+        .bind(cluster_id.to_string())
+        .bind(kind)
+        .bind(name)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| (sqlx::Row::get(&row, 0), sqlx::Row::get(&row, 1))))
+    }
+
+    pub async fn save_macro(&self, command_macro: &CommandMacro) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO command_macros (id, name, steps) VALUES (?, ?, ?)")
+            .bind(command_macro.id.to_string())
+            .bind(&command_macro.name)
+            .bind(serde_json::to_string(&command_macro.steps)?)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn list_macros(&self) -> Result<Vec<CommandMacro>> {
+        let rows = sqlx::query("SELECT id, name, steps FROM command_macros ORDER BY name")
+            .fetch_all(&self.pool)
+            .await?;
+
+        rows.iter()
+            .map(|row| {
+                let id: String = sqlx::Row::get(row, 0);
+                let steps: String = sqlx::Row::get(row, 2);
+                Ok(CommandMacro {
+                    id: Uuid::parse_str(&id)?,
+                    name: sqlx::Row::get(row, 1),
+                    steps: serde_json::from_str(&steps)?,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn get_macro(&self, id: &Uuid) -> Result<Option<CommandMacro>> {
+        let row = sqlx::query("SELECT name, steps FROM command_macros WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(&self.pool)
+            .await?;
+
+        row.map(|row| {
+            let steps: String = sqlx::Row::get(&row, 1);
+            Ok(CommandMacro {
+                id: *id,
+                name: sqlx::Row::get(&row, 0),
+                steps: serde_json::from_str(&steps)?,
+            })
+        })
+        .transpose()
+    }
+
+    pub async fn delete_macro(&self, id: &Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM command_macros WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn save_lag_alert_rule(&self, rule: &LagAlertRule) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO lag_alert_rules (id, cluster_id, group_id, topic, threshold, interval_secs)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(rule.id.to_string())
+        .bind(rule.cluster_id.to_string())
+        .bind(&rule.group_id)
+        .bind(&rule.topic)
+        .bind(rule.threshold)
+        .bind(rule.interval_secs as i64)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    pub async fn list_lag_alert_rules(&self, cluster_id: &Uuid) -> Result<Vec<LagAlertRule>> {
+        let rows = sqlx::query(
+            "SELECT id, group_id, topic, threshold, interval_secs FROM lag_alert_rules
+             WHERE cluster_id = ? ORDER BY group_id, topic",
+        )
+        .bind(cluster_id.to_string())
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                let id: String = sqlx::Row::get(row, 0);
+                let interval_secs: i64 = sqlx::Row::get(row, 4);
+                Ok(LagAlertRule {
+                    id: Uuid::parse_str(&id)?,
+                    cluster_id: *cluster_id,
+                    group_id: sqlx::Row::get(row, 1),
+                    topic: sqlx::Row::get(row, 2),
+                    threshold: sqlx::Row::get(row, 3),
+                    interval_secs: interval_secs as u64,
+                })
+            })
+            .collect()
+    }
+
+    pub async fn delete_lag_alert_rule(&self, id: &Uuid) -> Result<()> {
+        sqlx::query("DELETE FROM lag_alert_rules WHERE id = ?")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn record_isr_events(&self, events: &[IsrEvent]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for event in events {
+            let change = match event.change {
+                IsrChange::Shrink => "shrink",
+                IsrChange::Expand => "expand",
+            };
+            sqlx::query(
+                "INSERT INTO isr_events (cluster_id, topic, partition, change, brokers, isr, timestamp)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(event.cluster_id.to_string())
+            .bind(&event.topic)
+            .bind(event.partition)
+            .bind(change)
+            .bind(serde_json::to_string(&event.brokers)?)
+            .bind(serde_json::to_string(&event.isr)?)
+            .bind(event.timestamp)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Recorded ISR changes of a topic, newest first.
+    pub async fn list_isr_events(
+        &self,
+        cluster_id: &Uuid,
+        topic: &str,
+        limit: i64,
+    ) -> Result<Vec<IsrEvent>> {
+        let rows = sqlx::query(
+            "SELECT partition, change, brokers, isr, timestamp FROM isr_events
+             WHERE cluster_id = ? AND topic = ? ORDER BY timestamp DESC, id DESC LIMIT ?",
+        )
+        .bind(cluster_id.to_string())
+        .bind(topic)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let change: String = sqlx::Row::get(&row, 1);
+                let brokers: String = sqlx::Row::get(&row, 2);
+                let isr: String = sqlx::Row::get(&row, 3);
+                IsrEvent {
+                    cluster_id: *cluster_id,
+                    topic: topic.to_string(),
+                    partition: sqlx::Row::get(&row, 0),
+                    change: match change.as_str() {
+                        "expand" => IsrChange::Expand,
+                        _ => IsrChange::Shrink,
+                    },
+                    brokers: serde_json::from_str(&brokers).unwrap_or_default(),
+                    isr: serde_json::from_str(&isr).unwrap_or_default(),
+                    timestamp: sqlx::Row::get(&row, 4),
+                }
+            })
+            .collect())
+    }
+
+    pub async fn record_topic_events(&self, events: &[TopicEvent]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for event in events {
+            let change = match event.change {
+                TopicChange::Created => "created",
+                TopicChange::Deleted => "deleted",
+            };
+            sqlx::query(
+                "INSERT INTO topic_events (cluster_id, topic, change, partitions, timestamp)
+                 VALUES (?, ?, ?, ?, ?)",
+            )
+            .bind(event.cluster_id.to_string())
+            .bind(&event.topic)
+            .bind(change)
+            .bind(event.partitions as i64)
+            .bind(event.timestamp)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Most recent topic creations and deletions on the cluster, newest first.
+    pub async fn list_topic_events(
+        &self,
+        cluster_id: &Uuid,
+        limit: i64,
+    ) -> Result<Vec<TopicEvent>> {
+        let rows = sqlx::query(
+            "SELECT topic, change, partitions, timestamp FROM topic_events
+             WHERE cluster_id = ? ORDER BY timestamp DESC, id DESC LIMIT ?",
+        )
+        .bind(cluster_id.to_string())
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let change: String = sqlx::Row::get(&row, 1);
+                let partitions: i64 = sqlx::Row::get(&row, 2);
+                TopicEvent {
+                    cluster_id: *cluster_id,
+                    topic: sqlx::Row::get(&row, 0),
+                    change: match change.as_str() {
+                        "deleted" => TopicChange::Deleted,
+                        _ => TopicChange::Created,
+                    },
+                    partitions: partitions as usize,
+                    timestamp: sqlx::Row::get(&row, 3),
+                }
+            })
+            .collect())
+    }
+
+    pub async fn record_group_events(&self, events: &[GroupEvent]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for event in events {
+            sqlx::query(
+                "INSERT INTO group_events (cluster_id, group_id, from_state, to_state, members_before, members_after, timestamp)
+                 VALUES (?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(event.cluster_id.to_string())
+            .bind(&event.group_id)
+            .bind(&event.from_state)
+            .bind(&event.to_state)
+            .bind(event.members_before as i64)
+            .bind(event.members_after as i64)
+            .bind(event.timestamp)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// State transitions of a group within `[from, to]`, oldest first.
+    pub async fn list_group_events(
+        &self,
+        cluster_id: &Uuid,
+        group_id: &str,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<GroupEvent>> {
+        let rows = sqlx::query(
+            "SELECT from_state, to_state, members_before, members_after, timestamp FROM group_events
+             WHERE cluster_id = ? AND group_id = ? AND timestamp BETWEEN ? AND ?
+             ORDER BY timestamp, id",
+        )
+        .bind(cluster_id.to_string())
+        .bind(group_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let members_before: i64 = sqlx::Row::get(row, 2);
+                let members_after: i64 = sqlx::Row::get(row, 3);
+                GroupEvent {
+                    cluster_id: *cluster_id,
+                    group_id: group_id.to_string(),
+                    from_state: sqlx::Row::get(row, 0),
+                    to_state: sqlx::Row::get(row, 1),
+                    members_before: members_before as usize,
+                    members_after: members_after as usize,
+                    timestamp: sqlx::Row::get(row, 4),
+                }
+            })
+            .collect())
+    }
+
+    /// Marks the cluster deleted; it stays restorable until purged.
+    pub async fn last_receipt_signature(&self) -> Result<Option<String>> {
+        Ok(
+            sqlx::query_scalar(
+                "SELECT signature FROM production_receipts ORDER BY id DESC LIMIT 1",
+            )
+            .fetch_optional(&self.pool)
+            .await?,
+        )
+    }
+
+    pub async fn record_production_receipts(&self, receipts: &[ProductionReceipt]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for receipt in receipts {
+            sqlx::query(
+                "INSERT INTO production_receipts (cluster_id, topic, partition, offset, payload_sha256, user, timestamp, previous_signature, signature)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(receipt.cluster_id.to_string())
+            .bind(&receipt.topic)
+            .bind(receipt.partition)
+            .bind(receipt.offset)
+            .bind(&receipt.payload_sha256)
+            .bind(&receipt.user)
+            .bind(receipt.timestamp)
+            .bind(&receipt.previous_signature)
+            .bind(&receipt.signature)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// The whole receipt log, oldest first.
+    pub async fn list_production_receipts(&self) -> Result<Vec<ProductionReceipt>> {
+        let rows = sqlx::query(
+            "SELECT cluster_id, topic, partition, offset, payload_sha256, user, timestamp, previous_signature, signature
+             FROM production_receipts ORDER BY id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let cluster_id: String = sqlx::Row::get(&row, 0);
+                ProductionReceipt {
+                    cluster_id: Uuid::parse_str(&cluster_id).unwrap_or_default(),
+                    topic: sqlx::Row::get(&row, 1),
+                    partition: sqlx::Row::get(&row, 2),
+                    offset: sqlx::Row::get(&row, 3),
+                    payload_sha256: sqlx::Row::get(&row, 4),
+                    user: sqlx::Row::get(&row, 5),
+                    timestamp: sqlx::Row::get(&row, 6),
+                    previous_signature: sqlx::Row::get(&row, 7),
+                    signature: sqlx::Row::get(&row, 8),
+                }
+            })
+            .collect())
+    }
+
     pub async fn delete_cluster(&self, id: &Uuid) -> Result<()> {
-        sqlx::query("DELETE FROM clusters WHERE id = ?")
+        sqlx::query("UPDATE clusters SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL")
+            .bind(now_millis())
             .bind(id.to_string())
             .execute(&self.pool)
             .await?;
         Ok(())
     }
+
+    pub async fn restore_cluster(&self, id: &Uuid) -> Result<()> {
+        let result = sqlx::query(
+            "UPDATE clusters SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL",
+        )
+        .bind(id.to_string())
+        .execute(&self.pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Deleted cluster not found"));
+        }
+        Ok(())
+    }
+
+    /// Permanently removes clusters deleted before `deleted_before` and
+    /// returns their ids.
+    pub async fn purge_deleted_clusters(&self, deleted_before: i64) -> Result<Vec<Uuid>> {
+        let ids: Vec<String> = sqlx::query_scalar(
+            "DELETE FROM clusters WHERE deleted_at IS NOT NULL AND deleted_at < ? RETURNING id",
+        )
+        .bind(deleted_before)
+        .fetch_all(&self.pool)
+        .await?;
+        self.delete_orphaned_rows().await?;
+
+        Ok(ids
+            .iter()
+            .filter_map(|id| Uuid::parse_str(id).ok())
+            .collect())
+    }
+
+    /// Deletes per-cluster data whose cluster no longer exists and returns
+    /// how many rows went.
+    pub async fn delete_orphaned_rows(&self) -> Result<u64> {
+        let mut deleted = 0;
+        for table in CLUSTER_DATA_TABLES {
+            deleted += sqlx::query(&format!(
+                "DELETE FROM {} WHERE cluster_id NOT IN (SELECT id FROM clusters)",
+                table
+            ))
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        }
+        Ok(deleted)
+    }
+
+    /// Rows of per-cluster data whose cluster no longer exists, by table.
+    pub async fn count_orphaned_rows(&self) -> Result<Vec<(String, i64)>> {
+        let mut counts = Vec::new();
+        for table in CLUSTER_DATA_TABLES {
+            let count: i64 = sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM {} WHERE cluster_id NOT IN (SELECT id FROM clusters)",
+                table
+            ))
+            .fetch_one(&self.pool)
+            .await?;
+            if count > 0 {
+                counts.push((table.to_string(), count));
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Cluster and service ids of services whose cluster no longer exists;
+    /// their passwords may still sit in the keyring.
+    pub async fn list_orphaned_services(&self) -> Result<Vec<(String, String)>> {
+        let rows = sqlx::query(
+            "SELECT cluster_id, id FROM cluster_services
+             WHERE cluster_id NOT IN (SELECT id FROM clusters)",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .iter()
+            .map(|row| (sqlx::Row::get(row, 0), sqlx::Row::get(row, 1)))
+            .collect())
+    }
+
+    /// Cluster ids and topics of encryption settings whose cluster no longer
+    /// exists; their keys may still sit in the keyring.
+    pub async fn list_orphaned_topic_encryption(&self) -> Result<Vec<(String, String)>> {
+        let rows = sqlx::query(
+            "SELECT cluster_id, topic FROM topic_encryption
+             WHERE cluster_id NOT IN (SELECT id FROM clusters)",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(rows
+            .iter()
+            .map(|row| (sqlx::Row::get(row, 0), sqlx::Row::get(row, 1)))
+            .collect())
+    }
+
+    /// Problems `PRAGMA integrity_check` reports; empty when the database is
+    /// intact.
+    pub async fn integrity_check(&self) -> Result<Vec<String>> {
+        let rows: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows.into_iter().filter(|row| row != "ok").collect())
+    }
+
+    /// Stored ids and names of clusters whose id is not a valid UUID.
+    pub async fn list_invalid_cluster_ids(&self) -> Result<Vec<(String, String)>> {
+        let rows = sqlx::query("SELECT id, name FROM clusters")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| (sqlx::Row::get(row, 0), sqlx::Row::get(row, 1)))
+            .filter(|(id, _): &(String, String)| Uuid::parse_str(id).is_err())
+            .collect())
+    }
+
+    /// Stored ids and names of services whose id is not a valid UUID.
+    pub async fn list_invalid_service_ids(&self) -> Result<Vec<(String, String)>> {
+        let rows = sqlx::query("SELECT id, name FROM cluster_services")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(rows
+            .iter()
+            .map(|row| (sqlx::Row::get(row, 0), sqlx::Row::get(row, 1)))
+            .filter(|(id, _): &(String, String)| Uuid::parse_str(id).is_err())
+            .collect())
+    }
+
+    /// Gives a cluster stored under an unparsable id a new one, carrying its
+    /// per-cluster data along.
+    pub async fn change_cluster_id(&self, old_id: &str, new_id: &Uuid) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query("UPDATE clusters SET id = ? WHERE id = ?")
+            .bind(new_id.to_string())
+            .bind(old_id)
+            .execute(&mut *tx)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Cluster not found"));
+        }
+        for table in CLUSTER_DATA_TABLES {
+            sqlx::query(&format!(
+                "UPDATE {} SET cluster_id = ? WHERE cluster_id = ?",
+                table
+            ))
+            .bind(new_id.to_string())
+            .bind(old_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    pub async fn change_service_id(&self, old_id: &str, new_id: &Uuid) -> Result<()> {
+        let result = sqlx::query("UPDATE cluster_services SET id = ? WHERE id = ?")
+            .bind(new_id.to_string())
+            .bind(old_id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(anyhow::anyhow!("Service not found"));
+        }
+        Ok(())
+    }
+}
+
+async fn add_column_if_missing(
+    pool: &Pool<Sqlite>,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<()> {
+    let columns = sqlx::query(&format!("PRAGMA table_info({})", table))
+        .fetch_all(pool)
+        .await?;
+    let exists = columns
+        .iter()
+        .any(|row| sqlx::Row::get::<String, _>(row, 1) == column);
+    if !exists {
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(pool)
+        .await?;
+    }
+    Ok(())
 }
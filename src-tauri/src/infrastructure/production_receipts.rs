@@ -0,0 +1,125 @@
+//! Signing for the production receipt log. Receipts are signed with
+//! HMAC-SHA256 under a per-installation key kept in the keyring, generated on
+//! first use.
+
+use super::kafka::local_user;
+use super::persistence::sqlite_cluster_repository::SqliteClusterRepository;
+use super::session_manager::now_millis;
+use crate::domain::receipt::ProductionReceipt;
+use crate::domain::topic::PublishResult;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use uuid::Uuid;
+
+const KEY_LEN: usize = 32;
+
+pub struct ReceiptSigner {
+    key: Vec<u8>,
+}
+
+impl ReceiptSigner {
+    /// A new random key, base64-encoded for storage.
+    pub fn generate_key() -> String {
+        let mut key = [0u8; KEY_LEN];
+        OsRng.fill_bytes(&mut key);
+        STANDARD.encode(key)
+    }
+
+    pub fn from_base64_key(key: &str) -> Result<Self> {
+        let key = STANDARD
+            .decode(key.trim())
+            .map_err(|e| anyhow!("Receipt signing key is not valid base64: {}", e))?;
+        if key.len() != KEY_LEN {
+            return Err(anyhow!(
+                "Receipt signing key must be {} bytes, got {}",
+                KEY_LEN,
+                key.len()
+            ));
+        }
+        Ok(Self { key })
+    }
+
+    /// Signs the receipt in place, chaining it to `previous_signature`.
+    pub fn sign(&self, receipt: &mut ProductionReceipt, previous_signature: Option<String>) {
+        receipt.previous_signature = previous_signature;
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(receipt.signed_content().as_bytes());
+        receipt.signature = to_hex(&mac.finalize().into_bytes());
+    }
+}
+
+/// Appends receipts for records produced to one topic. Logs sharing a
+/// `chain` lock never interleave, so each receipt chains to the one before.
+pub struct ReceiptLog {
+    cluster_id: Uuid,
+    topic: String,
+    signer: ReceiptSigner,
+    repo: SqliteClusterRepository,
+    chain: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl ReceiptLog {
+    pub fn new(
+        cluster_id: Uuid,
+        topic: &str,
+        signer: ReceiptSigner,
+        repo: SqliteClusterRepository,
+        chain: Arc<tokio::sync::Mutex<()>>,
+    ) -> Self {
+        Self {
+            cluster_id,
+            topic: topic.to_string(),
+            signer,
+            repo,
+            chain,
+        }
+    }
+
+    /// Signs and stores a receipt for each produced record, given as the
+    /// digest of its payload and its delivery.
+    pub async fn record(&self, produced: &[(String, PublishResult)]) -> Result<()> {
+        if produced.is_empty() {
+            return Ok(());
+        }
+        let user = local_user();
+
+        let _chain = self.chain.lock().await;
+        let mut previous = self.repo.last_receipt_signature().await?;
+        let receipts: Vec<ProductionReceipt> = produced
+            .iter()
+            .map(|(digest, result)| {
+                let mut receipt = ProductionReceipt {
+                    cluster_id: self.cluster_id,
+                    topic: self.topic.clone(),
+                    partition: result.partition,
+                    offset: result.offset,
+                    payload_sha256: digest.clone(),
+                    user: user.clone(),
+                    timestamp: now_millis(),
+                    previous_signature: None,
+                    signature: String::new(),
+                };
+                self.signer.sign(&mut receipt, previous.take());
+                previous = Some(receipt.signature.clone());
+                receipt
+            })
+            .collect();
+        self.repo.record_production_receipts(&receipts).await
+    }
+}
+
+/// SHA-256 of a payload in hex.
+pub fn payload_digest(payload: &[u8]) -> String {
+    to_hex(&Sha256::digest(payload))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
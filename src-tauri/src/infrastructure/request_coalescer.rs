@@ -0,0 +1,118 @@
+use anyhow::{anyhow, Result};
+use futures::future::{BoxFuture, FutureExt, Shared};
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+type SharedRequest<T> = Shared<BoxFuture<'static, Result<T, String>>>;
+
+enum Entry<T> {
+    InFlight(SharedRequest<T>),
+    Ready {
+        value: T,
+        fetched_at: Instant,
+        max_age: Duration,
+    },
+}
+
+/// Collapses identical broker requests: concurrent callers with the same key
+/// await a single in-flight request, and results younger than `min_interval`
+/// are handed out again instead of hitting the broker.
+pub struct RequestCoalescer<K, T> {
+    min_interval: Duration,
+    entries: Mutex<HashMap<K, Entry<T>>>,
+}
+
+impl<K, T> RequestCoalescer<K, T>
+where
+    K: Eq + Hash + Clone,
+    T: Clone + Send + Sync + 'static,
+{
+    pub fn new(min_interval: Duration) -> Self {
+        Self {
+            min_interval,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub async fn run<F, Fut>(&self, key: K, fetch: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        self.run_with_max_age(key, self.min_interval, fetch).await
+    }
+
+    /// Like `run`, reusing a result for `max_age` instead of the default
+    /// interval.
+    pub async fn run_with_max_age<F, Fut>(&self, key: K, max_age: Duration, fetch: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let request = {
+            let mut entries = self.entries.lock().unwrap();
+            match entries.get(&key) {
+                Some(Entry::Ready {
+                    value, fetched_at, ..
+                }) if fetched_at.elapsed() < max_age => {
+                    return Ok(value.clone());
+                }
+                Some(Entry::InFlight(request)) => request.clone(),
+                _ => {
+                    let request = fetch()
+                        .map(|r| r.map_err(|e| e.to_string()))
+                        .boxed()
+                        .shared();
+                    entries.insert(key.clone(), Entry::InFlight(request.clone()));
+                    request
+                }
+            }
+        };
+
+        let result = request.clone().await;
+
+        let mut entries = self.entries.lock().unwrap();
+        // Only the caller that finds its own request still registered settles
+        // the entry; a newer request may already have replaced it.
+        let is_current = matches!(
+            entries.get(&key),
+            Some(Entry::InFlight(current)) if current.ptr_eq(&request)
+        );
+        if is_current {
+            match &result {
+                Ok(value) => {
+                    entries.insert(
+                        key,
+                        Entry::Ready {
+                            value: value.clone(),
+                            fetched_at: Instant::now(),
+                            max_age,
+                        },
+                    );
+                }
+                Err(_) => {
+                    entries.remove(&key);
+                }
+            }
+        }
+        entries.retain(|_, entry| match entry {
+            Entry::InFlight(_) => true,
+            Entry::Ready {
+                fetched_at,
+                max_age,
+                ..
+            } => fetched_at.elapsed() < *max_age,
+        });
+
+        result.map_err(|e| anyhow!(e))
+    }
+
+    /// Forgets cached results and in-flight requests for matching keys, so
+    /// the next call for them goes to the broker.
+    pub fn invalidate(&self, matches: impl Fn(&K) -> bool) {
+        self.entries.lock().unwrap().retain(|key, _| !matches(key));
+    }
+}
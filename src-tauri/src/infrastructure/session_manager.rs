@@ -0,0 +1,153 @@
+use crate::domain::session::{SessionInfo, SessionKind, SessionState};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::watch;
+use uuid::Uuid;
+
+struct SessionEntry {
+    info: SessionInfo,
+    control: watch::Sender<SessionState>,
+    records: Arc<AtomicU64>,
+    dropped: Arc<AtomicU64>,
+}
+
+/// Handed to a running session task. The session is asked to stop when the
+/// manager drops the control channel.
+pub struct SessionContext {
+    pub id: Uuid,
+    control: watch::Receiver<SessionState>,
+    records: Arc<AtomicU64>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl SessionContext {
+    /// Waits for the next control change; `None` means the session was stopped.
+    pub async fn changed(&mut self) -> Option<SessionState> {
+        self.control.changed().await.ok()?;
+        Some(*self.control.borrow_and_update())
+    }
+
+    pub fn record_processed(&self, count: u64) {
+        self.records.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub fn record_dropped(&self, count: u64) {
+        self.dropped.fetch_add(count, Ordering::Relaxed);
+    }
+}
+
+/// Registry of long-running background sessions (consumers, tails, jobs).
+#[derive(Clone, Default)]
+pub struct SessionManager {
+    sessions: Arc<Mutex<HashMap<Uuid, SessionEntry>>>,
+}
+
+impl SessionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn spawn<F, Fut>(
+        &self,
+        kind: SessionKind,
+        cluster_id: Uuid,
+        topic: String,
+        partition: Option<i32>,
+        run: F,
+    ) -> SessionInfo
+    where
+        F: FnOnce(SessionContext) -> Fut,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let id = Uuid::new_v4();
+        let (control, control_rx) = watch::channel(SessionState::Running);
+        let records = Arc::new(AtomicU64::new(0));
+        let dropped = Arc::new(AtomicU64::new(0));
+        let info = SessionInfo {
+            id,
+            kind,
+            cluster_id,
+            topic,
+            partition,
+            state: SessionState::Running,
+            started_at: now_millis(),
+            records_processed: 0,
+            records_dropped: 0,
+        };
+
+        self.sessions.lock().unwrap().insert(
+            id,
+            SessionEntry {
+                info: info.clone(),
+                control,
+                records: records.clone(),
+                dropped: dropped.clone(),
+            },
+        );
+
+        let task = run(SessionContext {
+            id,
+            control: control_rx,
+            records,
+            dropped,
+        });
+        let sessions = self.sessions.clone();
+        tokio::spawn(async move {
+            if let Err(e) = task.await {
+                eprintln!("Session {} ended with error: {}", id, e);
+            }
+            sessions.lock().unwrap().remove(&id);
+        });
+
+        info
+    }
+
+    pub fn list(&self) -> Vec<SessionInfo> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .values()
+            .map(|entry| entry.snapshot())
+            .collect()
+    }
+
+    pub fn set_state(&self, id: Uuid, state: SessionState) -> Result<SessionInfo> {
+        let mut sessions = self.sessions.lock().unwrap();
+        let entry = sessions
+            .get_mut(&id)
+            .ok_or_else(|| anyhow!("Session not found"))?;
+        entry.info.state = state;
+        entry.control.send_replace(state);
+        Ok(entry.snapshot())
+    }
+
+    pub fn stop(&self, id: Uuid) -> Result<()> {
+        self.sessions
+            .lock()
+            .unwrap()
+            .remove(&id)
+            .map(|_| ())
+            .ok_or_else(|| anyhow!("Session not found"))
+    }
+}
+
+impl SessionEntry {
+    fn snapshot(&self) -> SessionInfo {
+        SessionInfo {
+            records_processed: self.records.load(Ordering::Relaxed),
+            records_dropped: self.dropped.load(Ordering::Relaxed),
+            ..self.info.clone()
+        }
+    }
+}
+
+pub fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or_default()
+}
@@ -0,0 +1,190 @@
+//! Filter/transform steps run as sandboxed WebAssembly modules from the app
+//! data dir. Unlike deserializer plugins they cannot touch the host: a module
+//! gets no imports, and every record runs in a fresh instance with bounded
+//! memory and a fuel budget.
+//!
+//! A module exports:
+//!
+//! ```text
+//! memory                                     linear memory
+//! alloc(len: i32) -> i32                     buffer for the input record
+//! transform(ptr: i32, len: i32) -> i64       (out_ptr << 32) | out_len,
+//!                                            or -1 to drop the record
+//! ```
+
+use crate::domain::plugin::TransformPlugin;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use wasmtime::{Config, Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+/// Linear memory a module may grow to while handling one record.
+const MAX_MEMORY_BYTES: usize = 64 * 1024 * 1024;
+
+/// Instructions (roughly) a module may execute per record.
+const FUEL_PER_RECORD: u64 = 50_000_000;
+
+/// One loaded transform module.
+pub struct WasmTransform {
+    name: String,
+    path: PathBuf,
+    engine: Engine,
+    module: Module,
+}
+
+impl WasmTransform {
+    fn load(engine: &Engine, path: &Path) -> Result<Self> {
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .ok_or_else(|| anyhow!("Invalid module file name"))?
+            .to_string();
+        let module = Module::from_file(engine, path)?;
+        if module.imports().len() > 0 {
+            return Err(anyhow!("Transform modules may not import host functions"));
+        }
+        Ok(Self {
+            name,
+            path: path.to_path_buf(),
+            engine: engine.clone(),
+            module,
+        })
+    }
+
+    /// Transformed payload, or `None` when the module drops the record.
+    pub fn apply(&self, payload: &[u8]) -> Result<Option<Vec<u8>>> {
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(MAX_MEMORY_BYTES)
+            .instances(1)
+            .build();
+        let mut store: Store<StoreLimits> = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(FUEL_PER_RECORD)?;
+
+        let fail = |e: anyhow::Error| anyhow!("Transform {}: {}", self.name, e);
+        let instance = Instance::new(&mut store, &self.module, &[]).map_err(fail)?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or_else(|| anyhow!("Transform {} exports no memory", self.name))?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&mut store, "alloc")
+            .map_err(fail)?;
+        let transform = instance
+            .get_typed_func::<(i32, i32), i64>(&mut store, "transform")
+            .map_err(fail)?;
+
+        let len = i32::try_from(payload.len())
+            .map_err(|_| anyhow!("Payload too large for transform {}", self.name))?;
+        let ptr = alloc.call(&mut store, len).map_err(fail)?;
+        memory
+            .write(&mut store, ptr as u32 as usize, payload)
+            .map_err(|e| fail(e.into()))?;
+
+        let result = transform.call(&mut store, (ptr, len)).map_err(fail)?;
+        if result < 0 {
+            return Ok(None);
+        }
+        let out_ptr = (result >> 32) as u32 as usize;
+        let out_len = (result & 0xffff_ffff) as usize;
+        let out = memory
+            .data(&store)
+            .get(out_ptr..out_ptr + out_len)
+            .ok_or_else(|| anyhow!("Transform {} returned an out-of-bounds buffer", self.name))?;
+        Ok(Some(out.to_vec()))
+    }
+}
+
+/// Transforms applied in order; a record dropped by one skips the rest.
+#[derive(Default, Clone)]
+pub struct TransformPipeline {
+    transforms: Vec<Arc<WasmTransform>>,
+}
+
+impl TransformPipeline {
+    pub fn is_empty(&self) -> bool {
+        self.transforms.is_empty()
+    }
+
+    pub fn apply(&self, payload: &[u8]) -> Result<Option<Vec<u8>>> {
+        let mut payload = payload.to_vec();
+        for transform in &self.transforms {
+            match transform.apply(&payload)? {
+                Some(out) => payload = out,
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(payload))
+    }
+}
+
+/// Transform modules by name (file stem), loaded from one directory.
+pub struct TransformRegistry {
+    dir: PathBuf,
+    engine: Engine,
+    transforms: RwLock<HashMap<String, Arc<WasmTransform>>>,
+}
+
+impl TransformRegistry {
+    pub fn new(dir: PathBuf) -> Result<Self> {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let registry = Self {
+            dir,
+            engine: Engine::new(&config)?,
+            transforms: RwLock::new(HashMap::new()),
+        };
+        registry.reload();
+        Ok(registry)
+    }
+
+    /// Recompiles every `.wasm` module in the directory. Modules that fail to
+    /// compile are skipped and logged.
+    pub fn reload(&self) {
+        let mut transforms = HashMap::new();
+        if let Ok(entries) = std::fs::read_dir(&self.dir) {
+            for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+                if path.extension().and_then(|e| e.to_str()) != Some("wasm") {
+                    continue;
+                }
+                match WasmTransform::load(&self.engine, &path) {
+                    Ok(transform) => {
+                        transforms.insert(transform.name.clone(), Arc::new(transform));
+                    }
+                    Err(e) => eprintln!("Failed to load transform module {:?}: {}", path, e),
+                }
+            }
+        }
+        *self.transforms.write().unwrap() = transforms;
+    }
+
+    /// The named transforms as a pipeline, in the given order.
+    pub fn pipeline(&self, names: &[String]) -> Result<TransformPipeline> {
+        let loaded = self.transforms.read().unwrap();
+        let transforms = names
+            .iter()
+            .map(|name| {
+                loaded
+                    .get(name)
+                    .cloned()
+                    .ok_or_else(|| anyhow!("Transform {} is not installed", name))
+            })
+            .collect::<Result<_>>()?;
+        Ok(TransformPipeline { transforms })
+    }
+
+    pub fn list(&self) -> Vec<TransformPlugin> {
+        let mut transforms: Vec<TransformPlugin> = self
+            .transforms
+            .read()
+            .unwrap()
+            .values()
+            .map(|t| TransformPlugin {
+                name: t.name.clone(),
+                path: t.path.to_string_lossy().into_owned(),
+            })
+            .collect();
+        transforms.sort_by(|a, b| a.name.cmp(&b.name));
+        transforms
+    }
+}
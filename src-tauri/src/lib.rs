@@ -2,13 +2,55 @@ mod domain;
 mod infrastructure;
 mod usecase;
 
-use crate::domain::cluster::cluster::Cluster;
-use crate::domain::topic::{KafkaMessage, Topic};
-use crate::infrastructure::kafka::KafkaInfrastructure;
+use crate::domain::acl::{
+    AclImportPlan, ApplicationRole, PermissionReport, ProvisionedApplication,
+};
+use crate::domain::alert::LagAlertRule;
+use crate::domain::cluster::cluster::{
+    AuxiliaryService, BrokerCertificate, ClientLogLine, Cluster, ClusterFeature, TokenStatus,
+};
+use crate::domain::command_macro::{CommandMacro, MacroRunResult};
+use crate::domain::consumer_group::{
+    ConsumerGroup, ConsumerGroupDetail, ConsumerGroupLag, GroupEvent, GroupPartitionMatrix,
+    LagSample, OffsetResetPlan, OffsetResetTarget, OffsetSnapshot, PartitionOffsetReset,
+    TopicConsumer, TopicLagOverview,
+};
+use crate::domain::dlq::DlqSummary;
+use crate::domain::event::EventSink;
+use crate::domain::export::ExportSharding;
+use crate::domain::health::{
+    BrokerMaintenanceReport, IsrEvent, MinIsrRisk, TopicEvent, TopicHealth,
+};
+use crate::domain::integrity::{DatabaseRepair, DatabaseReport};
+use crate::domain::mirroring::{MirroringStatus, OffsetTranslation};
+use crate::domain::plugin::{DeserializerPlugin, TransformPlugin};
+use crate::domain::quota::QuotaUtilization;
+use crate::domain::refresh::RefreshTarget;
+use crate::domain::replay::RekeyStrategy;
+use crate::domain::session::{AutoOffsetReset, SessionInfo, SessionState, TailBufferLimits};
+use crate::domain::streams::{KeyedTablePage, StateStoreSnapshot, StreamsApplication};
+use crate::domain::topic::{
+    BreakdownSource, BulkPublishResult, BulkTextFormat, BulkTopicDeletion, ConsumeResult,
+    EnvironmentDrift, FieldBreakdown, HistogramBucket, KafkaMessage, KeyTimeline, MessageFilter,
+    MessageHeader, MultiClusterConsumeResult, Partition, PartitionIncrease, PartitionTruncation,
+    PayloadEncoding, PublishResult, SeekMode, Topic, TopicAnnotation, TopicConfigDiff,
+    TopicConfigEntry, TopicEncryption, TopicPreferences, TopicSelection, TopicSpecPlan,
+    TopicValidation,
+};
+use crate::domain::workspace::WorkspaceImportResult;
+use crate::infrastructure::deserializer_plugins::DeserializerRegistry;
+use crate::infrastructure::kafka::{DeliveryError, KafkaInfrastructure};
 use crate::infrastructure::persistence::keyring_secret_repository::KeyringSecretRepository;
-use crate::infrastructure::persistence::sqlite_cluster_repository::SqliteClusterRepository;
+use crate::infrastructure::persistence::sqlite_cluster_repository::{
+    SqliteClusterRepository, VersionConflict,
+};
+use crate::infrastructure::wasm_transforms::TransformRegistry;
 use crate::usecase::cluster_usecase::ClusterUsecase;
-use tauri::{Manager, State};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_notification::NotificationExt;
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -18,35 +60,364 @@ pub enum Error {
     Kafka(String),
     #[error("Internal error: {0}")]
     Internal(String),
+    #[error("Delivery error: {0}")]
+    Delivery(DeliveryError),
+    #[error("Conflict: {0}")]
+    Conflict(VersionConflict),
+    #[error("Invalid topic: {0}")]
+    Validation(TopicValidation),
 }
 
+/// Sent to the UI as `{ kind, message }` so it can tell a version conflict
+/// or a validation failure from other errors.
 impl serde::Serialize for Error {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::ser::Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        use serde::ser::SerializeStruct;
+
+        let kind = match self {
+            Error::Kafka(_) => "Kafka",
+            Error::Internal(_) => "Internal",
+            Error::Delivery(_) => "Delivery",
+            Error::Conflict(_) => "Conflict",
+            Error::Validation(_) => "Validation",
+        };
+        let mut error = serializer.serialize_struct("Error", 2)?;
+        error.serialize_field("kind", kind)?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
     }
 }
 
+/// Reported to the UI while the backend initializes in the background.
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(tag = "type", content = "message")]
+pub enum AppInitStatus {
+    Initializing,
+    Ready,
+    Failed(String),
+}
+
+/// Startup gives up rather than leave the UI waiting on a stuck disk or a
+/// locked database.
+const INIT_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub struct AppState {
-    pub cluster_usecase: ClusterUsecase,
+    cluster_usecase: OnceLock<ClusterUsecase>,
+    init_status: Mutex<AppInitStatus>,
+}
+
+impl AppState {
+    fn new() -> Self {
+        Self {
+            cluster_usecase: OnceLock::new(),
+            init_status: Mutex::new(AppInitStatus::Initializing),
+        }
+    }
+
+    fn cluster_usecase(&self) -> Result<&ClusterUsecase, Error> {
+        self.cluster_usecase
+            .get()
+            .ok_or_else(|| match &*self.init_status.lock().unwrap() {
+                AppInitStatus::Failed(e) => {
+                    Error::Internal(format!("Initialization failed: {}", e))
+                }
+                _ => Error::Internal("Application is still initializing".to_string()),
+            })
+    }
+}
+
+struct TauriEventSink {
+    handle: AppHandle,
+}
+
+impl EventSink for TauriEventSink {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        if let Err(e) = self.handle.emit(event, payload) {
+            eprintln!("Failed to emit {} event: {}", event, e);
+        }
+    }
+
+    fn notify(&self, title: &str, body: &str) {
+        if let Err(e) = self
+            .handle
+            .notification()
+            .builder()
+            .title(title)
+            .body(body)
+            .show()
+        {
+            eprintln!("Failed to show notification: {}", e);
+        }
+    }
 }
 
 #[tauri::command]
 async fn list_clusters(state: State<'_, AppState>) -> Result<Vec<Cluster>, Error> {
     state
-        .cluster_usecase
+        .cluster_usecase()?
         .list_clusters()
         .await
         .map_err(|e| Error::Internal(e.to_string()))
 }
 
 #[tauri::command]
-async fn list_topics(state: State<'_, AppState>, cluster_id: Uuid) -> Result<Vec<Topic>, Error> {
+async fn list_topics(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    include_activity: Option<bool>,
+    include_internal: Option<bool>,
+) -> Result<Vec<Topic>, Error> {
+    state
+        .cluster_usecase()?
+        .list_topics(
+            cluster_id,
+            include_activity.unwrap_or(false),
+            include_internal.unwrap_or(true),
+        )
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn refresh_topics(state: State<'_, AppState>, cluster_id: Uuid) -> Result<(), Error> {
+    state.cluster_usecase()?.refresh_topics(cluster_id);
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_metadata_cache_ttl(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    ttl_ms: Option<u64>,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .set_metadata_cache_ttl(cluster_id, ttl_ms)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn list_consumer_groups(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    states: Option<Vec<String>>,
+    search: Option<String>,
+) -> Result<Vec<ConsumerGroup>, Error> {
+    state
+        .cluster_usecase()?
+        .list_consumer_groups(cluster_id, states.unwrap_or_default(), search)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn describe_consumer_group(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+) -> Result<ConsumerGroupDetail, Error> {
+    state
+        .cluster_usecase()?
+        .describe_consumer_group(cluster_id, group_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_consumer_group_lag(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+) -> Result<ConsumerGroupLag, Error> {
+    state
+        .cluster_usecase()?
+        .get_consumer_group_lag(cluster_id, group_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_group_partition_matrix(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+) -> Result<GroupPartitionMatrix, Error> {
+    state
+        .cluster_usecase()?
+        .get_group_partition_matrix(cluster_id, group_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn find_topic_consumers(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+) -> Result<Vec<TopicConsumer>, Error> {
+    state
+        .cluster_usecase()?
+        .find_topic_consumers(cluster_id, topic)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn topic_lag_overview(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+) -> Result<TopicLagOverview, Error> {
+    state
+        .cluster_usecase()?
+        .topic_lag_overview(cluster_id, topic)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn delete_consumer_group(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .delete_consumer_group(cluster_id, group_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn delete_group_topic_offsets(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+    topic: String,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .delete_group_topic_offsets(cluster_id, group_id, topic)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn reset_group_offsets(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+    topic: String,
+    partitions: Option<Vec<i32>>,
+    target: OffsetResetTarget,
+) -> Result<Vec<PartitionOffsetReset>, Error> {
+    state
+        .cluster_usecase()?
+        .reset_group_offsets(cluster_id, group_id, topic, partitions, target)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn list_offset_snapshots(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+) -> Result<Vec<OffsetSnapshot>, Error> {
+    state
+        .cluster_usecase()?
+        .list_offset_snapshots(cluster_id, group_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn rollback_offset_reset(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+    snapshot_id: Uuid,
+) -> Result<Vec<PartitionOffsetReset>, Error> {
+    state
+        .cluster_usecase()?
+        .rollback_offset_reset(cluster_id, group_id, snapshot_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn preview_group_offset_reset(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+    topic: String,
+    partitions: Option<Vec<i32>>,
+    target: OffsetResetTarget,
+) -> Result<OffsetResetPlan, Error> {
+    state
+        .cluster_usecase()?
+        .preview_group_offset_reset(cluster_id, group_id, topic, partitions, target)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn export_offset_reset_plan(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+    topic: String,
+    partitions: Option<Vec<i32>>,
+    target: OffsetResetTarget,
+) -> Result<String, Error> {
+    state
+        .cluster_usecase()?
+        .export_offset_reset_plan(cluster_id, group_id, topic, partitions, target)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn apply_offset_reset_plan(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    json: String,
+) -> Result<Vec<PartitionOffsetReset>, Error> {
+    state
+        .cluster_usecase()?
+        .apply_offset_reset_plan(cluster_id, json)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn export_group_offsets(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+) -> Result<String, Error> {
+    state
+        .cluster_usecase()?
+        .export_group_offsets(cluster_id, group_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn import_group_offsets(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    json: String,
+    group_id: Option<String>,
+    dry_run: bool,
+) -> Result<Vec<PartitionOffsetReset>, Error> {
     state
-        .cluster_usecase
-        .list_topics(cluster_id)
+        .cluster_usecase()?
+        .import_group_offsets(cluster_id, json, group_id, dry_run)
         .await
         .map_err(|e| Error::Kafka(e.to_string()))
 }
@@ -58,12 +429,39 @@ async fn add_cluster(
     password: Option<String>,
 ) -> Result<(), Error> {
     state
-        .cluster_usecase
+        .cluster_usecase()?
         .add_cluster(cluster, password)
         .await
         .map_err(|e| Error::Internal(e.to_string()))
 }
 
+/// Adds an Azure Event Hubs namespace from its connection string; `name`
+/// defaults to the namespace.
+#[tauri::command]
+async fn add_event_hubs_cluster(
+    state: State<'_, AppState>,
+    name: Option<String>,
+    connection_string: String,
+) -> Result<Cluster, Error> {
+    state
+        .cluster_usecase()?
+        .add_event_hubs_cluster(name, connection_string)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_unsupported_features(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<Vec<ClusterFeature>, Error> {
+    state
+        .cluster_usecase()?
+        .get_unsupported_features(cluster_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
 #[tauri::command]
 async fn update_cluster(
     state: State<'_, AppState>,
@@ -71,10 +469,13 @@ async fn update_cluster(
     password: Option<String>,
 ) -> Result<(), Error> {
     state
-        .cluster_usecase
+        .cluster_usecase()?
         .update_cluster(cluster, password)
         .await
-        .map_err(|e| Error::Internal(e.to_string()))
+        .map_err(|e| match e.downcast::<VersionConflict>() {
+            Ok(conflict) => Error::Conflict(conflict),
+            Err(e) => Error::Internal(e.to_string()),
+        })
 }
 
 #[tauri::command]
@@ -84,10 +485,57 @@ async fn create_topic(
     name: String,
     partitions: i32,
     replication: i32,
+    configs: Option<HashMap<String, String>>,
 ) -> Result<(), Error> {
     state
-        .cluster_usecase
-        .create_topic(cluster_id, name, partitions, replication)
+        .cluster_usecase()?
+        .create_topic(
+            cluster_id,
+            name,
+            partitions,
+            replication,
+            configs.unwrap_or_default(),
+        )
+        .await
+        .map_err(|e| match e.downcast::<TopicValidation>() {
+            Ok(validation) => Error::Validation(validation),
+            Err(e) => Error::Kafka(e.to_string()),
+        })
+}
+
+#[tauri::command]
+async fn validate_topic(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    name: String,
+    partitions: i32,
+    replication: i32,
+) -> Result<TopicValidation, Error> {
+    state
+        .cluster_usecase()?
+        .validate_topic(cluster_id, name, partitions, replication)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn clone_topic(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    new_name: String,
+    target_cluster_id: Option<Uuid>,
+    include_data: Option<bool>,
+) -> Result<Option<SessionInfo>, Error> {
+    state
+        .cluster_usecase()?
+        .clone_topic(
+            cluster_id,
+            topic,
+            new_name,
+            target_cluster_id,
+            include_data.unwrap_or(false),
+        )
         .await
         .map_err(|e| Error::Kafka(e.to_string()))
 }
@@ -99,133 +547,1652 @@ async fn publish_message(
     topic: String,
     key: Option<String>,
     payload: String,
-) -> Result<(), Error> {
+    headers: Option<Vec<MessageHeader>>,
+) -> Result<PublishResult, Error> {
     state
-        .cluster_usecase
-        .publish_message(cluster_id, topic, key, payload)
+        .cluster_usecase()?
+        .publish_message(cluster_id, topic, key, payload, headers.unwrap_or_default())
         .await
-        .map_err(|e| Error::Kafka(e.to_string()))
+        .map_err(|e| match e.downcast::<DeliveryError>() {
+            Ok(delivery) => Error::Delivery(delivery),
+            Err(e) => Error::Kafka(e.to_string()),
+        })
 }
 
 #[tauri::command]
-async fn delete_topic(
+async fn publish_bulk_text(
     state: State<'_, AppState>,
     cluster_id: Uuid,
     topic: String,
-) -> Result<(), Error> {
+    text: String,
+    format: BulkTextFormat,
+) -> Result<BulkPublishResult, Error> {
     state
-        .cluster_usecase
-        .delete_topic(cluster_id, topic)
+        .cluster_usecase()?
+        .publish_bulk_text(cluster_id, topic, text, format)
         .await
         .map_err(|e| Error::Kafka(e.to_string()))
 }
 
 #[tauri::command]
-async fn delete_cluster(state: State<'_, AppState>, cluster_id: Uuid) -> Result<(), Error> {
+async fn get_dlq_summary(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<DlqSummary, Error> {
     state
-        .cluster_usecase
-        .delete_cluster(cluster_id)
+        .cluster_usecase()?
+        .get_dlq_summary(cluster_id)
         .await
-        .map_err(|e| Error::Internal(e.to_string()))
+        .map_err(|e| Error::Kafka(e.to_string()))
 }
 
 #[tauri::command]
-async fn test_connection(state: State<'_, AppState>, cluster_id: Uuid) -> Result<(), Error> {
+async fn delete_topics(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    selection: TopicSelection,
+    confirmation: Option<String>,
+) -> Result<BulkTopicDeletion, Error> {
     state
-        .cluster_usecase
-        .check_connection(cluster_id)
+        .cluster_usecase()?
+        .delete_topics(cluster_id, selection, confirmation)
         .await
         .map_err(|e| Error::Kafka(e.to_string()))
 }
 
 #[tauri::command]
-async fn consume_messages(
+async fn delete_records(
     state: State<'_, AppState>,
     cluster_id: Uuid,
     topic: String,
-    max_messages: usize,
-) -> Result<Vec<KafkaMessage>, Error> {
+    partition: i32,
+    offset: i64,
+    confirmation: String,
+    force: Option<bool>,
+) -> Result<Vec<PartitionTruncation>, Error> {
     state
-        .cluster_usecase
-        .consume_messages(cluster_id, topic, max_messages)
+        .cluster_usecase()?
+        .delete_records(
+            cluster_id,
+            topic,
+            partition,
+            offset,
+            confirmation,
+            force.unwrap_or(false),
+        )
         .await
         .map_err(|e| Error::Kafka(e.to_string()))
 }
 
 #[tauri::command]
-async fn get_topic_message_count(
+async fn purge_topic(
     state: State<'_, AppState>,
     cluster_id: Uuid,
     topic: String,
-) -> Result<i64, Error> {
+    confirmation: String,
+    force: Option<bool>,
+) -> Result<Vec<PartitionTruncation>, Error> {
     state
-        .cluster_usecase
-        .get_topic_message_count(cluster_id, topic)
+        .cluster_usecase()?
+        .purge_topic(cluster_id, topic, confirmation, force.unwrap_or(false))
         .await
         .map_err(|e| Error::Kafka(e.to_string()))
 }
 
-#[cfg_attr(mobile, tauri::mobile_entry_point)]
-pub fn run() {
-    tauri::Builder::default()
-        .setup(|app| {
-            let handle = app.handle().clone();
-            tauri::async_runtime::block_on(async move {
-                let app_dir = handle
-                    .path()
-                    .app_data_dir()
-                    .expect("Failed to get app data dir");
-                println!("Database directory: {:?}", app_dir);
-                if let Err(e) = std::fs::create_dir_all(&app_dir) {
-                    eprintln!("Failed to create app data directory: {}", e);
-                }
-
-                let db_path = app_dir.join("kafkust.db");
-                let database_url = format!("sqlite://{}", db_path.to_string_lossy());
-                println!("Connecting to database at: {}", database_url);
-
-                let cluster_repo = SqliteClusterRepository::new(&database_url)
-                    .await
-                    .map_err(|e| {
-                        eprintln!("Database initialization failed: {}", e);
-                        e
-                    })
-                    .expect("Failed to init DB");
-
-                // Seed default cluster if empty
-                if let Ok(clusters) = cluster_repo.list_clusters().await {
-                    if clusters.is_empty() {
-                        println!("Seeding default Local Kafka cluster");
-                        let local_kafka = Cluster {
-                            id: Uuid::new_v4(),
-                            name: "Local Kafka".to_string(),
-                            brokers: "localhost:9092".to_string(),
-                            security: crate::domain::cluster::cluster::SecurityConfig::Plaintext,
-                        };
-                        let _ = cluster_repo.save_cluster(&local_kafka).await;
-                    }
-                }
+#[tauri::command]
+async fn delete_topic(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    force: Option<bool>,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .delete_topic(cluster_id, topic, force.unwrap_or(false))
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
 
-                let secret_repo = KeyringSecretRepository::new("kafkust");
-                let kafka_infra = KafkaInfrastructure::new();
+#[tauri::command]
+async fn delete_cluster(state: State<'_, AppState>, cluster_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .delete_cluster(cluster_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
 
-                let cluster_usecase = ClusterUsecase::new(cluster_repo, secret_repo, kafka_infra);
-                handle.manage(AppState { cluster_usecase });
+#[tauri::command]
+async fn set_cluster_tags(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    tags: Vec<String>,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .set_cluster_tags(cluster_id, tags)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn set_production_receipts(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    enabled: bool,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .set_production_receipts(cluster_id, enabled)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn export_production_receipts(state: State<'_, AppState>) -> Result<String, Error> {
+    state
+        .cluster_usecase()?
+        .export_production_receipts()
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn set_cluster_group(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group: Option<String>,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .set_cluster_group(cluster_id, group)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn set_cluster_client_id(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    client_id: Option<String>,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .set_cluster_client_id(cluster_id, client_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn reorder_clusters(state: State<'_, AppState>, cluster_ids: Vec<Uuid>) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .reorder_clusters(cluster_ids)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn save_cluster_service(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    service: AuxiliaryService,
+    password: Option<String>,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .save_cluster_service(cluster_id, service, password)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn delete_cluster_service(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    service_id: Uuid,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .delete_cluster_service(cluster_id, service_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn list_deleted_clusters(state: State<'_, AppState>) -> Result<Vec<Cluster>, Error> {
+    state
+        .cluster_usecase()?
+        .list_deleted_clusters()
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn restore_cluster(state: State<'_, AppState>, cluster_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .restore_cluster(cluster_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+/// Checks the local database and keyring; see `repair_database` for the
+/// fixes on offer.
+#[tauri::command]
+async fn check_database(state: State<'_, AppState>) -> Result<DatabaseReport, Error> {
+    state
+        .cluster_usecase()?
+        .check_database(true)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn repair_database(state: State<'_, AppState>) -> Result<DatabaseRepair, Error> {
+    state
+        .cluster_usecase()?
+        .repair_database()
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn purge_deleted_clusters(state: State<'_, AppState>) -> Result<usize, Error> {
+    state
+        .cluster_usecase()?
+        .purge_deleted_clusters()
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn warm_up_cluster(state: State<'_, AppState>, cluster_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .warm_up_cluster(cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn test_connection(state: State<'_, AppState>, cluster_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .check_connection(cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn probe_permissions(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<PermissionReport, Error> {
+    state
+        .cluster_usecase()?
+        .probe_permissions(cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_token_status(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<TokenStatus, Error> {
+    state
+        .cluster_usecase()?
+        .get_token_status(cluster_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_client_logs(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<Vec<ClientLogLine>, Error> {
+    Ok(state.cluster_usecase()?.get_client_logs(cluster_id))
+}
+
+#[tauri::command]
+async fn clear_client_logs(state: State<'_, AppState>, cluster_id: Uuid) -> Result<(), Error> {
+    state.cluster_usecase()?.clear_client_logs(cluster_id);
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_client_debug(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    contexts: Vec<String>,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .set_client_debug(cluster_id, contexts)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn inspect_broker_certificates(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<Vec<BrokerCertificate>, Error> {
+    state
+        .cluster_usecase()?
+        .inspect_broker_certificates(cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn set_certificate_pin(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    pin: Option<String>,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .set_certificate_pin(cluster_id, pin)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn list_topic_encryption(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<Vec<TopicEncryption>, Error> {
+    state
+        .cluster_usecase()?
+        .list_topic_encryption(cluster_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn set_topic_encryption(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    encryption: TopicEncryption,
+    key: Option<String>,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .set_topic_encryption(cluster_id, encryption, key)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn remove_topic_encryption(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .remove_topic_encryption(cluster_id, topic)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn list_deserializer_plugins(
+    state: State<'_, AppState>,
+) -> Result<Vec<DeserializerPlugin>, Error> {
+    Ok(state.cluster_usecase()?.list_deserializer_plugins())
+}
+
+#[tauri::command]
+async fn reload_deserializer_plugins(
+    state: State<'_, AppState>,
+) -> Result<Vec<DeserializerPlugin>, Error> {
+    Ok(state.cluster_usecase()?.reload_deserializer_plugins())
+}
+
+#[tauri::command]
+async fn list_transform_plugins(state: State<'_, AppState>) -> Result<Vec<TransformPlugin>, Error> {
+    Ok(state.cluster_usecase()?.list_transform_plugins())
+}
+
+#[tauri::command]
+async fn reload_transform_plugins(
+    state: State<'_, AppState>,
+) -> Result<Vec<TransformPlugin>, Error> {
+    Ok(state.cluster_usecase()?.reload_transform_plugins())
+}
+
+#[tauri::command]
+async fn add_partitions(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    partitions: i32,
+) -> Result<PartitionIncrease, Error> {
+    state
+        .cluster_usecase()?
+        .add_partitions(cluster_id, topic, partitions)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn describe_topic_partitions(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+) -> Result<Vec<Partition>, Error> {
+    state
+        .cluster_usecase()?
+        .describe_topic_partitions(cluster_id, topic)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_topic_config(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+) -> Result<Vec<TopicConfigEntry>, Error> {
+    state
+        .cluster_usecase()?
+        .get_topic_config(cluster_id, topic)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_topic_preferences(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+) -> Result<TopicPreferences, Error> {
+    state
+        .cluster_usecase()?
+        .get_topic_preferences(cluster_id, topic)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn set_topic_preferences(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    preferences: TopicPreferences,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .set_topic_preferences(cluster_id, topic, preferences)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn list_topic_annotations(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    tag: Option<String>,
+    favorites_only: Option<bool>,
+) -> Result<Vec<TopicAnnotation>, Error> {
+    state
+        .cluster_usecase()?
+        .list_topic_annotations(cluster_id, tag, favorites_only.unwrap_or(false))
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn set_topic_protection(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    protected: bool,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .set_topic_protection(cluster_id, topic, protected)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn list_protected_topics(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<Vec<String>, Error> {
+    state
+        .cluster_usecase()?
+        .list_protected_topics(cluster_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn set_topic_annotation(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    annotation: TopicAnnotation,
+) -> Result<TopicAnnotation, Error> {
+    state
+        .cluster_usecase()?
+        .set_topic_annotation(cluster_id, annotation)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_payload_encoding(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+) -> Result<PayloadEncoding, Error> {
+    state
+        .cluster_usecase()?
+        .get_payload_encoding(cluster_id, topic)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn set_payload_encoding(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    encoding: PayloadEncoding,
+) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .set_payload_encoding(cluster_id, topic, encoding)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_messages_around(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    partition: i32,
+    offset: i64,
+    context: usize,
+) -> Result<Vec<KafkaMessage>, Error> {
+    state
+        .cluster_usecase()?
+        .get_messages_around(cluster_id, topic, partition, offset, context)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn fetch_full_message(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    partition: i32,
+    offset: i64,
+) -> Result<KafkaMessage, Error> {
+    state
+        .cluster_usecase()?
+        .fetch_full_message(cluster_id, topic, partition, offset)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn consume_messages(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    max_messages: usize,
+    seek: Option<SeekMode>,
+    partition: Option<i32>,
+    filter: Option<MessageFilter>,
+) -> Result<ConsumeResult, Error> {
+    state
+        .cluster_usecase()?
+        .consume_messages(
+            cluster_id,
+            topic,
+            partition,
+            seek.unwrap_or_default(),
+            max_messages,
+            filter.unwrap_or_default(),
+        )
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn consume_across_clusters(
+    state: State<'_, AppState>,
+    cluster_ids: Vec<Uuid>,
+    topic: String,
+    max_messages: usize,
+) -> Result<MultiClusterConsumeResult, Error> {
+    state
+        .cluster_usecase()?
+        .consume_across_clusters(cluster_ids, topic, max_messages)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_topic_histogram(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    from_ts: i64,
+    to_ts: i64,
+    bucket_ms: i64,
+) -> Result<Vec<HistogramBucket>, Error> {
+    state
+        .cluster_usecase()?
+        .get_topic_histogram(cluster_id, topic, from_ts, to_ts, bucket_ms)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_field_breakdown(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    source: BreakdownSource,
+    sample_size: usize,
+) -> Result<FieldBreakdown, Error> {
+    state
+        .cluster_usecase()?
+        .get_field_breakdown(cluster_id, topic, source, sample_size)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn list_streams_applications(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<Vec<StreamsApplication>, Error> {
+    state
+        .cluster_usecase()?
+        .list_streams_applications(cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_state_store_snapshot(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    limit: usize,
+) -> Result<StateStoreSnapshot, Error> {
+    state
+        .cluster_usecase()?
+        .get_state_store_snapshot(cluster_id, topic, limit)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn materialize_topic_by_key(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    offset: Option<usize>,
+    limit: usize,
+    key_filter: Option<String>,
+    refresh: Option<bool>,
+) -> Result<KeyedTablePage, Error> {
+    state
+        .cluster_usecase()?
+        .materialize_topic_by_key(
+            cluster_id,
+            topic,
+            offset.unwrap_or(0),
+            limit,
+            key_filter,
+            refresh.unwrap_or(false),
+        )
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_mirroring_status(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<MirroringStatus, Error> {
+    state
+        .cluster_usecase()?
+        .get_mirroring_status(cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_quota_utilization(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<QuotaUtilization, Error> {
+    state
+        .cluster_usecase()?
+        .get_quota_utilization(cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn translate_offsets(
+    state: State<'_, AppState>,
+    source_cluster_id: Uuid,
+    dest_cluster_id: Uuid,
+    group_id: String,
+    source_alias: Option<String>,
+) -> Result<OffsetTranslation, Error> {
+    state
+        .cluster_usecase()?
+        .translate_offsets(source_cluster_id, dest_cluster_id, group_id, source_alias)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn check_environment_drift(
+    state: State<'_, AppState>,
+    topic: String,
+    cluster_ids: Vec<Uuid>,
+) -> Result<EnvironmentDrift, Error> {
+    state
+        .cluster_usecase()?
+        .check_environment_drift(topic, cluster_ids)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn diff_topic_across_clusters(
+    state: State<'_, AppState>,
+    topic: String,
+    left_cluster_id: Uuid,
+    right_cluster_id: Uuid,
+) -> Result<TopicConfigDiff, Error> {
+    state
+        .cluster_usecase()?
+        .diff_topic_across_clusters(topic, left_cluster_id, right_cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn export_acls(state: State<'_, AppState>, cluster_id: Uuid) -> Result<String, Error> {
+    state
+        .cluster_usecase()?
+        .export_acls(cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn provision_application(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    app_name: String,
+    topics: Vec<String>,
+    role: ApplicationRole,
+) -> Result<ProvisionedApplication, Error> {
+    state
+        .cluster_usecase()?
+        .provision_application(cluster_id, app_name, topics, role)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn import_acls(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    yaml: String,
+    dry_run: bool,
+) -> Result<AclImportPlan, Error> {
+    state
+        .cluster_usecase()?
+        .import_acls(cluster_id, yaml, dry_run)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn apply_topics_from_file(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    path: String,
+    dry_run: bool,
+) -> Result<TopicSpecPlan, Error> {
+    state
+        .cluster_usecase()?
+        .apply_topics_from_file(cluster_id, path, dry_run)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_key_timeline(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    key: String,
+    limit: usize,
+) -> Result<KeyTimeline, Error> {
+    state
+        .cluster_usecase()?
+        .get_key_timeline(cluster_id, topic, key, limit)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_topic_message_count(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+) -> Result<i64, Error> {
+    state
+        .cluster_usecase()?
+        .get_topic_message_count(cluster_id, topic)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn create_test_consumer(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    group_id: String,
+    from: AutoOffsetReset,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .create_test_consumer(cluster_id, topic, group_id, from)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn pause_test_consumer(
+    state: State<'_, AppState>,
+    session_id: Uuid,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .set_test_consumer_state(session_id, SessionState::Paused)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn resume_test_consumer(
+    state: State<'_, AppState>,
+    session_id: Uuid,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .set_test_consumer_state(session_id, SessionState::Running)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn stop_test_consumer(state: State<'_, AppState>, session_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .stop_test_consumer(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn watch_key(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    key: String,
+    buffer: Option<TailBufferLimits>,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .watch_key(cluster_id, topic, key, buffer.unwrap_or_default())
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn start_tail(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    buffer: Option<TailBufferLimits>,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .start_tail(cluster_id, topic, buffer.unwrap_or_default())
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn stop_tail(state: State<'_, AppState>, session_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .stop_tail(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn search_by_key(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    key: String,
+    hashed_partition_only: Option<bool>,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .search_by_key(
+            cluster_id,
+            topic,
+            key,
+            hashed_partition_only.unwrap_or(false),
+        )
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn cancel_key_search(state: State<'_, AppState>, session_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .cancel_key_search(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn stop_key_watch(state: State<'_, AppState>, session_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .stop_key_watch(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn start_replay(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    source: String,
+    destination: String,
+    rekey: RekeyStrategy,
+    transforms: Option<Vec<String>>,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .start_replay(
+            cluster_id,
+            source,
+            destination,
+            rekey,
+            transforms.unwrap_or_default(),
+        )
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn stop_replay(state: State<'_, AppState>, session_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .stop_replay(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn start_topic_export(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    directory: String,
+    sharding: ExportSharding,
+    shards: Option<Vec<String>>,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .start_topic_export(cluster_id, topic, directory, sharding, shards)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn stop_topic_export(state: State<'_, AppState>, session_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .stop_topic_export(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn start_eos_verification(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    message_count: u64,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .start_eos_verification(cluster_id, topic, message_count)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn stop_eos_verification(state: State<'_, AppState>, session_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .stop_eos_verification(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn start_lag_sampler(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    groups: Vec<String>,
+    interval_secs: u64,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .start_lag_sampler(cluster_id, groups, interval_secs)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn stop_lag_sampler(state: State<'_, AppState>, session_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .stop_lag_sampler(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn subscribe_refresh(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    target: RefreshTarget,
+    interval_secs: u64,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .subscribe_refresh(cluster_id, target, interval_secs)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn unsubscribe_refresh(state: State<'_, AppState>, session_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .unsubscribe_refresh(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn start_watermark_watch(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    interval_ms: Option<u64>,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .start_watermark_watch(cluster_id, topic, interval_ms)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn stop_watermark_watch(state: State<'_, AppState>, session_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .stop_watermark_watch(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn save_lag_alert_rule(state: State<'_, AppState>, rule: LagAlertRule) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .save_lag_alert_rule(rule)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn list_lag_alert_rules(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<Vec<LagAlertRule>, Error> {
+    state
+        .cluster_usecase()?
+        .list_lag_alert_rules(cluster_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn delete_lag_alert_rule(state: State<'_, AppState>, rule_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .delete_lag_alert_rule(rule_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn start_lag_alerts(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .start_lag_alerts(cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn stop_lag_alerts(state: State<'_, AppState>, session_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .stop_lag_alerts(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_lag_history(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+    from: i64,
+    to: i64,
+) -> Result<Vec<LagSample>, Error> {
+    state
+        .cluster_usecase()?
+        .get_lag_history(cluster_id, group_id, from, to)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn start_health_monitor(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .start_health_monitor(cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn stop_health_monitor(state: State<'_, AppState>, session_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .stop_health_monitor(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn prepare_broker_maintenance(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    broker_id: i32,
+) -> Result<BrokerMaintenanceReport, Error> {
+    state
+        .cluster_usecase()?
+        .prepare_broker_maintenance(cluster_id, broker_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn cluster_health(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<Vec<TopicHealth>, Error> {
+    state
+        .cluster_usecase()?
+        .cluster_health(cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn analyze_min_isr_risk(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+) -> Result<Vec<MinIsrRisk>, Error> {
+    state
+        .cluster_usecase()?
+        .analyze_min_isr_risk(cluster_id)
+        .await
+        .map_err(|e| Error::Kafka(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_isr_events(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    topic: String,
+    limit: usize,
+) -> Result<Vec<IsrEvent>, Error> {
+    state
+        .cluster_usecase()?
+        .get_isr_events(cluster_id, topic, limit)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_group_events(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    group_id: String,
+    from: i64,
+    to: i64,
+) -> Result<Vec<GroupEvent>, Error> {
+    state
+        .cluster_usecase()?
+        .get_group_events(cluster_id, group_id, from, to)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn get_topic_events(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    limit: usize,
+) -> Result<Vec<TopicEvent>, Error> {
+    state
+        .cluster_usecase()?
+        .get_topic_events(cluster_id, limit)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn list_sessions(state: State<'_, AppState>) -> Result<Vec<SessionInfo>, Error> {
+    Ok(state.cluster_usecase()?.list_sessions())
+}
+
+#[tauri::command]
+async fn pause_session(state: State<'_, AppState>, session_id: Uuid) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .pause_session(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn resume_session(
+    state: State<'_, AppState>,
+    session_id: Uuid,
+) -> Result<SessionInfo, Error> {
+    state
+        .cluster_usecase()?
+        .resume_session(session_id)
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+async fn init_cluster_usecase(handle: &AppHandle) -> anyhow::Result<ClusterUsecase> {
+    let app_dir = handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| anyhow::anyhow!("Failed to get app data dir: {}", e))?;
+    println!("Database directory: {:?}", app_dir);
+    std::fs::create_dir_all(&app_dir)
+        .map_err(|e| anyhow::anyhow!("Failed to create app data directory: {}", e))?;
+
+    let db_path = app_dir.join("kafkust.db");
+    let database_url = format!("sqlite://{}", db_path.to_string_lossy());
+    println!("Connecting to database at: {}", database_url);
+
+    let cluster_repo = SqliteClusterRepository::new(&database_url)
+        .await
+        .map_err(|e| anyhow::anyhow!("Database initialization failed: {}", e))?;
+
+    // Seed default cluster if empty
+    if let Ok(clusters) = cluster_repo.list_clusters().await {
+        if clusters.is_empty() {
+            println!("Seeding default Local Kafka cluster");
+            let local_kafka = Cluster {
+                id: Uuid::new_v4(),
+                name: "Local Kafka".to_string(),
+                brokers: "localhost:9092".to_string(),
+                security: crate::domain::cluster::cluster::SecurityConfig::Plaintext,
+                version: None,
+                updated_at: None,
+                deleted_at: None,
+                tags: Vec::new(),
+                group: None,
+                sort_order: None,
+                services: Vec::new(),
+                client_id: None,
+                certificate_pin: None,
+                production_receipts: false,
+                metadata_cache_ttl_ms: None,
+                client_debug: Vec::new(),
+                usage: Default::default(),
+            };
+            let _ = cluster_repo.save_cluster(&local_kafka).await;
+        }
+    }
+
+    let secret_repo = KeyringSecretRepository::new("kafkust");
+    let kafka_infra = KafkaInfrastructure::new();
+    let deserializers = DeserializerRegistry::new(app_dir.join("plugins").join("deserializers"));
+    let transforms = TransformRegistry::new(app_dir.join("plugins").join("transforms"))
+        .map_err(|e| anyhow::anyhow!("Failed to initialize transform runtime: {}", e))?;
+
+    let events = Arc::new(TauriEventSink {
+        handle: handle.clone(),
+    });
+
+    let cluster_usecase = ClusterUsecase::new(
+        cluster_repo,
+        secret_repo,
+        kafka_infra,
+        deserializers,
+        transforms,
+        events,
+    );
+    match cluster_usecase.purge_deleted_clusters().await {
+        Ok(0) => {}
+        Ok(n) => println!("Purged {} deleted clusters past retention", n),
+        Err(e) => eprintln!("Failed to purge deleted clusters: {}", e),
+    }
+    if let Err(e) = cluster_usecase.prune_lag_samples().await {
+        eprintln!("Failed to prune lag samples: {}", e);
+    }
+    // The keyring is left out here so startup never prompts for it.
+    match cluster_usecase.check_database(false).await {
+        Ok(report) if !report.is_healthy() => {
+            for issue in &report.issues {
+                eprintln!("Database check: {}", issue.message);
+            }
+            if let Err(e) = handle.emit("database-issues", &report) {
+                eprintln!("Failed to emit database-issues event: {}", e);
+            }
+        }
+        Ok(_) => {}
+        Err(e) => eprintln!("Failed to check database: {}", e),
+    }
+    Ok(cluster_usecase)
+}
+
+#[tauri::command]
+async fn save_macro(state: State<'_, AppState>, command_macro: CommandMacro) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .save_macro(command_macro)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn list_macros(state: State<'_, AppState>) -> Result<Vec<CommandMacro>, Error> {
+    state
+        .cluster_usecase()?
+        .list_macros()
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn delete_macro(state: State<'_, AppState>, macro_id: Uuid) -> Result<(), Error> {
+    state
+        .cluster_usecase()?
+        .delete_macro(macro_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn export_workspace(state: State<'_, AppState>, cluster_id: Uuid) -> Result<String, Error> {
+    state
+        .cluster_usecase()?
+        .export_workspace(cluster_id)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn import_workspace(
+    state: State<'_, AppState>,
+    cluster_id: Uuid,
+    json: String,
+) -> Result<WorkspaceImportResult, Error> {
+    state
+        .cluster_usecase()?
+        .import_workspace(cluster_id, json)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+async fn run_macro(
+    state: State<'_, AppState>,
+    macro_id: Uuid,
+    cluster_id: Uuid,
+    params: HashMap<String, String>,
+) -> Result<MacroRunResult, Error> {
+    state
+        .cluster_usecase()?
+        .run_macro(macro_id, cluster_id, params)
+        .await
+        .map_err(|e| Error::Internal(e.to_string()))
+}
+
+#[tauri::command]
+fn get_app_init_status(state: State<'_, AppState>) -> AppInitStatus {
+    state.init_status.lock().unwrap().clone()
+}
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    tauri::Builder::default()
+        .plugin(tauri_plugin_notification::init())
+        .setup(|app| {
+            app.manage(AppState::new());
+
+            let handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let result = tokio::time::timeout(INIT_TIMEOUT, init_cluster_usecase(&handle))
+                    .await
+                    .unwrap_or_else(|_| {
+                        Err(anyhow::anyhow!(
+                            "Timed out after {}s",
+                            INIT_TIMEOUT.as_secs()
+                        ))
+                    });
+
+                let state = handle.state::<AppState>();
+                let status = match result {
+                    Ok(cluster_usecase) => {
+                        let _ = state.cluster_usecase.set(cluster_usecase);
+                        AppInitStatus::Ready
+                    }
+                    Err(e) => {
+                        eprintln!("Initialization failed: {}", e);
+                        AppInitStatus::Failed(e.to_string())
+                    }
+                };
+                *state.init_status.lock().unwrap() = status.clone();
+
+                let event = match status {
+                    AppInitStatus::Ready => "app-ready",
+                    _ => "app-init-failed",
+                };
+                if let Err(e) = handle.emit(event, status) {
+                    eprintln!("Failed to emit {} event: {}", event, e);
+                }
             });
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
+            get_app_init_status,
             list_clusters,
             list_topics,
+            refresh_topics,
+            set_metadata_cache_ttl,
+            list_consumer_groups,
+            describe_consumer_group,
+            get_consumer_group_lag,
+            get_group_partition_matrix,
+            find_topic_consumers,
+            topic_lag_overview,
+            delete_consumer_group,
+            delete_group_topic_offsets,
+            reset_group_offsets,
+            preview_group_offset_reset,
+            export_offset_reset_plan,
+            apply_offset_reset_plan,
+            list_offset_snapshots,
+            rollback_offset_reset,
+            export_group_offsets,
+            import_group_offsets,
             add_cluster,
+            add_event_hubs_cluster,
+            get_unsupported_features,
             update_cluster,
             delete_cluster,
+            set_cluster_tags,
+            set_production_receipts,
+            export_production_receipts,
+            set_cluster_group,
+            set_cluster_client_id,
+            reorder_clusters,
+            save_cluster_service,
+            delete_cluster_service,
+            list_deleted_clusters,
+            restore_cluster,
+            purge_deleted_clusters,
+            check_database,
+            repair_database,
             test_connection,
+            probe_permissions,
+            inspect_broker_certificates,
+            set_certificate_pin,
+            get_token_status,
+            get_client_logs,
+            clear_client_logs,
+            set_client_debug,
+            warm_up_cluster,
             create_topic,
+            validate_topic,
+            clone_topic,
             delete_topic,
+            delete_topics,
+            delete_records,
+            purge_topic,
+            get_dlq_summary,
             publish_message,
+            publish_bulk_text,
             consume_messages,
-            get_topic_message_count
+            get_messages_around,
+            fetch_full_message,
+            list_topic_encryption,
+            set_topic_encryption,
+            remove_topic_encryption,
+            get_payload_encoding,
+            set_payload_encoding,
+            add_partitions,
+            list_deserializer_plugins,
+            reload_deserializer_plugins,
+            list_transform_plugins,
+            reload_transform_plugins,
+            describe_topic_partitions,
+            get_topic_config,
+            get_topic_preferences,
+            set_topic_preferences,
+            list_topic_annotations,
+            set_topic_annotation,
+            set_topic_protection,
+            list_protected_topics,
+            consume_across_clusters,
+            get_topic_message_count,
+            get_topic_histogram,
+            get_field_breakdown,
+            list_streams_applications,
+            get_state_store_snapshot,
+            materialize_topic_by_key,
+            get_mirroring_status,
+            get_quota_utilization,
+            translate_offsets,
+            check_environment_drift,
+            diff_topic_across_clusters,
+            export_acls,
+            import_acls,
+            apply_topics_from_file,
+            provision_application,
+            get_key_timeline,
+            create_test_consumer,
+            pause_test_consumer,
+            resume_test_consumer,
+            stop_test_consumer,
+            watch_key,
+            stop_key_watch,
+            start_tail,
+            stop_tail,
+            search_by_key,
+            cancel_key_search,
+            start_replay,
+            stop_replay,
+            start_topic_export,
+            stop_topic_export,
+            start_eos_verification,
+            stop_eos_verification,
+            start_lag_sampler,
+            stop_lag_sampler,
+            subscribe_refresh,
+            unsubscribe_refresh,
+            start_watermark_watch,
+            stop_watermark_watch,
+            save_lag_alert_rule,
+            list_lag_alert_rules,
+            delete_lag_alert_rule,
+            start_lag_alerts,
+            stop_lag_alerts,
+            save_macro,
+            list_macros,
+            delete_macro,
+            export_workspace,
+            import_workspace,
+            run_macro,
+            get_lag_history,
+            start_health_monitor,
+            stop_health_monitor,
+            get_isr_events,
+            get_topic_events,
+            get_group_events,
+            prepare_broker_maintenance,
+            analyze_min_isr_risk,
+            cluster_health,
+            list_sessions,
+            pause_session,
+            resume_session
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
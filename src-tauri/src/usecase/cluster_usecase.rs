@@ -1,15 +1,120 @@
-use crate::domain::cluster::cluster::Cluster;
-use crate::domain::topic::{KafkaMessage, Topic};
+use crate::domain::acl::{
+    application_acls, AclBinding, AclDocument, AclImportPlan, ApplicationRole, PermissionReport,
+    ProvisionedApplication,
+};
+use crate::domain::alert::LagAlertRule;
+use crate::domain::cluster::cluster::{
+    normalize_fingerprint, parse_event_hubs_connection_string, AuxiliaryService, BrokerCertificate,
+    ClientLogLine, Cluster, ClusterFeature, SaslMechanism, SecurityConfig, TokenStatus,
+    EVENT_HUBS_USERNAME,
+};
+use crate::domain::command_macro::{CommandMacro, MacroRunResult, MacroStep, MacroStepFailure};
+use crate::domain::consumer_group::{
+    filter_groups, group_partition_matrix, CommittedOffset, ConsumerGroup, ConsumerGroupDetail,
+    ConsumerGroupLag, GroupEvent, GroupLagTotal, GroupOffsetDocument, GroupPartitionMatrix,
+    LagSample, OffsetResetPlan, OffsetResetTarget, OffsetSnapshot, PartitionOffsetReset,
+    TopicConsumer, TopicLagOverview,
+};
+use crate::domain::dlq::{dlq_naming, DlqSummary, DlqTopic};
+use crate::domain::event::EventSink;
+use crate::domain::export::ExportSharding;
+use crate::domain::health::{
+    analyze_min_isr_risk, plan_broker_maintenance, scan_partition_health, BrokerMaintenanceReport,
+    IsrEvent, MinIsrRisk, TopicEvent, TopicHealth,
+};
+use crate::domain::integrity::{
+    DatabaseRepair, DatabaseReport, IntegrityIssue, IntegrityIssueKind,
+};
+use crate::domain::mirroring::{
+    classify_mm2_topic, MirroringStatus, Mm2TopicKind, OffsetTranslation, TranslatedOffset,
+    TranslationMethod,
+};
+use crate::domain::plugin::{DeserializerPlugin, TransformPlugin};
+use crate::domain::quota::QuotaUtilization;
+use crate::domain::refresh::RefreshTarget;
+use crate::domain::replay::RekeyStrategy;
+use crate::domain::session::{
+    AutoOffsetReset, SessionInfo, SessionKind, SessionState, TailBufferLimits,
+};
+use crate::domain::streams::{
+    classify_internal_topic, KeyedTablePage, StateStoreEntry, StateStoreSnapshot,
+    StreamsApplication, StreamsInternalTopic,
+};
+use crate::domain::topic::{
+    deletion_token, diff_config_entries, parse_bulk_text, validate_new_topic, BreakdownSource,
+    BulkPublishResult, BulkTextFormat, BulkTopicDeletion, ClusterDivergence, ClusterMessage,
+    ConfigEntryDiff, ConfigEntrySource, ConsumeResult, DriftCluster, DriftRow, EnvironmentDrift,
+    FieldBreakdown, HistogramBucket, KafkaMessage, KeyTimeline, MessageDeserializer, MessageFilter,
+    MessageHeader, MultiClusterConsumeResult, Partition, PartitionIncrease, PartitionTruncation,
+    PayloadEncoding, PublishResult, SeekMode, Topic, TopicAnnotation, TopicConfigDiff,
+    TopicConfigEntry, TopicDeletion, TopicEncryption, TopicPreferences, TopicSelection,
+    TopicSpecAction, TopicSpecChange, TopicSpecDocument, TopicSpecPlan, TopicValidation,
+    DEFAULT_MAX_PAYLOAD_BYTES,
+};
+use crate::domain::workspace::{TopicView, WorkspaceBundle, WorkspaceImportResult};
+use crate::infrastructure::client_logs::DEBUG_CONTEXTS;
+use crate::infrastructure::deserializer_plugins::DeserializerRegistry;
 use crate::infrastructure::kafka::KafkaInfrastructure;
+use crate::infrastructure::payload_cipher::PayloadCipher;
+use crate::infrastructure::payload_decoder::PayloadDecoder;
 use crate::infrastructure::persistence::keyring_secret_repository::KeyringSecretRepository;
 use crate::infrastructure::persistence::sqlite_cluster_repository::SqliteClusterRepository;
+use crate::infrastructure::production_receipts::{payload_digest, ReceiptLog, ReceiptSigner};
+use crate::infrastructure::session_manager::{now_millis, SessionManager};
+use crate::infrastructure::wasm_transforms::TransformRegistry;
 use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
 use uuid::Uuid;
 
+/// Topic configs compared by the environment drift check, chosen for their
+/// effect on durability, retention and client compatibility.
+const DRIFT_CONFIG_KEYS: &[&str] = &[
+    "cleanup.policy",
+    "retention.ms",
+    "retention.bytes",
+    "min.insync.replicas",
+    "max.message.bytes",
+    "compression.type",
+    "message.timestamp.type",
+    "segment.bytes",
+];
+
+/// How long a deleted cluster can be restored before it is purged.
+const DELETED_CLUSTER_RETENTION_MS: i64 = 30 * 24 * 60 * 60 * 1000;
+
+/// How many of a dead-letter topic's newest records the DLQ summary shows.
+const DLQ_RECENT_FAILURES: usize = 5;
+
+/// Keyring account holding the production receipt signing key.
+const RECEIPT_SIGNING_KEY: &str = "production-receipts/signing-key";
+
+/// How long recorded consumer lag samples are kept.
+const LAG_SAMPLE_RETENTION_MS: i64 = 7 * 24 * 60 * 60 * 1000;
+
+/// Metadata checks, half a second apart, for a freshly cloned topic to
+/// appear before copying records into it.
+const CLONE_METADATA_ATTEMPTS: usize = 10;
+
+/// Watermark watches poll no faster than this, and once a second unless
+/// asked otherwise.
+const MIN_WATERMARK_INTERVAL_MS: u64 = 250;
+const DEFAULT_WATERMARK_INTERVAL_MS: u64 = 1_000;
+
 pub struct ClusterUsecase {
     cluster_repo: SqliteClusterRepository,
     secret_repo: KeyringSecretRepository,
     kafka_infra: KafkaInfrastructure,
+    deserializers: DeserializerRegistry,
+    transforms: TransformRegistry,
+    sessions: SessionManager,
+    events: Arc<dyn EventSink>,
+    /// Held while appending receipts so each chains to the one before it.
+    receipt_chain: Arc<tokio::sync::Mutex<()>>,
 }
 
 impl ClusterUsecase {
@@ -17,12 +122,111 @@ impl ClusterUsecase {
         cluster_repo: SqliteClusterRepository,
         secret_repo: KeyringSecretRepository,
         kafka_infra: KafkaInfrastructure,
+        deserializers: DeserializerRegistry,
+        transforms: TransformRegistry,
+        events: Arc<dyn EventSink>,
     ) -> Self {
         Self {
             cluster_repo,
             secret_repo,
             kafka_infra,
+            deserializers,
+            transforms,
+            sessions: SessionManager::new(),
+            events,
+            receipt_chain: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    async fn cluster_with_password(&self, id: Uuid) -> Result<(Cluster, Option<String>)> {
+        let clusters = self.cluster_repo.list_clusters().await?;
+        let cluster = clusters
+            .into_iter()
+            .find(|c| c.id == id)
+            .ok_or_else(|| anyhow::anyhow!("Cluster not found"))?;
+
+        if let Err(e) = self.cluster_repo.record_cluster_use(&cluster.id).await {
+            eprintln!("Failed to record use of cluster {}: {}", cluster.id, e);
+        }
+
+        let password = self.secret_repo.get_password(&cluster.id.to_string()).ok();
+        self.kafka_infra
+            .refresh_oauth_token(&cluster, password.clone())
+            .await?;
+        Ok((cluster, password))
+    }
+
+    /// Runs a metadata fetch and keeps its result as the cluster's snapshot
+    /// of `kind`/`name`. When the fetch fails and a snapshot exists, the
+    /// snapshot is returned with the time it was taken instead, so names
+    /// and settings stay available during broker outages.
+    async fn with_metadata_snapshot<T, F>(
+        &self,
+        id: Uuid,
+        kind: &str,
+        name: &str,
+        fetch: F,
+    ) -> Result<(T, Option<i64>)>
+    where
+        T: Serialize + DeserializeOwned,
+        F: Future<Output = Result<T>>,
+    {
+        let error = match fetch.await {
+            Ok(value) => {
+                let saved = match serde_json::to_string(&value) {
+                    Ok(payload) => {
+                        self.cluster_repo
+                            .save_metadata_snapshot(&id, kind, name, &payload, now_millis())
+                            .await
+                    }
+                    Err(e) => Err(e.into()),
+                };
+                if let Err(e) = saved {
+                    eprintln!("Failed to save {} snapshot of cluster {}: {}", kind, id, e);
+                }
+                return Ok((value, None));
+            }
+            Err(e) => e,
+        };
+
+        match self
+            .cluster_repo
+            .get_metadata_snapshot(&id, kind, name)
+            .await
+        {
+            Ok(Some((payload, fetched_at))) => {
+                eprintln!(
+                    "Serving cached {} of cluster {} from {}: {}",
+                    kind, id, fetched_at, error
+                );
+                Ok((serde_json::from_str(&payload)?, Some(fetched_at)))
+            }
+            _ => Err(error),
+        }
+    }
+
+    /// Fails when the group has members, which would overwrite offsets
+    /// committed on their behalf at their next commit.
+    async fn ensure_group_idle(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        group_id: &str,
+        action: &str,
+    ) -> Result<()> {
+        let group = self
+            .kafka_infra
+            .describe_consumer_group(cluster, password, group_id)
+            .await?;
+        if !group.members.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Consumer group {} has {} active member(s); stop them before {}",
+                group_id,
+                group.members.len(),
+                action
+            ));
         }
+        Ok(())
     }
 
     pub async fn add_cluster(&self, cluster: Cluster, password: Option<String>) -> Result<()> {
@@ -34,133 +238,3731 @@ impl ClusterUsecase {
         Ok(())
     }
 
-    pub async fn list_clusters(&self) -> Result<Vec<Cluster>> {
-        self.cluster_repo.list_clusters().await
+    /// Saves an Azure Event Hubs namespace as a cluster, deriving brokers and
+    /// SASL settings from its connection string. The connection string is
+    /// the SASL password and goes to the keyring.
+    pub async fn add_event_hubs_cluster(
+        &self,
+        name: Option<String>,
+        connection_string: String,
+    ) -> Result<Cluster> {
+        let connection = parse_event_hubs_connection_string(&connection_string)
+            .map_err(|e| anyhow::anyhow!(e))?;
+        let name = name
+            .map(|n| n.trim().to_string())
+            .filter(|n| !n.is_empty())
+            .unwrap_or_else(|| match &connection.entity_path {
+                Some(entity) => format!("{}/{}", connection.namespace, entity),
+                None => connection.namespace.clone(),
+            });
+
+        let cluster = Cluster {
+            id: Uuid::new_v4(),
+            name,
+            brokers: connection.bootstrap,
+            security: SecurityConfig::SaslSsl {
+                mechanism: SaslMechanism::Plain,
+                username: EVENT_HUBS_USERNAME.to_string(),
+                ca_location: None,
+                token_endpoint: None,
+                oauth_scope: None,
+            },
+            version: None,
+            updated_at: None,
+            deleted_at: None,
+            tags: Vec::new(),
+            group: None,
+            sort_order: None,
+            services: Vec::new(),
+            client_id: None,
+            certificate_pin: None,
+            production_receipts: false,
+            metadata_cache_ttl_ms: None,
+            client_debug: Vec::new(),
+            usage: Default::default(),
+        };
+        self.add_cluster(cluster.clone(), Some(connection_string.trim().to_string()))
+            .await?;
+        Ok(cluster)
     }
 
-    pub async fn list_topics(&self, id: Uuid) -> Result<Vec<Topic>> {
-        let clusters = self.cluster_repo.list_clusters().await?;
-        let cluster = clusters
+    /// Kafka APIs the cluster's endpoint lacks, so the UI can hide the
+    /// operations needing them.
+    pub async fn get_unsupported_features(&self, id: Uuid) -> Result<Vec<ClusterFeature>> {
+        let cluster = self
+            .cluster_repo
+            .list_clusters()
+            .await?
             .into_iter()
             .find(|c| c.id == id)
             .ok_or_else(|| anyhow::anyhow!("Cluster not found"))?;
+        Ok(cluster.unsupported_features())
+    }
 
-        let password = self.secret_repo.get_password(&cluster.id.to_string()).ok();
-
-        self.kafka_infra.list_topics(&cluster, password).await
+    pub async fn list_clusters(&self) -> Result<Vec<Cluster>> {
+        self.cluster_repo.list_clusters().await
     }
 
-    pub async fn create_topic(
+    /// Topics of the cluster; with `include_activity`, also their message
+    /// count estimate and last activity so empty and stale topics can be
+    /// badged.
+    pub async fn list_topics(
         &self,
         id: Uuid,
-        name: String,
-        partitions: i32,
-        replication: i32,
-    ) -> Result<()> {
-        let clusters = self.cluster_repo.list_clusters().await?;
-        let cluster = clusters
+        include_activity: bool,
+        include_internal: bool,
+    ) -> Result<Vec<Topic>> {
+        let (mut topics, cached_at) = self
+            .with_metadata_snapshot(id, "topics", "", async {
+                let (cluster, password) = self.cluster_with_password(id).await?;
+                if include_activity {
+                    self.kafka_infra
+                        .list_topics_with_activity(&cluster, password)
+                        .await
+                } else {
+                    self.kafka_infra.list_topics(&cluster, password).await
+                }
+            })
+            .await?;
+        for topic in &mut topics {
+            topic.cached_at = cached_at;
+        }
+        if !include_internal {
+            topics.retain(|t| !t.is_internal);
+        }
+
+        let mut annotations: HashMap<String, TopicAnnotation> = self
+            .cluster_repo
+            .list_topic_annotations(&id)
+            .await?
             .into_iter()
-            .find(|c| c.id == id)
-            .ok_or_else(|| anyhow::anyhow!("Cluster not found"))?;
+            .map(|a| (a.topic.clone(), a))
+            .collect();
+        let protected: HashSet<String> = self
+            .cluster_repo
+            .list_protected_topics(&id)
+            .await?
+            .into_iter()
+            .collect();
+        for topic in &mut topics {
+            topic.annotation = annotations.remove(&topic.name);
+            topic.protected = protected.contains(&topic.name);
+        }
+        // Favorites first, otherwise in the order the brokers listed them.
+        topics.sort_by_key(|t| !t.annotation.as_ref().is_some_and(|a| a.favorite));
+        Ok(topics)
+    }
 
-        let password = self.secret_repo.get_password(&cluster.id.to_string()).ok();
+    /// Forgets the cached topic metadata of a cluster, so the next listing
+    /// reflects changes made outside kafkust right away.
+    pub fn refresh_topics(&self, id: Uuid) {
+        self.kafka_infra.invalidate_topic_cache(id);
+    }
+
+    /// Sets how long topic metadata of a cluster is cached, or restores the
+    /// default with `None`.
+    pub async fn set_metadata_cache_ttl(&self, id: Uuid, ttl_ms: Option<u64>) -> Result<()> {
+        self.cluster_repo
+            .set_metadata_cache_ttl(&id, ttl_ms)
+            .await?;
+        self.kafka_infra.invalidate_topic_cache(id);
+        Ok(())
+    }
+
+    /// Groups in one of `states` (all when empty) whose id contains `search`.
+    pub async fn list_consumer_groups(
+        &self,
+        id: Uuid,
+        states: Vec<String>,
+        search: Option<String>,
+    ) -> Result<Vec<ConsumerGroup>> {
+        let (mut groups, cached_at) = self
+            .with_metadata_snapshot(id, "consumer_groups", "", async {
+                let (cluster, password) = self.cluster_with_password(id).await?;
+                self.kafka_infra
+                    .list_consumer_groups(&cluster, password)
+                    .await
+            })
+            .await?;
+        for group in &mut groups {
+            group.cached_at = cached_at;
+        }
+        Ok(filter_groups(groups, &states, search.as_deref()))
+    }
+
+    pub async fn describe_consumer_group(
+        &self,
+        id: Uuid,
+        group_id: String,
+    ) -> Result<ConsumerGroupDetail> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
 
         self.kafka_infra
-            .create_topic(&cluster, password, name, partitions, replication)
+            .describe_consumer_group(&cluster, password, &group_id)
             .await
     }
 
-    pub async fn publish_message(
+    /// Lag of every partition the group has committed offsets for.
+    pub async fn get_consumer_group_lag(
+        &self,
+        id: Uuid,
+        group_id: String,
+    ) -> Result<ConsumerGroupLag> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.kafka_infra
+            .get_consumer_group_lag(&cluster, password, &group_id)
+            .await
+    }
+
+    /// Committed offset, end offset, lag and assigned member of every
+    /// partition the group reads, shaped for a heatmap.
+    pub async fn get_group_partition_matrix(
+        &self,
+        id: Uuid,
+        group_id: String,
+    ) -> Result<GroupPartitionMatrix> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let detail = self
+            .kafka_infra
+            .describe_consumer_group(&cluster, password.clone(), &group_id)
+            .await?;
+        let lag = self
+            .kafka_infra
+            .get_consumer_group_lag(&cluster, password.clone(), &group_id)
+            .await?;
+
+        let committed: HashSet<(&str, i32)> = lag
+            .partitions
+            .iter()
+            .map(|p| (p.topic.as_str(), p.partition))
+            .collect();
+        let uncommitted: Vec<(String, i32)> = detail
+            .members
+            .iter()
+            .flat_map(|m| &m.assignments)
+            .flat_map(|a| a.partitions.iter().map(move |p| (a.topic.clone(), *p)))
+            .filter(|(topic, partition)| !committed.contains(&(topic.as_str(), *partition)))
+            .collect();
+        let end_offsets = if uncommitted.is_empty() {
+            HashMap::new()
+        } else {
+            self.kafka_infra
+                .get_end_offsets(&cluster, password, uncommitted)
+                .await?
+        };
+
+        Ok(group_partition_matrix(&detail, &lag, &end_offsets))
+    }
+
+    /// Groups reading `topic`, to check before changing or deleting it.
+    pub async fn find_topic_consumers(
         &self,
         id: Uuid,
         topic: String,
-        key: Option<String>,
-        payload: String,
-    ) -> Result<()> {
-        let clusters = self.cluster_repo.list_clusters().await?;
-        let cluster = clusters
+    ) -> Result<Vec<TopicConsumer>> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.kafka_infra
+            .find_topic_consumers(&cluster, password, &topic)
+            .await
+    }
+
+    /// Lag on `topic` per consuming group, largest first.
+    pub async fn topic_lag_overview(&self, id: Uuid, topic: String) -> Result<TopicLagOverview> {
+        let consumers = self.find_topic_consumers(id, topic.clone()).await?;
+
+        let mut groups: Vec<GroupLagTotal> = consumers
             .into_iter()
-            .find(|c| c.id == id)
-            .ok_or_else(|| anyhow::anyhow!("Cluster not found"))?;
+            .map(|c| GroupLagTotal {
+                max_partition_lag: c.partitions.iter().map(|p| p.lag).max().unwrap_or(0),
+                group_id: c.group_id,
+                state: c.state,
+                total_lag: c.total_lag,
+            })
+            .collect();
+        groups.sort_by(|a, b| {
+            b.total_lag
+                .cmp(&a.total_lag)
+                .then_with(|| a.group_id.cmp(&b.group_id))
+        });
 
-        let password = self.secret_repo.get_password(&cluster.id.to_string()).ok();
+        Ok(TopicLagOverview {
+            topic,
+            total_lag: groups.iter().map(|g| g.total_lag).sum(),
+            groups,
+        })
+    }
+
+    pub async fn delete_consumer_group(&self, id: Uuid, group_id: String) -> Result<()> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
 
         self.kafka_infra
-            .publish_message(&cluster, password, &topic, key, payload)
+            .delete_consumer_group(&cluster, password, &group_id)
             .await
     }
 
-    pub async fn delete_topic(&self, id: Uuid, name: String) -> Result<()> {
-        let clusters = self.cluster_repo.list_clusters().await?;
-        let cluster = clusters
-            .into_iter()
-            .find(|c| c.id == id)
-            .ok_or_else(|| anyhow::anyhow!("Cluster not found"))?;
+    pub async fn delete_group_topic_offsets(
+        &self,
+        id: Uuid,
+        group_id: String,
+        topic: String,
+    ) -> Result<()> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
 
-        let password = self.secret_repo.get_password(&cluster.id.to_string()).ok();
+        self.kafka_infra
+            .delete_group_topic_offsets(&cluster, password, &group_id, &topic, None)
+            .await
+    }
 
-        self.kafka_infra.delete_topic(&cluster, password, name).await
+    /// Moves a group's committed offsets on one topic, for all partitions or
+    /// only `partitions`. Refuses while the group has active members, since
+    /// they would overwrite the new offsets on their next commit.
+    pub async fn reset_group_offsets(
+        &self,
+        id: Uuid,
+        group_id: String,
+        topic: String,
+        partitions: Option<Vec<i32>>,
+        target: OffsetResetTarget,
+    ) -> Result<Vec<PartitionOffsetReset>> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.ensure_group_idle(&cluster, password.clone(), &group_id, "resetting offsets")
+            .await?;
+
+        let plan = self
+            .kafka_infra
+            .plan_offset_reset(
+                &cluster,
+                password.clone(),
+                &group_id,
+                &topic,
+                partitions,
+                target,
+            )
+            .await?;
+        self.commit_with_snapshot(&cluster, password, &group_id, &plan)
+            .await?;
+
+        Ok(plan)
     }
 
-    pub async fn update_cluster(&self, cluster: Cluster, password: Option<String>) -> Result<()> {
-        self.cluster_repo.save_cluster(&cluster).await?;
-        if let Some(p) = password {
-            if !p.is_empty() {
-                self.secret_repo
-                    .save_password(&cluster.id.to_string(), &p)?;
-            }
+    /// Computes the offsets a reset would commit, with the timestamp of the
+    /// record at each current and target position, without committing them.
+    pub async fn preview_group_offset_reset(
+        &self,
+        id: Uuid,
+        group_id: String,
+        topic: String,
+        partitions: Option<Vec<i32>>,
+        target: OffsetResetTarget,
+    ) -> Result<OffsetResetPlan> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let mut offsets = self
+            .kafka_infra
+            .plan_offset_reset(
+                &cluster,
+                password.clone(),
+                &group_id,
+                &topic,
+                partitions,
+                target,
+            )
+            .await?;
+
+        let position = |o: &PartitionOffsetReset, offset: i64| CommittedOffset {
+            topic: o.topic.clone(),
+            partition: o.partition,
+            offset,
+        };
+        let before: Vec<CommittedOffset> = offsets
+            .iter()
+            .filter_map(|o| o.before.map(|b| position(o, b)))
+            .collect();
+        let after: Vec<CommittedOffset> = offsets.iter().map(|o| position(o, o.after)).collect();
+        let before = self
+            .kafka_infra
+            .fetch_record_timestamps(&cluster, password.clone(), &before)
+            .await?;
+        let after = self
+            .kafka_infra
+            .fetch_record_timestamps(&cluster, password, &after)
+            .await?;
+        for o in &mut offsets {
+            let key = (o.topic.clone(), o.partition);
+            o.before_timestamp = before.get(&key).copied().flatten();
+            o.after_timestamp = after.get(&key).copied().flatten();
         }
-        Ok(())
+
+        Ok(OffsetResetPlan {
+            group_id,
+            topic,
+            created_at: now_millis(),
+            offsets,
+        })
     }
 
-    pub async fn delete_cluster(&self, id: Uuid) -> Result<()> {
-        self.cluster_repo.delete_cluster(&id).await?;
-        let _ = self.secret_repo.delete_password(&id.to_string());
-        Ok(())
+    /// Serializes a previewed reset so it can be reviewed and applied later.
+    pub async fn export_offset_reset_plan(
+        &self,
+        id: Uuid,
+        group_id: String,
+        topic: String,
+        partitions: Option<Vec<i32>>,
+        target: OffsetResetTarget,
+    ) -> Result<String> {
+        let plan = self
+            .preview_group_offset_reset(id, group_id, topic, partitions, target)
+            .await?;
+        Ok(serde_json::to_string_pretty(&plan)?)
     }
 
-    pub async fn check_connection(&self, id: Uuid) -> Result<()> {
-        let clusters = self.cluster_repo.list_clusters().await?;
-        let cluster = clusters
+    /// Commits exactly the offsets of an exported plan. Refuses when any
+    /// partition's committed offset moved since the plan was made, since
+    /// the plan was reviewed against those offsets.
+    pub async fn apply_offset_reset_plan(
+        &self,
+        id: Uuid,
+        json: String,
+    ) -> Result<Vec<PartitionOffsetReset>> {
+        let plan: OffsetResetPlan = serde_json::from_str(&json)
+            .map_err(|e| anyhow::anyhow!("Invalid offset reset plan: {}", e))?;
+        if let Some(other) = plan.offsets.iter().find(|o| o.topic != plan.topic) {
+            return Err(anyhow::anyhow!(
+                "Offset reset plan for {} contains partitions of {}",
+                plan.topic,
+                other.topic
+            ));
+        }
+        let (cluster, password) = self.cluster_with_password(id).await?;
+        self.ensure_group_idle(
+            &cluster,
+            password.clone(),
+            &plan.group_id,
+            "applying a reset plan",
+        )
+        .await?;
+
+        let current: HashMap<i32, i64> = self
+            .kafka_infra
+            .fetch_committed_offsets(&cluster, password.clone(), &plan.group_id)
+            .await?
             .into_iter()
-            .find(|c| c.id == id)
-            .ok_or_else(|| anyhow::anyhow!("Cluster not found"))?;
+            .filter(|o| o.topic == plan.topic)
+            .map(|o| (o.partition, o.offset))
+            .collect();
+        let moved: Vec<String> = plan
+            .offsets
+            .iter()
+            .filter(|o| current.get(&o.partition).copied() != o.before)
+            .map(|o| o.partition.to_string())
+            .collect();
+        if !moved.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Committed offsets of {} partition(s) {} changed since the plan was made",
+                plan.topic,
+                moved.join(", ")
+            ));
+        }
 
-        let password = self.secret_repo.get_password(&cluster.id.to_string()).ok();
+        self.commit_with_snapshot(&cluster, password, &plan.group_id, &plan.offsets)
+            .await?;
+        Ok(plan.offsets)
+    }
 
-        self.kafka_infra.check_connection(&cluster, password).await
+    /// Offset snapshots taken of a group before its resets, newest first.
+    pub async fn list_offset_snapshots(
+        &self,
+        id: Uuid,
+        group_id: String,
+    ) -> Result<Vec<OffsetSnapshot>> {
+        self.cluster_repo
+            .list_offset_snapshots(&id, &group_id)
+            .await
     }
 
-    pub async fn consume_messages(
+    /// Restores the committed offsets a group had when `snapshot_id` was
+    /// taken. Offsets committed since for partitions absent from the
+    /// snapshot are deleted. Returns the restored partitions.
+    pub async fn rollback_offset_reset(
         &self,
         id: Uuid,
-        topic: String,
-        max_messages: usize,
-    ) -> Result<Vec<KafkaMessage>> {
-        let clusters = self.cluster_repo.list_clusters().await?;
-        let cluster = clusters
+        group_id: String,
+        snapshot_id: Uuid,
+    ) -> Result<Vec<PartitionOffsetReset>> {
+        let snapshot = self
+            .cluster_repo
+            .get_offset_snapshot(&snapshot_id)
+            .await?
+            .filter(|s| s.cluster_id == id && s.group_id == group_id)
+            .ok_or_else(|| anyhow::anyhow!("Offset snapshot not found for group {}", group_id))?;
+        let (cluster, password) = self.cluster_with_password(id).await?;
+        self.ensure_group_idle(
+            &cluster,
+            password.clone(),
+            &group_id,
+            "rolling back offsets",
+        )
+        .await?;
+
+        let current = self
+            .kafka_infra
+            .fetch_committed_offsets(&cluster, password.clone(), &group_id)
+            .await?;
+        let snapshotted: HashSet<(&str, i32)> = snapshot
+            .offsets
+            .iter()
+            .map(|o| (o.topic.as_str(), o.partition))
+            .collect();
+        let mut added: HashMap<String, Vec<i32>> = HashMap::new();
+        for offset in &current {
+            if !snapshotted.contains(&(offset.topic.as_str(), offset.partition)) {
+                added
+                    .entry(offset.topic.clone())
+                    .or_default()
+                    .push(offset.partition);
+            }
+        }
+
+        let current: HashMap<(&str, i32), i64> = current
+            .iter()
+            .map(|o| ((o.topic.as_str(), o.partition), o.offset))
+            .collect();
+        let restored: Vec<PartitionOffsetReset> = snapshot
+            .offsets
+            .iter()
+            .map(|o| PartitionOffsetReset {
+                topic: o.topic.clone(),
+                partition: o.partition,
+                before: current.get(&(o.topic.as_str(), o.partition)).copied(),
+                after: o.offset,
+                before_timestamp: None,
+                after_timestamp: None,
+            })
+            .collect();
+
+        self.commit_with_snapshot(&cluster, password.clone(), &group_id, &restored)
+            .await?;
+        for (topic, partitions) in added {
+            self.kafka_infra
+                .delete_group_topic_offsets(
+                    &cluster,
+                    password.clone(),
+                    &group_id,
+                    &topic,
+                    Some(partitions),
+                )
+                .await?;
+        }
+        Ok(restored)
+    }
+
+    /// Saves the group's committed offsets as a snapshot, then commits
+    /// `offsets`, so every reset can be rolled back.
+    async fn commit_with_snapshot(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        group_id: &str,
+        offsets: &[PartitionOffsetReset],
+    ) -> Result<()> {
+        let current = self
+            .kafka_infra
+            .fetch_committed_offsets(cluster, password.clone(), group_id)
+            .await?;
+        self.cluster_repo
+            .save_offset_snapshot(&OffsetSnapshot {
+                id: Uuid::new_v4(),
+                cluster_id: cluster.id,
+                group_id: group_id.to_string(),
+                created_at: now_millis(),
+                offsets: current,
+            })
+            .await?;
+
+        self.kafka_infra
+            .commit_group_offsets(cluster, password, group_id, offsets)
+            .await
+    }
+
+    /// Serializes a group's committed offsets to a JSON document.
+    pub async fn export_group_offsets(&self, id: Uuid, group_id: String) -> Result<String> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let mut offsets = self
+            .kafka_infra
+            .fetch_committed_offsets(&cluster, password, &group_id)
+            .await?;
+        offsets.sort_by(|a, b| (&a.topic, a.partition).cmp(&(&b.topic, b.partition)));
+        Ok(serde_json::to_string_pretty(&GroupOffsetDocument {
+            group_id,
+            exported_at: now_millis(),
+            offsets,
+        })?)
+    }
+
+    /// Commits the offsets of an exported document to `group_id`, or to the
+    /// group it was exported from. A dry run only reports the changes.
+    pub async fn import_group_offsets(
+        &self,
+        id: Uuid,
+        json: String,
+        group_id: Option<String>,
+        dry_run: bool,
+    ) -> Result<Vec<PartitionOffsetReset>> {
+        let document: GroupOffsetDocument = serde_json::from_str(&json)
+            .map_err(|e| anyhow::anyhow!("Invalid offsets document: {}", e))?;
+        let group_id = group_id.unwrap_or(document.group_id);
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        if !dry_run {
+            self.ensure_group_idle(&cluster, password.clone(), &group_id, "importing offsets")
+                .await?;
+        }
+
+        let current: HashMap<(String, i32), i64> = self
+            .kafka_infra
+            .fetch_committed_offsets(&cluster, password.clone(), &group_id)
+            .await?
             .into_iter()
-            .find(|c| c.id == id)
-            .ok_or_else(|| anyhow::anyhow!("Cluster not found"))?;
+            .map(|o| ((o.topic, o.partition), o.offset))
+            .collect();
+        let plan: Vec<PartitionOffsetReset> = document
+            .offsets
+            .into_iter()
+            .map(|o| PartitionOffsetReset {
+                before: current.get(&(o.topic.clone(), o.partition)).copied(),
+                topic: o.topic,
+                partition: o.partition,
+                after: o.offset,
+                before_timestamp: None,
+                after_timestamp: None,
+            })
+            .collect();
 
-        let password = self.secret_repo.get_password(&cluster.id.to_string()).ok();
+        if !dry_run {
+            self.commit_with_snapshot(&cluster, password, &group_id, &plan)
+                .await?;
+        }
+        Ok(plan)
+    }
+
+    /// Creates a topic with the given topic-level configs, e.g.
+    /// `retention.ms` or `cleanup.policy`, in place of the broker defaults.
+    pub async fn create_topic(
+        &self,
+        id: Uuid,
+        name: String,
+        partitions: i32,
+        replication: i32,
+        configs: HashMap<String, String>,
+    ) -> Result<()> {
+        let configs: HashMap<String, String> = configs
+            .into_iter()
+            .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+            .collect();
+        if configs.keys().any(|key| key.is_empty()) {
+            return Err(anyhow::anyhow!("Config names must not be empty"));
+        }
+        let (cluster, password) = self.cluster_with_password(id).await?;
 
+        let validation = self
+            .validate_new_topic(&cluster, password.clone(), &name, partitions, replication)
+            .await?;
+        if !validation.is_valid() {
+            return Err(validation.into());
+        }
         self.kafka_infra
-            .consume_messages(&cluster, password, &topic, max_messages)
+            .create_topic(&cluster, password, name, partitions, replication, &configs)
             .await
     }
 
-    pub async fn get_topic_message_count(&self, id: Uuid, topic: String) -> Result<i64> {
-        let clusters = self.cluster_repo.list_clusters().await?;
-        let cluster = clusters
+    /// Checks a topic before `create_topic`: Kafka's naming rules, metric
+    /// name collisions with existing topics, and the replication factor
+    /// against the live broker count.
+    pub async fn validate_topic(
+        &self,
+        id: Uuid,
+        name: String,
+        partitions: i32,
+        replication: i32,
+    ) -> Result<TopicValidation> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.validate_new_topic(&cluster, password, &name, partitions, replication)
+            .await
+    }
+
+    async fn validate_new_topic(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        name: &str,
+        partitions: i32,
+        replication: i32,
+    ) -> Result<TopicValidation> {
+        let existing: Vec<String> = self
+            .kafka_infra
+            .list_topics(cluster, password.clone())
+            .await?
             .into_iter()
-            .find(|c| c.id == id)
-            .ok_or_else(|| anyhow::anyhow!("Cluster not found"))?;
+            .map(|t| t.name)
+            .collect();
+        let broker_count = self
+            .kafka_infra
+            .count_live_brokers(cluster, password)
+            .await?;
+        Ok(validate_new_topic(
+            name,
+            partitions,
+            replication,
+            &existing,
+            broker_count,
+        ))
+    }
 
-        let password = self.secret_repo.get_password(&cluster.id.to_string()).ok();
+    /// Creates `new_name` with the partition count, replication factor and
+    /// topic-level config overrides of `topic`, on the same cluster or on
+    /// `target_cluster_id`. With `include_data`, a replay copying the
+    /// existing records is started and returned; that only works within
+    /// one cluster.
+    pub async fn clone_topic(
+        &self,
+        id: Uuid,
+        topic: String,
+        new_name: String,
+        target_cluster_id: Option<Uuid>,
+        include_data: bool,
+    ) -> Result<Option<SessionInfo>> {
+        let new_name = new_name.trim().to_string();
+        if new_name.is_empty() {
+            return Err(anyhow::anyhow!("A topic name is required"));
+        }
+        let target_id = target_cluster_id.unwrap_or(id);
+        if target_id == id && new_name == topic {
+            return Err(anyhow::anyhow!(
+                "The clone needs a different name or cluster"
+            ));
+        }
+        if include_data && target_id != id {
+            return Err(anyhow::anyhow!(
+                "Records can only be copied to a clone on the same cluster"
+            ));
+        }
+
+        let (cluster, password) = self.cluster_with_password(id).await?;
+        let source = self
+            .kafka_infra
+            .describe_topic(&cluster, password.clone(), &topic)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Topic not found"))?;
+        let configs: HashMap<String, String> = self
+            .kafka_infra
+            .describe_topic_configs(&cluster, password, std::slice::from_ref(&topic))
+            .await?
+            .remove(&topic)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|entry| entry.source == ConfigEntrySource::DynamicTopic)
+            .filter_map(|entry| Some((entry.name, entry.value?)))
+            .collect();
 
+        let (target, target_password) = self.cluster_with_password(target_id).await?;
         self.kafka_infra
-            .get_topic_message_count(&cluster, password, &topic)
+            .create_topic(
+                &target,
+                target_password.clone(),
+                new_name.clone(),
+                source.partitions,
+                source.replication_factor,
+                &configs,
+            )
+            .await?;
+        if !include_data {
+            return Ok(None);
+        }
+
+        // The new topic can take a moment to show up in broker metadata,
+        // and the replay checks that its destination exists.
+        for _ in 0..CLONE_METADATA_ATTEMPTS {
+            let created = self
+                .kafka_infra
+                .describe_topic(&target, target_password.clone(), &new_name)
+                .await?;
+            if created.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        self.start_replay(id, topic, new_name, RekeyStrategy::Keep, Vec::new())
             .await
+            .map(Some)
+    }
+
+    pub async fn publish_message(
+        &self,
+        id: Uuid,
+        topic: String,
+        key: Option<String>,
+        payload: String,
+        headers: Vec<MessageHeader>,
+    ) -> Result<PublishResult> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let cipher = self.publish_cipher(&id, &topic).await?;
+        let digest = payload_digest(payload.as_bytes());
+        let result = self
+            .kafka_infra
+            .publish_message(
+                &cluster,
+                password,
+                &topic,
+                key,
+                payload,
+                &headers,
+                cipher.as_ref(),
+            )
+            .await?;
+        self.record_receipts(&cluster, &topic, vec![(digest, result.clone())])
+            .await?;
+        Ok(result)
+    }
+
+    /// Publishes pasted text as one record per line. Nothing is produced when
+    /// any line fails validation, so a paste can be fixed and retried whole.
+    pub async fn publish_bulk_text(
+        &self,
+        id: Uuid,
+        topic: String,
+        text: String,
+        format: BulkTextFormat,
+    ) -> Result<BulkPublishResult> {
+        let (records, errors) = parse_bulk_text(&text, format);
+        if !errors.is_empty() {
+            return Ok(BulkPublishResult {
+                produced: 0,
+                errors,
+            });
+        }
+
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let cipher = self.publish_cipher(&id, &topic).await?;
+        let mut errors = Vec::new();
+        let mut produced = Vec::new();
+        let deliveries = self
+            .kafka_infra
+            .publish_batch(&cluster, password, &topic, &records, cipher.as_ref())
+            .await?;
+        for (delivery, record) in deliveries.into_iter().zip(&records) {
+            match delivery {
+                Ok(result) => produced.push((payload_digest(record.payload.as_bytes()), result)),
+                Err(error) => errors.push(error),
+            }
+        }
+        self.record_receipts(&cluster, &topic, produced).await?;
+        Ok(BulkPublishResult {
+            produced: records.len() - errors.len(),
+            errors,
+        })
+    }
+
+    /// Appends a signed receipt for each produced payload, given as its
+    /// digest and delivery, when the cluster has receipts enabled.
+    async fn record_receipts(
+        &self,
+        cluster: &Cluster,
+        topic: &str,
+        produced: Vec<(String, PublishResult)>,
+    ) -> Result<()> {
+        if produced.is_empty() {
+            return Ok(());
+        }
+        let fail = |e: anyhow::Error| {
+            anyhow::anyhow!(
+                "Produced {} message(s) to {} but failed to record receipts: {}",
+                produced.len(),
+                topic,
+                e
+            )
+        };
+        match self.receipt_log(cluster, topic).map_err(fail)? {
+            Some(log) => log.record(&produced).await.map_err(fail),
+            None => Ok(()),
+        }
+    }
+
+    /// Receipt log for records produced to `topic`, `None` when the cluster
+    /// has receipts disabled.
+    fn receipt_log(&self, cluster: &Cluster, topic: &str) -> Result<Option<ReceiptLog>> {
+        if !cluster.production_receipts {
+            return Ok(None);
+        }
+        Ok(Some(ReceiptLog::new(
+            cluster.id,
+            topic,
+            self.receipt_signer()?,
+            self.cluster_repo.clone(),
+            self.receipt_chain.clone(),
+        )))
+    }
+
+    /// Signer for production receipts, creating its key on first use.
+    fn receipt_signer(&self) -> Result<ReceiptSigner> {
+        // Only a missing key is replaced. Overwriting it after any other
+        // keyring error would leave the existing receipts unverifiable.
+        let key = match self.secret_repo.find_password(RECEIPT_SIGNING_KEY)? {
+            Some(key) => key,
+            None => {
+                let key = ReceiptSigner::generate_key();
+                self.secret_repo.save_password(RECEIPT_SIGNING_KEY, &key)?;
+                key
+            }
+        };
+        ReceiptSigner::from_base64_key(&key)
+    }
+
+    pub async fn set_production_receipts(&self, id: Uuid, enabled: bool) -> Result<()> {
+        if enabled {
+            self.receipt_signer()?;
+        }
+        self.cluster_repo
+            .set_production_receipts(&id, enabled)
+            .await
+    }
+
+    /// The receipt log of every cluster as JSON lines, oldest first. The
+    /// chain only verifies over the whole log, so it is not filtered.
+    pub async fn export_production_receipts(&self) -> Result<String> {
+        let receipts = self.cluster_repo.list_production_receipts().await?;
+        let lines = receipts
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        Ok(lines.join("\n"))
+    }
+
+    /// Topics following a dead-letter naming convention, with their depth,
+    /// source topic and most recent failures.
+    pub async fn get_dlq_summary(&self, id: Uuid) -> Result<DlqSummary> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let topics = self
+            .kafka_infra
+            .list_topics(&cluster, password.clone())
+            .await?;
+        let names: HashSet<&str> = topics.iter().map(|t| t.name.as_str()).collect();
+
+        let mut dlqs = Vec::new();
+        for topic in &topics {
+            let Some(named_source) = dlq_naming(&topic.name) else {
+                continue;
+            };
+            let depth = self
+                .kafka_infra
+                .get_topic_message_count(&cluster, password.clone(), &topic.name)
+                .await?;
+            let recent_failures = self
+                .kafka_infra
+                .read_dlq_failures(&cluster, password.clone(), &topic.name, DLQ_RECENT_FAILURES)
+                .await?;
+
+            let source_topic = named_source
+                .filter(|source| names.contains(source))
+                .map(str::to_string)
+                .or_else(|| {
+                    recent_failures
+                        .iter()
+                        .filter_map(|f| f.original_topic.as_deref())
+                        .find(|source| names.contains(source))
+                        .map(str::to_string)
+                });
+            dlqs.push(DlqTopic {
+                topic: topic.name.clone(),
+                source_topic,
+                depth,
+                recent_failures,
+            });
+        }
+        dlqs.sort_by(|a, b| b.depth.cmp(&a.depth).then_with(|| a.topic.cmp(&b.topic)));
+
+        Ok(DlqSummary {
+            total_depth: dlqs.iter().map(|d| d.depth).sum(),
+            topics: dlqs,
+        })
+    }
+
+    /// Deletes a topic. Protected topics are refused unless `force` is set.
+    pub async fn delete_topic(&self, id: Uuid, name: String, force: bool) -> Result<()> {
+        self.ensure_unprotected(id, &name, force).await?;
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.kafka_infra.delete_topic(&cluster, password, name).await
+    }
+
+    /// Deletes every topic a selection matches. Without a confirmation this
+    /// is a dry run reporting the topics, their message counts and the token
+    /// that confirms deleting exactly them.
+    pub async fn delete_topics(
+        &self,
+        id: Uuid,
+        selection: TopicSelection,
+        confirmation: Option<String>,
+    ) -> Result<BulkTopicDeletion> {
+        let pattern = selection
+            .pattern
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(|p| regex::Regex::new(&format!("^(?:{})$", p)))
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Invalid topic pattern: {}", e))?;
+        if pattern.is_none() && selection.topics.is_empty() {
+            return Err(anyhow::anyhow!("A pattern or a list of topics is required"));
+        }
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let existing = self
+            .kafka_infra
+            .list_topics(&cluster, password.clone())
+            .await?;
+        let mut not_found = Vec::new();
+        let mut names: Vec<String> = Vec::new();
+        for topic in &selection.topics {
+            if !existing.iter().any(|t| &t.name == topic) {
+                not_found.push(topic.clone());
+            } else if !names.contains(topic) {
+                names.push(topic.clone());
+            }
+        }
+        if let Some(pattern) = &pattern {
+            for topic in &existing {
+                if !topic.is_internal
+                    && pattern.is_match(&topic.name)
+                    && !names.contains(&topic.name)
+                {
+                    names.push(topic.name.clone());
+                }
+            }
+        }
+        names.sort();
+
+        let protected: HashSet<String> = self
+            .cluster_repo
+            .list_protected_topics(&id)
+            .await?
+            .into_iter()
+            .collect();
+
+        let token = deletion_token(&names);
+        let delete = match confirmation {
+            Some(confirmation) if confirmation == token => true,
+            Some(_) => {
+                return Err(anyhow::anyhow!(
+                    "Confirmation does not match the topics selected; run a dry run again"
+                ))
+            }
+            None => false,
+        };
+
+        let mut topics = Vec::with_capacity(names.len());
+        for topic in names {
+            let message_count = self
+                .kafka_infra
+                .get_topic_message_count(&cluster, password.clone(), &topic)
+                .await?;
+            let error = if protected.contains(&topic) {
+                Some("Topic is protected".to_string())
+            } else if delete {
+                self.kafka_infra
+                    .delete_topic(&cluster, password.clone(), topic.clone())
+                    .await
+                    .err()
+                    .map(|e| e.to_string())
+            } else {
+                None
+            };
+            topics.push(TopicDeletion {
+                topic,
+                message_count,
+                error,
+            });
+        }
+
+        Ok(BulkTopicDeletion {
+            total_messages: topics.iter().map(|t| t.message_count).sum(),
+            topics,
+            not_found,
+            confirmation: token,
+            deleted: delete,
+        })
+    }
+
+    /// Deletes the records of one partition below `offset`. Destructive, so
+    /// `confirmation` must repeat the topic name, and protected topics need
+    /// `force`.
+    pub async fn delete_records(
+        &self,
+        id: Uuid,
+        topic: String,
+        partition: i32,
+        offset: i64,
+        confirmation: String,
+        force: bool,
+    ) -> Result<Vec<PartitionTruncation>> {
+        check_confirmation(&topic, &confirmation)?;
+        self.ensure_unprotected(id, &topic, force).await?;
+        if offset < 0 {
+            return Err(anyhow::anyhow!("Offset must not be negative"));
+        }
+        let (cluster, password) = self.cluster_with_password(id).await?;
+        ensure_supported(&cluster, ClusterFeature::DeleteRecords)?;
+
+        self.kafka_infra
+            .delete_records(&cluster, password, topic, vec![(partition, Some(offset))])
+            .await
+    }
+
+    /// Deletes every record of every partition of `topic`, keeping the topic
+    /// and its configuration. `confirmation` must repeat the topic name, and
+    /// protected topics need `force`.
+    pub async fn purge_topic(
+        &self,
+        id: Uuid,
+        topic: String,
+        confirmation: String,
+        force: bool,
+    ) -> Result<Vec<PartitionTruncation>> {
+        check_confirmation(&topic, &confirmation)?;
+        self.ensure_unprotected(id, &topic, force).await?;
+        let (cluster, password) = self.cluster_with_password(id).await?;
+        ensure_supported(&cluster, ClusterFeature::DeleteRecords)?;
+
+        let current = self
+            .kafka_infra
+            .describe_topic(&cluster, password.clone(), &topic)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Topic {} not found", topic))?;
+        let offsets = current
+            .partition_ids
+            .iter()
+            .map(|&partition| (partition, None))
+            .collect();
+        self.kafka_infra
+            .delete_records(&cluster, password, topic, offsets)
+            .await
+    }
+
+    /// Raises a topic's partition count. Partitions cannot be removed, so
+    /// the new count must exceed the current one.
+    pub async fn add_partitions(
+        &self,
+        id: Uuid,
+        topic: String,
+        partitions: i32,
+    ) -> Result<PartitionIncrease> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+        ensure_supported(&cluster, ClusterFeature::CreatePartitions)?;
+
+        let current = self
+            .kafka_infra
+            .describe_topic(&cluster, password.clone(), &topic)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Topic {} not found", topic))?;
+        if partitions <= current.partitions {
+            return Err(anyhow::anyhow!(
+                "Topic {} already has {} partitions; the new count must be greater",
+                topic,
+                current.partitions
+            ));
+        }
+
+        let keyed_data = self
+            .kafka_infra
+            .has_keyed_records(&cluster, password.clone(), &topic)
+            .await?;
+        self.kafka_infra
+            .create_partitions(&cluster, password, &topic, partitions as usize)
+            .await?;
+
+        Ok(PartitionIncrease {
+            topic,
+            previous_partitions: current.partitions,
+            partitions,
+            keyed_data,
+        })
+    }
+
+    pub fn list_deserializer_plugins(&self) -> Vec<DeserializerPlugin> {
+        self.deserializers.list()
+    }
+
+    /// Rescans the plugin directory, picking up added or replaced plugins.
+    pub fn reload_deserializer_plugins(&self) -> Vec<DeserializerPlugin> {
+        self.deserializers.reload();
+        self.deserializers.list()
+    }
+
+    pub fn list_transform_plugins(&self) -> Vec<TransformPlugin> {
+        self.transforms.list()
+    }
+
+    /// Recompiles the WebAssembly transforms in the plugin directory.
+    pub fn reload_transform_plugins(&self) -> Vec<TransformPlugin> {
+        self.transforms.reload();
+        self.transforms.list()
+    }
+
+    pub async fn describe_topic_partitions(
+        &self,
+        id: Uuid,
+        topic: String,
+    ) -> Result<Vec<Partition>> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.kafka_infra
+            .describe_topic_partitions(&cluster, password, &topic)
+            .await
+    }
+
+    /// Every config entry of a topic, including defaults and read-only
+    /// entries, sorted by name.
+    pub async fn get_topic_config(&self, id: Uuid, topic: String) -> Result<Vec<TopicConfigEntry>> {
+        let (mut entries, cached_at) = self
+            .with_metadata_snapshot(id, "topic_config", &topic, async {
+                let (cluster, password) = self.cluster_with_password(id).await?;
+                let mut entries = self
+                    .kafka_infra
+                    .describe_topic_configs(&cluster, password, std::slice::from_ref(&topic))
+                    .await?
+                    .remove(&topic)
+                    .ok_or_else(|| anyhow::anyhow!("Topic {} not found", topic))?;
+                entries.sort_by(|a, b| a.name.cmp(&b.name));
+                Ok(entries)
+            })
+            .await?;
+        for entry in &mut entries {
+            entry.cached_at = cached_at;
+        }
+        Ok(entries)
+    }
+
+    pub async fn update_cluster(&self, cluster: Cluster, password: Option<String>) -> Result<()> {
+        self.cluster_repo.save_cluster(&cluster).await?;
+        if let Some(p) = password {
+            if !p.is_empty() {
+                self.secret_repo
+                    .save_password(&cluster.id.to_string(), &p)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn set_cluster_tags(&self, id: Uuid, tags: Vec<String>) -> Result<()> {
+        let mut tags: Vec<String> = tags
+            .iter()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        self.cluster_repo.set_cluster_tags(&id, &tags).await
+    }
+
+    pub async fn set_cluster_group(&self, id: Uuid, group: Option<String>) -> Result<()> {
+        let group = group.as_deref().map(str::trim).filter(|g| !g.is_empty());
+        self.cluster_repo.set_cluster_group(&id, group).await
+    }
+
+    /// Sets the `client.id` the cluster's clients send; `None` restores the
+    /// default.
+    pub async fn set_cluster_client_id(&self, id: Uuid, client_id: Option<String>) -> Result<()> {
+        let client_id = client_id
+            .as_deref()
+            .map(str::trim)
+            .filter(|c| !c.is_empty());
+        self.cluster_repo.set_client_id(&id, client_id).await
+    }
+
+    pub async fn reorder_clusters(&self, ids: Vec<Uuid>) -> Result<()> {
+        self.cluster_repo.reorder_clusters(&ids).await
+    }
+
+    /// Soft-deletes the cluster. Its password is kept so a restore brings the
+    /// configuration back intact; both are dropped once purged.
+    pub async fn delete_cluster(&self, id: Uuid) -> Result<()> {
+        self.cluster_repo.delete_cluster(&id).await?;
+        self.kafka_infra.release_clients(id);
+        Ok(())
+    }
+
+    pub async fn list_deleted_clusters(&self) -> Result<Vec<Cluster>> {
+        self.cluster_repo.list_deleted_clusters().await
+    }
+
+    pub async fn restore_cluster(&self, id: Uuid) -> Result<()> {
+        self.cluster_repo.restore_cluster(&id).await
+    }
+
+    /// Checks the local database for damage, clusters and services stored
+    /// under unparsable ids, and data left behind by deleted clusters. With
+    /// `include_secrets`, the keyring is consulted too, which can prompt
+    /// the user on some platforms.
+    pub async fn check_database(&self, include_secrets: bool) -> Result<DatabaseReport> {
+        let issue = |kind, subject: &str, message: String, repairable| IntegrityIssue {
+            kind,
+            subject: subject.to_string(),
+            message,
+            repairable,
+        };
+        let mut issues = Vec::new();
+
+        for problem in self.cluster_repo.integrity_check().await? {
+            issues.push(issue(
+                IntegrityIssueKind::Corruption,
+                "database",
+                format!("Database is damaged: {}", problem),
+                false,
+            ));
+        }
+        for (id, name) in self.cluster_repo.list_invalid_cluster_ids().await? {
+            issues.push(issue(
+                IntegrityIssueKind::InvalidClusterId,
+                &id,
+                format!("Cluster {} is stored under the invalid id {:?}", name, id),
+                true,
+            ));
+        }
+        for (id, name) in self.cluster_repo.list_invalid_service_ids().await? {
+            issues.push(issue(
+                IntegrityIssueKind::InvalidServiceId,
+                &id,
+                format!(
+                    "Service {} is stored under the invalid id {:?}; its password has to be \
+                     entered again after repair",
+                    name, id
+                ),
+                true,
+            ));
+        }
+        for (table, count) in self.cluster_repo.count_orphaned_rows().await? {
+            issues.push(issue(
+                IntegrityIssueKind::OrphanedRows,
+                &table,
+                format!("{} rows in {} belong to deleted clusters", count, table),
+                true,
+            ));
+        }
+
+        if include_secrets {
+            for key in self.orphaned_secret_keys().await? {
+                if self.secret_repo.get_password(&key).is_ok() {
+                    issues.push(issue(
+                        IntegrityIssueKind::OrphanedSecret,
+                        &key,
+                        format!("Keyring entry {} belongs to a deleted cluster", key),
+                        true,
+                    ));
+                }
+            }
+            for cluster in self.cluster_repo.list_clusters().await? {
+                if cluster.id.is_nil() {
+                    continue;
+                }
+                let needs_password = matches!(
+                    &cluster.security,
+                    SecurityConfig::SaslSsl { mechanism, .. }
+                        if !matches!(mechanism, SaslMechanism::Gssapi)
+                );
+                if needs_password
+                    && self
+                        .secret_repo
+                        .get_password(&cluster.id.to_string())
+                        .is_err()
+                {
+                    issues.push(issue(
+                        IntegrityIssueKind::MissingSecret,
+                        &cluster.id.to_string(),
+                        format!(
+                            "Password of cluster {} is missing from the keyring",
+                            cluster.name
+                        ),
+                        false,
+                    ));
+                }
+                for service in cluster.services.iter().filter(|s| s.username.is_some()) {
+                    let key = service_secret_key(&cluster.id, &service.id);
+                    if self.secret_repo.get_password(&key).is_err() {
+                        issues.push(issue(
+                            IntegrityIssueKind::MissingSecret,
+                            &key,
+                            format!(
+                                "Password of service {} of cluster {} is missing from the keyring",
+                                service.name, cluster.name
+                            ),
+                            false,
+                        ));
+                    }
+                }
+            }
+        }
+
+        Ok(DatabaseReport {
+            checked_at: now_millis(),
+            issues,
+        })
+    }
+
+    /// Fixes what `check_database` reports as repairable: clusters and
+    /// services get fresh ids, and data and keyring entries of deleted
+    /// clusters are removed. Damage found by the integrity check is left
+    /// alone.
+    pub async fn repair_database(&self) -> Result<DatabaseRepair> {
+        let mut actions = Vec::new();
+
+        for (old_id, name) in self.cluster_repo.list_invalid_cluster_ids().await? {
+            let new_id = Uuid::new_v4();
+            self.cluster_repo
+                .change_cluster_id(&old_id, &new_id)
+                .await?;
+            self.move_secret(&old_id, &new_id.to_string());
+            let services = self
+                .cluster_repo
+                .list_clusters()
+                .await?
+                .into_iter()
+                .find(|c| c.id == new_id)
+                .map(|c| c.services)
+                .unwrap_or_default();
+            for service in services {
+                self.move_secret(
+                    &format!("{}/{}", old_id, service.id),
+                    &service_secret_key(&new_id, &service.id),
+                );
+            }
+            for encryption in self.cluster_repo.list_topic_encryption(&new_id).await? {
+                self.move_secret(
+                    &format!("{}/encryption/{}", old_id, encryption.topic),
+                    &encryption_secret_key(&new_id, &encryption.topic),
+                );
+            }
+            actions.push(format!("Gave cluster {} the new id {}", name, new_id));
+        }
+        for (old_id, name) in self.cluster_repo.list_invalid_service_ids().await? {
+            let new_id = Uuid::new_v4();
+            self.cluster_repo
+                .change_service_id(&old_id, &new_id)
+                .await?;
+            actions.push(format!(
+                "Gave service {} the new id {}; enter its password again",
+                name, new_id
+            ));
+        }
+        for key in self.orphaned_secret_keys().await? {
+            if self.secret_repo.delete_password(&key).is_ok() {
+                actions.push(format!("Removed keyring entry {}", key));
+            }
+        }
+        let rows = self.cluster_repo.delete_orphaned_rows().await?;
+        if rows > 0 {
+            actions.push(format!("Removed {} rows of deleted clusters", rows));
+        }
+
+        Ok(DatabaseRepair {
+            actions,
+            report: self.check_database(true).await?,
+        })
+    }
+
+    /// Keyring entries of services and topic encryption whose cluster no
+    /// longer exists.
+    async fn orphaned_secret_keys(&self) -> Result<Vec<String>> {
+        let mut keys = Vec::new();
+        for (cluster_id, service_id) in self.cluster_repo.list_orphaned_services().await? {
+            if let (Ok(cluster_id), Ok(service_id)) =
+                (Uuid::parse_str(&cluster_id), Uuid::parse_str(&service_id))
+            {
+                keys.push(service_secret_key(&cluster_id, &service_id));
+            }
+        }
+        for (cluster_id, topic) in self.cluster_repo.list_orphaned_topic_encryption().await? {
+            if let Ok(cluster_id) = Uuid::parse_str(&cluster_id) {
+                keys.push(encryption_secret_key(&cluster_id, &topic));
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Moves a keyring entry to another key, if there is one to move.
+    fn move_secret(&self, from: &str, to: &str) {
+        let Ok(secret) = self.secret_repo.get_password(from) else {
+            return;
+        };
+        match self.secret_repo.save_password(to, &secret) {
+            Ok(()) => {
+                let _ = self.secret_repo.delete_password(from);
+            }
+            Err(e) => eprintln!("Failed to move keyring entry {}: {}", from, e),
+        }
+    }
+
+    /// Permanently removes clusters that have been deleted for longer than
+    /// the retention period. Returns how many were purged.
+    pub async fn purge_deleted_clusters(&self) -> Result<usize> {
+        let cutoff = now_millis() - DELETED_CLUSTER_RETENTION_MS;
+        let deleted = self.cluster_repo.list_deleted_clusters().await?;
+        let mut encrypted_topics = HashMap::new();
+        for cluster in &deleted {
+            encrypted_topics.insert(
+                cluster.id,
+                self.cluster_repo.list_topic_encryption(&cluster.id).await?,
+            );
+        }
+        let purged = self.cluster_repo.purge_deleted_clusters(cutoff).await?;
+
+        for cluster in deleted.iter().filter(|c| purged.contains(&c.id)) {
+            let _ = self.secret_repo.delete_password(&cluster.id.to_string());
+            for service in &cluster.services {
+                let _ = self
+                    .secret_repo
+                    .delete_password(&service_secret_key(&cluster.id, &service.id));
+            }
+            for encryption in encrypted_topics.get(&cluster.id).into_iter().flatten() {
+                let _ = self
+                    .secret_repo
+                    .delete_password(&encryption_secret_key(&cluster.id, &encryption.topic));
+            }
+        }
+        Ok(purged.len())
+    }
+
+    /// Adds or replaces an auxiliary service of a cluster. An empty password
+    /// keeps the stored one.
+    pub async fn save_cluster_service(
+        &self,
+        cluster_id: Uuid,
+        service: AuxiliaryService,
+        password: Option<String>,
+    ) -> Result<()> {
+        if !service.url.starts_with("http://") && !service.url.starts_with("https://") {
+            return Err(anyhow::anyhow!(
+                "Service URL must start with http:// or https://"
+            ));
+        }
+        self.cluster_with_password(cluster_id).await?;
+
+        self.cluster_repo
+            .save_service(&cluster_id, &service)
+            .await?;
+        if let Some(p) = password {
+            if !p.is_empty() {
+                self.secret_repo
+                    .save_password(&service_secret_key(&cluster_id, &service.id), &p)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn delete_cluster_service(&self, cluster_id: Uuid, service_id: Uuid) -> Result<()> {
+        self.cluster_repo
+            .delete_service(&cluster_id, &service_id)
+            .await?;
+        let _ = self
+            .secret_repo
+            .delete_password(&service_secret_key(&cluster_id, &service_id));
+        Ok(())
+    }
+
+    pub async fn list_topic_encryption(&self, cluster_id: Uuid) -> Result<Vec<TopicEncryption>> {
+        self.cluster_repo.list_topic_encryption(&cluster_id).await
+    }
+
+    /// Configures payload encryption for a topic. The key is a base64-encoded
+    /// 256-bit key; an empty or missing key keeps the stored one.
+    pub async fn set_topic_encryption(
+        &self,
+        cluster_id: Uuid,
+        encryption: TopicEncryption,
+        key: Option<String>,
+    ) -> Result<()> {
+        self.cluster_with_password(cluster_id).await?;
+        let secret_key = encryption_secret_key(&cluster_id, &encryption.topic);
+
+        match key.filter(|k| !k.trim().is_empty()) {
+            Some(key) => {
+                PayloadCipher::from_base64_key(&key)?;
+                self.secret_repo.save_password(&secret_key, key.trim())?;
+            }
+            None => {
+                self.secret_repo
+                    .get_password(&secret_key)
+                    .map_err(|_| anyhow::anyhow!("An encryption key is required"))?;
+            }
+        }
+
+        self.cluster_repo
+            .save_topic_encryption(&cluster_id, &encryption)
+            .await
+    }
+
+    pub async fn remove_topic_encryption(&self, cluster_id: Uuid, topic: String) -> Result<()> {
+        self.cluster_repo
+            .delete_topic_encryption(&cluster_id, &topic)
+            .await?;
+        let _ = self
+            .secret_repo
+            .delete_password(&encryption_secret_key(&cluster_id, &topic));
+        Ok(())
+    }
+
+    /// Last saved consumption setup of a topic, or the defaults.
+    pub async fn get_topic_preferences(
+        &self,
+        cluster_id: Uuid,
+        topic: String,
+    ) -> Result<TopicPreferences> {
+        Ok(self
+            .cluster_repo
+            .get_topic_preferences(&cluster_id, &topic)
+            .await?
+            .unwrap_or_default())
+    }
+
+    pub async fn set_topic_preferences(
+        &self,
+        cluster_id: Uuid,
+        topic: String,
+        preferences: TopicPreferences,
+    ) -> Result<()> {
+        if preferences.limit == 0 {
+            return Err(anyhow::anyhow!("Message limit must be at least 1"));
+        }
+        self.cluster_repo
+            .save_topic_preferences(&cluster_id, &topic, &preferences)
+            .await
+    }
+
+    /// Annotations of a cluster's topics, narrowed to favorites and to those
+    /// carrying `tag` when given.
+    pub async fn list_topic_annotations(
+        &self,
+        cluster_id: Uuid,
+        tag: Option<String>,
+        favorites_only: bool,
+    ) -> Result<Vec<TopicAnnotation>> {
+        let tag = tag.map(|t| t.trim().to_string()).filter(|t| !t.is_empty());
+        let mut annotations = self
+            .cluster_repo
+            .list_topic_annotations(&cluster_id)
+            .await?;
+        annotations
+            .retain(|a| (!favorites_only || a.favorite) && tag.iter().all(|t| a.tags.contains(t)));
+        Ok(annotations)
+    }
+
+    /// Marks a topic as protected, or lifts the protection. Protected topics
+    /// cannot be deleted, purged or truncated without an explicit override.
+    pub async fn set_topic_protection(
+        &self,
+        cluster_id: Uuid,
+        topic: String,
+        protected: bool,
+    ) -> Result<()> {
+        if topic.trim().is_empty() {
+            return Err(anyhow::anyhow!("Topic name must not be empty"));
+        }
+        self.cluster_repo
+            .set_topic_protected(&cluster_id, &topic, protected, now_millis())
+            .await
+    }
+
+    pub async fn list_protected_topics(&self, cluster_id: Uuid) -> Result<Vec<String>> {
+        self.cluster_repo.list_protected_topics(&cluster_id).await
+    }
+
+    async fn ensure_unprotected(&self, cluster_id: Uuid, topic: &str, force: bool) -> Result<()> {
+        if !force
+            && self
+                .cluster_repo
+                .is_topic_protected(&cluster_id, topic)
+                .await?
+        {
+            return Err(anyhow::anyhow!(
+                "Topic {} is protected; lift the protection or override it to continue",
+                topic
+            ));
+        }
+        Ok(())
+    }
+
+    /// Replaces a topic's favorite flag, notes and tags. Clearing all of
+    /// them removes the annotation.
+    pub async fn set_topic_annotation(
+        &self,
+        cluster_id: Uuid,
+        annotation: TopicAnnotation,
+    ) -> Result<TopicAnnotation> {
+        if annotation.topic.trim().is_empty() {
+            return Err(anyhow::anyhow!("Topic name must not be empty"));
+        }
+        let annotation = annotation.normalized();
+        self.cluster_repo
+            .save_topic_annotation(&cluster_id, &annotation)
+            .await?;
+        Ok(annotation)
+    }
+
+    pub async fn get_payload_encoding(
+        &self,
+        cluster_id: Uuid,
+        topic: String,
+    ) -> Result<PayloadEncoding> {
+        self.cluster_repo
+            .get_payload_encoding(&cluster_id, &topic)
+            .await
+    }
+
+    /// Overrides how a topic's payloads are unwrapped for display; `Auto`
+    /// restores detection.
+    pub async fn set_payload_encoding(
+        &self,
+        cluster_id: Uuid,
+        topic: String,
+        encoding: PayloadEncoding,
+    ) -> Result<()> {
+        self.cluster_repo
+            .set_payload_encoding(&cluster_id, &topic, encoding)
+            .await
+    }
+
+    /// Cipher for a topic with encryption configured, `None` otherwise.
+    async fn topic_cipher(&self, cluster_id: &Uuid, topic: &str) -> Result<Option<PayloadCipher>> {
+        if self
+            .cluster_repo
+            .get_topic_encryption(cluster_id, topic)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+        let key = self
+            .secret_repo
+            .get_password(&encryption_secret_key(cluster_id, topic))?;
+        Ok(Some(PayloadCipher::from_base64_key(&key)?))
+    }
+
+    /// Cipher for records published to a topic set to encrypt on publish.
+    async fn publish_cipher(
+        &self,
+        cluster_id: &Uuid,
+        topic: &str,
+    ) -> Result<Option<PayloadCipher>> {
+        match self
+            .cluster_repo
+            .get_topic_encryption(cluster_id, topic)
+            .await?
+        {
+            Some(encryption) if encryption.encrypt_on_publish => {
+                self.topic_cipher(cluster_id, topic).await
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Connects to the cluster ahead of use, called when it is selected.
+    pub async fn warm_up_cluster(&self, id: Uuid) -> Result<()> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.kafka_infra.warm_up(&cluster, password).await?;
+        self.cluster_repo.record_cluster_connected(&id).await
+    }
+
+    /// State of the cluster's cached OAUTHBEARER token. Does not fetch one.
+    pub async fn get_token_status(&self, id: Uuid) -> Result<TokenStatus> {
+        let clusters = self.cluster_repo.list_clusters().await?;
+        if !clusters.iter().any(|c| c.id == id) {
+            return Err(anyhow::anyhow!("Cluster not found"));
+        }
+        Ok(self.kafka_infra.oauth_token_status(id))
+    }
+
+    /// librdkafka log lines captured for the cluster since the app started,
+    /// oldest first.
+    pub fn get_client_logs(&self, id: Uuid) -> Vec<ClientLogLine> {
+        self.kafka_infra.client_logs(id)
+    }
+
+    pub fn clear_client_logs(&self, id: Uuid) {
+        self.kafka_infra.clear_client_logs(id)
+    }
+
+    /// Enables librdkafka `debug` contexts for the cluster's clients, or
+    /// turns debug logging off with an empty list. Pooled clients pick the
+    /// change up the next time they are used.
+    pub async fn set_client_debug(&self, id: Uuid, contexts: Vec<String>) -> Result<()> {
+        let mut contexts: Vec<String> = contexts
+            .iter()
+            .map(|c| c.trim().to_lowercase())
+            .filter(|c| !c.is_empty())
+            .collect();
+        contexts.sort();
+        contexts.dedup();
+        if let Some(unknown) = contexts
+            .iter()
+            .find(|c| !DEBUG_CONTEXTS.contains(&c.as_str()))
+        {
+            return Err(anyhow::anyhow!(
+                "Unknown debug context '{}'; expected one of {}",
+                unknown,
+                DEBUG_CONTEXTS.join(", ")
+            ));
+        }
+        self.cluster_repo.set_client_debug(&id, &contexts).await
+    }
+
+    pub async fn check_connection(&self, id: Uuid) -> Result<()> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.kafka_infra
+            .check_connection(&cluster, password)
+            .await?;
+        self.cluster_repo.record_cluster_connected(&id).await
+    }
+
+    /// Checks what the cluster's principal may do, so the UI can hide
+    /// actions that would fail with authorization errors.
+    pub async fn probe_permissions(&self, id: Uuid) -> Result<PermissionReport> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let checks = self
+            .kafka_infra
+            .probe_permissions(&cluster, password)
+            .await?;
+        let principal = match &cluster.security {
+            SecurityConfig::SaslSsl { username, .. } => Some(username.clone()),
+            _ => None,
+        };
+        Ok(PermissionReport {
+            cluster_id: cluster.id,
+            principal,
+            probed_at: now_millis(),
+            checks,
+        })
+    }
+
+    /// Fingerprints of the certificates the cluster's brokers serve, e.g. to
+    /// set up or review its certificate pin.
+    pub async fn inspect_broker_certificates(&self, id: Uuid) -> Result<Vec<BrokerCertificate>> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.kafka_infra
+            .inspect_certificates(&cluster, password)
+            .await
+    }
+
+    /// Pins the SHA-256 fingerprint brokers must serve, or removes the pin.
+    pub async fn set_certificate_pin(&self, id: Uuid, pin: Option<String>) -> Result<()> {
+        let pin = pin
+            .as_deref()
+            .map(normalize_fingerprint)
+            .filter(|p| !p.is_empty());
+        if let Some(pin) = &pin {
+            if pin.replace(':', "").len() != 64 {
+                return Err(anyhow::anyhow!(
+                    "A SHA-256 fingerprint has 64 hex digits, got '{}'",
+                    pin
+                ));
+            }
+        }
+        self.cluster_repo
+            .set_certificate_pin(&id, pin.as_deref())
+            .await
+    }
+
+    pub async fn consume_messages(
+        &self,
+        id: Uuid,
+        topic: String,
+        partition: Option<i32>,
+        seek: SeekMode,
+        max_messages: usize,
+        filter: MessageFilter,
+    ) -> Result<ConsumeResult> {
+        let matcher = filter.compile().map_err(|e| anyhow::anyhow!(e))?;
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let preferences = self
+            .cluster_repo
+            .get_topic_preferences(&id, &topic)
+            .await?
+            .unwrap_or_default();
+        let decoder = self.topic_decoder(&id, &topic, &preferences).await?;
+        let transforms = self.transforms.pipeline(&preferences.transforms)?;
+        let mut result = self
+            .kafka_infra
+            .consume_messages(
+                &cluster,
+                password,
+                &topic,
+                partition,
+                &seek,
+                max_messages,
+                &decoder,
+                &transforms,
+                preferences.dedupe.as_ref(),
+                &matcher,
+            )
+            .await?;
+        let max_payload_bytes = preferences
+            .max_payload_bytes
+            .unwrap_or(DEFAULT_MAX_PAYLOAD_BYTES);
+        for message in &mut result.messages {
+            message.truncate_payload(max_payload_bytes);
+        }
+        Ok(result)
+    }
+
+    /// One record in full, for opening a message whose payload the browser
+    /// received truncated.
+    pub async fn fetch_full_message(
+        &self,
+        id: Uuid,
+        topic: String,
+        partition: i32,
+        offset: i64,
+    ) -> Result<KafkaMessage> {
+        self.get_messages_around(id, topic, partition, offset, 0)
+            .await?
+            .into_iter()
+            .find(|m| m.offset == offset)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Offset {} of partition {} no longer exists",
+                    offset,
+                    partition
+                )
+            })
+    }
+
+    /// Records around an offset of one partition, e.g. the one an error
+    /// report points at, decoded like the message browser does.
+    pub async fn get_messages_around(
+        &self,
+        id: Uuid,
+        topic: String,
+        partition: i32,
+        offset: i64,
+        context: usize,
+    ) -> Result<Vec<KafkaMessage>> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let preferences = self
+            .cluster_repo
+            .get_topic_preferences(&id, &topic)
+            .await?
+            .unwrap_or_default();
+        let decoder = self.topic_decoder(&id, &topic, &preferences).await?;
+        self.kafka_infra
+            .get_messages_around(
+                &cluster, password, &topic, partition, offset, context, &decoder,
+            )
+            .await
+    }
+
+    /// Decoder for a topic's payloads: its cipher, text encoding and
+    /// deserializer plugin, if any.
+    async fn topic_decoder(
+        &self,
+        id: &Uuid,
+        topic: &str,
+        preferences: &TopicPreferences,
+    ) -> Result<PayloadDecoder> {
+        let mut decoder = PayloadDecoder::new(
+            self.topic_cipher(id, topic).await?,
+            self.cluster_repo.get_payload_encoding(id, topic).await?,
+        );
+        if let MessageDeserializer::Plugin(name) = &preferences.deserializer {
+            let plugin = self
+                .deserializers
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("Deserializer plugin {} is not installed", name))?;
+            decoder = decoder.with_plugin(plugin, topic);
+        }
+        Ok(decoder)
+    }
+
+    /// Fetches the latest records of the same topic from several clusters and
+    /// reports how they diverge, e.g. to validate dual writes during a
+    /// migration. Records are matched by key and payload, and only within the
+    /// time range every cluster's window covers whole, so that windows ending
+    /// at different offsets do not count as divergence.
+    pub async fn consume_across_clusters(
+        &self,
+        ids: Vec<Uuid>,
+        topic: String,
+        max_messages: usize,
+    ) -> Result<MultiClusterConsumeResult> {
+        if ids.len() < 2 {
+            return Err(anyhow::anyhow!("At least two clusters are required"));
+        }
+
+        // Records written once reading started may not be on every cluster
+        // yet.
+        let compared_until = now_millis();
+        let mut fetched = Vec::with_capacity(ids.len());
+        for id in &ids {
+            let result = self
+                .consume_messages(
+                    *id,
+                    topic.clone(),
+                    None,
+                    SeekMode::Latest,
+                    max_messages,
+                    MessageFilter::default(),
+                )
+                .await
+                .map_err(|e| anyhow::anyhow!("Cluster {}: {}", id, e))?;
+            // A partial window would show up as divergence that is not there.
+            if let Some(error) = result.errors.first() {
+                return Err(anyhow::anyhow!(
+                    "Cluster {}: fetch incomplete: {}",
+                    id,
+                    error.message
+                ));
+            }
+            fetched.push((*id, result.messages));
+        }
+
+        let compared_from = fetched
+            .iter()
+            .filter_map(|(_, messages)| window_start(messages))
+            .max();
+        let in_range = |msg: &KafkaMessage| {
+            msg.timestamp.is_some_and(|ts| {
+                compared_from.is_some_and(|from| ts >= from) && ts < compared_until
+            })
+        };
+
+        let mut occurrences: HashMap<(Option<String>, Option<String>), Vec<usize>> = HashMap::new();
+        let mut compared = vec![0; fetched.len()];
+        for (index, (_, messages)) in fetched.iter().enumerate() {
+            for msg in messages.iter().filter(|msg| in_range(msg)) {
+                compared[index] += 1;
+                let counts = occurrences
+                    .entry((msg.key.clone(), msg.payload.clone()))
+                    .or_insert_with(|| vec![0; fetched.len()]);
+                counts[index] += 1;
+            }
+        }
+
+        let mut matched = 0;
+        let mut unmatched = vec![0; fetched.len()];
+        for counts in occurrences.values() {
+            let common = counts.iter().copied().min().unwrap_or(0);
+            matched += common;
+            for (index, count) in counts.iter().enumerate() {
+                unmatched[index] += count - common;
+            }
+        }
+
+        let divergence = fetched
+            .iter()
+            .zip(compared.into_iter().zip(unmatched))
+            .map(
+                |((cluster_id, messages), (compared, unmatched))| ClusterDivergence {
+                    cluster_id: *cluster_id,
+                    fetched: messages.len(),
+                    compared,
+                    unmatched,
+                },
+            )
+            .collect();
+
+        let mut messages: Vec<ClusterMessage> = fetched
+            .into_iter()
+            .flat_map(|(cluster_id, messages)| {
+                messages.into_iter().map(move |message| ClusterMessage {
+                    cluster_id,
+                    message,
+                })
+            })
+            .collect();
+        messages.sort_by_key(|m| std::cmp::Reverse(m.message.timestamp));
+
+        Ok(MultiClusterConsumeResult {
+            messages,
+            matched,
+            divergence,
+            compared_from,
+            compared_until,
+        })
+    }
+
+    pub async fn get_topic_histogram(
+        &self,
+        id: Uuid,
+        topic: String,
+        from_ts: i64,
+        to_ts: i64,
+        bucket_ms: i64,
+    ) -> Result<Vec<HistogramBucket>> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.kafka_infra
+            .get_topic_histogram(&cluster, password, &topic, from_ts, to_ts, bucket_ms)
+            .await
+    }
+
+    pub async fn get_field_breakdown(
+        &self,
+        id: Uuid,
+        topic: String,
+        source: BreakdownSource,
+        sample_size: usize,
+    ) -> Result<FieldBreakdown> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.kafka_infra
+            .get_field_breakdown(&cluster, password, &topic, &source, sample_size)
+            .await
+    }
+
+    /// Groups Kafka Streams changelog and repartition topics under the
+    /// application id that owns them.
+    pub async fn list_streams_applications(&self, id: Uuid) -> Result<Vec<StreamsApplication>> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let topics = self
+            .kafka_infra
+            .list_topics(&cluster, password.clone())
+            .await?;
+        let group_ids = self
+            .kafka_infra
+            .list_group_ids(&cluster, password.clone())
+            .await?;
+
+        let internal: Vec<(Topic, (String, String, _))> = topics
+            .into_iter()
+            .filter_map(|t| {
+                let classified = classify_internal_topic(&t.name, &group_ids)?;
+                Some((t, classified))
+            })
+            .collect();
+
+        let names: Vec<String> = internal.iter().map(|(t, _)| t.name.clone()).collect();
+        let configs = self
+            .kafka_infra
+            .describe_topic_configs(&cluster, password, &names)
+            .await?;
+
+        let mut apps: HashMap<String, Vec<StreamsInternalTopic>> = HashMap::new();
+        for (topic, (application_id, store_name, role)) in internal {
+            let compacted = configs
+                .get(&topic.name)
+                .and_then(|entries| entries.iter().find(|e| e.name == "cleanup.policy"))
+                .and_then(|e| e.value.as_deref())
+                .map(|policy| policy.contains("compact"))
+                .unwrap_or(false);
+            apps.entry(application_id)
+                .or_default()
+                .push(StreamsInternalTopic {
+                    name: topic.name,
+                    role,
+                    store_name,
+                    partitions: topic.partitions,
+                    compacted,
+                });
+        }
+
+        let mut apps: Vec<StreamsApplication> = apps
+            .into_iter()
+            .map(|(application_id, mut topics)| {
+                topics.sort_by(|a, b| a.name.cmp(&b.name));
+                StreamsApplication {
+                    application_id,
+                    topics,
+                }
+            })
+            .collect();
+        apps.sort_by(|a, b| a.application_id.cmp(&b.application_id));
+        Ok(apps)
+    }
+
+    pub async fn get_state_store_snapshot(
+        &self,
+        id: Uuid,
+        topic: String,
+        limit: usize,
+    ) -> Result<StateStoreSnapshot> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.kafka_infra
+            .read_state_store(&cluster, password, &topic, limit)
+            .await
+    }
+
+    /// A page of the latest record per key of `topic`, optionally only keys
+    /// containing `key_filter`. The topic is scanned once and the table
+    /// reused while paging, unless `refresh` asks for a new scan.
+    pub async fn materialize_topic_by_key(
+        &self,
+        id: Uuid,
+        topic: String,
+        offset: usize,
+        limit: usize,
+        key_filter: Option<String>,
+        refresh: bool,
+    ) -> Result<KeyedTablePage> {
+        if limit == 0 {
+            return Err(anyhow::anyhow!("Page size must be at least 1"));
+        }
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let table = self
+            .kafka_infra
+            .materialize_topic_by_key(&cluster, password, &topic, refresh)
+            .await?;
+        let key_filter = key_filter.filter(|f| !f.is_empty());
+        let matching: Vec<&StateStoreEntry> = table
+            .entries
+            .iter()
+            .filter(|e| match &key_filter {
+                Some(filter) => e
+                    .key
+                    .as_deref()
+                    .is_some_and(|k| k.contains(filter.as_str())),
+                None => true,
+            })
+            .collect();
+
+        Ok(KeyedTablePage {
+            topic,
+            entries: matching
+                .iter()
+                .skip(offset)
+                .take(limit)
+                .map(|e| (*e).clone())
+                .collect(),
+            offset,
+            total_keys: matching.len(),
+            records_scanned: table.records_scanned,
+            deleted_keys: table.deleted_keys,
+            complete: table.complete,
+        })
+    }
+
+    pub async fn get_mirroring_status(&self, id: Uuid) -> Result<MirroringStatus> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let internal_topics = self
+            .kafka_infra
+            .list_topics(&cluster, password.clone())
+            .await?
+            .iter()
+            .filter_map(|t| classify_mm2_topic(&t.name))
+            .collect();
+
+        self.kafka_infra
+            .get_mirroring_status(&cluster, password, internal_topics)
+            .await
+    }
+
+    pub async fn get_quota_utilization(&self, id: Uuid) -> Result<QuotaUtilization> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+        self.kafka_infra
+            .get_quota_utilization(&cluster, password)
+            .await
+    }
+
+    /// Computes where `group_id` should resume on `dest_id` after failing over
+    /// from `source_id`. MirrorMaker 2 checkpoints on the destination are used
+    /// when present; otherwise the timestamps of the group's next records on
+    /// the source are looked up on the destination.
+    ///
+    /// `source_alias` is the source cluster's alias in the MirrorMaker 2
+    /// configuration and defaults to its name. Only checkpoints of that alias
+    /// and topics replicated as `<alias>.<topic>` are considered.
+    pub async fn translate_offsets(
+        &self,
+        source_id: Uuid,
+        dest_id: Uuid,
+        group_id: String,
+        source_alias: Option<String>,
+    ) -> Result<OffsetTranslation> {
+        let (source, source_password) = self.cluster_with_password(source_id).await?;
+        let (dest, dest_password) = self.cluster_with_password(dest_id).await?;
+        let alias = source_alias.unwrap_or_else(|| source.name.clone());
+        let remote_prefix = format!("{}.", alias);
+
+        let dest_topics = self
+            .kafka_infra
+            .list_topics(&dest, dest_password.clone())
+            .await?;
+        let checkpoint_topics: Vec<_> = dest_topics
+            .iter()
+            .filter_map(|t| classify_mm2_topic(&t.name))
+            .filter(|t| {
+                t.kind == Mm2TopicKind::Checkpoints && t.alias.as_deref() == Some(alias.as_str())
+            })
+            .collect();
+
+        if !checkpoint_topics.is_empty() {
+            let status = self
+                .kafka_infra
+                .get_mirroring_status(&dest, dest_password.clone(), checkpoint_topics)
+                .await?;
+            let offsets: Vec<TranslatedOffset> = status
+                .checkpoints
+                .into_iter()
+                .filter(|cp| cp.consumer_group_id == group_id)
+                .map(|cp| TranslatedOffset {
+                    source_topic: cp
+                        .topic
+                        .strip_prefix(&remote_prefix)
+                        .unwrap_or(&cp.topic)
+                        .to_string(),
+                    destination_topic: cp.topic,
+                    partition: cp.partition,
+                    source_offset: cp.upstream_offset,
+                    destination_offset: cp.downstream_offset,
+                })
+                .collect();
+            if !offsets.is_empty() {
+                return Ok(OffsetTranslation {
+                    group_id,
+                    method: TranslationMethod::Checkpoint,
+                    offsets,
+                    unmatched_topics: Vec::new(),
+                });
+            }
+        }
+
+        let committed = self
+            .kafka_infra
+            .fetch_committed_offsets(&source, source_password.clone(), &group_id)
+            .await?;
+        if committed.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Group '{}' has no committed offsets on the source cluster",
+                group_id
+            ));
+        }
+        let timestamps = self
+            .kafka_infra
+            .fetch_record_timestamps(&source, source_password, &committed)
+            .await?;
+
+        let mut by_topic: HashMap<String, Vec<(i32, i64, Option<i64>)>> = HashMap::new();
+        for c in &committed {
+            let ts = timestamps
+                .get(&(c.topic.clone(), c.partition))
+                .copied()
+                .flatten();
+            by_topic
+                .entry(c.topic.clone())
+                .or_default()
+                .push((c.partition, c.offset, ts));
+        }
+
+        let mut offsets = Vec::new();
+        let mut unmatched_topics = Vec::new();
+        for (topic, partitions) in by_topic {
+            // Identity replication keeps the name; the default policy prefixes
+            // it with the source alias.
+            let remote_topic = format!("{}{}", remote_prefix, topic);
+            let Some(dest_topic) = dest_topics
+                .iter()
+                .find(|t| t.name == topic)
+                .or_else(|| dest_topics.iter().find(|t| t.name == remote_topic))
+            else {
+                unmatched_topics.push(topic);
+                continue;
+            };
+
+            let requested: Vec<(i32, Option<i64>)> =
+                partitions.iter().map(|(p, _, ts)| (*p, *ts)).collect();
+            let resolved = self
+                .kafka_infra
+                .resolve_offsets_for_times(
+                    &dest,
+                    dest_password.clone(),
+                    &dest_topic.name,
+                    &requested,
+                )
+                .await?;
+
+            for (partition, source_offset, _) in partitions {
+                if let Some(destination_offset) = resolved.get(&partition) {
+                    offsets.push(TranslatedOffset {
+                        source_topic: topic.clone(),
+                        destination_topic: dest_topic.name.clone(),
+                        partition,
+                        source_offset,
+                        destination_offset: *destination_offset,
+                    });
+                }
+            }
+        }
+
+        offsets.sort_by(|a, b| {
+            (&a.destination_topic, a.partition).cmp(&(&b.destination_topic, b.partition))
+        });
+        unmatched_topics.sort();
+
+        Ok(OffsetTranslation {
+            group_id,
+            method: TranslationMethod::Timestamp,
+            offsets,
+            unmatched_topics,
+        })
+    }
+
+    /// Compares partitions, replication factor and key configs of `topic`
+    /// across clusters. A row drifts when any two clusters disagree, including
+    /// when the topic is missing from some of them.
+    pub async fn check_environment_drift(
+        &self,
+        topic: String,
+        ids: Vec<Uuid>,
+    ) -> Result<EnvironmentDrift> {
+        if ids.len() < 2 {
+            return Err(anyhow::anyhow!("At least two clusters are required"));
+        }
+
+        let mut clusters = Vec::with_capacity(ids.len());
+        let mut columns: Vec<HashMap<String, String>> = Vec::with_capacity(ids.len());
+        for id in ids {
+            let (cluster, password) = self.cluster_with_password(id).await?;
+            let described = self
+                .kafka_infra
+                .describe_topic(&cluster, password.clone(), &topic)
+                .await
+                .map_err(|e| anyhow::anyhow!("Cluster {}: {}", cluster.name, e))?;
+
+            let mut values = HashMap::new();
+            if let Some(t) = &described {
+                values.insert("partitions".to_string(), t.partitions.to_string());
+                values.insert(
+                    "replication.factor".to_string(),
+                    t.replication_factor.to_string(),
+                );
+                let configs = self
+                    .kafka_infra
+                    .describe_topic_configs(&cluster, password, std::slice::from_ref(&topic))
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Cluster {}: {}", cluster.name, e))?;
+                for entry in configs
+                    .into_values()
+                    .flatten()
+                    .filter(|e| DRIFT_CONFIG_KEYS.contains(&e.name.as_str()))
+                {
+                    if let Some(value) = entry.value {
+                        values.insert(entry.name, value);
+                    }
+                }
+            }
+
+            clusters.push(DriftCluster {
+                cluster_id: cluster.id,
+                cluster_name: cluster.name,
+                topic_exists: described.is_some(),
+            });
+            columns.push(values);
+        }
+
+        let rows: Vec<DriftRow> = ["partitions", "replication.factor"]
+            .iter()
+            .chain(DRIFT_CONFIG_KEYS)
+            .map(|property| {
+                let values: Vec<Option<String>> = columns
+                    .iter()
+                    .map(|column| column.get(*property).cloned())
+                    .collect();
+                let drifted = values.iter().any(|v| v != &values[0]);
+                DriftRow {
+                    property: property.to_string(),
+                    values,
+                    drifted,
+                }
+            })
+            .collect();
+        let drifted = rows.iter().any(|row| row.drifted);
+
+        Ok(EnvironmentDrift {
+            topic,
+            clusters,
+            rows,
+            drifted,
+        })
+    }
+
+    /// Partition count, replication factor and every config entry of a topic
+    /// compared between two clusters, e.g. staging and prod.
+    pub async fn diff_topic_across_clusters(
+        &self,
+        topic: String,
+        left_id: Uuid,
+        right_id: Uuid,
+    ) -> Result<TopicConfigDiff> {
+        if left_id == right_id {
+            return Err(anyhow::anyhow!("Two different clusters are required"));
+        }
+        let (left, left_topic, left_configs) = self.topic_settings(left_id, &topic).await?;
+        let (right, right_topic, right_configs) = self.topic_settings(right_id, &topic).await?;
+
+        let configs = diff_config_entries(&left_configs, &right_configs);
+        let left_partitions = left_topic.as_ref().map(|t| t.partitions);
+        let right_partitions = right_topic.as_ref().map(|t| t.partitions);
+        let left_replication_factor = left_topic.as_ref().map(|t| t.replication_factor);
+        let right_replication_factor = right_topic.as_ref().map(|t| t.replication_factor);
+        let identical = left.topic_exists == right.topic_exists
+            && left_partitions == right_partitions
+            && left_replication_factor == right_replication_factor
+            && configs.is_empty();
+
+        Ok(TopicConfigDiff {
+            topic,
+            left,
+            right,
+            left_partitions,
+            right_partitions,
+            left_replication_factor,
+            right_replication_factor,
+            configs,
+            identical,
+        })
+    }
+
+    /// A topic's metadata and config entries on one cluster, both empty when
+    /// the topic does not exist there.
+    async fn topic_settings(
+        &self,
+        id: Uuid,
+        topic: &str,
+    ) -> Result<(DriftCluster, Option<Topic>, Vec<TopicConfigEntry>)> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+        let fail = |e: anyhow::Error| anyhow::anyhow!("Cluster {}: {}", cluster.name, e);
+
+        let described = self
+            .kafka_infra
+            .describe_topic(&cluster, password.clone(), topic)
+            .await
+            .map_err(fail)?;
+        let configs = match described {
+            Some(_) => self
+                .kafka_infra
+                .describe_topic_configs(&cluster, password, &[topic.to_string()])
+                .await
+                .map_err(fail)?
+                .remove(topic)
+                .unwrap_or_default(),
+            None => Vec::new(),
+        };
+
+        let side = DriftCluster {
+            cluster_id: cluster.id,
+            cluster_name: cluster.name.clone(),
+            topic_exists: described.is_some(),
+        };
+        Ok((side, described, configs))
+    }
+
+    /// Serializes every ACL binding on the cluster to a YAML document.
+    pub async fn export_acls(&self, id: Uuid) -> Result<String> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+        ensure_supported(&cluster, ClusterFeature::Acls)?;
+
+        let mut acls = self.kafka_infra.describe_acls(&cluster, password).await?;
+        acls.sort();
+        Ok(serde_yaml::to_string(&AclDocument { acls })?)
+    }
+
+    /// Creates the application's missing topics with broker defaults, a SCRAM
+    /// user named after it and the ACLs its role needs. A failure undoes the
+    /// steps already taken, newest first.
+    pub async fn provision_application(
+        &self,
+        id: Uuid,
+        app_name: String,
+        topics: Vec<String>,
+        role: ApplicationRole,
+    ) -> Result<ProvisionedApplication> {
+        let app_name = app_name.trim().to_string();
+        if app_name.is_empty() {
+            return Err(anyhow::anyhow!("Application name must not be empty"));
+        }
+        let mut topics: Vec<String> = topics
+            .iter()
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty())
+            .collect();
+        topics.sort();
+        topics.dedup();
+        if topics.is_empty() {
+            return Err(anyhow::anyhow!("At least one topic is required"));
+        }
+
+        let (cluster, password) = self.cluster_with_password(id).await?;
+        ensure_supported(&cluster, ClusterFeature::Acls)?;
+        let mechanism = match &cluster.security {
+            SecurityConfig::SaslSsl {
+                mechanism: mechanism @ (SaslMechanism::ScramSha256 | SaslMechanism::ScramSha512),
+                ..
+            } => mechanism.clone(),
+            _ => SaslMechanism::ScramSha512,
+        };
+
+        if self
+            .kafka_infra
+            .scram_user_exists(&cluster, password.clone(), app_name.clone())
+            .await?
+        {
+            return Err(anyhow::anyhow!("User {} already exists", app_name));
+        }
+
+        let existing_topics: HashSet<String> = self
+            .kafka_infra
+            .list_topics(&cluster, password.clone())
+            .await?
+            .into_iter()
+            .map(|t| t.name)
+            .collect();
+        let existing_acls: HashSet<AclBinding> = self
+            .kafka_infra
+            .describe_acls(&cluster, password.clone())
+            .await?
+            .into_iter()
+            .collect();
+
+        let principal = format!("User:{}", app_name);
+        let acls: Vec<AclBinding> = application_acls(&principal, &app_name, &topics, role)
+            .into_iter()
+            .filter(|acl| !existing_acls.contains(acl))
+            .collect();
+        let user_password = Uuid::new_v4().simple().to_string();
+
+        let mut done = Vec::new();
+        let outcome: Result<()> = async {
+            for topic in topics.iter().filter(|t| !existing_topics.contains(*t)) {
+                self.kafka_infra
+                    .create_topic(
+                        &cluster,
+                        password.clone(),
+                        topic.clone(),
+                        -1,
+                        -1,
+                        &HashMap::new(),
+                    )
+                    .await?;
+                done.push(ProvisionStep::Topic(topic.clone()));
+            }
+
+            self.kafka_infra
+                .upsert_scram_user(
+                    &cluster,
+                    password.clone(),
+                    app_name.clone(),
+                    mechanism.clone(),
+                    user_password.clone(),
+                )
+                .await?;
+            done.push(ProvisionStep::ScramUser);
+
+            // Recorded up front: a partially applied request still needs undoing.
+            done.push(ProvisionStep::Acls);
+            self.kafka_infra
+                .create_acls(&cluster, password.clone(), acls.clone())
+                .await
+        }
+        .await;
+
+        if let Err(e) = outcome {
+            let mut failures = Vec::new();
+            for step in done.into_iter().rev() {
+                let undone = match &step {
+                    ProvisionStep::Topic(topic) => {
+                        self.kafka_infra
+                            .delete_topic(&cluster, password.clone(), topic.clone())
+                            .await
+                    }
+                    ProvisionStep::ScramUser => {
+                        self.kafka_infra
+                            .delete_scram_user(
+                                &cluster,
+                                password.clone(),
+                                app_name.clone(),
+                                mechanism.clone(),
+                            )
+                            .await
+                    }
+                    ProvisionStep::Acls => {
+                        self.kafka_infra
+                            .delete_acls(&cluster, password.clone(), acls.clone())
+                            .await
+                    }
+                };
+                if let Err(undo_error) = undone {
+                    eprintln!("Failed to roll back {:?}: {}", step, undo_error);
+                    failures.push(format!("{:?}", step));
+                }
+            }
+
+            return Err(if failures.is_empty() {
+                anyhow::anyhow!("Provisioning failed and was rolled back: {}", e)
+            } else {
+                anyhow::anyhow!(
+                    "Provisioning failed: {}; rollback incomplete for {}",
+                    e,
+                    failures.join(", ")
+                )
+            });
+        }
+
+        Ok(ProvisionedApplication {
+            principal,
+            username: app_name,
+            password: user_password,
+            mechanism,
+            created_topics: topics
+                .into_iter()
+                .filter(|t| !existing_topics.contains(t))
+                .collect(),
+            created_acls: acls,
+        })
+    }
+
+    /// Compares an exported ACL document with the cluster and, unless
+    /// `dry_run` is set, creates the bindings the cluster is missing.
+    pub async fn import_acls(
+        &self,
+        id: Uuid,
+        yaml: String,
+        dry_run: bool,
+    ) -> Result<AclImportPlan> {
+        let document: AclDocument = serde_yaml::from_str(&yaml)
+            .map_err(|e| anyhow::anyhow!("Invalid ACL document: {}", e))?;
+        let (cluster, password) = self.cluster_with_password(id).await?;
+        ensure_supported(&cluster, ClusterFeature::Acls)?;
+
+        let existing = self
+            .kafka_infra
+            .describe_acls(&cluster, password.clone())
+            .await?;
+        let existing_set: HashSet<_> = existing.iter().collect();
+        let wanted: HashSet<_> = document.acls.iter().collect();
+
+        let mut to_create: Vec<_> = wanted
+            .iter()
+            .filter(|acl| !existing_set.contains(*acl))
+            .map(|acl| (*acl).clone())
+            .collect();
+        to_create.sort();
+        let mut only_on_cluster: Vec<_> = existing
+            .iter()
+            .filter(|acl| !wanted.contains(acl))
+            .cloned()
+            .collect();
+        only_on_cluster.sort();
+        let already_present = wanted.len() - to_create.len();
+
+        if !dry_run {
+            self.kafka_infra
+                .create_acls(&cluster, password, to_create.clone())
+                .await?;
+        }
+
+        Ok(AclImportPlan {
+            to_create,
+            already_present,
+            only_on_cluster,
+            applied: !dry_run,
+        })
+    }
+
+    /// Creates and updates topics to match a YAML or JSON spec file, topic
+    /// by topic, returning what was planned for each and any failure.
+    /// Partitions are only ever added and replication factors only reported;
+    /// topics missing from the file are left alone.
+    pub async fn apply_topics_from_file(
+        &self,
+        id: Uuid,
+        path: String,
+        dry_run: bool,
+    ) -> Result<TopicSpecPlan> {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+        // YAML is a superset of JSON, so one parser reads both.
+        let document: TopicSpecDocument = serde_yaml::from_str(&text)
+            .map_err(|e| anyhow::anyhow!("Invalid topic spec: {}", e))?;
+        let mut names = HashSet::new();
+        for spec in &document.topics {
+            if spec.name.trim().is_empty() {
+                return Err(anyhow::anyhow!("Topic names must not be empty"));
+            }
+            if spec.partitions < 1 {
+                return Err(anyhow::anyhow!(
+                    "{}: partitions must be at least 1",
+                    spec.name
+                ));
+            }
+            if !names.insert(spec.name.as_str()) {
+                return Err(anyhow::anyhow!("{} is listed more than once", spec.name));
+            }
+        }
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let existing: HashMap<String, Topic> = self
+            .kafka_infra
+            .list_topics(&cluster, password.clone())
+            .await?
+            .into_iter()
+            .map(|t| (t.name.clone(), t))
+            .collect();
+        let existing_names: Vec<String> = document
+            .topics
+            .iter()
+            .filter(|spec| existing.contains_key(&spec.name))
+            .map(|spec| spec.name.clone())
+            .collect();
+        let mut current_configs = self
+            .kafka_infra
+            .describe_topic_configs(&cluster, password.clone(), &existing_names)
+            .await?;
+
+        let mut changes = Vec::with_capacity(document.topics.len());
+        for spec in document.topics {
+            let mut change = TopicSpecChange {
+                topic: spec.name.clone(),
+                action: TopicSpecAction::Create,
+                current_partitions: None,
+                partitions: spec.partitions,
+                configs: Vec::new(),
+                problems: Vec::new(),
+                error: None,
+            };
+            let mut configs: Vec<(String, String)> = spec.configs.into_iter().collect();
+            configs.sort();
+
+            let Some(topic) = existing.get(&spec.name) else {
+                change.configs = configs
+                    .iter()
+                    .map(|(name, value)| ConfigEntryDiff {
+                        name: name.clone(),
+                        left: None,
+                        right: Some(value.clone()),
+                    })
+                    .collect();
+                if !dry_run {
+                    let configs: HashMap<String, String> = configs.into_iter().collect();
+                    change.error = self
+                        .kafka_infra
+                        .create_topic(
+                            &cluster,
+                            password.clone(),
+                            spec.name,
+                            spec.partitions,
+                            spec.replication_factor.unwrap_or(-1),
+                            &configs,
+                        )
+                        .await
+                        .err()
+                        .map(|e| e.to_string());
+                }
+                changes.push(change);
+                continue;
+            };
+
+            change.current_partitions = Some(topic.partitions);
+            if spec.partitions < topic.partitions {
+                change.problems.push(format!(
+                    "Partitions cannot be reduced from {} to {}",
+                    topic.partitions, spec.partitions
+                ));
+            }
+            if let Some(rf) = spec
+                .replication_factor
+                .filter(|rf| *rf != topic.replication_factor)
+            {
+                change.problems.push(format!(
+                    "Replication factor is {}, not {}; changing it needs a partition reassignment",
+                    topic.replication_factor, rf
+                ));
+            }
+            let current: HashMap<String, Option<String>> = current_configs
+                .remove(&spec.name)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|entry| (entry.name, entry.value))
+                .collect();
+            change.configs = configs
+                .into_iter()
+                .filter_map(|(name, value)| {
+                    let left = current.get(&name).cloned().flatten();
+                    (left.as_deref() != Some(value.as_str())).then_some(ConfigEntryDiff {
+                        name,
+                        left,
+                        right: Some(value),
+                    })
+                })
+                .collect();
+
+            let add_partitions = spec.partitions > topic.partitions;
+            if !add_partitions && change.configs.is_empty() {
+                change.action = TopicSpecAction::Unchanged;
+                changes.push(change);
+                continue;
+            }
+            change.action = TopicSpecAction::Update;
+            if !dry_run {
+                let outcome: Result<()> = async {
+                    if add_partitions {
+                        self.kafka_infra
+                            .create_partitions(
+                                &cluster,
+                                password.clone(),
+                                &spec.name,
+                                spec.partitions as usize,
+                            )
+                            .await?;
+                    }
+                    if !change.configs.is_empty() {
+                        let configs: HashMap<String, String> = change
+                            .configs
+                            .iter()
+                            .filter_map(|c| Some((c.name.clone(), c.right.clone()?)))
+                            .collect();
+                        self.kafka_infra
+                            .alter_topic_configs(&cluster, password.clone(), &spec.name, &configs)
+                            .await?;
+                    }
+                    Ok(())
+                }
+                .await;
+                change.error = outcome.err().map(|e| e.to_string());
+            }
+            changes.push(change);
+        }
+
+        Ok(TopicSpecPlan {
+            changes,
+            applied: !dry_run,
+        })
+    }
+
+    pub async fn get_key_timeline(
+        &self,
+        id: Uuid,
+        topic: String,
+        key: String,
+        limit: usize,
+    ) -> Result<KeyTimeline> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.kafka_infra
+            .get_key_timeline(&cluster, password, &topic, &key, limit)
+            .await
+    }
+
+    pub async fn get_topic_message_count(&self, id: Uuid, topic: String) -> Result<i64> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        self.kafka_infra
+            .get_topic_message_count(&cluster, password, &topic)
+            .await
+    }
+
+    pub async fn create_test_consumer(
+        &self,
+        id: Uuid,
+        topic: String,
+        group_id: String,
+        from: AutoOffsetReset,
+    ) -> Result<SessionInfo> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let consumer = self
+            .kafka_infra
+            .create_test_consumer(&cluster, password, &topic, &group_id, from)?;
+
+        Ok(self
+            .sessions
+            .spawn(SessionKind::TestConsumer, cluster.id, topic, None, |ctx| {
+                consumer.run(ctx)
+            }))
+    }
+
+    pub async fn watch_key(
+        &self,
+        id: Uuid,
+        topic: String,
+        key: String,
+        buffer: TailBufferLimits,
+    ) -> Result<SessionInfo> {
+        if buffer.max_messages == 0 || buffer.max_bytes == 0 {
+            return Err(anyhow::anyhow!("Buffer limits must be greater than zero"));
+        }
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let watcher = self
+            .kafka_infra
+            .create_key_watcher(&cluster, password, &topic, &key, buffer)?;
+        let partition = watcher.partition();
+        let events = self.events.clone();
+
+        Ok(self.sessions.spawn(
+            SessionKind::KeyWatch,
+            cluster.id,
+            topic,
+            Some(partition),
+            |ctx| watcher.run(ctx, events),
+        ))
+    }
+
+    /// Streams records appended to `topic` from now on as `message-received`
+    /// events until stopped, delivering at most `buffer` between deliveries.
+    pub async fn start_tail(
+        &self,
+        id: Uuid,
+        topic: String,
+        buffer: TailBufferLimits,
+    ) -> Result<SessionInfo> {
+        if buffer.max_messages == 0 || buffer.max_bytes == 0 {
+            return Err(anyhow::anyhow!("Buffer limits must be greater than zero"));
+        }
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let tail = self
+            .kafka_infra
+            .create_topic_tail(&cluster, password, &topic, buffer)?;
+        let events = self.events.clone();
+
+        Ok(self
+            .sessions
+            .spawn(SessionKind::Tail, cluster.id, topic, None, |ctx| {
+                tail.run(ctx, events)
+            }))
+    }
+
+    pub fn stop_tail(&self, session_id: Uuid) -> Result<()> {
+        self.sessions.stop(session_id)
+    }
+
+    /// Scans `topic` in the background for records keyed `key`, emitting
+    /// `key-search-match` per record found and `key-search-progress` as it
+    /// goes. With `hashed_partition_only`, only the partition the default
+    /// partitioner assigns the key is scanned.
+    pub async fn search_by_key(
+        &self,
+        id: Uuid,
+        topic: String,
+        key: String,
+        hashed_partition_only: bool,
+    ) -> Result<SessionInfo> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let search = self.kafka_infra.create_key_search(
+            &cluster,
+            password,
+            &topic,
+            &key,
+            hashed_partition_only,
+        )?;
+        let events = self.events.clone();
+
+        Ok(self
+            .sessions
+            .spawn(SessionKind::KeySearch, cluster.id, topic, None, |ctx| {
+                search.run(ctx, events)
+            }))
+    }
+
+    pub fn cancel_key_search(&self, session_id: Uuid) -> Result<()> {
+        self.sessions.stop(session_id)
+    }
+
+    /// Copies `source` into `destination` in the background, passing each
+    /// payload through `transforms` and computing its key with `rekey`. Only
+    /// records present when the replay starts are copied.
+    pub async fn start_replay(
+        &self,
+        id: Uuid,
+        source: String,
+        destination: String,
+        rekey: RekeyStrategy,
+        transforms: Vec<String>,
+    ) -> Result<SessionInfo> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let transforms = self.transforms.pipeline(&transforms)?;
+        let receipts = self.receipt_log(&cluster, &destination)?;
+        let replay = self.kafka_infra.create_replay(
+            &cluster,
+            password,
+            &source,
+            &destination,
+            rekey,
+            transforms,
+            receipts,
+        )?;
+        let events = self.events.clone();
+
+        Ok(self
+            .sessions
+            .spawn(SessionKind::Replay, cluster.id, source, None, |ctx| {
+                replay.run(ctx, events)
+            }))
+    }
+
+    pub fn stop_replay(&self, session_id: Uuid) -> Result<()> {
+        self.sessions.stop(session_id)
+    }
+
+    /// Exports a topic into one JSON-lines file per shard under `directory`,
+    /// plus a manifest, emitting `export-finished` when done. Passing
+    /// `shards` re-exports only those files, e.g. after one failed
+    /// downstream, and updates their manifest entries.
+    pub async fn start_topic_export(
+        &self,
+        id: Uuid,
+        topic: String,
+        directory: String,
+        sharding: ExportSharding,
+        shards: Option<Vec<String>>,
+    ) -> Result<SessionInfo> {
+        if let ExportSharding::TimeBucket { bucket_ms } = sharding {
+            if bucket_ms <= 0 {
+                return Err(anyhow::anyhow!("Bucket size must be positive"));
+            }
+        }
+        if directory.trim().is_empty() {
+            return Err(anyhow::anyhow!("An export directory is required"));
+        }
+        let only: Option<HashSet<String>> = shards
+            .filter(|shards| !shards.is_empty())
+            .map(|shards| shards.into_iter().collect());
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let export = self.kafka_infra.create_topic_export(
+            &cluster,
+            password,
+            &topic,
+            directory.trim().into(),
+            sharding,
+            only,
+        )?;
+        let events = self.events.clone();
+
+        Ok(self
+            .sessions
+            .spawn(SessionKind::Export, cluster.id, topic, None, |ctx| {
+                export.run(ctx, events)
+            }))
+    }
+
+    pub fn stop_topic_export(&self, session_id: Uuid) -> Result<()> {
+        self.sessions.stop(session_id)
+    }
+
+    /// Writes `message_count` sequence-numbered records to `topic` through a
+    /// transactional producer while reading them back read_committed, then
+    /// emits an `EosReport` of duplicates, gaps and reordering.
+    pub async fn start_eos_verification(
+        &self,
+        id: Uuid,
+        topic: String,
+        message_count: u64,
+    ) -> Result<SessionInfo> {
+        if message_count == 0 {
+            return Err(anyhow::anyhow!("Message count must be at least one"));
+        }
+        let (cluster, password) = self.cluster_with_password(id).await?;
+        ensure_supported(&cluster, ClusterFeature::Transactions)?;
+
+        let receipts = self.receipt_log(&cluster, &topic)?;
+        let verification = self.kafka_infra.create_eos_verification(
+            &cluster,
+            password,
+            &topic,
+            message_count,
+            receipts,
+        )?;
+        let events = self.events.clone();
+
+        Ok(self.sessions.spawn(
+            SessionKind::EosVerification,
+            cluster.id,
+            topic,
+            None,
+            |ctx| verification.run(ctx, events),
+        ))
+    }
+
+    pub fn stop_eos_verification(&self, session_id: Uuid) -> Result<()> {
+        self.sessions.stop(session_id)
+    }
+
+    /// Starts recording the lag of `groups` (every group when empty) every
+    /// `interval_secs`, keeping samples for `LAG_SAMPLE_RETENTION_MS`.
+    pub async fn start_lag_sampler(
+        &self,
+        id: Uuid,
+        groups: Vec<String>,
+        interval_secs: u64,
+    ) -> Result<SessionInfo> {
+        if interval_secs == 0 {
+            return Err(anyhow::anyhow!(
+                "Sampling interval must be at least one second"
+            ));
+        }
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let sampler = self.kafka_infra.create_lag_sampler(
+            &cluster,
+            password,
+            groups,
+            Duration::from_secs(interval_secs),
+            LAG_SAMPLE_RETENTION_MS,
+            self.cluster_repo.clone(),
+        );
+
+        Ok(self.sessions.spawn(
+            SessionKind::LagSampler,
+            cluster.id,
+            String::new(),
+            None,
+            |ctx| sampler.run(ctx),
+        ))
+    }
+
+    pub fn stop_lag_sampler(&self, session_id: Uuid) -> Result<()> {
+        self.sessions.stop(session_id)
+    }
+
+    /// Re-runs a read every `interval_secs` and emits `refresh-update` with
+    /// the rows that changed, so views don't poll on their own.
+    pub async fn subscribe_refresh(
+        &self,
+        id: Uuid,
+        target: RefreshTarget,
+        interval_secs: u64,
+    ) -> Result<SessionInfo> {
+        if interval_secs == 0 {
+            return Err(anyhow::anyhow!(
+                "Refresh interval must be at least one second"
+            ));
+        }
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let topic = target.topic().to_string();
+        let poller = self.kafka_infra.create_refresh_poller(
+            &cluster,
+            password,
+            target,
+            Duration::from_secs(interval_secs),
+            self.events.clone(),
+        )?;
+
+        Ok(self
+            .sessions
+            .spawn(SessionKind::Refresh, cluster.id, topic, None, |ctx| {
+                poller.run(ctx)
+            }))
+    }
+
+    pub fn unsubscribe_refresh(&self, session_id: Uuid) -> Result<()> {
+        self.sessions.stop(session_id)
+    }
+
+    /// Polls the watermarks of `topic` every `interval_ms` and emits
+    /// `watermark-update` whenever they move, so the topic view follows
+    /// appends and retention without polling itself.
+    pub async fn start_watermark_watch(
+        &self,
+        id: Uuid,
+        topic: String,
+        interval_ms: Option<u64>,
+    ) -> Result<SessionInfo> {
+        let interval_ms = interval_ms.unwrap_or(DEFAULT_WATERMARK_INTERVAL_MS);
+        if interval_ms < MIN_WATERMARK_INTERVAL_MS {
+            return Err(anyhow::anyhow!(
+                "Watch interval must be at least {} ms",
+                MIN_WATERMARK_INTERVAL_MS
+            ));
+        }
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let watcher = self.kafka_infra.create_watermark_watcher(
+            &cluster,
+            password,
+            &topic,
+            Duration::from_millis(interval_ms),
+            self.events.clone(),
+        )?;
+
+        Ok(self.sessions.spawn(
+            SessionKind::WatermarkWatch,
+            cluster.id,
+            topic,
+            None,
+            |ctx| watcher.run(ctx),
+        ))
+    }
+
+    pub fn stop_watermark_watch(&self, session_id: Uuid) -> Result<()> {
+        self.sessions.stop(session_id)
+    }
+
+    /// Creates or replaces a lag alert rule. Running evaluators pick it up on
+    /// their next tick.
+    pub async fn save_lag_alert_rule(&self, rule: LagAlertRule) -> Result<()> {
+        if rule.interval_secs == 0 {
+            return Err(anyhow::anyhow!(
+                "Evaluation interval must be at least one second"
+            ));
+        }
+        if rule.threshold < 0 {
+            return Err(anyhow::anyhow!("Lag threshold cannot be negative"));
+        }
+        let clusters = self.cluster_repo.list_clusters().await?;
+        if !clusters.iter().any(|c| c.id == rule.cluster_id) {
+            return Err(anyhow::anyhow!("Cluster not found"));
+        }
+        self.cluster_repo.save_lag_alert_rule(&rule).await
+    }
+
+    pub async fn list_lag_alert_rules(&self, id: Uuid) -> Result<Vec<LagAlertRule>> {
+        self.cluster_repo.list_lag_alert_rules(&id).await
+    }
+
+    pub async fn delete_lag_alert_rule(&self, rule_id: Uuid) -> Result<()> {
+        self.cluster_repo.delete_lag_alert_rule(&rule_id).await
+    }
+
+    /// Starts evaluating the cluster's lag alert rules in the background.
+    pub async fn start_lag_alerts(&self, id: Uuid) -> Result<SessionInfo> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let evaluator = self.kafka_infra.create_lag_alert_evaluator(
+            &cluster,
+            password,
+            self.cluster_repo.clone(),
+            self.events.clone(),
+        );
+
+        Ok(self.sessions.spawn(
+            SessionKind::LagAlert,
+            cluster.id,
+            String::new(),
+            None,
+            |ctx| evaluator.run(ctx),
+        ))
+    }
+
+    pub fn stop_lag_alerts(&self, session_id: Uuid) -> Result<()> {
+        self.sessions.stop(session_id)
+    }
+
+    pub async fn get_lag_history(
+        &self,
+        id: Uuid,
+        group_id: String,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<LagSample>> {
+        self.cluster_repo
+            .list_lag_samples(&id, &group_id, from, to)
+            .await
+    }
+
+    /// Drops lag samples past the retention period, for samplers that are no
+    /// longer running to prune them.
+    pub async fn prune_lag_samples(&self) -> Result<u64> {
+        self.cluster_repo
+            .prune_lag_samples(now_millis() - LAG_SAMPLE_RETENTION_MS)
+            .await
+    }
+
+    /// Starts polling the cluster's metadata in the background, recording ISR
+    /// shrink and expand events as they are observed.
+    pub async fn start_health_monitor(&self, id: Uuid) -> Result<SessionInfo> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let monitor = self.kafka_infra.create_health_monitor(
+            &cluster,
+            password,
+            self.cluster_repo.clone(),
+            self.events.clone(),
+        )?;
+
+        Ok(self.sessions.spawn(
+            SessionKind::HealthMonitor,
+            cluster.id,
+            String::new(),
+            None,
+            |ctx| monitor.run(ctx),
+        ))
+    }
+
+    pub fn stop_health_monitor(&self, session_id: Uuid) -> Result<()> {
+        self.sessions.stop(session_id)
+    }
+
+    /// Reports the partitions a restart of `broker_id` would affect.
+    pub async fn prepare_broker_maintenance(
+        &self,
+        id: Uuid,
+        broker_id: i32,
+    ) -> Result<BrokerMaintenanceReport> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let partitions = self
+            .kafka_infra
+            .describe_partitions(&cluster, password.clone())
+            .await?;
+
+        let hosted: Vec<String> = partitions
+            .iter()
+            .filter(|(_, parts)| parts.iter().any(|p| p.replicas.contains(&broker_id)))
+            .map(|(topic, _)| topic.clone())
+            .collect();
+        let min_insync_replicas = self
+            .min_insync_replicas(&cluster, password, &hosted)
+            .await?;
+
+        Ok(plan_broker_maintenance(
+            broker_id,
+            &partitions,
+            &min_insync_replicas,
+        ))
+    }
+
+    /// Offline and under-replicated partitions across the cluster, grouped
+    /// by topic.
+    pub async fn cluster_health(&self, id: Uuid) -> Result<Vec<TopicHealth>> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let partitions = self
+            .kafka_infra
+            .describe_partitions(&cluster, password)
+            .await?;
+        Ok(scan_partition_health(&partitions))
+    }
+
+    /// Partitions that would become unwritable for `acks=all` producers if
+    /// any single broker failed.
+    pub async fn analyze_min_isr_risk(&self, id: Uuid) -> Result<Vec<MinIsrRisk>> {
+        let (cluster, password) = self.cluster_with_password(id).await?;
+
+        let partitions = self
+            .kafka_infra
+            .describe_partitions(&cluster, password.clone())
+            .await?;
+
+        let topics: Vec<String> = partitions.keys().cloned().collect();
+        let min_insync_replicas = self
+            .min_insync_replicas(&cluster, password, &topics)
+            .await?;
+
+        Ok(analyze_min_isr_risk(&partitions, &min_insync_replicas))
+    }
+
+    async fn min_insync_replicas(
+        &self,
+        cluster: &Cluster,
+        password: Option<String>,
+        topics: &[String],
+    ) -> Result<HashMap<String, i32>> {
+        let configs = self
+            .kafka_infra
+            .describe_topic_configs(cluster, password, topics)
+            .await?;
+
+        Ok(configs
+            .into_iter()
+            .filter_map(|(topic, entries)| {
+                entries
+                    .into_iter()
+                    .find(|e| e.name == "min.insync.replicas")
+                    .and_then(|e| e.value)
+                    .and_then(|v| v.parse().ok())
+                    .map(|v| (topic, v))
+            })
+            .collect())
+    }
+
+    pub async fn get_isr_events(
+        &self,
+        id: Uuid,
+        topic: String,
+        limit: usize,
+    ) -> Result<Vec<IsrEvent>> {
+        self.cluster_repo
+            .list_isr_events(&id, &topic, limit as i64)
+            .await
+    }
+
+    /// State transitions of a group seen by the health monitor in `[from, to]`.
+    pub async fn get_group_events(
+        &self,
+        id: Uuid,
+        group_id: String,
+        from: i64,
+        to: i64,
+    ) -> Result<Vec<GroupEvent>> {
+        self.cluster_repo
+            .list_group_events(&id, &group_id, from, to)
+            .await
+    }
+
+    /// Topic creations and deletions seen by the health monitor.
+    pub async fn get_topic_events(&self, id: Uuid, limit: usize) -> Result<Vec<TopicEvent>> {
+        self.cluster_repo.list_topic_events(&id, limit as i64).await
+    }
+
+    pub fn stop_key_watch(&self, session_id: Uuid) -> Result<()> {
+        self.sessions.stop(session_id)
+    }
+
+    pub fn set_test_consumer_state(
+        &self,
+        session_id: Uuid,
+        state: SessionState,
+    ) -> Result<SessionInfo> {
+        self.sessions.set_state(session_id, state)
+    }
+
+    pub fn stop_test_consumer(&self, session_id: Uuid) -> Result<()> {
+        self.sessions.stop(session_id)
+    }
+
+    pub async fn save_macro(&self, command_macro: CommandMacro) -> Result<()> {
+        if command_macro.name.trim().is_empty() {
+            return Err(anyhow::anyhow!("Macro name must not be empty"));
+        }
+        self.cluster_repo.save_macro(&command_macro).await
+    }
+
+    pub async fn list_macros(&self) -> Result<Vec<CommandMacro>> {
+        self.cluster_repo.list_macros().await
+    }
+
+    pub async fn delete_macro(&self, macro_id: Uuid) -> Result<()> {
+        self.cluster_repo.delete_macro(&macro_id).await
+    }
+
+    /// Serializes every macro and the saved topic views of a cluster to a
+    /// JSON bundle another installation can import.
+    pub async fn export_workspace(&self, id: Uuid) -> Result<String> {
+        let macros = self.cluster_repo.list_macros().await?;
+        let views = self
+            .cluster_repo
+            .list_topic_preferences(&id)
+            .await?
+            .into_iter()
+            .map(|(topic, preferences)| TopicView { topic, preferences })
+            .collect();
+        Ok(serde_json::to_string_pretty(&WorkspaceBundle {
+            exported_at: now_millis(),
+            macros,
+            views,
+        })?)
+    }
+
+    /// Saves the macros of a bundle, replacing those with the same id, and
+    /// its topic views onto the given cluster. Nothing is saved when any
+    /// entry is invalid.
+    pub async fn import_workspace(&self, id: Uuid, json: String) -> Result<WorkspaceImportResult> {
+        let bundle: WorkspaceBundle = serde_json::from_str(&json)
+            .map_err(|e| anyhow::anyhow!("Invalid workspace bundle: {}", e))?;
+        if let Some(command_macro) = bundle.macros.iter().find(|m| m.name.trim().is_empty()) {
+            return Err(anyhow::anyhow!(
+                "Macro {} has an empty name",
+                command_macro.id
+            ));
+        }
+        if let Some(view) = bundle.views.iter().find(|v| v.preferences.limit == 0) {
+            return Err(anyhow::anyhow!(
+                "View of {}: message limit must be at least 1",
+                view.topic
+            ));
+        }
+        self.cluster_with_password(id).await?;
+
+        for command_macro in &bundle.macros {
+            self.cluster_repo.save_macro(command_macro).await?;
+        }
+        for view in &bundle.views {
+            self.cluster_repo
+                .save_topic_preferences(&id, &view.topic, &view.preferences)
+                .await?;
+        }
+        Ok(WorkspaceImportResult {
+            macros: bundle.macros.len(),
+            views: bundle.views.len(),
+        })
+    }
+
+    /// Runs a macro's steps in order against a cluster, stopping at the
+    /// first failure. Every placeholder must have a parameter before any
+    /// step runs.
+    pub async fn run_macro(
+        &self,
+        macro_id: Uuid,
+        id: Uuid,
+        params: HashMap<String, String>,
+    ) -> Result<MacroRunResult> {
+        let command_macro = self
+            .cluster_repo
+            .get_macro(&macro_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Macro not found"))?;
+        let steps = command_macro
+            .steps
+            .iter()
+            .map(|step| step.with_params(&params))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| anyhow::anyhow!(e))?;
+
+        for (index, step) in steps.iter().enumerate() {
+            if let Err(e) = self.run_macro_step(id, step.clone()).await {
+                return Ok(MacroRunResult {
+                    completed_steps: index,
+                    failed_step: Some(MacroStepFailure {
+                        index,
+                        description: step.describe(),
+                        error: e.to_string(),
+                    }),
+                });
+            }
+        }
+        Ok(MacroRunResult {
+            completed_steps: steps.len(),
+            failed_step: None,
+        })
+    }
+
+    async fn run_macro_step(&self, id: Uuid, step: MacroStep) -> Result<()> {
+        match step {
+            MacroStep::CreateTopic {
+                topic,
+                partitions,
+                replication,
+                configs,
+            } => {
+                self.create_topic(id, topic, partitions, replication, configs)
+                    .await
+            }
+            MacroStep::SetTopicConfigs { topic, configs } => {
+                let (cluster, password) = self.cluster_with_password(id).await?;
+                self.kafka_infra
+                    .alter_topic_configs(&cluster, password, &topic, &configs)
+                    .await
+            }
+            MacroStep::Publish {
+                topic,
+                key,
+                payload,
+            } => self
+                .publish_message(id, topic, key, payload, Vec::new())
+                .await
+                .map(|_| ()),
+            MacroStep::PublishBulk {
+                topic,
+                text,
+                format,
+            } => {
+                let result = self.publish_bulk_text(id, topic, text, format).await?;
+                match result.errors.first() {
+                    None => Ok(()),
+                    Some(first) => Err(anyhow::anyhow!(
+                        "{} record(s) failed, first on line {}: {}",
+                        result.errors.len(),
+                        first.line,
+                        first.message
+                    )),
+                }
+            }
+            MacroStep::DeleteTopic { topic } => self.delete_topic(id, topic, false).await,
+        }
+    }
+
+    pub fn list_sessions(&self) -> Vec<SessionInfo> {
+        self.sessions.list()
+    }
+
+    /// Freezes a tail or job where it is: consumers stop fetching and jobs
+    /// stop after the current record, keeping their place until resumed.
+    pub fn pause_session(&self, session_id: Uuid) -> Result<SessionInfo> {
+        self.sessions.set_state(session_id, SessionState::Paused)
+    }
+
+    pub fn resume_session(&self, session_id: Uuid) -> Result<SessionInfo> {
+        self.sessions.set_state(session_id, SessionState::Running)
+    }
+}
+
+/// Provisioning steps completed so far, undone in reverse on failure.
+#[derive(Debug)]
+enum ProvisionStep {
+    Topic(String),
+    ScramUser,
+    Acls,
+}
+
+/// Timestamp from which a window of latest records holds every record of its
+/// partitions: the newest of the oldest timestamps read per partition, as
+/// the others may have older records the window left out.
+fn window_start(messages: &[KafkaMessage]) -> Option<i64> {
+    let mut oldest: HashMap<i32, i64> = HashMap::new();
+    for msg in messages {
+        if let Some(ts) = msg.timestamp {
+            oldest
+                .entry(msg.partition)
+                .and_modify(|oldest| *oldest = (*oldest).min(ts))
+                .or_insert(ts);
+        }
+    }
+    oldest.into_values().max()
+}
+
+/// Keyring account under which an auxiliary service's password is stored.
+fn service_secret_key(cluster_id: &Uuid, service_id: &Uuid) -> String {
+    format!("{}/{}", cluster_id, service_id)
+}
+
+/// Keyring account under which a topic's payload encryption key is stored.
+fn encryption_secret_key(cluster_id: &Uuid, topic: &str) -> String {
+    format!("{}/encryption/{}", cluster_id, topic)
+}
+
+/// Refuses an operation the cluster's endpoint cannot serve.
+fn ensure_supported(cluster: &Cluster, feature: ClusterFeature) -> Result<()> {
+    if cluster.unsupported_features().contains(&feature) {
+        let endpoint = if cluster.is_event_hubs() {
+            "Azure Event Hubs"
+        } else {
+            "This cluster"
+        };
+        return Err(anyhow::anyhow!(
+            "{} does not support {}",
+            endpoint,
+            feature.label()
+        ));
+    }
+    Ok(())
+}
+
+/// Destructive operations take the topic name, typed again by the user, as
+/// a confirmation token.
+fn check_confirmation(topic: &str, confirmation: &str) -> Result<()> {
+    if confirmation != topic {
+        return Err(anyhow::anyhow!(
+            "Confirmation does not match topic name {}",
+            topic
+        ));
     }
+    Ok(())
 }
@@ -1,5 +1,5 @@
 import { QueryClient, QueryClientProvider, useQuery, useMutation } from '@tanstack/react-query'
-import { apiBridge } from './api/bridge'
+import { apiBridge, isTauri, ApiError } from './api/bridge'
 import { Database, RefreshCw, Plus, Server, Shield, HardDrive, Send, LayoutGrid, Sun, Moon, Inbox, Trash2, Settings } from 'lucide-react'
 import { useState, useEffect } from 'react'
 import { ProducerLab } from './components/ProducerLab'
@@ -12,12 +12,76 @@ interface Cluster {
   name: string;
   brokers: string;
   security: { type: string; config?: any };
+  // Bumped on every save; an update carrying a stale version is rejected.
+  version?: number;
 }
 
 interface Topic {
   name: string;
   partitions: number;
   replication_factor: number;
+  partition_ids?: number[];
+}
+
+type AppInitStatus =
+  | { type: 'Initializing' }
+  | { type: 'Ready' }
+  | { type: 'Failed'; message: string };
+
+// The backend initializes in the background and rejects commands until it
+// is done. It reports the outcome once, so listen before asking for the
+// current status to not miss an event sent in between.
+function useAppInitStatus(): AppInitStatus {
+  const [status, setStatus] = useState<AppInitStatus>(() =>
+    isTauri() ? { type: 'Initializing' } : { type: 'Ready' }
+  );
+
+  useEffect(() => {
+    if (!isTauri()) return;
+    let cancelled = false;
+    const unlisteners: (() => void)[] = [];
+
+    (async () => {
+      const { listen } = await import('@tauri-apps/api/event');
+      for (const event of ['app-ready', 'app-init-failed']) {
+        const unlisten = await listen<AppInitStatus>(event, (e) => setStatus(e.payload));
+        if (cancelled) unlisten(); else unlisteners.push(unlisten);
+      }
+      const current = await apiBridge<AppInitStatus>('get_app_init_status');
+      if (!cancelled) setStatus(prev => prev.type === 'Initializing' ? current : prev);
+    })().catch((error) => {
+      if (!cancelled) setStatus({ type: 'Failed', message: String(error) });
+    });
+
+    return () => {
+      cancelled = true;
+      unlisteners.forEach(unlisten => unlisten());
+    };
+  }, []);
+
+  return status;
+}
+
+function InitScreen({ status }: { status: AppInitStatus }) {
+  return (
+    <div className="flex h-screen w-screen items-center justify-center bg-white dark:bg-slate-950 text-slate-900 dark:text-slate-50 font-sans">
+      {status.type === 'Failed' ? (
+        <div className="max-w-md p-4 bg-red-50 dark:bg-red-500/10 border border-red-200 dark:border-red-500/20 rounded-xl flex items-start gap-2">
+          <Shield size={14} className="text-red-500 mt-0.5 shrink-0" />
+          <div>
+            <p className="text-sm text-red-600 dark:text-red-400 font-bold">Startup failed</p>
+            <p className="text-xs text-red-600 dark:text-red-400 font-medium mt-1 break-words">{status.message}</p>
+            <p className="text-xs text-slate-500 mt-2">Restart the app to try again.</p>
+          </div>
+        </div>
+      ) : (
+        <div className="flex flex-col items-center gap-3">
+          <div className="w-6 h-6 border-2 border-slate-200 dark:border-slate-800 border-t-blue-500 rounded-full animate-spin"></div>
+          <p className="text-xs text-slate-500 font-bold">Starting...</p>
+        </div>
+      )}
+    </div>
+  )
 }
 
 interface ClusterInfo {
@@ -97,14 +161,20 @@ function Dashboard() {
   const updateClusterMutation = useMutation({
     mutationFn: async () => {
       if (!editingCluster) return;
+      // The form only edits the username; keep the mechanism, CA and
+      // OAuth settings the cluster already has.
+      const saslConfig = editingCluster.security.type === 'SaslSsl'
+        ? editingCluster.security.config
+        : { mechanism: 'Plain' };
       const cluster: Cluster = {
         id: editingCluster.id,
         name: newCluster.name,
         brokers: newCluster.brokers,
         security: newCluster.username ? {
           type: 'SaslSsl',
-          config: { mechanism: 'Plain', username: newCluster.username }
-        } : { type: 'Plaintext' }
+          config: { ...saslConfig, username: newCluster.username }
+        } : { type: 'Plaintext' },
+        version: editingCluster.version
       };
 
       return await apiBridge('update_cluster', {
@@ -118,6 +188,14 @@ function Dashboard() {
       setEditingCluster(null);
       setNewCluster({ name: '', brokers: '', username: '', password: '' });
     },
+    onError: async (error) => {
+      if (!(error instanceof ApiError) || error.kind !== 'Conflict' || !editingCluster) return;
+      // Saved elsewhere since the form opened: edit on top of the stored
+      // cluster so that saving again is a deliberate overwrite.
+      const { data } = await refetchClusters();
+      const current = data?.find((c) => c.id === editingCluster.id);
+      if (current) setEditingCluster(current);
+    },
   });
 
   // Create Topic Mutation
@@ -201,6 +279,14 @@ function Dashboard() {
     }
   }, [clusters, selectedClusterId]);
 
+  // Connect to the selected cluster in the background so the first view is fast
+  useEffect(() => {
+    if (!selectedClusterId) return;
+    apiBridge('warm_up_cluster', { clusterId: selectedClusterId }).catch((error) => {
+      console.warn('Failed to warm up cluster:', error);
+    });
+  }, [selectedClusterId]);
+
   // Fetch Topics for Selected Cluster
   const { data: topics, isLoading: isLoadingTopics, error: topicError, refetch: refetchTopics } = useQuery<Topic[]>({
     queryKey: ['topics', selectedClusterId],
@@ -279,6 +365,7 @@ function Dashboard() {
                   username: cluster.security.type === 'SaslSsl' ? cluster.security.config.username : '',
                   password: ''
                 });
+                updateClusterMutation.reset();
                 setIsAddingCluster(true);
               }}
               className="absolute -top-1 -right-1 w-4 h-4 bg-blue-500 text-white rounded-full flex items-center justify-center opacity-0 group-hover/btn:opacity-100 transition-opacity shadow-sm z-10"
@@ -665,6 +752,16 @@ function Dashboard() {
                   </div>
                 </div>
               </div>
+              {editingCluster && updateClusterMutation.error && (
+                <div className="mt-4 p-4 bg-red-50 dark:bg-red-500/10 border border-red-200 dark:border-red-500/20 rounded-xl flex items-start gap-3">
+                  <Shield size={16} className="text-red-500 mt-0.5" />
+                  <p className="text-xs text-red-600 dark:text-red-400 font-medium leading-relaxed">
+                    {updateClusterMutation.error instanceof ApiError && updateClusterMutation.error.kind === 'Conflict'
+                      ? 'This cluster was changed elsewhere since you opened it. Review your changes and update again to overwrite them.'
+                      : String(updateClusterMutation.error)}
+                  </p>
+                </div>
+              )}
               <button
                 onClick={() => editingCluster ? updateClusterMutation.mutate() : addClusterMutation.mutate()}
                 disabled={
@@ -815,9 +912,13 @@ function Dashboard() {
 }
 
 function App() {
+  const initStatus = useAppInitStatus();
+
+  // The dashboard's queries run on mount, so it mounts once the backend
+  // accepts commands.
   return (
     <QueryClientProvider client={queryClient}>
-      <Dashboard />
+      {initStatus.type === 'Ready' ? <Dashboard /> : <InitScreen status={initStatus} />}
     </QueryClientProvider>
   )
 }
@@ -77,7 +77,8 @@ const commandMappings: Record<string, CommandMapper> = {
   
   consume_messages: (args) => {
     const { clusterId, ...rest } = args || {};
-    return httpRequest('POST', `/clusters/${clusterId}/consume`, convertKeysToSnakeCase(rest));
+    return httpRequest<unknown[]>('POST', `/clusters/${clusterId}/consume`, convertKeysToSnakeCase(rest))
+      .then((messages) => ({ messages, errors: [] }));
   },
   
   get_cluster_info: (args) => httpRequest('GET', `/clusters/${args?.clusterId}/info`),
@@ -85,15 +86,41 @@ const commandMappings: Record<string, CommandMapper> = {
   delete_topic: (args) => 
     httpRequest('DELETE', `/clusters/${args?.clusterId}/topics/${encodeURIComponent(args?.topic)}`),
   
+  // Connections are per request in web mode, so there is nothing to warm up
+  warm_up_cluster: async () => undefined,
+
   add_partitions: (args) => 
     httpRequest('PUT', `/clusters/${args?.clusterId}/topics/${encodeURIComponent(args?.topic)}/partitions`, { partitions: args?.partitions }),
 };
 
+// Error reported by a backend command. `kind` tells a version conflict or
+// a validation failure apart from other errors.
+export class ApiError extends Error {
+  kind: string;
+
+  constructor(kind: string, message: string) {
+    super(message);
+    this.name = 'ApiError';
+    this.kind = kind;
+  }
+
+  toString() {
+    return this.message;
+  }
+}
+
 // Bridge function that calls Tauri invoke or HTTP API
 export async function apiBridge<T>(command: string, args?: Record<string, any>): Promise<T> {
   if (isTauri()) {
     const { invoke } = await import('@tauri-apps/api/core');
-    return invoke<T>(command, args);
+    try {
+      return await invoke<T>(command, args);
+    } catch (error: any) {
+      if (error && typeof error.kind === 'string' && typeof error.message === 'string') {
+        throw new ApiError(error.kind, error.message);
+      }
+      throw error;
+    }
   }
   
   // Use HTTP API mapping
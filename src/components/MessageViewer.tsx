@@ -11,6 +11,18 @@ interface KafkaMessage {
     payload: string | null;
 }
 
+interface FetchError {
+    kind: 'Authorization' | 'PartitionEof' | 'UnknownTopicOrPartition' | 'Transient' | 'Other';
+    partition: number | null;
+    message: string;
+    count: number;
+}
+
+interface ConsumeResult {
+    messages: KafkaMessage[];
+    errors: FetchError[];
+}
+
 interface MessageViewerProps {
     selectedClusterId: string | null;
     topic: string;
@@ -21,11 +33,11 @@ export function MessageViewer({ selectedClusterId, topic, theme: _theme = 'dark'
     const [maxMessages, setMaxMessages] = useState(50);
     const [selectedMessage, setSelectedMessage] = useState<KafkaMessage | null>(null);
 
-    const { data: messages, isLoading, error, refetch } = useQuery<KafkaMessage[]>({
+    const { data, isLoading, error, refetch } = useQuery<ConsumeResult>({
         queryKey: ['messages', selectedClusterId, topic, maxMessages],
         queryFn: async () => {
-            if (!selectedClusterId || !topic) return [];
-            return await apiBridge<KafkaMessage[]>('consume_messages', {
+            if (!selectedClusterId || !topic) return { messages: [], errors: [] };
+            return await apiBridge<ConsumeResult>('consume_messages', {
                 clusterId: selectedClusterId,
                 topic,
                 maxMessages,
@@ -34,6 +46,8 @@ export function MessageViewer({ selectedClusterId, topic, theme: _theme = 'dark'
         enabled: !!selectedClusterId && !!topic,
         refetchOnWindowFocus: false,
     });
+    const messages = data?.messages;
+    const fetchErrors = data?.errors ?? [];
 
     const formatTimestamp = (ts: number | null) => {
         if (!ts) return 'N/A';
@@ -93,6 +107,17 @@ export function MessageViewer({ selectedClusterId, topic, theme: _theme = 'dark'
                 </div>
             </div>
 
+            {fetchErrors.length > 0 && (
+                <div className="px-6 py-3 border-b border-amber-200 dark:border-amber-900 bg-amber-50 dark:bg-amber-500/10 text-amber-700 dark:text-amber-400 text-sm shrink-0">
+                    <p className="font-bold">Results may be incomplete</p>
+                    {fetchErrors.map((e, i) => (
+                        <p key={i} className="font-mono text-xs mt-1">
+                            {e.kind}{e.partition !== null ? ` (P${e.partition})` : ''}: {e.message}{e.count > 1 ? ` ×${e.count}` : ''}
+                        </p>
+                    ))}
+                </div>
+            )}
+
             {/* Content */}
             <div className="flex-1 flex overflow-hidden">
                 {/* Message List */}